@@ -0,0 +1,82 @@
+//! Measure the cost of anchors on a CI-style configuration which anchors many nodes and aliases
+//! few of them.
+//!
+//! Anchored nodes are only copied once an alias refers to them, unless a node filter is set, in
+//! which case every anchored node is copied as it is loaded. The latter is used as the baseline.
+//!
+//! Run with `cargo bench --bench anchors`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use saphyr::{FilterAction, Yaml, YamlLoader};
+use saphyr_parser::{BufferedInput, Parser};
+
+const JOBS: usize = 2000;
+const RUNS: u32 = 20;
+
+/// Count the allocations of the whole program.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn load(source: &str, eager: bool) -> (Duration, usize) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let mut loader = YamlLoader::<Yaml>::new();
+    if eager {
+        loader.node_filter(|_, _| FilterAction::Keep);
+    }
+    let mut parser = Parser::new(BufferedInput::new(source.chars()));
+    parser.load(&mut loader, true).unwrap();
+    let documents = loader.into_documents();
+    let elapsed = start.elapsed();
+    assert_eq!(documents[0].as_hash().unwrap().len(), JOBS + 1);
+    (elapsed, ALLOCATIONS.load(Ordering::Relaxed) - allocations)
+}
+
+fn main() {
+    let mut source = String::from(
+        ".defaults: &defaults\n  image: ruby:3.3\n  tags: [docker, linux]\n  \
+         retry: {max: 2, when: [runner_system_failure]}\n",
+    );
+    for i in 0..JOBS {
+        // Every job anchors its script and rules, and only one in a hundred is reused.
+        let parent = if i % 100 == 1 { "*defaults" } else { "{}" };
+        source.push_str(&format!(
+            "job-{i}: &job-{i}\n  extends: {parent}\n  stage: &stage-{i} test\n  \
+             script: &script-{i}\n    - bundle install\n    - bundle exec rake test:{i}\n  \
+             rules: &rules-{i}\n    - if: $CI_COMMIT_BRANCH == \"main\"\n      when: always\n    \
+             - when: manual\n  variables: &variables-{i} {{SHARD: \"{i}\"}}\n"
+        ));
+    }
+
+    for (name, eager) in [("copy on alias", false), ("copy on anchor", true)] {
+        let mut total = Duration::ZERO;
+        let mut allocations = 0;
+        for _ in 0..RUNS {
+            let (elapsed, count) = load(&source, eager);
+            total += elapsed;
+            allocations = count;
+        }
+        println!(
+            "{name}: {:?} and {allocations} allocations per load of {JOBS} jobs",
+            total / RUNS
+        );
+    }
+}
@@ -0,0 +1,42 @@
+//! Compare editing one document of a large stream against re-loading the whole stream.
+//!
+//! Run with `cargo bench --bench incremental`.
+
+use std::time::{Duration, Instant};
+
+use saphyr::{IncrementalLoader, MarkedYaml};
+
+const DOCUMENTS: usize = 500;
+const EDITS: u32 = 50;
+
+fn main() {
+    let mut source = String::new();
+    for i in 0..DOCUMENTS {
+        source.push_str(&format!(
+            "---\nid: {i}\nname: document {i}\ntags: [a, b, c]\nspec:\n  replicas: 3\n  \
+             containers:\n    - image: app:{i}\n      ports: [80, 443]\n"
+        ));
+    }
+    let edit_at = source.find("id: 250\n").unwrap() + 4;
+
+    let mut loader = IncrementalLoader::new(source.as_str()).unwrap();
+    let mut incremental = Duration::ZERO;
+    let mut full = Duration::ZERO;
+    for n in 0..EDITS {
+        let replacement = (250 + n).to_string();
+        let end = edit_at + loader.source()[edit_at..].find('\n').unwrap();
+
+        let start = Instant::now();
+        loader.apply_edit(edit_at..end, &replacement).unwrap();
+        incremental += start.elapsed();
+
+        let start = Instant::now();
+        let documents = MarkedYaml::load_from_str(loader.source()).unwrap();
+        full += start.elapsed();
+        assert_eq!(documents.len(), DOCUMENTS);
+    }
+
+    println!("{DOCUMENTS} documents, {EDITS} edits of a single document:");
+    println!("  incremental: {:?} per edit", incremental / EDITS);
+    println!("  full reload: {:?} per edit", full / EDITS);
+}
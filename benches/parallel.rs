@@ -0,0 +1,35 @@
+//! Compare loading a stream of many small documents on one thread and on several threads.
+//!
+//! Run with `cargo bench --features parallel --bench parallel`.
+
+use std::time::Instant;
+
+use saphyr::Yaml;
+
+const DOCUMENTS: usize = 100_000;
+
+fn main() {
+    let mut source = String::new();
+    for i in 0..DOCUMENTS {
+        source.push_str(&format!(
+            "---\nid: {i}\nname: \"record {i}\"\ntags: [a, b, c]\nnotes: |\n  ---\n  line\n"
+        ));
+    }
+    println!("{DOCUMENTS} documents, {} bytes:", source.len());
+
+    let start = Instant::now();
+    let expected = Yaml::load_from_str(&source).unwrap();
+    let sequential = start.elapsed();
+    println!("  load_from_str: {sequential:?}");
+
+    for threads in [1, 2, 4, 8, 16] {
+        let start = Instant::now();
+        let docs = Yaml::load_from_str_parallel(&source, threads).unwrap();
+        let elapsed = start.elapsed();
+        assert_eq!(docs, expected);
+        println!(
+            "  load_from_str_parallel on {threads} threads: {elapsed:?} ({:.2}x)",
+            sequential.as_secs_f64() / elapsed.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,72 @@
+//! Compare loading many tiny streams with a new loader each time against reusing one loader and
+//! one vector of documents.
+//!
+//! Run with `cargo bench --bench tiny_documents`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use saphyr::{Yaml, YamlLoader};
+use saphyr_parser::{BufferedInput, Parser};
+
+const STREAMS: usize = 100_000;
+
+/// Count the allocations of the whole program.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn measure(name: &str, mut load: impl FnMut(&str) -> usize) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let mut documents = 0;
+    for _ in 0..STREAMS {
+        documents += load("[1, {a: b}]");
+    }
+    let elapsed: Duration = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    assert_eq!(documents, STREAMS);
+    println!(
+        "{name}: {elapsed:?}, {} allocations per stream",
+        allocations / STREAMS
+    );
+}
+
+fn main() {
+    println!("{STREAMS} streams of one tiny document:");
+    measure("  load_from_str", |source| {
+        Yaml::load_from_str(source).unwrap().len()
+    });
+
+    let mut docs = Vec::new();
+    measure("  load_from_str_into", |source| {
+        docs.clear();
+        Yaml::load_from_str_into(source, &mut docs).unwrap();
+        docs.len()
+    });
+
+    let mut loader = YamlLoader::<Yaml>::new();
+    measure("  reused loader", |source| {
+        docs.clear();
+        loader.reset();
+        let mut parser = Parser::new(BufferedInput::new(source.chars()));
+        parser.load(&mut loader, true).unwrap();
+        loader.append_documents(&mut docs);
+        docs.len()
+    });
+}
@@ -0,0 +1,605 @@
+//! Checked access to the nodes of a [`Yaml`] document.
+
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::cmp::Ordering;
+use core::fmt::{self, Display};
+
+use crate::{emitter::compare_keys, Array, Hash, HashEntry, Yaml, YamlKind};
+
+/// An error returned by the checked accessors of [`Yaml`], e.g. [`Yaml::try_index`] or
+/// [`Yaml::expect_str`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IndexError {
+    /// The mapping does not contain the key.
+    MissingKey(String),
+    /// The index is beyond the end of the sequence.
+    OutOfBounds {
+        /// The index that was requested.
+        index: usize,
+        /// The length of the sequence.
+        len: usize,
+    },
+    /// The node is not of the expected type.
+    WrongType {
+        /// The type that was expected, e.g. [`YamlKind::Hash`].
+        expected: YamlKind,
+        /// The type of the node, e.g. [`YamlKind::String`].
+        actual: YamlKind,
+    },
+}
+
+/// Lookups and reordering of the entries of a [`Hash`].
+///
+/// [`Hash`] is a [`LinkedHashMap`](hashlink::LinkedHashMap), which already provides
+/// [`keys`](hashlink::LinkedHashMap::keys), [`values`](hashlink::LinkedHashMap::values) and
+/// [`get`](hashlink::LinkedHashMap::get) for keys of any type, including collections (see
+/// [`Yaml::key`]). Keys cannot be mutated once inserted: the map only gives shared references
+/// to them, so that their hash cannot change. To change a key, remove its entry and insert it
+/// again.
+///
+/// ```
+/// use saphyr::{yaml, HashExt, Yaml};
+///
+/// let doc = Yaml::load_one_from_str("? [a, b]\n: pair\nc: single").unwrap();
+/// let hash = doc.as_hash().unwrap();
+/// assert_eq!(hash.get_by_str("c").and_then(Yaml::as_str), Some("single"));
+/// assert_eq!(hash[&Yaml::key(["a", "b"])].as_str(), Some("pair"));
+/// assert_eq!(hash.keys().next(), Some(&yaml!(["a", "b"])));
+/// ```
+///
+/// Removing a key and inserting it again moves it to the end of the mapping. The other methods
+/// keep the order of the entries they do not move:
+///
+/// ```
+/// use saphyr::{yaml, HashExt, Yaml, YamlEmitter};
+///
+/// let source = "metadata: {name: web}\nkind: Pod\napiVersion: v1";
+/// let mut doc = Yaml::load_one_from_str(source).unwrap();
+/// let manifest = doc.as_mut_hash().unwrap();
+/// manifest.move_key_to_front("apiVersion");
+/// manifest.move_key_to_index("kind", 1);
+/// manifest.replace_value("metadata", yaml!({"name": "api"}));
+///
+/// let mut output = String::new();
+/// YamlEmitter::new(&mut output).dump(&doc).unwrap();
+/// assert_eq!(output, "---\napiVersion: v1\nkind: Pod\nmetadata:\n  name: api");
+/// ```
+pub trait HashExt {
+    /// Return the value of the key which resolved to the string `key`, as with `["key"]`.
+    fn get_by_str(&self, key: &str) -> Option<&Yaml>;
+
+    /// Return the value of the key which resolved to the string `key`, for modification.
+    fn get_by_str_mut(&mut self, key: &str) -> Option<&mut Yaml>;
+
+    /// Remove the entries whose key does not match `predicate`, keeping the others in order.
+    fn retain_keys(&mut self, predicate: impl FnMut(&Yaml) -> bool);
+
+    /// Sort the entries by key with `compare`. The sort is stable.
+    ///
+    /// See [`Yaml::sort_all_keys`] to sort the keys of nested mappings as well.
+    fn sort_keys_by(&mut self, compare: impl FnMut(&Yaml, &Yaml) -> Ordering);
+
+    /// Move the entry of `key` before the others. Returns whether the mapping contains `key`.
+    fn move_key_to_front(&mut self, key: impl Into<Yaml>) -> bool;
+
+    /// Move the entry of `key` to position `index`, or to the end of the mapping if `index` is
+    /// beyond it. Returns whether the mapping contains `key`.
+    fn move_key_to_index(&mut self, key: impl Into<Yaml>, index: usize) -> bool;
+
+    /// Set the value of `key` without moving its entry, or insert it at the end of the mapping if
+    /// it is not there yet. Returns the previous value, if any.
+    fn replace_value(&mut self, key: impl Into<Yaml>, value: Yaml) -> Option<Yaml>;
+}
+
+impl HashExt for Hash {
+    fn get_by_str(&self, key: &str) -> Option<&Yaml> {
+        self.get(&Yaml::String(key.to_owned()))
+    }
+
+    fn get_by_str_mut(&mut self, key: &str) -> Option<&mut Yaml> {
+        self.get_mut(&Yaml::String(key.to_owned()))
+    }
+
+    fn retain_keys(&mut self, mut predicate: impl FnMut(&Yaml) -> bool) {
+        self.retain(|key, _| predicate(key));
+    }
+
+    fn sort_keys_by(&mut self, mut compare: impl FnMut(&Yaml, &Yaml) -> Ordering) {
+        let mut entries: Vec<_> = core::mem::take(self).into_iter().collect();
+        entries.sort_by(|(a, _), (b, _)| compare(a, b));
+        self.extend(entries);
+    }
+
+    fn move_key_to_front(&mut self, key: impl Into<Yaml>) -> bool {
+        self.to_front(&key.into()).is_some()
+    }
+
+    fn move_key_to_index(&mut self, key: impl Into<Yaml>, index: usize) -> bool {
+        let key = key.into();
+        let Some(position) = self.keys().position(|k| *k == key) else {
+            return false;
+        };
+        let mut entries: Vec<_> = core::mem::take(self).into_iter().collect();
+        let entry = entries.remove(position);
+        entries.insert(index.min(entries.len()), entry);
+        self.extend(entries);
+        true
+    }
+
+    fn replace_value(&mut self, key: impl Into<Yaml>, value: Yaml) -> Option<Yaml> {
+        self.replace(Yaml::key(key), value)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for IndexError {}
+
+impl Display for IndexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexError::MissingKey(key) => write!(f, "missing key `{key}`"),
+            IndexError::OutOfBounds { index, len } => {
+                write!(
+                    f,
+                    "index {index} is out of bounds of a sequence of {len} items"
+                )
+            }
+            IndexError::WrongType { expected, actual } => {
+                write!(f, "expected {expected}, found {actual}")
+            }
+        }
+    }
+}
+
+impl Yaml {
+    /// Build a mapping key, e.g. a sequence or a mapping to look up a complex key with.
+    ///
+    /// Values are converted as with [`From`], so that `Yaml::key(["a", "b"])` is the key
+    /// written `? [a, b]` in a document.
+    ///
+    /// # Panics
+    /// In debug builds, panics if `key` contains a [`Yaml::BadValue`] or a [`Yaml::Alias`],
+    /// which no loaded key contains.
+    #[must_use]
+    pub fn key(key: impl Into<Yaml>) -> Yaml {
+        let key = key.into();
+        debug_assert!(
+            key.is_valid_key(),
+            "mapping keys cannot contain bad values or aliases"
+        );
+        key
+    }
+
+    fn is_valid_key(&self) -> bool {
+        match self {
+            Yaml::BadValue | Yaml::Alias(_) => false,
+            Yaml::Tagged(_, node) => node.is_valid_key(),
+            Yaml::Array(items) => items.iter().all(Yaml::is_valid_key),
+            Yaml::Hash(entries) => entries
+                .iter()
+                .all(|(key, value)| key.is_valid_key() && value.is_valid_key()),
+            _ => true,
+        }
+    }
+
+    /// Return the value of the key which resolved to the string `key`.
+    ///
+    /// Keys are looked up as with `["key"]` (see [`Index`](core::ops::Index)), which returns
+    /// [`Yaml::BadValue`] rather than failing.
+    ///
+    /// ```
+    /// # use saphyr::{IndexError, Yaml};
+    /// let doc = Yaml::load_one_from_str("db: {host: localhost}").unwrap();
+    /// assert_eq!(doc.try_index("db")?.try_index("host")?.expect_str()?, "localhost");
+    /// assert_eq!(
+    ///     doc.try_index("db")?.try_index("port"),
+    ///     Err(IndexError::MissingKey("port".into()))
+    /// );
+    /// # Ok::<(), IndexError>(())
+    /// ```
+    ///
+    /// # Errors
+    /// Returns [`IndexError::MissingKey`] if `self` does not contain the key, and
+    /// [`IndexError::WrongType`] if `self` is not a mapping.
+    pub fn try_index(&self, key: &str) -> Result<&Yaml, IndexError> {
+        self.expect_hash()?
+            .get_by_str(key)
+            .ok_or_else(|| IndexError::MissingKey(key.to_owned()))
+    }
+
+    /// Return the item at `index` in the sequence.
+    ///
+    /// Unlike `[index]`, this does not look up integer keys in mappings.
+    ///
+    /// # Errors
+    /// Returns [`IndexError::OutOfBounds`] if `index` is beyond the end of `self`, and
+    /// [`IndexError::WrongType`] if `self` is not a sequence.
+    pub fn try_index_seq(&self, index: usize) -> Result<&Yaml, IndexError> {
+        let array = self.expect_array()?;
+        array.get(index).ok_or(IndexError::OutOfBounds {
+            index,
+            len: array.len(),
+        })
+    }
+
+    /// Return the node at the [JSON Pointer](https://datatracker.ietf.org/doc/html/rfc6901)
+    /// `pointer`, e.g. `/spec/containers/0/image`, if there is one.
+    ///
+    /// The empty pointer is `self`. Each segment is the index of an item in a sequence, or a key
+    /// of a mapping as a [`YamlPath`](crate::YamlPath) displays it: the key which resolved to
+    /// the string is looked up first, then one which displays the same (e.g. the integer `1` for
+    /// `/1`). As in JSON Pointers, `~1` stands for `/` and `~0` for `~` within segments.
+    ///
+    /// ```
+    /// # use saphyr::Yaml;
+    /// let doc = Yaml::load_one_from_str("spec:\n  containers:\n    - image: web:1.2").unwrap();
+    /// assert_eq!(
+    ///     doc.pointer("/spec/containers/0/image").and_then(Yaml::as_str),
+    ///     Some("web:1.2")
+    /// );
+    /// assert_eq!(doc.pointer("/spec/containers/1"), None);
+    /// assert_eq!(doc.pointer(""), Some(&doc));
+    /// ```
+    #[must_use]
+    pub fn pointer(&self, pointer: &str) -> Option<&Yaml> {
+        pointer_segments(pointer)?.try_fold(self, |node, segment| match node.untagged() {
+            Yaml::Array(items) => items.get(parse_index(&segment)?),
+            Yaml::Hash(hash) => hash.get_by_str(&segment).or_else(|| {
+                hash.iter()
+                    .find(|(key, _)| key_matches(key, &segment))
+                    .map(|(_, value)| value)
+            }),
+            _ => None,
+        })
+    }
+
+    /// Return the node at the JSON Pointer `pointer`, for modification.
+    ///
+    /// See [`Self::pointer`] for the syntax of `pointer`.
+    ///
+    /// ```
+    /// # use saphyr::{yaml, Yaml};
+    /// let mut doc = yaml!({"spec": {"replicas": 1}});
+    /// *doc.pointer_mut("/spec/replicas").unwrap() = Yaml::Integer(3);
+    /// assert_eq!(doc, yaml!({"spec": {"replicas": 3}}));
+    /// ```
+    #[must_use]
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Yaml> {
+        pointer_segments(pointer)?.try_fold(self, |node, segment| {
+            let node = match node {
+                Yaml::Tagged(_, node) => &mut **node,
+                node => node,
+            };
+            match node {
+                Yaml::Array(items) => items.get_mut(parse_index(&segment)?),
+                Yaml::Hash(hash) => {
+                    if hash.get_by_str(&segment).is_some() {
+                        hash.get_by_str_mut(&segment)
+                    } else {
+                        hash.iter_mut()
+                            .find(|(key, _)| key_matches(key, &segment))
+                            .map(|(_, value)| value)
+                    }
+                }
+                _ => None,
+            }
+        })
+    }
+
+    /// Return the entries of `self` if it is a mapping.
+    ///
+    /// # Errors
+    /// Returns [`IndexError::WrongType`] if `self` is not a mapping.
+    pub fn expect_hash(&self) -> Result<&Hash, IndexError> {
+        self.as_hash()
+            .ok_or_else(|| self.wrong_type(YamlKind::Hash))
+    }
+
+    /// Return the items of `self` if it is a sequence.
+    ///
+    /// # Errors
+    /// Returns [`IndexError::WrongType`] if `self` is not a sequence.
+    pub fn expect_array(&self) -> Result<&Array, IndexError> {
+        self.as_vec()
+            .ok_or_else(|| self.wrong_type(YamlKind::Array))
+    }
+
+    /// Return the value of `self` if it is a string.
+    ///
+    /// # Errors
+    /// Returns [`IndexError::WrongType`] if `self` is not a string.
+    pub fn expect_str(&self) -> Result<&str, IndexError> {
+        self.as_str()
+            .ok_or_else(|| self.wrong_type(YamlKind::String))
+    }
+
+    /// Return the value of `self` if it is an integer.
+    ///
+    /// # Errors
+    /// Returns [`IndexError::WrongType`] if `self` is not an integer.
+    pub fn expect_i64(&self) -> Result<i64, IndexError> {
+        self.as_i64().ok_or_else(|| self.wrong_type(YamlKind::Int))
+    }
+
+    /// Return the value of `self` if it is a float.
+    ///
+    /// # Errors
+    /// Returns [`IndexError::WrongType`] if `self` is not a float.
+    pub fn expect_f64(&self) -> Result<f64, IndexError> {
+        self.as_f64().ok_or_else(|| self.wrong_type(YamlKind::Real))
+    }
+
+    /// Return the value of `self` if it is a boolean.
+    ///
+    /// # Errors
+    /// Returns [`IndexError::WrongType`] if `self` is not a boolean.
+    pub fn expect_bool(&self) -> Result<bool, IndexError> {
+        self.as_bool()
+            .ok_or_else(|| self.wrong_type(YamlKind::Bool))
+    }
+
+    /// Return the entries of `self`, converting it to an empty mapping if it is
+    /// [`Yaml::Null`] or [`Yaml::BadValue`].
+    ///
+    /// # Panics
+    /// Panics if `self` is of another type. See [`Self::try_ensure_hash`] for a checked version.
+    pub fn ensure_hash(&mut self) -> &mut Hash {
+        self.try_ensure_hash()
+            .unwrap_or_else(|error| panic!("cannot use the node as a mapping: {error}"))
+    }
+
+    /// Return the entries of `self`, converting it to an empty mapping if it is
+    /// [`Yaml::Null`] or [`Yaml::BadValue`].
+    ///
+    /// # Errors
+    /// Returns [`IndexError::WrongType`] if `self` is of another type, leaving it unchanged.
+    pub fn try_ensure_hash(&mut self) -> Result<&mut Hash, IndexError> {
+        if matches!(self, Yaml::Null | Yaml::BadValue) {
+            *self = Yaml::Hash(Hash::new());
+        }
+        match self {
+            Yaml::Hash(hash) => Ok(hash),
+            other => Err(other.wrong_type(YamlKind::Hash)),
+        }
+    }
+
+    /// Return the items of `self`, converting it to an empty sequence if it is [`Yaml::Null`]
+    /// or [`Yaml::BadValue`].
+    ///
+    /// # Panics
+    /// Panics if `self` is of another type. See [`Self::try_ensure_array`] for a checked
+    /// version.
+    pub fn ensure_array(&mut self) -> &mut Array {
+        self.try_ensure_array()
+            .unwrap_or_else(|error| panic!("cannot use the node as a sequence: {error}"))
+    }
+
+    /// Return the items of `self`, converting it to an empty sequence if it is [`Yaml::Null`]
+    /// or [`Yaml::BadValue`].
+    ///
+    /// # Errors
+    /// Returns [`IndexError::WrongType`] if `self` is of another type, leaving it unchanged.
+    pub fn try_ensure_array(&mut self) -> Result<&mut Array, IndexError> {
+        if matches!(self, Yaml::Null | Yaml::BadValue) {
+            *self = Yaml::Array(Array::new());
+        }
+        match self {
+            Yaml::Array(array) => Ok(array),
+            other => Err(other.wrong_type(YamlKind::Array)),
+        }
+    }
+
+    /// Return the value of `key`, inserting `default` first if the mapping does not contain it.
+    ///
+    /// As with [`Self::ensure_hash`], a [`Yaml::Null`] or [`Yaml::BadValue`] is first converted
+    /// to an empty mapping, so that nested documents can be built from nothing:
+    ///
+    /// ```
+    /// use saphyr::{yaml, Yaml};
+    ///
+    /// let mut doc = Yaml::Null;
+    /// let steps = doc
+    ///     .hash_entry_or_insert("jobs", Yaml::Null)
+    ///     .hash_entry_or_insert("build", Yaml::Null)
+    ///     .hash_entry_or_insert("steps", Yaml::Null);
+    /// steps.push(yaml!({"uses": "checkout"}));
+    /// steps.push(yaml!({"run": "cargo build"}));
+    /// steps.push(Yaml::Null);
+    /// steps[2].hash_entry_or_insert("run", Yaml::Null).push(yaml!("cargo test"));
+    ///
+    /// assert_eq!(doc["jobs"]["build"]["steps"][2]["run"][0].as_str(), Some("cargo test"));
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if `self` is not a mapping, [`Yaml::Null`] nor [`Yaml::BadValue`]. See
+    /// [`Self::try_hash_entry_or_insert`] for a checked version.
+    pub fn hash_entry_or_insert(&mut self, key: impl Into<Yaml>, default: Yaml) -> &mut Yaml {
+        self.ensure_hash().entry(Yaml::key(key)).or_insert(default)
+    }
+
+    /// Return the value of `key`, inserting `default` first if the mapping does not contain it.
+    ///
+    /// See [`Self::hash_entry_or_insert`] for behavior.
+    ///
+    /// # Errors
+    /// Returns [`IndexError::WrongType`] if `self` is not a mapping, [`Yaml::Null`] nor
+    /// [`Yaml::BadValue`], leaving it unchanged.
+    pub fn try_hash_entry_or_insert(
+        &mut self,
+        key: impl Into<Yaml>,
+        default: Yaml,
+    ) -> Result<&mut Yaml, IndexError> {
+        Ok(self
+            .try_ensure_hash()?
+            .entry(Yaml::key(key))
+            .or_insert(default))
+    }
+
+    /// Return the value of `key`, if `self` is a mapping which contains it.
+    ///
+    /// Unlike `["key"]`, keys of any type can be looked up, as with [`Hash::get`]: `get(1)`
+    /// finds the integer key `1` and `get("1")` the string key `'1'`.
+    ///
+    /// ```
+    /// use saphyr::{yaml, Yaml};
+    ///
+    /// let doc = yaml!({"name": "web", 80: "http"});
+    /// assert_eq!(doc.get("name"), Some(&yaml!("web")));
+    /// assert_eq!(doc.get(80).and_then(Yaml::as_str), Some("http"));
+    /// assert_eq!(doc.get("80"), None);
+    /// assert_eq!(yaml!(["name"]).get("name"), None);
+    /// ```
+    pub fn get(&self, key: impl Into<Yaml>) -> Option<&Yaml> {
+        self.as_hash()?.get(&key.into())
+    }
+
+    /// Return the value of `key` for modification, if `self` is a mapping which contains it.
+    ///
+    /// See [`Self::get`] for how keys are looked up.
+    pub fn get_mut(&mut self, key: impl Into<Yaml>) -> Option<&mut Yaml> {
+        self.as_mut_hash()?.get_mut(&key.into())
+    }
+
+    /// Set the value of `key`, converting `self` to an empty mapping first if it is
+    /// [`Yaml::Null`] or [`Yaml::BadValue`]. Returns the previous value, if any.
+    ///
+    /// As with [`HashExt::replace_value`], an existing entry keeps its position in the mapping.
+    ///
+    /// ```
+    /// use saphyr::{yaml, Yaml};
+    ///
+    /// let mut doc = yaml!({"image": "web:1.2", "replicas": 1});
+    /// assert_eq!(doc.insert("image", yaml!("web:1.3")), Ok(Some(yaml!("web:1.2"))));
+    /// assert_eq!(doc.insert("debug", yaml!(true)), Ok(None));
+    /// assert_eq!(doc, yaml!({"image": "web:1.3", "replicas": 1, "debug": true}));
+    /// assert!(doc["image"].insert("tag", yaml!("1.3")).is_err());
+    /// ```
+    ///
+    /// # Errors
+    /// Returns [`IndexError::WrongType`] if `self` is of another type, leaving it unchanged.
+    pub fn insert(
+        &mut self,
+        key: impl Into<Yaml>,
+        value: Yaml,
+    ) -> Result<Option<Yaml>, IndexError> {
+        Ok(self.try_ensure_hash()?.replace_value(key, value))
+    }
+
+    /// Remove the entry of `key` and return its value, if `self` is a mapping which contains
+    /// it. The other entries keep their order.
+    ///
+    /// See [`Self::get`] for how keys are looked up.
+    pub fn remove(&mut self, key: impl Into<Yaml>) -> Option<Yaml> {
+        self.as_mut_hash()?.remove(&key.into())
+    }
+
+    /// Return the entry of `key`, converting `self` to an empty mapping first if it is
+    /// [`Yaml::Null`] or [`Yaml::BadValue`].
+    ///
+    /// New entries are inserted at the end of the mapping.
+    ///
+    /// ```
+    /// use saphyr::{yaml, Yaml};
+    ///
+    /// let mut doc = yaml!({"retries": 1});
+    /// doc.entry("retries")?.and_modify(|retries| *retries = yaml!(2));
+    /// doc.entry("labels")?.or_insert(Yaml::Null).insert("app", yaml!("web"))?;
+    /// assert_eq!(doc, yaml!({"retries": 2, "labels": {"app": "web"}}));
+    /// # Ok::<(), saphyr::IndexError>(())
+    /// ```
+    ///
+    /// # Errors
+    /// Returns [`IndexError::WrongType`] if `self` is of another type, leaving it unchanged.
+    pub fn entry(&mut self, key: impl Into<Yaml>) -> Result<HashEntry<'_>, IndexError> {
+        Ok(self.try_ensure_hash()?.entry(Yaml::key(key)))
+    }
+
+    /// Append `value` to the sequence, converting `self` to an empty sequence first if it is
+    /// [`Yaml::Null`] or [`Yaml::BadValue`].
+    ///
+    /// # Panics
+    /// Panics if `self` is of another type, as with [`Self::ensure_array`].
+    pub fn push(&mut self, value: Yaml) {
+        self.ensure_array().push(value);
+    }
+
+    /// Sort the keys of every mapping within `self`, in the order of
+    /// [`KeyOrder::Sorted`](crate::KeyOrder::Sorted).
+    ///
+    /// Mapping keys themselves are left as they are, since they cannot be modified in place.
+    ///
+    /// ```
+    /// use saphyr::Yaml;
+    ///
+    /// let mut doc = Yaml::load_one_from_str("b: [{z: 1, y: 2}]\na: 3").unwrap();
+    /// doc.sort_all_keys();
+    /// assert_eq!(doc, Yaml::load_one_from_str("a: 3\nb: [{y: 2, z: 1}]").unwrap());
+    /// ```
+    pub fn sort_all_keys(&mut self) {
+        self.sort_all_keys_by(&mut compare_keys);
+    }
+
+    /// Sort the keys of every mapping within `self` with `compare`, as with
+    /// [`HashExt::sort_keys_by`].
+    pub fn sort_all_keys_by(&mut self, compare: &mut impl FnMut(&Yaml, &Yaml) -> Ordering) {
+        match self {
+            Yaml::Array(items) => {
+                for item in items {
+                    item.sort_all_keys_by(compare);
+                }
+            }
+            Yaml::Hash(hash) => {
+                hash.sort_keys_by(&mut *compare);
+                for value in hash.values_mut() {
+                    value.sort_all_keys_by(compare);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn wrong_type(&self, expected: YamlKind) -> IndexError {
+        IndexError::WrongType {
+            expected,
+            actual: self.kind(),
+        }
+    }
+}
+
+/// Return the unescaped segments of the JSON Pointer `pointer`, or `None` if it is not one.
+pub(crate) fn pointer_segments(pointer: &str) -> Option<impl Iterator<Item = String> + '_> {
+    if !pointer.is_empty() && !pointer.starts_with('/') {
+        return None;
+    }
+    Some(
+        pointer
+            .split('/')
+            .skip(1)
+            .map(|segment| segment.replace("~1", "/").replace("~0", "~")),
+    )
+}
+
+/// Parse the segment of a JSON Pointer as a sequence index, which has no sign nor leading zero.
+pub(crate) fn parse_index(segment: &str) -> Option<usize> {
+    if segment.starts_with('+') || (segment.len() > 1 && segment.starts_with('0')) {
+        return None;
+    }
+    segment.parse().ok()
+}
+
+/// Return whether the scalar `key` displays as `segment` in a [`YamlPath`](crate::YamlPath).
+pub(crate) fn key_matches(key: &Yaml, segment: &str) -> bool {
+    match key {
+        Yaml::String(v) | Yaml::Real(v) => v == segment,
+        Yaml::SharedString(v) => **v == *segment,
+        Yaml::Integer(v) => v.to_string() == segment,
+        Yaml::Boolean(v) => segment.parse() == Ok(*v),
+        Yaml::Null => segment == "null",
+        Yaml::Tagged(_, node) => key_matches(node, segment),
+        _ => false,
+    }
+}
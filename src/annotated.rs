@@ -1,12 +1,16 @@
 //! Utilities for extracting YAML with certain metadata.
 
+pub mod commented_yaml;
 pub mod marked_yaml;
 
-use std::ops::{Index, IndexMut};
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
+use core::ops::{Index, IndexMut};
 
 use hashlink::LinkedHashMap;
 
 use crate::loader::parse_f64;
+use crate::Float;
+use crate::Timestamp;
 
 /// YAML data for nodes that will contain annotations.
 ///
@@ -41,17 +45,23 @@ use crate::loader::parse_f64;
 #[derive(Clone, PartialEq, PartialOrd, Debug, Eq, Ord, Hash)]
 pub enum YamlData<Node>
 where
-    Node: std::hash::Hash + std::cmp::Eq + From<Self>,
+    Node: core::hash::Hash + core::cmp::Eq + From<Self>,
 {
     /// Float types are stored as String and parsed on demand.
     /// Note that `f64` does NOT implement Eq trait and can NOT be stored in `BTreeMap`.
     Real(String),
+    /// A float stored as an `f64`, compared bit by bit.
+    Float(Float),
     /// YAML int is stored as i64.
     Integer(i64),
     /// YAML scalar.
     String(String),
     /// YAML bool, e.g. `true` or `false`.
     Boolean(bool),
+    /// YAML 1.1 timestamp, e.g. `2001-12-14t21:59:43.10-05:00`.
+    Timestamp(Timestamp),
+    /// YAML 1.1 binary data, loaded from scalars tagged `!!binary`.
+    Binary(Vec<u8>),
     /// YAML array, can be accessed as a `Vec`.
     Array(AnnotatedArray<Node>),
     /// YAML hash, can be accessed as a `LinkedHashMap`.
@@ -77,11 +87,14 @@ pub type AnnotatedHash<Node> = LinkedHashMap<Node, Node>;
 
 impl<Node> YamlData<Node>
 where
-    Node: std::hash::Hash + std::cmp::Eq + From<Self>,
+    Node: core::hash::Hash + core::cmp::Eq + From<Self>,
 {
     define_as!(as_bool, bool, Boolean);
     define_as!(as_i64, i64, Integer);
+    define_as!(as_timestamp, Timestamp, Timestamp);
 
+    define_as_ref!(as_float, &Float, Float);
+    define_as_ref!(as_binary, &[u8], Binary);
     define_as_ref!(as_hash, &AnnotatedHash<Node>, Hash);
     define_as_ref!(as_str, &str, String);
     define_as_ref!(as_vec, &AnnotatedArray<Node>, Array);
@@ -103,25 +116,26 @@ where
     define_is!(is_integer, Self::Integer(_));
     define_is!(is_null, Self::Null);
     define_is!(is_real, Self::Real(_));
+    define_is!(is_float, Self::Float(_));
     define_is!(is_string, Self::String(_));
 
     /// Return the `f64` value contained in this YAML node.
     ///
     /// If the node is not a [`YamlData::Real`] YAML node or its contents is not a valid `f64`
-    /// string, `None` is returned.
+    /// string, `None` is returned. The value of a `YamlData::Float` is returned as well.
     #[must_use]
     pub fn as_f64(&self) -> Option<f64> {
-        if let Self::Real(ref v) = self {
-            parse_f64(v)
-        } else {
-            None
+        match self {
+            Self::Real(v) => parse_f64(v),
+            Self::Float(v) => Some(v.value),
+            _ => None,
         }
     }
 
     /// Return the `f64` value contained in this YAML node.
     ///
     /// If the node is not a [`YamlData::Real`] YAML node or its contents is not a valid `f64`
-    /// string, `None` is returned.
+    /// string, `None` is returned. The value of a `YamlData::Float` is returned as well.
     #[must_use]
     pub fn into_f64(self) -> Option<f64> {
         self.as_f64()
@@ -158,7 +172,7 @@ where
 
 impl<'a, Node> Index<&'a str> for YamlData<Node>
 where
-    Node: std::hash::Hash + std::cmp::Eq + From<Self>,
+    Node: core::hash::Hash + core::cmp::Eq + From<Self>,
 {
     type Output = Node;
 
@@ -179,7 +193,7 @@ where
 
 impl<'a, Node> IndexMut<&'a str> for YamlData<Node>
 where
-    Node: std::hash::Hash + std::cmp::Eq + From<Self>,
+    Node: core::hash::Hash + core::cmp::Eq + From<Self>,
 {
     /// Perform indexing if `self` is a mapping.
     ///
@@ -198,7 +212,7 @@ where
 
 impl<Node> Index<usize> for YamlData<Node>
 where
-    Node: std::hash::Hash + std::cmp::Eq + From<Self>,
+    Node: core::hash::Hash + core::cmp::Eq + From<Self>,
 {
     type Output = Node;
 
@@ -225,7 +239,7 @@ where
 
 impl<Node> IndexMut<usize> for YamlData<Node>
 where
-    Node: std::hash::Hash + std::cmp::Eq + From<Self>,
+    Node: core::hash::Hash + core::cmp::Eq + From<Self>,
 {
     /// Perform indexing if `self` is a sequence or a mapping.
     ///
@@ -250,7 +264,7 @@ where
 
 impl<Node> IntoIterator for YamlData<Node>
 where
-    Node: std::hash::Hash + std::cmp::Eq + From<Self>,
+    Node: core::hash::Hash + core::cmp::Eq + From<Self>,
 {
     type Item = Node;
     type IntoIter = AnnotatedYamlIter<Node>;
@@ -266,14 +280,14 @@ where
 #[allow(clippy::module_name_repetitions)]
 pub struct AnnotatedYamlIter<Node>
 where
-    Node: std::hash::Hash + std::cmp::Eq + From<YamlData<Node>>,
+    Node: core::hash::Hash + core::cmp::Eq + From<YamlData<Node>>,
 {
-    yaml: std::vec::IntoIter<Node>,
+    yaml: alloc::vec::IntoIter<Node>,
 }
 
 impl<Node> Iterator for AnnotatedYamlIter<Node>
 where
-    Node: std::hash::Hash + std::cmp::Eq + From<YamlData<Node>>,
+    Node: core::hash::Hash + core::cmp::Eq + From<YamlData<Node>>,
 {
     type Item = Node;
 
@@ -0,0 +1,386 @@
+//! A YAML node with position in the source document and the comments surrounding it.
+//!
+//! This is set aside so as to not clutter `annotated.rs`.
+
+use alloc::{borrow::ToOwned, collections::BTreeMap, string::String, vec::Vec};
+
+use hashlink::LinkedHashMap;
+use saphyr_parser::{BufferedInput, Parser, ScanError, Span};
+
+use crate::{LoadableYamlNode, PathSegment, Yaml, YamlData, YamlLoader, YamlPath};
+
+/// The comments attached to a [`CommentedYaml`] node.
+///
+/// Comments are stored without their leading `#`, but with the whitespace that follows it, so
+/// that `# foo` and `#foo` are written back as they were read.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Comments {
+    /// Comments on their own lines, right before the node.
+    pub leading: Vec<String>,
+    /// The comment at the end of the line introducing the node.
+    ///
+    /// For scalars, this is the comment following the scalar. For collections that are mapping
+    /// values, this is the comment following the `:` of their key.
+    pub trailing: Option<String>,
+}
+
+impl Comments {
+    /// Return whether there is no comment.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.leading.is_empty() && self.trailing.is_none()
+    }
+}
+
+/// A YAML node with [`Marker`]s pointing to the start of the node and the [`Comments`] around it.
+///
+/// This structure is meant for tools which edit YAML files maintained by humans. Comments are
+/// recovered with [`Self::load_from_str`] and written back with [`YamlEmitter::dump_commented`].
+/// The guarantees are restricted to the most common cases:
+///   * Own-line comments before a mapping key or a sequence item are written back before it.
+///   * End-of-line comments after a scalar value are written back after it.
+///
+/// Other comments (e.g.: within flow collections, at the end of the stream) may be lost.
+///
+/// ```
+/// # use saphyr::{CommentedYaml, YamlEmitter};
+/// let source = "# The version.\nversion: 1 # Bump me.";
+/// let mut docs = CommentedYaml::load_from_str(source).unwrap();
+/// docs[0].data["version"].data = saphyr::YamlData::Integer(2);
+///
+/// let mut output = String::new();
+/// YamlEmitter::new(&mut output).dump_commented(&docs[0]).unwrap();
+/// assert_eq!(output, "# The version.\n---\nversion: 2 # Bump me.");
+/// ```
+///
+/// [`Marker`]: crate::Marker
+/// [`YamlEmitter::dump_commented`]: crate::YamlEmitter::dump_commented
+#[derive(Clone, Debug)]
+pub struct CommentedYaml {
+    /// The input position of the node.
+    ///
+    /// See [`MarkedYaml::span`] for details.
+    ///
+    /// [`MarkedYaml::span`]: crate::MarkedYaml::span
+    pub span: Span,
+    /// The comments attached to the node.
+    pub comments: Comments,
+    /// The YAML contents of the node.
+    pub data: YamlData<CommentedYaml>,
+}
+
+impl CommentedYaml {
+    /// Load the given string as an array of YAML documents, along with their comments.
+    ///
+    /// Comments are attached to nodes as follows:
+    ///   * An own-line comment is a leading comment of the outermost node that starts first after
+    ///     it.
+    ///   * An end-of-line comment after a scalar is a trailing comment of that scalar.
+    ///   * An end-of-line comment after the `:` of a key is a trailing comment of its value.
+    ///
+    /// See the function [`load_from_str`] for more details.
+    ///
+    /// # Errors
+    /// Returns `ScanError` when loading fails.
+    ///
+    /// [`load_from_str`]: `Yaml::load_from_str`
+    pub fn load_from_str(source: &str) -> Result<Vec<Self>, ScanError> {
+        let mut loader = YamlLoader::<Self>::default();
+        let mut parser = Parser::new(BufferedInput::new(source.chars()));
+        parser.load(&mut loader, true)?;
+        loader.take_scan_error()?;
+        let mut documents = loader.into_documents();
+        attach_comments(source, &mut documents);
+        Ok(documents)
+    }
+
+    /// Collect the comments of `self` and its children, indexed by their path from `self`.
+    pub(crate) fn collect_comments(
+        &self,
+        path: &mut YamlPath,
+        comments: &mut BTreeMap<YamlPath, Comments>,
+    ) {
+        if !self.comments.is_empty() {
+            comments.insert(path.clone(), self.comments.clone());
+        }
+        match &self.data {
+            YamlData::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    path.push(PathSegment::Index(i));
+                    item.collect_comments(path, comments);
+                    path.pop();
+                }
+            }
+            YamlData::Hash(mapping) => {
+                for (i, (key, value)) in mapping.iter().enumerate() {
+                    path.push(PathSegment::EntryKey(i));
+                    key.collect_comments(path, comments);
+                    path.pop();
+                    path.push(PathSegment::Key(key.clone().into()));
+                    value.collect_comments(path, comments);
+                    path.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl PartialEq for CommentedYaml {
+    fn eq(&self, other: &Self) -> bool {
+        self.data.eq(&other.data)
+    }
+}
+
+// See `MarkedYaml`.
+impl Eq for CommentedYaml {}
+
+impl core::hash::Hash for CommentedYaml {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.data.hash(state);
+    }
+}
+
+impl From<YamlData<CommentedYaml>> for CommentedYaml {
+    fn from(value: YamlData<CommentedYaml>) -> Self {
+        Self {
+            span: Span::default(),
+            comments: Comments::default(),
+            data: value,
+        }
+    }
+}
+
+impl From<CommentedYaml> for Yaml {
+    /// Convert a [`CommentedYaml`] to a [`Yaml`], discarding all markers and comments.
+    fn from(value: CommentedYaml) -> Self {
+        match value.data {
+            YamlData::Real(x) => Yaml::Real(x),
+            YamlData::Integer(x) => Yaml::Integer(x),
+            YamlData::String(x) => Yaml::String(x),
+            YamlData::Boolean(x) => Yaml::Boolean(x),
+            YamlData::Float(x) => Yaml::Float(x),
+            YamlData::Timestamp(x) => Yaml::Timestamp(x),
+            YamlData::Binary(x) => Yaml::Binary(x),
+            YamlData::Array(x) => Yaml::Array(x.into_iter().map(Yaml::from).collect()),
+            YamlData::Hash(x) => Yaml::Hash(
+                x.into_iter()
+                    .map(|(k, v)| (Yaml::from(k), Yaml::from(v)))
+                    .collect(),
+            ),
+            YamlData::Alias(x) => Yaml::Alias(x),
+            YamlData::Null => Yaml::Null,
+            YamlData::BadValue => Yaml::BadValue,
+        }
+    }
+}
+
+impl LoadableYamlNode for CommentedYaml {
+    fn from_bare_yaml(yaml: Yaml) -> Self {
+        Self {
+            span: Span::default(),
+            comments: Comments::default(),
+            data: match yaml {
+                Yaml::Real(x) => YamlData::Real(x),
+                Yaml::Integer(x) => YamlData::Integer(x),
+                Yaml::String(x) => YamlData::String(x),
+                Yaml::SharedString(x) => YamlData::String(String::from(&*x)),
+                Yaml::Boolean(x) => YamlData::Boolean(x),
+                Yaml::Float(x) => YamlData::Float(x),
+                Yaml::Timestamp(x) => YamlData::Timestamp(x),
+                Yaml::Binary(x) => YamlData::Binary(x),
+                // Array and Hash will always have their container empty.
+                Yaml::Array(_) => YamlData::Array(vec![]),
+                Yaml::Hash(_) => YamlData::Hash(LinkedHashMap::new()),
+                Yaml::Alias(x) => YamlData::Alias(x),
+                Yaml::Null => YamlData::Null,
+                Yaml::BadValue => YamlData::BadValue,
+                // Tags are not kept.
+                Yaml::Tagged(_, node) => Self::from_bare_yaml(*node).data,
+            },
+        }
+    }
+
+    fn is_array(&self) -> bool {
+        self.data.is_array()
+    }
+
+    fn is_hash(&self) -> bool {
+        self.data.is_hash()
+    }
+
+    fn is_badvalue(&self) -> bool {
+        self.data.is_badvalue()
+    }
+
+    fn array_mut(&mut self) -> &mut Vec<Self> {
+        if let YamlData::Array(x) = &mut self.data {
+            x
+        } else {
+            panic!("Called array_mut on a non-array");
+        }
+    }
+
+    fn hash_mut(&mut self) -> &mut LinkedHashMap<Self, Self> {
+        if let YamlData::Hash(x) = &mut self.data {
+            x
+        } else {
+            panic!("Called hash_mut on a non-hash");
+        }
+    }
+
+    fn take(&mut self) -> Self {
+        core::mem::replace(self, YamlData::BadValue.into())
+    }
+
+    fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    fn to_bare_yaml(&self) -> Yaml {
+        self.clone().into()
+    }
+}
+
+/// Attach the comments of `source` to the nodes loaded from it.
+fn attach_comments(source: &str, documents: &mut [CommentedYaml]) {
+    let lines: Vec<&str> = source
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .collect();
+
+    // Index nodes in pre-order, which is the order in which they are attached comments.
+    let mut nodes = vec![];
+    for document in documents.iter() {
+        index_nodes(document, &mut nodes);
+    }
+
+    // Lines within multi-line scalars (e.g. block scalars) may look like comments but are not.
+    let mut in_scalar = vec![false; lines.len() + 1];
+    for node in nodes.iter().filter(|node| node.is_scalar) {
+        let (start, end) = (node.span.start, node.span.end);
+        let last = if end.col() == 0 {
+            end.line().saturating_sub(1)
+        } else {
+            end.line()
+        };
+        let (first, last) = (start.line() + 1, last.min(lines.len()));
+        if first <= last {
+            in_scalar[first..=last].fill(true);
+        }
+    }
+
+    let mut attached: Vec<Comments> = vec![Comments::default(); nodes.len()];
+
+    // Own-line comments go to the first node starting after them. Nodes copied from an anchor
+    // keep the markers of the anchor, so they may be out of order.
+    let mut by_position: Vec<usize> = (0..nodes.len()).collect();
+    by_position.sort_by_key(|&i| (nodes[i].span.start.index(), i));
+    for (i, line) in lines.iter().enumerate() {
+        let line_number = i + 1;
+        let Some(comment) = line.trim_start().strip_prefix('#') else {
+            continue;
+        };
+        if in_scalar[line_number] {
+            continue;
+        }
+        let next = by_position.partition_point(|&n| nodes[n].span.start.line() <= line_number);
+        if let Some(&n) = by_position.get(next) {
+            attached[n].leading.push(comment.trim_end().to_owned());
+        }
+    }
+
+    // End-of-line comments go to the scalar they follow, or to the value of the key they follow.
+    for (n, node) in nodes.iter().enumerate() {
+        if !node.is_scalar || node.span.end.line() == 0 {
+            continue;
+        }
+        let Some(line) = lines.get(node.span.end.line() - 1) else {
+            continue;
+        };
+        let (before, after) = split_at_char(line, node.span.end.col());
+        if before.trim().is_empty() {
+            continue;
+        }
+        if let Some(comment) = after.trim_start().strip_prefix('#') {
+            attached[n].trailing = Some(comment.trim_end().to_owned());
+        } else if let (Some(value), Some(comment)) = (
+            node.value,
+            after
+                .trim_start()
+                .strip_prefix(':')
+                .and_then(|after| after.trim_start().strip_prefix('#')),
+        ) {
+            attached[value]
+                .trailing
+                .get_or_insert_with(|| comment.trim_end().to_owned());
+        }
+    }
+
+    let mut attached = attached.into_iter();
+    for document in documents {
+        assign_comments(document, &mut attached);
+    }
+}
+
+/// What [`attach_comments`] needs to know about a node.
+struct IndexedNode {
+    span: Span,
+    is_scalar: bool,
+    /// If the node is a mapping key, the index of its value.
+    value: Option<usize>,
+}
+
+/// Append `node` and its children to `nodes`, in pre-order.
+fn index_nodes(node: &CommentedYaml, nodes: &mut Vec<IndexedNode>) {
+    nodes.push(IndexedNode {
+        span: node.span,
+        is_scalar: !matches!(node.data, YamlData::Array(_) | YamlData::Hash(_)),
+        value: None,
+    });
+    match &node.data {
+        YamlData::Array(items) => {
+            for item in items {
+                index_nodes(item, nodes);
+            }
+        }
+        YamlData::Hash(mapping) => {
+            for (key, value) in mapping {
+                let key_index = nodes.len();
+                index_nodes(key, nodes);
+                nodes[key_index].value = Some(nodes.len());
+                index_nodes(value, nodes);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Set the comments of `node` and its children from `comments`, in pre-order.
+fn assign_comments(node: &mut CommentedYaml, comments: &mut impl Iterator<Item = Comments>) {
+    node.comments = comments.next().unwrap_or_default();
+    match &mut node.data {
+        YamlData::Array(items) => {
+            for item in items {
+                assign_comments(item, comments);
+            }
+        }
+        YamlData::Hash(mapping) => {
+            // Keys cannot be mutated in place. Their hash does not depend on their comments.
+            let entries = core::mem::replace(mapping, LinkedHashMap::new());
+            for (mut key, mut value) in entries {
+                assign_comments(&mut key, comments);
+                assign_comments(&mut value, comments);
+                mapping.insert(key, value);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Split `line` at the given character index.
+fn split_at_char(line: &str, col: usize) -> (&str, &str) {
+    let byte = line.char_indices().nth(col).map_or(line.len(), |(i, _)| i);
+    line.split_at(byte)
+}
@@ -2,10 +2,15 @@
 //!
 //! This is set aside so as to not clutter `annotated.rs`.
 
+use alloc::{string::String, vec::Vec};
+
 use hashlink::LinkedHashMap;
 use saphyr_parser::{BufferedInput, Input, Parser, ScanError, Span};
 
-use crate::{LoadableYamlNode, Yaml, YamlData, YamlLoader};
+use crate::{
+    loader::{load_non_empty, load_single, load_with_options},
+    ByteRange, LoadError, LoadableYamlNode, LoaderOptions, Marker, Yaml, YamlData, YamlLoader,
+};
 
 /// A YAML node with [`Marker`]s pointing to the start of the node.
 ///
@@ -16,13 +21,35 @@ pub struct MarkedYaml {
     /// The input position of the node.
     ///
     /// The span is relative to the start of the input stream that was given to the parser, not
-    /// to the start of the document within the input stream.
+    /// to the start of the document within the input stream. It covers the whole node: the quotes
+    /// of quoted scalars, the delimiters of flow collections, and every entry of block
+    /// collections. Block scalars end after their last line break.
     pub span: Span,
     /// The YAML contents of the node.
     pub data: YamlData<MarkedYaml>,
 }
 
 impl MarkedYaml {
+    /// Return the range of bytes of `source` covered by [`Self::span`].
+    ///
+    /// Markers count characters rather than bytes. `source` must be the input the node was
+    /// loaded from.
+    ///
+    /// ```
+    /// # use saphyr::MarkedYaml;
+    /// let source = "é: [1, 'two']";
+    /// let doc = MarkedYaml::load_one_from_str(source).unwrap();
+    /// let (_, value) = doc.data.as_hash().unwrap().front().unwrap();
+    /// assert_eq!(&source[value.byte_range(source)], "[1, 'two']");
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if the span lies beyond the end of `source`.
+    #[must_use]
+    pub fn byte_range(&self, source: &str) -> ByteRange {
+        byte_index(source, self.span.start)..byte_index(source, self.span.end)
+    }
+
     /// Load the given string as an array of YAML documents.
     ///
     /// See the function [`load_from_str`] for more details.
@@ -59,8 +86,77 @@ impl MarkedYaml {
     pub fn load_from_parser<I: Input>(parser: &mut Parser<I>) -> Result<Vec<Self>, ScanError> {
         let mut loader = YamlLoader::<Self>::default();
         parser.load(&mut loader, true)?;
+        loader.take_scan_error()?;
         Ok(loader.into_documents())
     }
+
+    /// Load the given string as a single YAML document.
+    ///
+    /// See the function [`load_one_from_str`] for more details.
+    ///
+    /// # Errors
+    /// Returns `LoadError::Scan` when loading fails and `LoadError::ExpectedSingleDocument` if
+    /// the stream does not contain exactly one document.
+    ///
+    /// [`load_one_from_str`]: `Yaml::load_one_from_str`
+    pub fn load_one_from_str(source: &str) -> Result<Self, LoadError> {
+        load_single(&mut Parser::new(BufferedInput::new(source.chars())))
+    }
+
+    /// Load the given string as an array of YAML documents, failing if there are none.
+    ///
+    /// See the function [`load_from_str_non_empty`] for more details.
+    ///
+    /// # Errors
+    /// Returns `LoadError::Scan` when loading fails and `LoadError::EmptyStream` if the stream
+    /// contains no document.
+    ///
+    /// [`load_from_str_non_empty`]: `Yaml::load_from_str_non_empty`
+    pub fn load_from_str_non_empty(source: &str) -> Result<Vec<Self>, LoadError> {
+        load_non_empty(&mut Parser::new(BufferedInput::new(source.chars())))
+    }
+
+    /// Load the given string as an array of YAML documents.
+    ///
+    /// See the function [`try_load_from_str`] for more details.
+    ///
+    /// # Errors
+    /// Returns `LoadError::Scan` when loading fails, or an error reported by the loader (see
+    /// [`YamlLoader::error`]).
+    ///
+    /// [`try_load_from_str`]: `Yaml::try_load_from_str`
+    pub fn try_load_from_str(source: &str) -> Result<Vec<Self>, LoadError> {
+        Self::load_from_str_with_options(source, LoaderOptions::default())
+    }
+
+    /// Load the given string as an array of YAML documents, with the given options.
+    ///
+    /// See the function [`load_from_str_with_options`] for more details.
+    ///
+    /// # Errors
+    /// Returns `LoadError::Scan` when loading fails, or an error reported by the loader (see
+    /// [`YamlLoader::error`]).
+    ///
+    /// [`load_from_str_with_options`]: `Yaml::load_from_str_with_options`
+    pub fn load_from_str_with_options(
+        source: &str,
+        options: LoaderOptions,
+    ) -> Result<Vec<Self>, LoadError> {
+        load_with_options(source, options).map(YamlLoader::into_documents)
+    }
+}
+
+/// Return the byte offset of `marker` within `source`.
+///
+/// # Panics
+/// Panics if `marker` lies beyond the end of `source`.
+pub(crate) fn byte_index(source: &str, marker: Marker) -> usize {
+    source
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(core::iter::once(source.len()))
+        .nth(marker.index())
+        .expect("the span lies beyond the end of the source")
 }
 
 impl PartialEq for MarkedYaml {
@@ -72,8 +168,8 @@ impl PartialEq for MarkedYaml {
 // I don't know if it's okay to implement that, but we need it for the hashmap.
 impl Eq for MarkedYaml {}
 
-impl std::hash::Hash for MarkedYaml {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+impl core::hash::Hash for MarkedYaml {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
         self.data.hash(state);
     }
 }
@@ -87,6 +183,30 @@ impl From<YamlData<MarkedYaml>> for MarkedYaml {
     }
 }
 
+impl From<MarkedYaml> for Yaml {
+    /// Convert a [`MarkedYaml`] to a [`Yaml`], discarding all markers.
+    fn from(value: MarkedYaml) -> Self {
+        match value.data {
+            YamlData::Real(x) => Yaml::Real(x),
+            YamlData::Integer(x) => Yaml::Integer(x),
+            YamlData::String(x) => Yaml::String(x),
+            YamlData::Boolean(x) => Yaml::Boolean(x),
+            YamlData::Float(x) => Yaml::Float(x),
+            YamlData::Timestamp(x) => Yaml::Timestamp(x),
+            YamlData::Binary(x) => Yaml::Binary(x),
+            YamlData::Array(x) => Yaml::Array(x.into_iter().map(Yaml::from).collect()),
+            YamlData::Hash(x) => Yaml::Hash(
+                x.into_iter()
+                    .map(|(k, v)| (Yaml::from(k), Yaml::from(v)))
+                    .collect(),
+            ),
+            YamlData::Alias(x) => Yaml::Alias(x),
+            YamlData::Null => Yaml::Null,
+            YamlData::BadValue => Yaml::BadValue,
+        }
+    }
+}
+
 impl LoadableYamlNode for MarkedYaml {
     fn from_bare_yaml(yaml: Yaml) -> Self {
         Self {
@@ -95,13 +215,19 @@ impl LoadableYamlNode for MarkedYaml {
                 Yaml::Real(x) => YamlData::Real(x),
                 Yaml::Integer(x) => YamlData::Integer(x),
                 Yaml::String(x) => YamlData::String(x),
+                Yaml::SharedString(x) => YamlData::String(String::from(&*x)),
                 Yaml::Boolean(x) => YamlData::Boolean(x),
+                Yaml::Float(x) => YamlData::Float(x),
+                Yaml::Timestamp(x) => YamlData::Timestamp(x),
+                Yaml::Binary(x) => YamlData::Binary(x),
                 // Array and Hash will always have their container empty.
                 Yaml::Array(_) => YamlData::Array(vec![]),
                 Yaml::Hash(_) => YamlData::Hash(LinkedHashMap::new()),
                 Yaml::Alias(x) => YamlData::Alias(x),
                 Yaml::Null => YamlData::Null,
                 Yaml::BadValue => YamlData::BadValue,
+                // Tags are not kept.
+                Yaml::Tagged(_, node) => Self::from_bare_yaml(*node).data,
             },
         }
     }
@@ -139,7 +265,7 @@ impl LoadableYamlNode for MarkedYaml {
             span: Span::default(),
             data: YamlData::BadValue,
         };
-        std::mem::swap(&mut taken_out, self);
+        core::mem::swap(&mut taken_out, self);
         taken_out
     }
 
@@ -147,4 +273,8 @@ impl LoadableYamlNode for MarkedYaml {
         self.span = span;
         self
     }
+
+    fn to_bare_yaml(&self) -> Yaml {
+        self.clone().into()
+    }
 }
@@ -0,0 +1,364 @@
+//! YAML documents stored in an arena, with nodes referring to each other by index.
+
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use core::ops::Range;
+
+use saphyr_parser::{BufferedInput, Event, Parser, ScanError};
+
+use crate::loader::parse_f64;
+use crate::{Hash, LoaderOptions, Yaml, YamlKind};
+
+/// The index of a node within a [`YamlArena`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    /// Return the index of the node within [`YamlArena::nodes`], in the order the nodes were
+    /// completed: children come before their collection.
+    #[must_use]
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+/// YAML documents whose nodes are stored in a few flat vectors rather than each in its own
+/// allocations.
+///
+/// The nodes of all documents are stored one after the other, and refer to their children by
+/// [`NodeId`]. The children of all collections are stored in a single vector, and the text of
+/// all strings and floats in a single string. Loading a document thus allocates about as many
+/// times as the vectors grow, rather than at least once per string and per collection, and
+/// reading it touches memory that is mostly contiguous. This pays off for documents of hundreds
+/// of megabytes.
+///
+/// An alias refers to the node of its anchor rather than to a copy of it. [`ArenaNode::to_yaml`]
+/// and [`YamlArena::to_documents`] make copies, as loading into [`Yaml`] does.
+///
+/// Nodes cannot be built by a [`YamlLoader`](crate::YamlLoader), which loads nodes that own
+/// their children (see [`LoadableYamlNode`](crate::LoadableYamlNode)): scalars are resolved
+/// with the default [`LoaderOptions`], and tags are dropped.
+///
+/// ```
+/// use saphyr::{Yaml, YamlArena};
+///
+/// let source = "defaults: &defaults {retries: 3}\njobs:\n  - name: lint\n    retry: *defaults\n";
+/// let arena = YamlArena::load_from_str(source).unwrap();
+/// let doc = arena.document(0).unwrap();
+///
+/// let job = doc.get("jobs").and_then(|jobs| jobs.index(0)).unwrap();
+/// assert_eq!(job.get("name").and_then(|name| name.as_str()), Some("lint"));
+/// // The alias is the very node of the anchor.
+/// assert_eq!(job.get("retry").unwrap().id(), doc.get("defaults").unwrap().id());
+///
+/// assert_eq!(arena.to_documents(), Yaml::load_from_str(source).unwrap());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct YamlArena {
+    nodes: Vec<Node>,
+    /// The children of all collections. Those of each collection are contiguous, and those of a
+    /// mapping alternate keys and values.
+    children: Vec<NodeId>,
+    /// The text of all strings and floats.
+    text: String,
+    /// The root node of each document.
+    documents: Vec<NodeId>,
+}
+
+/// A node of a [`YamlArena`], whose contents are ranges within the vectors of the arena.
+#[derive(Clone, Debug)]
+enum Node {
+    /// A string, at a range of [`YamlArena::text`].
+    String(Range<usize>),
+    /// A float, as written, at a range of [`YamlArena::text`].
+    Real(Range<usize>),
+    /// Any other scalar.
+    Scalar(Yaml),
+    /// A sequence, whose items are at a range of [`YamlArena::children`].
+    Array(Range<usize>),
+    /// A mapping, whose keys and values are at a range of [`YamlArena::children`].
+    Hash(Range<usize>),
+}
+
+/// A node of a [`YamlArena`], along with the arena to find its children in.
+#[derive(Clone, Copy, Debug)]
+pub struct ArenaNode<'a> {
+    arena: &'a YamlArena,
+    id: NodeId,
+}
+
+impl YamlArena {
+    /// Load the given string as YAML documents in an arena.
+    ///
+    /// The documents are those of [`Yaml::load_from_str`], which [`Self::to_documents`] returns.
+    ///
+    /// # Errors
+    /// Returns `ScanError` when loading fails.
+    pub fn load_from_str(source: &str) -> Result<Self, ScanError> {
+        let mut builder = ArenaBuilder::default();
+        for event in Parser::new(BufferedInput::new(source.chars())) {
+            let (event, _) = event?;
+            builder.on_event(event);
+        }
+        Ok(builder.arena)
+    }
+
+    /// Return the number of nodes in the arena.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Return whether the arena has no nodes, i.e. no documents.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Return the root node of the document at `index`, if there is one.
+    #[must_use]
+    pub fn document(&self, index: usize) -> Option<ArenaNode<'_>> {
+        self.documents.get(index).map(|&id| self.node(id))
+    }
+
+    /// Return the root nodes of the documents, in order.
+    #[must_use]
+    pub fn documents(&self) -> impl ExactSizeIterator<Item = ArenaNode<'_>> + '_ {
+        self.documents.iter().map(|&id| self.node(id))
+    }
+
+    /// Return the node `id`.
+    ///
+    /// # Panics
+    /// Panics if `id` is not the id of a node of this arena.
+    #[must_use]
+    pub fn node(&self, id: NodeId) -> ArenaNode<'_> {
+        assert!(id.0 < self.nodes.len(), "no node {} in the arena", id.0);
+        ArenaNode { arena: self, id }
+    }
+
+    /// Return the nodes of the arena, in the order they were completed.
+    #[must_use]
+    pub fn nodes(&self) -> impl ExactSizeIterator<Item = ArenaNode<'_>> + '_ {
+        (0..self.nodes.len()).map(|index| self.node(NodeId(index)))
+    }
+
+    /// Convert the documents into [`Yaml`]s.
+    #[must_use]
+    pub fn to_documents(&self) -> Vec<Yaml> {
+        self.documents().map(|doc| doc.to_yaml()).collect()
+    }
+}
+
+impl<'a> ArenaNode<'a> {
+    /// Return the id of the node within its arena.
+    #[must_use]
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    /// Return the variant of [`Yaml`] the node converts to.
+    #[must_use]
+    pub fn kind(&self) -> YamlKind {
+        match self.node() {
+            Node::String(_) => YamlKind::String,
+            Node::Real(_) => YamlKind::Real,
+            Node::Scalar(yaml) => yaml.kind(),
+            Node::Array(_) => YamlKind::Array,
+            Node::Hash(_) => YamlKind::Hash,
+        }
+    }
+
+    /// Return the string of the node, if it is a string.
+    #[must_use]
+    pub fn as_str(&self) -> Option<&'a str> {
+        match self.node() {
+            Node::String(range) => Some(&self.arena.text[range.clone()]),
+            _ => None,
+        }
+    }
+
+    /// Return the value of the node, if it is an integer.
+    #[must_use]
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.node() {
+            Node::Scalar(yaml) => yaml.as_i64(),
+            _ => None,
+        }
+    }
+
+    /// Return the value of the node, if it is a float, as with [`Yaml::as_f64`].
+    #[must_use]
+    pub fn as_f64(&self) -> Option<f64> {
+        match self.node() {
+            Node::Real(range) => parse_f64(&self.arena.text[range.clone()]),
+            Node::Scalar(yaml) => yaml.as_f64(),
+            _ => None,
+        }
+    }
+
+    /// Return the value of the node, if it is a boolean.
+    #[must_use]
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.node() {
+            Node::Scalar(yaml) => yaml.as_bool(),
+            _ => None,
+        }
+    }
+
+    /// Return whether the node is null.
+    #[must_use]
+    pub fn is_null(&self) -> bool {
+        matches!(self.node(), Node::Scalar(Yaml::Null))
+    }
+
+    /// Return the items of the node if it is a sequence, or its keys and values, alternately,
+    /// if it is a mapping. Scalars have no children.
+    #[must_use]
+    pub fn children(&self) -> impl ExactSizeIterator<Item = ArenaNode<'a>> + 'a {
+        let arena = self.arena;
+        let range = match self.node() {
+            Node::Array(range) | Node::Hash(range) => range.clone(),
+            _ => 0..0,
+        };
+        arena.children[range]
+            .iter()
+            .map(move |&id| ArenaNode { arena, id })
+    }
+
+    /// Return the item at `index`, if the node is a sequence with that many items.
+    #[must_use]
+    pub fn index(&self, index: usize) -> Option<ArenaNode<'a>> {
+        match self.node() {
+            Node::Array(_) => self.children().nth(index),
+            _ => None,
+        }
+    }
+
+    /// Return the entries of the node, if it is a mapping, in the order they were written.
+    ///
+    /// Unlike the entries of a [`Yaml::Hash`], those of duplicate keys are all returned.
+    pub fn entries(&self) -> impl Iterator<Item = (ArenaNode<'a>, ArenaNode<'a>)> + 'a {
+        let mut children = match self.node() {
+            Node::Hash(_) => Some(self.children()),
+            _ => None,
+        }
+        .into_iter()
+        .flatten();
+        core::iter::from_fn(move || Some((children.next()?, children.next()?)))
+    }
+
+    /// Return the value of the string key `key`, if the node is a mapping which has it.
+    ///
+    /// If the key is duplicated, this is its last value, as in the mapping loaded into [`Yaml`].
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<ArenaNode<'a>> {
+        let mut value = None;
+        for (k, v) in self.entries() {
+            if k.as_str() == Some(key) {
+                value = Some(v);
+            }
+        }
+        value
+    }
+
+    /// Convert the node and its children into a [`Yaml`].
+    #[must_use]
+    pub fn to_yaml(&self) -> Yaml {
+        match self.node() {
+            Node::String(range) => Yaml::String(self.arena.text[range.clone()].into()),
+            Node::Real(range) => Yaml::Real(self.arena.text[range.clone()].into()),
+            Node::Scalar(yaml) => yaml.clone(),
+            Node::Array(_) => Yaml::Array(self.children().map(|item| item.to_yaml()).collect()),
+            Node::Hash(_) => {
+                let mut hash = Hash::new();
+                for (key, value) in self.entries() {
+                    hash.insert(key.to_yaml(), value.to_yaml());
+                }
+                Yaml::Hash(hash)
+            }
+        }
+    }
+
+    fn node(&self) -> &'a Node {
+        &self.arena.nodes[self.id.0]
+    }
+}
+
+/// Build a [`YamlArena`] from the events of the parser.
+#[derive(Default)]
+struct ArenaBuilder {
+    arena: YamlArena,
+    /// The open collections: whether they are mappings, their anchor, and where their children
+    /// start in `pending`.
+    stack: Vec<(bool, usize, usize)>,
+    /// The children of the open collections, innermost last.
+    pending: Vec<NodeId>,
+    anchors: BTreeMap<usize, NodeId>,
+    options: LoaderOptions,
+}
+
+impl ArenaBuilder {
+    fn on_event(&mut self, event: Event) {
+        match event {
+            Event::SequenceStart(aid, _) => self.stack.push((false, aid, self.pending.len())),
+            Event::MappingStart(aid, _) => self.stack.push((true, aid, self.pending.len())),
+            Event::SequenceEnd | Event::MappingEnd => {
+                if let Some((is_hash, aid, start)) = self.stack.pop() {
+                    let children = &mut self.arena.children;
+                    let first = children.len();
+                    children.extend(self.pending.drain(start..));
+                    let range = first..children.len();
+                    let node = if is_hash {
+                        Node::Hash(range)
+                    } else {
+                        Node::Array(range)
+                    };
+                    self.insert(node, aid);
+                }
+            }
+            Event::Scalar(v, style, aid, tag) => {
+                let node = match self.options.resolve_scalar(v, style, tag.as_ref()) {
+                    Yaml::String(v) => Node::String(self.push_text(&v)),
+                    Yaml::Real(v) => Node::Real(self.push_text(&v)),
+                    yaml => Node::Scalar(yaml),
+                };
+                self.insert(node, aid);
+            }
+            Event::Alias(id) => match self.anchors.get(&id) {
+                Some(&id) => self.insert_id(id),
+                None => self.insert(Node::Scalar(Yaml::BadValue), 0),
+            },
+            Event::Nothing
+            | Event::StreamStart
+            | Event::StreamEnd
+            | Event::DocumentStart(_)
+            | Event::DocumentEnd => {}
+        }
+    }
+
+    /// Add a complete node to the arena, and insert it in its parent.
+    fn insert(&mut self, node: Node, aid: usize) {
+        let id = NodeId(self.arena.nodes.len());
+        self.arena.nodes.push(node);
+        // Valid anchor ids start from 1.
+        if aid > 0 {
+            self.anchors.insert(aid, id);
+        }
+        self.insert_id(id);
+    }
+
+    /// Insert the node `id` in its parent, or as the root of a document.
+    fn insert_id(&mut self, id: NodeId) {
+        if self.stack.is_empty() {
+            self.arena.documents.push(id);
+        } else {
+            self.pending.push(id);
+        }
+    }
+
+    fn push_text(&mut self, text: &str) -> Range<usize> {
+        let start = self.arena.text.len();
+        self.arena.text.push_str(text);
+        start..self.arena.text.len()
+    }
+}
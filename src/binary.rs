@@ -0,0 +1,70 @@
+//! YAML 1.1 binary scalars, encoded in base64.
+//!
+//! Binary nodes are always emitted, but only decoded by the loader with the `binary` feature.
+
+use alloc::string::String;
+#[cfg(feature = "binary")]
+use alloc::vec::Vec;
+
+/// The characters of the base64 alphabet, by value.
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Return the value of the base64 character `c`, if it is one.
+#[cfg(feature = "binary")]
+fn value(c: u8) -> Option<u8> {
+    match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+/// Decode the contents of a [`!!binary`](https://yaml.org/type/binary.html) scalar.
+///
+/// Whitespace is ignored, so that the text may be split on several lines. Padding is optional,
+/// but if it is present, the text must be a whole number of groups of 4 characters.
+#[cfg(feature = "binary")]
+pub(crate) fn decode(text: &str) -> Option<Vec<u8>> {
+    let text: Vec<u8> = text.bytes().filter(|c| !c.is_ascii_whitespace()).collect();
+    let digits = match text.strip_suffix(b"==").or_else(|| text.strip_suffix(b"=")) {
+        Some(_) if text.len() % 4 != 0 => return None,
+        Some(digits) => digits,
+        None => &text,
+    };
+    if digits.len() % 4 == 1 {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(digits.len() / 4 * 3 + 2);
+    for group in digits.chunks(4) {
+        let mut bits = 0u32;
+        for &c in group {
+            bits = (bits << 6) | u32::from(value(c)?);
+        }
+        // Left-align the bits of incomplete groups, as if they were padded with zeros.
+        bits <<= 6 * (4 - group.len());
+        let [_, a, b, c] = bits.to_be_bytes();
+        bytes.extend_from_slice(&[a, b, c][..group.len() - 1]);
+    }
+    Some(bytes)
+}
+
+/// Encode `bytes` in base64, with padding.
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for group in bytes.chunks(3) {
+        let mut bits = [0; 4];
+        bits[1..=group.len()].copy_from_slice(group);
+        let bits = u32::from_be_bytes(bits);
+        for i in 0..4 {
+            if i <= group.len() {
+                text.push(char::from(ALPHABET[(bits >> (18 - 6 * i)) as usize & 63]));
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
@@ -0,0 +1,358 @@
+//! YAML nodes borrowing their strings from the source they were loaded from.
+
+use alloc::{borrow::Cow, collections::BTreeMap, string::String, vec::Vec};
+use core::ops::Index;
+
+use hashlink::LinkedHashMap;
+use saphyr_parser::{BufferedInput, Event, Parser, ScanError, Span, TScalarStyle};
+
+use crate::loader::parse_f64;
+use crate::Float;
+use crate::Timestamp;
+use crate::{LoaderOptions, Yaml};
+
+/// A YAML node whose strings borrow from the source it was loaded from, where they can.
+///
+/// This is the same as [`Yaml`], except that strings and floats are [`Cow`]s: a scalar written
+/// as it is loaded, without escapes nor line folding (e.g. `name` or `'name'`, but not `"a\tb"`
+/// or block scalars), refers to the source rather than to a copy of it. For large documents,
+/// this saves most of the memory held by the loaded strings (see [`Self::load_from_str`]).
+///
+/// ```
+/// use std::borrow::Cow;
+/// use saphyr::BorrowedYaml;
+///
+/// let source = "name: web\nmotd: \"hello\\tworld\"";
+/// let docs = BorrowedYaml::load_from_str(source).unwrap();
+/// assert!(matches!(&docs[0]["name"], BorrowedYaml::String(Cow::Borrowed("web"))));
+/// assert!(matches!(&docs[0]["motd"], BorrowedYaml::String(Cow::Owned(_))));
+/// assert_eq!(docs[0]["motd"].as_str(), Some("hello\tworld"));
+/// ```
+#[derive(Clone, PartialEq, PartialOrd, Debug, Eq, Ord, Hash)]
+pub enum BorrowedYaml<'a> {
+    /// A float, stored as written and parsed on demand. See [`Yaml::Real`].
+    Real(Cow<'a, str>),
+    /// A float stored as an `f64`. See [`Yaml::Float`].
+    Float(Float),
+    /// YAML int is stored as i64.
+    Integer(i64),
+    /// YAML scalar.
+    String(Cow<'a, str>),
+    /// YAML bool, e.g. `true` or `false`.
+    Boolean(bool),
+    /// YAML 1.1 timestamp. See [`Yaml::Timestamp`].
+    Timestamp(Timestamp),
+    /// YAML 1.1 binary data. See [`Yaml::Binary`].
+    Binary(Vec<u8>),
+    /// YAML array.
+    Array(Vec<BorrowedYaml<'a>>),
+    /// YAML hash, in insertion order.
+    Hash(LinkedHashMap<BorrowedYaml<'a>, BorrowedYaml<'a>>),
+    /// Alias, not fully supported yet.
+    Alias(usize),
+    /// YAML null, e.g. `null` or `~`.
+    Null,
+    /// Accessing a nonexistent node via the Index trait returns `BadValue`.
+    BadValue,
+}
+
+static BAD_VALUE: BorrowedYaml<'static> = BorrowedYaml::BadValue;
+
+impl<'a> BorrowedYaml<'a> {
+    /// Load the given string as an array of YAML documents, borrowing strings from it.
+    ///
+    /// The documents are those of [`Yaml::load_from_str`], which [`Self::into_owned`] returns.
+    /// The parser still builds each scalar as a `String`, which is dropped as soon as the scalar
+    /// is found in `source` as it is: the documents only hold the strings that could not be
+    /// borrowed. Limits on the length of keys and tags (see [`LoaderOptions`]) are not enforced.
+    ///
+    /// # Errors
+    /// Returns `ScanError` when loading fails.
+    pub fn load_from_str(source: &'a str) -> Result<Vec<Self>, ScanError> {
+        let mut loader = BorrowedLoader::new(source);
+        for event in Parser::new(BufferedInput::new(source.chars())) {
+            let (event, span) = event?;
+            loader.on_event(event, span);
+        }
+        Ok(loader.docs)
+    }
+
+    /// Convert `self` into a [`Yaml`], copying the borrowed strings.
+    #[must_use]
+    pub fn into_owned(self) -> Yaml {
+        match self {
+            BorrowedYaml::Real(v) => Yaml::Real(v.into_owned()),
+            BorrowedYaml::Float(v) => Yaml::Float(v),
+            BorrowedYaml::Integer(v) => Yaml::Integer(v),
+            BorrowedYaml::String(v) => Yaml::String(v.into_owned()),
+            BorrowedYaml::Boolean(v) => Yaml::Boolean(v),
+            BorrowedYaml::Timestamp(v) => Yaml::Timestamp(v),
+            BorrowedYaml::Binary(v) => Yaml::Binary(v),
+            BorrowedYaml::Array(items) => {
+                Yaml::Array(items.into_iter().map(Self::into_owned).collect())
+            }
+            BorrowedYaml::Hash(entries) => Yaml::Hash(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key.into_owned(), value.into_owned()))
+                    .collect(),
+            ),
+            BorrowedYaml::Alias(id) => Yaml::Alias(id),
+            BorrowedYaml::Null => Yaml::Null,
+            BorrowedYaml::BadValue => Yaml::BadValue,
+        }
+    }
+
+    /// Return the string contained in this node, if it is a [`BorrowedYaml::String`].
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            BorrowedYaml::String(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Return the integer contained in this node, if it is a [`BorrowedYaml::Integer`].
+    #[must_use]
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            BorrowedYaml::Integer(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Return the `f64` value contained in this node, as with [`Yaml::as_f64`].
+    #[must_use]
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            BorrowedYaml::Real(v) => parse_f64(v),
+            BorrowedYaml::Float(v) => Some(v.value),
+            _ => None,
+        }
+    }
+
+    /// Return the boolean contained in this node, if it is a [`BorrowedYaml::Boolean`].
+    #[must_use]
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            BorrowedYaml::Boolean(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Return the items of this node, if it is a [`BorrowedYaml::Array`].
+    #[must_use]
+    pub fn as_vec(&self) -> Option<&Vec<BorrowedYaml<'a>>> {
+        match self {
+            BorrowedYaml::Array(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Return the entries of this node, if it is a [`BorrowedYaml::Hash`].
+    #[must_use]
+    pub fn as_hash(&self) -> Option<&LinkedHashMap<BorrowedYaml<'a>, BorrowedYaml<'a>>> {
+        match self {
+            BorrowedYaml::Hash(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Return whether this node is a [`BorrowedYaml::Null`].
+    #[must_use]
+    pub fn is_null(&self) -> bool {
+        matches!(self, BorrowedYaml::Null)
+    }
+
+    /// Return whether this node is a [`BorrowedYaml::BadValue`].
+    #[must_use]
+    pub fn is_badvalue(&self) -> bool {
+        matches!(self, BorrowedYaml::BadValue)
+    }
+}
+
+impl From<Yaml> for BorrowedYaml<'_> {
+    /// Convert a [`Yaml`] node, whose strings are then owned. Tags are dropped.
+    fn from(yaml: Yaml) -> Self {
+        match yaml {
+            Yaml::Real(v) => BorrowedYaml::Real(v.into()),
+            Yaml::Float(v) => BorrowedYaml::Float(v),
+            Yaml::Integer(v) => BorrowedYaml::Integer(v),
+            Yaml::String(v) => BorrowedYaml::String(v.into()),
+            Yaml::SharedString(v) => BorrowedYaml::String(String::from(&*v).into()),
+            Yaml::Boolean(v) => BorrowedYaml::Boolean(v),
+            Yaml::Timestamp(v) => BorrowedYaml::Timestamp(v),
+            Yaml::Binary(v) => BorrowedYaml::Binary(v),
+            Yaml::Array(items) => BorrowedYaml::Array(items.into_iter().map(Self::from).collect()),
+            Yaml::Hash(entries) => BorrowedYaml::Hash(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key.into(), value.into()))
+                    .collect(),
+            ),
+            Yaml::Tagged(_, node) => Self::from(*node),
+            Yaml::Alias(id) => BorrowedYaml::Alias(id),
+            Yaml::Null => BorrowedYaml::Null,
+            Yaml::BadValue => BorrowedYaml::BadValue,
+        }
+    }
+}
+
+impl<'a> Index<&str> for BorrowedYaml<'a> {
+    type Output = BorrowedYaml<'a>;
+
+    /// Return the value of the key which resolved to the string `idx`, as with [`Yaml`].
+    ///
+    /// Returns [`BorrowedYaml::BadValue`] if `self` is not a mapping or does not contain the key.
+    fn index(&self, idx: &str) -> &BorrowedYaml<'a> {
+        let Some(hash) = self.as_hash() else {
+            return &BAD_VALUE;
+        };
+        // Looking the key up with `get` would need it to live for `'a`, which `idx` does not.
+        hash.iter()
+            .find(|(key, _)| key.as_str() == Some(idx))
+            .map_or(&BAD_VALUE, |(_, value)| value)
+    }
+}
+
+impl<'a> Index<usize> for BorrowedYaml<'a> {
+    type Output = BorrowedYaml<'a>;
+
+    /// Return the item at `idx` of a sequence, or the value of the key `idx` of a mapping.
+    ///
+    /// Returns [`BorrowedYaml::BadValue`] if there is none.
+    fn index(&self, idx: usize) -> &BorrowedYaml<'a> {
+        if let Some(items) = self.as_vec() {
+            items.get(idx).unwrap_or(&BAD_VALUE)
+        } else if let Some(hash) = self.as_hash() {
+            let key = BorrowedYaml::Integer(i64::try_from(idx).unwrap());
+            hash.get(&key).unwrap_or(&BAD_VALUE)
+        } else {
+            &BAD_VALUE
+        }
+    }
+}
+
+/// Build [`BorrowedYaml`] documents from the events of the parser.
+///
+/// This mirrors what [`YamlLoader`](crate::YamlLoader) does with the default options, which its
+/// nodes cannot do: they are built without access to the source.
+struct BorrowedLoader<'a> {
+    source: &'a str,
+    /// A character index in `source` and its byte offset, from which to find the next ones.
+    cursor: (usize, usize),
+    docs: Vec<BorrowedYaml<'a>>,
+    /// The open collections, with their anchor and, for mappings, the key of the next value.
+    stack: Vec<(BorrowedYaml<'a>, usize, Option<BorrowedYaml<'a>>)>,
+    anchors: BTreeMap<usize, BorrowedYaml<'a>>,
+    options: LoaderOptions,
+}
+
+impl<'a> BorrowedLoader<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            cursor: (0, 0),
+            docs: vec![],
+            stack: vec![],
+            anchors: BTreeMap::new(),
+            options: LoaderOptions::default(),
+        }
+    }
+
+    fn on_event(&mut self, event: Event, span: Span) {
+        match event {
+            Event::SequenceStart(aid, _) => {
+                self.stack.push((BorrowedYaml::Array(vec![]), aid, None));
+            }
+            Event::MappingStart(aid, _) => {
+                self.stack
+                    .push((BorrowedYaml::Hash(LinkedHashMap::new()), aid, None));
+            }
+            Event::SequenceEnd | Event::MappingEnd => {
+                if let Some((node, aid, _)) = self.stack.pop() {
+                    self.insert(node, aid);
+                }
+            }
+            Event::Scalar(v, style, aid, tag) => {
+                let node = match self.options.resolve_scalar(v, style, tag.as_ref()) {
+                    Yaml::String(v) => BorrowedYaml::String(self.borrow(v, style, span)),
+                    Yaml::Real(v) => BorrowedYaml::Real(self.borrow(v, style, span)),
+                    yaml => yaml.into(),
+                };
+                self.insert(node, aid);
+            }
+            Event::Alias(id) => {
+                let node = self
+                    .anchors
+                    .get(&id)
+                    .cloned()
+                    .unwrap_or(BorrowedYaml::BadValue);
+                self.insert(node, 0);
+            }
+            Event::Nothing
+            | Event::StreamStart
+            | Event::StreamEnd
+            | Event::DocumentStart(_)
+            | Event::DocumentEnd => {}
+        }
+    }
+
+    /// Insert a complete node in its parent, or as the root of a document.
+    fn insert(&mut self, node: BorrowedYaml<'a>, aid: usize) {
+        // Valid anchor ids start from 1.
+        if aid > 0 {
+            self.anchors.insert(aid, node.clone());
+        }
+        match self.stack.last_mut() {
+            Some((BorrowedYaml::Array(items), ..)) => items.push(node),
+            Some((BorrowedYaml::Hash(entries), _, key)) => match key.take() {
+                Some(key) => {
+                    entries.insert(key, node);
+                }
+                None => *key = Some(node),
+            },
+            Some(_) => unreachable!("only collections are open"),
+            None => self.docs.push(node),
+        }
+    }
+
+    /// Return `value`, borrowed from the source if it is written there as it is.
+    fn borrow(&mut self, value: String, style: TScalarStyle, span: Span) -> Cow<'a, str> {
+        let start = self.byte_offset(span.start.index());
+        let end = self.byte_offset(span.end.index());
+        let written = &self.source[start..end.max(start)];
+        // The span of quoted scalars covers their quotes.
+        let written = match style {
+            TScalarStyle::SingleQuoted => written
+                .strip_prefix('\'')
+                .and_then(|w| w.strip_suffix('\'')),
+            TScalarStyle::DoubleQuoted => {
+                written.strip_prefix('"').and_then(|w| w.strip_suffix('"'))
+            }
+            TScalarStyle::Plain => Some(written),
+            _ => None,
+        };
+        match written {
+            Some(written) if written == value => Cow::Borrowed(written),
+            _ => Cow::Owned(value),
+        }
+    }
+
+    /// Return the byte offset of the character at `index` in the source, or its length.
+    ///
+    /// Scalars come in the order of the source, so this mostly moves forward from the last
+    /// offset returned.
+    fn byte_offset(&mut self, index: usize) -> usize {
+        if index < self.cursor.0 {
+            self.cursor = (0, 0);
+        }
+        let (chars, bytes) = self.cursor;
+        let offset = self.source[bytes..]
+            .char_indices()
+            .nth(index - chars)
+            .map_or(self.source.len(), |(offset, _)| bytes + offset);
+        self.cursor = (index, offset);
+        offset
+    }
+}
@@ -0,0 +1,54 @@
+//! Cooperative cancellation of loading and emission.
+
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// A flag to cancel a load or an emission from another thread.
+///
+/// Tokens are cheap to clone, and all clones share the same flag. See
+/// [`LoaderOptions::cancel`] and [`YamlEmitter::cancel_token`].
+///
+/// ```
+/// # use saphyr::{CancelToken, LoadError, LoaderOptions, Yaml};
+/// let token = CancelToken::new();
+/// let options = LoaderOptions {
+///     cancel: Some(token.clone()),
+///     ..LoaderOptions::default()
+/// };
+/// std::thread::spawn(move || token.cancel()).join().unwrap();
+/// let result = Yaml::load_from_str_with_options("a: 1", options);
+/// assert!(matches!(result, Err(LoadError::Cancelled)));
+/// ```
+///
+/// [`LoaderOptions::cancel`]: crate::LoaderOptions::cancel
+/// [`YamlEmitter::cancel_token`]: crate::YamlEmitter::cancel_token
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Create a new token, not cancelled.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request the cancellation of whatever uses this token or any of its clones.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Return whether [`Self::cancel`] was called on this token or any of its clones.
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl PartialEq for CancelToken {
+    /// Tokens are equal if they are clones of one another.
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for CancelToken {}
@@ -0,0 +1,110 @@
+//! Runtime introspection of the features of the library.
+
+use alloc::string::String;
+
+use crate::{Hash, LoaderOptions, Yaml, YamlEmitter};
+
+/// The optional behaviors compiled into the library and the defaults it uses.
+///
+/// This is meant for tools embedding saphyr which need to check compatibility at runtime (e.g.:
+/// plugins) or to report their configuration. New fields are added as the library grows.
+///
+/// ```
+/// let capabilities = saphyr::capabilities();
+/// assert_eq!(capabilities.encoding, cfg!(feature = "encoding"));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Capabilities {
+    /// The version of the library.
+    pub version: &'static str,
+    /// Whether the `std` feature (i.e. readers, writers and includes) is enabled.
+    pub std: bool,
+    /// Whether the `encoding` feature (i.e. [`YamlDecoder`]) is enabled.
+    ///
+    /// [`YamlDecoder`]: crate::YamlDecoder
+    pub encoding: bool,
+    /// Whether the `json` feature (i.e. conversions to and from `serde_json::Value`) is enabled.
+    pub json: bool,
+    /// Whether the `serde` feature (i.e. `saphyr::from_str` and `saphyr::to_string`) is enabled.
+    pub serde: bool,
+    /// Whether the `timestamps` feature (i.e. `Yaml::Timestamp`) is enabled.
+    pub timestamps: bool,
+    /// Whether the `binary` feature (i.e. `Yaml::Binary`) is enabled.
+    pub binary: bool,
+    /// Whether the `floats` feature (i.e. `Yaml::Float`) is enabled.
+    pub floats: bool,
+    /// Whether the `number` feature (i.e. `Yaml::as_number`) is enabled.
+    pub number: bool,
+    /// Whether the `parallel` feature (i.e. `Yaml::load_from_str_parallel`) is enabled.
+    pub parallel: bool,
+    /// Whether merge keys (`<<`) are resolved by default (see [`LoaderOptions::merge_keys`]).
+    pub merge_keys: bool,
+    /// The options used when loading documents, unless specified otherwise.
+    pub loader_defaults: LoaderOptions,
+}
+
+impl Capabilities {
+    /// Return a summary of the capabilities, e.g. for diagnostics or support bundles.
+    ///
+    /// The summary is a mapping from capability names (in `snake_case`) to their values. It
+    /// includes the default settings of the loader (see [`LoaderOptions::describe`]) and of the
+    /// emitter (see [`YamlEmitter::describe`]).
+    #[must_use]
+    pub fn describe(&self) -> Yaml {
+        let mut features = Hash::new();
+        features.insert(Yaml::String("std".into()), Yaml::Boolean(self.std));
+        features.insert(
+            Yaml::String("encoding".into()),
+            Yaml::Boolean(self.encoding),
+        );
+        features.insert(Yaml::String("json".into()), Yaml::Boolean(self.json));
+        features.insert(Yaml::String("serde".into()), Yaml::Boolean(self.serde));
+        features.insert(
+            Yaml::String("timestamps".into()),
+            Yaml::Boolean(self.timestamps),
+        );
+        features.insert(Yaml::String("binary".into()), Yaml::Boolean(self.binary));
+        features.insert(Yaml::String("floats".into()), Yaml::Boolean(self.floats));
+        features.insert(Yaml::String("number".into()), Yaml::Boolean(self.number));
+        features.insert(
+            Yaml::String("parallel".into()),
+            Yaml::Boolean(self.parallel),
+        );
+
+        let mut output = String::new();
+        let emitter_defaults = YamlEmitter::new(&mut output).describe();
+
+        let mut capabilities = Hash::new();
+        for (key, value) in [
+            ("version", Yaml::String(self.version.into())),
+            ("features", Yaml::Hash(features)),
+            ("merge_keys", Yaml::Boolean(self.merge_keys)),
+            ("loader_defaults", self.loader_defaults.describe()),
+            ("emitter_defaults", emitter_defaults),
+        ] {
+            capabilities.insert(Yaml::String(key.into()), value);
+        }
+        Yaml::Hash(capabilities)
+    }
+}
+
+/// Return the optional behaviors compiled into the library and the defaults it uses.
+#[must_use]
+pub fn capabilities() -> Capabilities {
+    Capabilities {
+        version: env!("CARGO_PKG_VERSION"),
+        std: cfg!(feature = "std"),
+        encoding: cfg!(feature = "encoding"),
+        json: cfg!(feature = "json"),
+        serde: cfg!(feature = "serde"),
+        timestamps: cfg!(feature = "timestamps"),
+        binary: cfg!(feature = "binary"),
+        floats: cfg!(feature = "floats"),
+        number: cfg!(feature = "number"),
+        parallel: cfg!(feature = "parallel"),
+        merge_keys: LoaderOptions::default().merge_keys,
+        loader_defaults: LoaderOptions::default(),
+    }
+}
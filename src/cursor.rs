@@ -0,0 +1,261 @@
+//! Typed extraction of the nodes of a [`Yaml`] document, with errors giving their path.
+
+use core::fmt::{self, Display};
+
+use crate::{Array, Hash, PathSegment, Yaml, YamlKind, YamlPath};
+
+static BAD_VALUE: Yaml = Yaml::BadValue;
+
+/// A node was not of the type it was extracted as, e.g. by [`Yaml::try_as_i64`].
+///
+/// It displays with the path of the node, e.g. ``expected int at `/server/port`, found
+/// string``.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExpectedType {
+    /// The type that was expected, e.g. [`YamlKind::Int`].
+    pub expected: YamlKind,
+    /// The type of the node, or [`YamlKind::BadValue`] if there is no node at `path`.
+    pub found: YamlKind,
+    /// The path of the node from the node extraction started at (see [`Yaml::cursor`]).
+    pub path: YamlPath,
+}
+
+/// A node of a document along with its path, to extract typed values from it and its children.
+///
+/// Lookups never fail: a child which does not exist is a [`Yaml::BadValue`], as with `["key"]`.
+/// Errors are reported when values are extracted, with the path of the node at fault.
+///
+/// ```
+/// use saphyr::Yaml;
+///
+/// let doc = Yaml::load_one_from_str("server: {host: localhost, port: '8080'}").unwrap();
+/// let server = doc.cursor().get("server");
+/// assert_eq!(server.get("host").try_as_str(), Ok("localhost"));
+/// assert_eq!(
+///     server.get("port").try_as_i64().unwrap_err().to_string(),
+///     "expected int at `/server/port`, found string"
+/// );
+/// assert_eq!(
+///     server.get("tls").get("cert").try_as_str().unwrap_err().to_string(),
+///     "expected string at `/server/tls/cert`, found nothing"
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct YamlCursor<'a> {
+    node: &'a Yaml,
+    path: YamlPath,
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ExpectedType {}
+
+impl Display for ExpectedType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected {}", self.expected)?;
+        if !self.path.is_root() {
+            write!(f, " at `{}`", self.path)?;
+        }
+        match self.found {
+            YamlKind::BadValue => f.write_str(", found nothing"),
+            found => write!(f, ", found {found}"),
+        }
+    }
+}
+
+impl<'a> YamlCursor<'a> {
+    /// Create a cursor at `node`, whose path is the root path.
+    #[must_use]
+    pub fn new(node: &'a Yaml) -> Self {
+        Self {
+            node,
+            path: YamlPath::new(),
+        }
+    }
+
+    /// Return the node the cursor is at.
+    #[must_use]
+    pub fn node(&self) -> &'a Yaml {
+        self.node
+    }
+
+    /// Return the path of the node, from the node the cursor was created at.
+    #[must_use]
+    pub fn path(&self) -> &YamlPath {
+        &self.path
+    }
+
+    /// Return a cursor at the value of `key`, looked up as with [`Yaml::get`].
+    #[must_use]
+    pub fn get(&self, key: impl Into<Yaml>) -> Self {
+        let key = key.into();
+        Self {
+            node: self.node.get(key.clone()).unwrap_or(&BAD_VALUE),
+            path: self.path.join(PathSegment::Key(key)),
+        }
+    }
+
+    /// Return a cursor at the item at `index` in the sequence.
+    #[must_use]
+    pub fn index(&self, index: usize) -> Self {
+        Self {
+            node: self.node.try_index_seq(index).unwrap_or(&BAD_VALUE),
+            path: self.path.join(PathSegment::Index(index)),
+        }
+    }
+
+    /// Return cursors at the items of the sequence.
+    ///
+    /// # Errors
+    /// Returns an [`ExpectedType`] if the node is not a sequence.
+    pub fn items(&self) -> Result<impl Iterator<Item = YamlCursor<'a>> + '_, ExpectedType> {
+        let items = self.try_as_vec()?;
+        Ok(items.iter().enumerate().map(|(index, node)| Self {
+            node,
+            path: self.path.join(PathSegment::Index(index)),
+        }))
+    }
+
+    /// Return the value of the node if it is a boolean.
+    ///
+    /// # Errors
+    /// Returns an [`ExpectedType`] if the node is not a boolean.
+    pub fn try_as_bool(&self) -> Result<bool, ExpectedType> {
+        self.extract(YamlKind::Bool, self.node.as_bool())
+    }
+
+    /// Return the value of the node if it is an integer.
+    ///
+    /// # Errors
+    /// Returns an [`ExpectedType`] if the node is not an integer.
+    pub fn try_as_i64(&self) -> Result<i64, ExpectedType> {
+        self.extract(YamlKind::Int, self.node.as_i64())
+    }
+
+    /// Return the value of the node if it is an integer in the range of a `u64`, as with
+    /// [`Yaml::as_u64`].
+    ///
+    /// # Errors
+    /// Returns an [`ExpectedType`] if the node is not such an integer.
+    pub fn try_as_u64(&self) -> Result<u64, ExpectedType> {
+        self.extract(YamlKind::Int, self.node.as_u64())
+    }
+
+    /// Return the value of the node if it is a float, as with [`Yaml::as_f64`].
+    ///
+    /// # Errors
+    /// Returns an [`ExpectedType`] if the node is not a float.
+    pub fn try_as_f64(&self) -> Result<f64, ExpectedType> {
+        self.extract(YamlKind::Real, self.node.as_f64())
+    }
+
+    /// Return the value of the node if it is a string.
+    ///
+    /// # Errors
+    /// Returns an [`ExpectedType`] if the node is not a string.
+    pub fn try_as_str(&self) -> Result<&'a str, ExpectedType> {
+        self.extract(YamlKind::String, self.node.as_str())
+    }
+
+    /// Return the items of the node if it is a sequence.
+    ///
+    /// # Errors
+    /// Returns an [`ExpectedType`] if the node is not a sequence.
+    pub fn try_as_vec(&self) -> Result<&'a Array, ExpectedType> {
+        self.extract(YamlKind::Array, self.node.as_vec())
+    }
+
+    /// Return the entries of the node if it is a mapping.
+    ///
+    /// # Errors
+    /// Returns an [`ExpectedType`] if the node is not a mapping.
+    pub fn try_as_hash(&self) -> Result<&'a Hash, ExpectedType> {
+        self.extract(YamlKind::Hash, self.node.as_hash())
+    }
+
+    fn extract<T>(&self, expected: YamlKind, value: Option<T>) -> Result<T, ExpectedType> {
+        value.ok_or_else(|| ExpectedType {
+            expected,
+            found: self.node.kind(),
+            path: self.path.clone(),
+        })
+    }
+}
+
+impl Yaml {
+    /// Return a [`YamlCursor`] at `self`, to extract typed values from the nodes within it.
+    #[must_use]
+    pub fn cursor(&self) -> YamlCursor<'_> {
+        YamlCursor::new(self)
+    }
+
+    /// Return the value of `self` if it is a boolean.
+    ///
+    /// # Errors
+    /// Returns an [`ExpectedType`] if `self` is not a boolean.
+    pub fn try_as_bool(&self) -> Result<bool, ExpectedType> {
+        self.cursor().try_as_bool()
+    }
+
+    /// Return the value of `self` if it is an integer.
+    ///
+    /// ```
+    /// use saphyr::{yaml, ExpectedType, YamlKind, YamlPath};
+    ///
+    /// assert_eq!(yaml!(8080).try_as_i64(), Ok(8080));
+    /// assert_eq!(
+    ///     yaml!("8080").try_as_i64(),
+    ///     Err(ExpectedType {
+    ///         expected: YamlKind::Int,
+    ///         found: YamlKind::String,
+    ///         path: YamlPath::new(),
+    ///     })
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an [`ExpectedType`] if `self` is not an integer.
+    pub fn try_as_i64(&self) -> Result<i64, ExpectedType> {
+        self.cursor().try_as_i64()
+    }
+
+    /// Return the value of `self` if it is an integer in the range of a `u64`, as with
+    /// [`Self::as_u64`].
+    ///
+    /// # Errors
+    /// Returns an [`ExpectedType`] if `self` is not such an integer.
+    pub fn try_as_u64(&self) -> Result<u64, ExpectedType> {
+        self.cursor().try_as_u64()
+    }
+
+    /// Return the value of `self` if it is a float, as with [`Self::as_f64`].
+    ///
+    /// # Errors
+    /// Returns an [`ExpectedType`] if `self` is not a float.
+    pub fn try_as_f64(&self) -> Result<f64, ExpectedType> {
+        self.cursor().try_as_f64()
+    }
+
+    /// Return the value of `self` if it is a string.
+    ///
+    /// # Errors
+    /// Returns an [`ExpectedType`] if `self` is not a string.
+    pub fn try_as_str(&self) -> Result<&str, ExpectedType> {
+        self.cursor().try_as_str()
+    }
+
+    /// Return the items of `self` if it is a sequence.
+    ///
+    /// # Errors
+    /// Returns an [`ExpectedType`] if `self` is not a sequence.
+    pub fn try_as_vec(&self) -> Result<&Array, ExpectedType> {
+        self.cursor().try_as_vec()
+    }
+
+    /// Return the entries of `self` if it is a mapping.
+    ///
+    /// # Errors
+    /// Returns an [`ExpectedType`] if `self` is not a mapping.
+    pub fn try_as_hash(&self) -> Result<&Hash, ExpectedType> {
+        self.cursor().try_as_hash()
+    }
+}
@@ -0,0 +1,573 @@
+//! Deserialization of Rust values from YAML with [`serde`].
+
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+    vec::Vec,
+};
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+};
+
+use saphyr_parser::{BufferedInput, Event, Marker, Parser, ScanError, Span, TScalarStyle, Tag};
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, Error as _, IntoDeserializer, MapAccess,
+    SeqAccess, VariantAccess, Visitor,
+};
+
+use crate::{loader::parse_f64, schema::is_large_integer, LoaderOptions, Yaml};
+
+/// How many times the number of events read from the source aliases may expand to.
+///
+/// This bounds the work done for documents nesting aliases to grow exponentially (e.g. "billion
+/// laughs"), while leaving room for documents which reuse anchored nodes a lot.
+const ALIAS_EXPANSION_RATIO: usize = 100;
+
+/// Deserialize an instance of `T` from the only document of `source`.
+///
+/// ```
+/// # use serde::Deserialize;
+/// #[derive(Deserialize)]
+/// struct Config {
+///     name: String,
+///     ports: Vec<u16>,
+///     debug: Option<bool>,
+/// }
+///
+/// let config: Config = saphyr::from_str("name: web\nports: [80, 443]").unwrap();
+/// assert_eq!(config.name, "web");
+/// assert_eq!(config.ports, [80, 443]);
+/// assert_eq!(config.debug, None);
+/// ```
+///
+/// # Errors
+/// Returns an error if `source` is not valid YAML, if it does not contain exactly one document,
+/// or if the document does not match `T`.
+pub fn from_str<T: DeserializeOwned>(source: &str) -> Result<T, DeserializeError> {
+    let mut deserializer = Deserializer::new(source);
+    let value = T::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+/// An error when deserializing a value from YAML.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeserializeError {
+    /// What went wrong.
+    pub message: String,
+    /// Where the offending node starts, if known.
+    pub marker: Option<Marker>,
+}
+
+impl DeserializeError {
+    /// Create an error at `marker`.
+    fn at(message: impl Display, marker: Marker) -> Self {
+        DeserializeError {
+            message: message.to_string(),
+            marker: Some(marker),
+        }
+    }
+
+    /// Set the location of `self` to `marker`, unless it is already known.
+    fn or_at(mut self, marker: Marker) -> Self {
+        self.marker = self.marker.or(Some(marker));
+        self
+    }
+}
+
+impl From<ScanError> for DeserializeError {
+    fn from(error: ScanError) -> Self {
+        DeserializeError::at(error.info(), *error.marker())
+    }
+}
+
+impl std::error::Error for DeserializeError {}
+
+impl Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)?;
+        if let Some(marker) = self.marker {
+            write!(f, " at line {} column {}", marker.line(), marker.col() + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl de::Error for DeserializeError {
+    fn custom<T: Display>(message: T) -> Self {
+        DeserializeError {
+            message: message.to_string(),
+            marker: None,
+        }
+    }
+}
+
+/// A YAML deserializer, driving [`serde`] from the events of the parser.
+///
+/// Values are read from the events as they are parsed, without loading the document into a
+/// [`Yaml`] first. Plain scalars are resolved as the loader does with the default
+/// [`LoaderOptions`], except for fields expecting strings, which are given the text of any scalar
+/// (e.g. `version: 1.10` is read as `"1.10"`). Aliases are replaced by the node they refer to.
+/// Enums are read from a scalar for unit variants (`Red`), and from a mapping with a single key
+/// for the others (`{Rgb: [255, 0, 0]}`).
+///
+/// The document must be deserialized in one go, after which [`Deserializer::end`] checks that
+/// the stream holds nothing else. [`from_str`] does both.
+pub struct Deserializer<'a> {
+    /// The events of the stream.
+    events: Box<dyn Iterator<Item = Result<(Event, Span), ScanError>> + 'a>,
+    /// The next event, if it has been peeked at.
+    peeked: Option<(Event, Span)>,
+    /// The events of the aliased nodes being expanded, in reverse order.
+    replay: Vec<(Event, Span)>,
+    /// The events of the anchored nodes read so far, by anchor id.
+    anchors: HashMap<usize, Vec<(Event, Span)>>,
+    /// The anchored nodes being read, innermost last.
+    recordings: Vec<Recording>,
+    /// The number of events read from the stream.
+    read: usize,
+    /// The number of events yielded through aliases.
+    replayed: usize,
+    /// How to resolve plain scalars.
+    options: LoaderOptions,
+}
+
+/// The events of an anchored node being read.
+struct Recording {
+    /// The id of the anchor.
+    id: usize,
+    /// The number of collections of the node which have not ended yet.
+    depth: usize,
+    /// The events read so far.
+    events: Vec<(Event, Span)>,
+}
+
+impl<'a> Deserializer<'a> {
+    /// Create a deserializer reading the YAML stream `source`.
+    #[must_use]
+    pub fn new(source: &'a str) -> Self {
+        Self::with_events(Parser::new(BufferedInput::new(source.chars())))
+    }
+
+    /// Create a deserializer reading the given events, as the parser would produce them.
+    ///
+    /// ```
+    /// # use saphyr::{yaml, Deserializer};
+    /// # use serde::Deserialize;
+    /// let events = yaml!([1, 2]).into_events().map(|ev| (ev, Default::default()));
+    /// let mut deserializer = Deserializer::from_events(events);
+    /// let value = Vec::<u8>::deserialize(&mut deserializer).unwrap();
+    /// deserializer.end().unwrap();
+    /// assert_eq!(value, [1, 2]);
+    /// ```
+    pub fn from_events(events: impl IntoIterator<Item = (Event, Span)> + 'a) -> Self {
+        Self::with_events(events.into_iter().map(Ok))
+    }
+
+    /// Create a deserializer reading the events of `events`.
+    fn with_events(events: impl Iterator<Item = Result<(Event, Span), ScanError>> + 'a) -> Self {
+        Deserializer {
+            events: Box::new(events),
+            peeked: None,
+            replay: vec![],
+            anchors: HashMap::new(),
+            recordings: vec![],
+            read: 0,
+            replayed: 0,
+            options: LoaderOptions::default(),
+        }
+    }
+
+    /// Check that the document has been read entirely and that the stream holds no other one.
+    ///
+    /// # Errors
+    /// Returns an error if the stream goes on after the document.
+    pub fn end(&mut self) -> Result<(), DeserializeError> {
+        loop {
+            match self.next_event()? {
+                (Event::DocumentEnd, _) => {}
+                (Event::StreamEnd, _) => return Ok(()),
+                (Event::DocumentStart(_), span) => {
+                    return Err(DeserializeError::at(
+                        "expected a single document, found several",
+                        span.start,
+                    ))
+                }
+                (_, span) => {
+                    return Err(DeserializeError::at(
+                        "expected the end of the document",
+                        span.start,
+                    ))
+                }
+            }
+        }
+    }
+
+    /// Return the next event of the document, skipping the start of the stream and document.
+    fn next(&mut self) -> Result<(Event, Span), DeserializeError> {
+        loop {
+            match self.next_event()? {
+                (Event::StreamStart | Event::DocumentStart(_) | Event::Nothing, _) => {}
+                next => return Ok(next),
+            }
+        }
+    }
+
+    /// Return the next event, without consuming it.
+    fn peek(&mut self) -> Result<&(Event, Span), DeserializeError> {
+        if self.peeked.is_none() {
+            let next = self.next()?;
+            self.peeked = Some(next);
+        }
+        Ok(self.peeked.as_ref().unwrap())
+    }
+
+    /// Return the next event of the stream, expanding aliases.
+    fn next_event(&mut self) -> Result<(Event, Span), DeserializeError> {
+        if let Some(next) = self.peeked.take() {
+            return Ok(next);
+        }
+        loop {
+            let (event, span, replayed) = if let Some((event, span)) = self.replay.pop() {
+                (event, span, true)
+            } else if let Some(next) = self.events.next() {
+                let (event, span) = next?;
+                self.read += 1;
+                (event, span, false)
+            } else {
+                return Err(DeserializeError::custom("unexpected end of the events"));
+            };
+            if let Event::Alias(id) = event {
+                let events = self.anchors.get(&id).ok_or_else(|| {
+                    DeserializeError::at(
+                        "alias does not refer to a complete anchored node",
+                        span.start,
+                    )
+                })?;
+                self.replayed += events.len();
+                if self.replayed > self.read.saturating_mul(ALIAS_EXPANSION_RATIO) {
+                    return Err(DeserializeError::at(
+                        "aliases expand to too many nodes",
+                        span.start,
+                    ));
+                }
+                self.replay.extend(events.iter().rev().cloned());
+                continue;
+            }
+            self.record(&event, span, replayed);
+            return Ok((event, span));
+        }
+    }
+
+    /// Add `event` to the anchored nodes being read, and start reading the node it starts if it
+    /// is anchored.
+    ///
+    /// Anchors are not recorded again when their node is `replayed` through an alias.
+    fn record(&mut self, event: &Event, span: Span, replayed: bool) {
+        for recording in &mut self.recordings {
+            recording.events.push((event.clone(), span));
+            match event {
+                Event::SequenceStart(..) | Event::MappingStart(..) => recording.depth += 1,
+                Event::SequenceEnd | Event::MappingEnd => recording.depth -= 1,
+                _ => {}
+            }
+        }
+        match event {
+            Event::Scalar(_, _, id, _)
+            | Event::SequenceStart(id, _)
+            | Event::MappingStart(id, _)
+                if *id != 0 && !replayed =>
+            {
+                self.recordings.push(Recording {
+                    id: *id,
+                    depth: usize::from(!matches!(event, Event::Scalar(..))),
+                    events: vec![(event.clone(), span)],
+                });
+            }
+            _ => {}
+        }
+        while self
+            .recordings
+            .last()
+            .is_some_and(|recording| recording.depth == 0)
+        {
+            let recording = self.recordings.pop().unwrap();
+            self.anchors.insert(recording.id, recording.events);
+        }
+    }
+
+    /// Consume the end of a collection, failing if the collection goes on.
+    fn end_collection(&mut self, what: &str) -> Result<(), DeserializeError> {
+        match self.next()? {
+            (Event::SequenceEnd | Event::MappingEnd, _) => Ok(()),
+            (_, span) => Err(DeserializeError::at(
+                format!("the {what} has more entries than expected"),
+                span.start,
+            )),
+        }
+    }
+
+    /// Visit a scalar, resolved as the loader would.
+    fn visit_scalar<'de, V: Visitor<'de>>(
+        &self,
+        value: String,
+        style: TScalarStyle,
+        tag: Option<&Tag>,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        if style != TScalarStyle::Plain && tag.is_none() {
+            return visitor.visit_string(value);
+        }
+        match self.options.resolve_scalar(value.clone(), style, tag) {
+            Yaml::Null => visitor.visit_unit(),
+            Yaml::Boolean(v) => visitor.visit_bool(v),
+            Yaml::Integer(v) => visitor.visit_i64(v),
+            Yaml::Real(v) if is_large_integer(&v) => {
+                if let Ok(v) = v.parse::<u64>() {
+                    visitor.visit_u64(v)
+                } else if let Ok(v) = v.parse::<i128>() {
+                    visitor.visit_i128(v)
+                } else {
+                    visitor.visit_u128(v.parse::<u128>().map_err(DeserializeError::custom)?)
+                }
+            }
+            Yaml::Real(v) => match parse_f64(&v) {
+                Some(v) => visitor.visit_f64(v),
+                None => visitor.visit_string(v),
+            },
+            Yaml::Float(v) => visitor.visit_f64(v.value),
+            Yaml::Binary(v) => visitor.visit_byte_buf(v),
+            Yaml::String(v) => visitor.visit_string(v),
+            Yaml::BadValue => Err(DeserializeError::custom(format!(
+                "`{value}` is not a valid value for its tag"
+            ))),
+            _ => visitor.visit_string(value),
+        }
+    }
+
+    /// Return whether `event` is a scalar resolved to null.
+    fn is_null(&self, event: &Event) -> bool {
+        match event {
+            Event::Scalar(value, style, _, tag) => matches!(
+                self.options
+                    .resolve_scalar(value.clone(), *style, tag.as_ref()),
+                Yaml::Null
+            ),
+            _ => false,
+        }
+    }
+}
+
+/// Return an error for an event which does not start a node.
+fn unexpected(event: &Event, span: Span) -> DeserializeError {
+    let found = match event {
+        Event::StreamEnd => "the stream does not contain any document",
+        Event::DocumentEnd => "expected a node, found the end of the document",
+        Event::SequenceEnd => "expected a node, found the end of a sequence",
+        Event::MappingEnd => "expected a node, found the end of a mapping",
+        _ => "expected a node, found an unexpected event",
+    };
+    DeserializeError::at(found, span.start)
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'_> {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        let (event, span) = self.next()?;
+        let result = match event {
+            Event::Scalar(value, style, _, tag) => {
+                self.visit_scalar(value, style, tag.as_ref(), visitor)
+            }
+            Event::SequenceStart(..) => visitor
+                .visit_seq(Entries { de: &mut *self })
+                .and_then(|value| self.end_collection("sequence").map(|()| value)),
+            Event::MappingStart(..) => visitor
+                .visit_map(Entries { de: &mut *self })
+                .and_then(|value| self.end_collection("mapping").map(|()| value)),
+            event => return Err(unexpected(&event, span)),
+        };
+        result.map_err(|error| error.or_at(span.start))
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        match self.peek()? {
+            (Event::Scalar(..), _) => match self.next()? {
+                (Event::Scalar(value, ..), span) => visitor
+                    .visit_string::<DeserializeError>(value)
+                    .map_err(|error| error.or_at(span.start)),
+                _ => unreachable!("peeked at a scalar"),
+            },
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        let marker = self.peek()?.1.start;
+        if self.is_null(&self.peeked.as_ref().unwrap().0) {
+            self.next()?;
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(&mut *self)
+        }
+        .map_err(|error| error.or_at(marker))
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        let (event, span) = self.next()?;
+        let result = match event {
+            Event::Scalar(value, ..) => visitor.visit_enum(value.into_deserializer()),
+            Event::MappingStart(..) => visitor
+                .visit_enum(Entries { de: &mut *self })
+                .and_then(|value| self.end_collection("mapping").map(|()| value)),
+            Event::SequenceStart(..) => Err(DeserializeError::custom(
+                "expected a scalar or a mapping with a single key for an enum, found a sequence",
+            )),
+            event => return Err(unexpected(&event, span)),
+        };
+        result.map_err(|error| error.or_at(span.start))
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        let mut depth = 0usize;
+        loop {
+            match self.next()? {
+                (Event::SequenceStart(..) | Event::MappingStart(..), _) => depth += 1,
+                (Event::SequenceEnd | Event::MappingEnd, _) if depth > 0 => depth -= 1,
+                (Event::Scalar(..), _) => {}
+                (event, span) => return Err(unexpected(&event, span)),
+            }
+            if depth == 0 {
+                return visitor.visit_unit();
+            }
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 bytes byte_buf unit unit_struct
+        seq tuple tuple_struct map struct
+    }
+}
+
+/// The entries of a collection being deserialized, or the only entry of a mapping holding an
+/// enum variant.
+struct Entries<'b, 'a> {
+    de: &'b mut Deserializer<'a>,
+}
+
+impl<'de> SeqAccess<'de> for Entries<'_, '_> {
+    type Error = DeserializeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, DeserializeError> {
+        if let (Event::SequenceEnd, _) = self.de.peek()? {
+            return Ok(None);
+        }
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+}
+
+impl<'de> MapAccess<'de> for Entries<'_, '_> {
+    type Error = DeserializeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, DeserializeError> {
+        if let (Event::MappingEnd, _) = self.de.peek()? {
+            return Ok(None);
+        }
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, DeserializeError> {
+        seed.deserialize(&mut *self.de)
+    }
+}
+
+impl<'de> EnumAccess<'de> for Entries<'_, '_> {
+    type Error = DeserializeError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self), DeserializeError> {
+        if let (Event::MappingEnd, span) = self.de.peek()? {
+            return Err(DeserializeError::at(
+                "expected a mapping with a single key for an enum, found an empty mapping",
+                span.start,
+            ));
+        }
+        let variant = seed.deserialize(&mut *self.de)?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Entries<'_, '_> {
+    type Error = DeserializeError;
+
+    fn unit_variant(self) -> Result<(), DeserializeError> {
+        de::Deserialize::deserialize(self.de)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, DeserializeError> {
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        de::Deserializer::deserialize_seq(self.de, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        de::Deserializer::deserialize_map(self.de, visitor)
+    }
+}
@@ -0,0 +1,276 @@
+//! Semantic comparison of [`Yaml`] documents.
+
+use alloc::vec::Vec;
+
+use crate::{loader::parse_f64, Hash, PathSegment, Yaml, YamlPath};
+
+/// Options governing [`Yaml::semantic_eq_with`] and [`Yaml::diff_with`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CompareOptions {
+    /// Whether `.nan` is equal to itself. Defaults to `true`.
+    pub nans_equal: bool,
+    /// Whether a mapping key whose value is `null` is equal to a missing key. Defaults to
+    /// `false`.
+    pub null_is_missing: bool,
+    /// Whether sequences are compared as sets, regardless of the order of their items. Defaults
+    /// to `false`.
+    ///
+    /// Items are still counted: `[1, 1]` does not equal `[1]`. Each item is compared with the
+    /// items of the other sequence, which takes quadratic time.
+    pub unordered_sequences: bool,
+}
+
+impl Default for CompareOptions {
+    fn default() -> Self {
+        Self {
+            nans_equal: true,
+            null_is_missing: false,
+            unordered_sequences: false,
+        }
+    }
+}
+
+/// A difference between two documents, as returned by [`Yaml::diff`].
+///
+/// The path is that of the node in the document it is found in: [`YamlDiff::Added`] nodes are
+/// only found in the second document, [`YamlDiff::Removed`] nodes in the first one. Paths
+/// display as JSON Pointers (see [`YamlPath`]).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum YamlDiff {
+    /// A mapping entry or a sequence item only exists in the second document.
+    Added {
+        /// The path to the node.
+        path: YamlPath,
+        /// The node in the second document.
+        value: Yaml,
+    },
+    /// A mapping entry or a sequence item only exists in the first document.
+    Removed {
+        /// The path to the node.
+        path: YamlPath,
+        /// The node in the first document.
+        value: Yaml,
+    },
+    /// A node differs between the two documents.
+    Changed {
+        /// The path to the node.
+        path: YamlPath,
+        /// The node in the first document.
+        from: Yaml,
+        /// The node in the second document.
+        to: Yaml,
+    },
+}
+
+impl YamlDiff {
+    /// Return the path to the node that differs.
+    #[must_use]
+    pub fn path(&self) -> &YamlPath {
+        match self {
+            YamlDiff::Added { path, .. }
+            | YamlDiff::Removed { path, .. }
+            | YamlDiff::Changed { path, .. } => path,
+        }
+    }
+}
+
+impl Yaml {
+    /// Return whether `self` and `other` hold the same data, with the default
+    /// [`CompareOptions`].
+    ///
+    /// Unlike `==`, mapping keys are compared regardless of their order and floats are compared
+    /// numerically, whatever their representation. Integers and floats are never equal, as they
+    /// are of different types.
+    ///
+    /// ```
+    /// # use saphyr::Yaml;
+    /// let a = Yaml::load_one_from_str("{a: 1.0, b: [.nan]}").unwrap();
+    /// let b = Yaml::load_one_from_str("{b: [.NAN], a: 1.00}").unwrap();
+    /// assert_ne!(a, b);
+    /// assert!(a.semantic_eq(&b));
+    /// ```
+    #[must_use]
+    pub fn semantic_eq(&self, other: &Yaml) -> bool {
+        self.semantic_eq_with(other, CompareOptions::default())
+    }
+
+    /// Return whether `self` and `other` hold the same data. See [`Self::semantic_eq`].
+    #[must_use]
+    pub fn semantic_eq_with(&self, other: &Yaml, options: CompareOptions) -> bool {
+        match (self, other) {
+            (Yaml::Real(a), Yaml::Real(b)) => reals_eq(a, b, options),
+            (Yaml::Float(_), Yaml::Real(_) | Yaml::Float(_)) | (Yaml::Real(_), Yaml::Float(_)) => {
+                match (self.as_f64(), other.as_f64()) {
+                    (Some(a), Some(b)) => floats_eq(a, b, options),
+                    _ => false,
+                }
+            }
+            (Yaml::Array(a), Yaml::Array(b)) if options.unordered_sequences => {
+                a.len() == b.len() && match_items(a, b, options).iter().all(Option::is_some)
+            }
+            (Yaml::Array(a), Yaml::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.semantic_eq_with(b, options))
+            }
+            (Yaml::Hash(a), Yaml::Hash(b)) => {
+                a.iter()
+                    .all(|(key, value)| match find_key(b, key, options) {
+                        Some(other) => value.semantic_eq_with(other, options),
+                        None => is_missing(value, options),
+                    })
+                    && b.iter().all(|(key, value)| {
+                        find_key(a, key, options).is_some() || is_missing(value, options)
+                    })
+            }
+            _ => self == other,
+        }
+    }
+
+    /// List the differences between `self` and `other`, with the default [`CompareOptions`].
+    ///
+    /// Nodes are compared as in [`Self::semantic_eq`]. Mappings and sequences are compared
+    /// entry by entry, and item by item, so that only the nodes that differ are listed.
+    ///
+    /// With [`CompareOptions::unordered_sequences`], the items of a sequence which have no equal
+    /// in the other one are listed as removed or added, at their index in their own sequence.
+    ///
+    /// ```
+    /// # use saphyr::Yaml;
+    /// let a = Yaml::load_one_from_str("{a: 1, b: {c: 2, d: 3}, e: [1]}").unwrap();
+    /// let b = Yaml::load_one_from_str("{b: {c: 2, d: 4}, e: [1, 2], a: 1.0}").unwrap();
+    /// let paths: Vec<_> = a.diff(&b).iter().map(|diff| diff.path().to_string()).collect();
+    /// assert_eq!(paths, ["/a", "/b/d", "/e/1"]);
+    /// ```
+    #[must_use]
+    pub fn diff(&self, other: &Yaml) -> Vec<YamlDiff> {
+        self.diff_with(other, CompareOptions::default())
+    }
+
+    /// List the differences between `self` and `other`. See [`Self::diff`].
+    #[must_use]
+    pub fn diff_with(&self, other: &Yaml, options: CompareOptions) -> Vec<YamlDiff> {
+        let mut diffs = vec![];
+        diff(self, other, options, &mut YamlPath::new(), &mut diffs);
+        diffs
+    }
+}
+
+fn reals_eq(a: &str, b: &str, options: CompareOptions) -> bool {
+    match (parse_f64(a), parse_f64(b)) {
+        (Some(a), Some(b)) => floats_eq(a, b, options),
+        _ => a == b,
+    }
+}
+
+fn floats_eq(a: f64, b: f64, options: CompareOptions) -> bool {
+    a == b || (options.nans_equal && a.is_nan() && b.is_nan())
+}
+
+/// Return whether a mapping value is equivalent to a missing key.
+fn is_missing(value: &Yaml, options: CompareOptions) -> bool {
+    options.null_is_missing && value.is_null()
+}
+
+/// Return, for each item of `a`, the index of the item of `b` it is paired with, if any.
+///
+/// Each item of `b` is paired with at most one item of `a`, the first one which is semantically
+/// equal to it.
+fn match_items(a: &[Yaml], b: &[Yaml], options: CompareOptions) -> Vec<Option<usize>> {
+    let mut paired = vec![false; b.len()];
+    a.iter()
+        .map(|item| {
+            let j = (0..b.len()).find(|&j| !paired[j] && item.semantic_eq_with(&b[j], options))?;
+            paired[j] = true;
+            Some(j)
+        })
+        .collect()
+}
+
+/// Return the value of the key of `hash` which is semantically equal to `key`, if any.
+fn find_key<'a>(hash: &'a Hash, key: &Yaml, options: CompareOptions) -> Option<&'a Yaml> {
+    hash.get(key).or_else(|| {
+        hash.iter()
+            .find(|(other, _)| key.semantic_eq_with(other, options))
+            .map(|(_, value)| value)
+    })
+}
+
+fn diff(
+    a: &Yaml,
+    b: &Yaml,
+    options: CompareOptions,
+    path: &mut YamlPath,
+    diffs: &mut Vec<YamlDiff>,
+) {
+    match (a, b) {
+        (Yaml::Array(a), Yaml::Array(b)) if options.unordered_sequences => {
+            let pairs = match_items(a, b, options);
+            let mut paired = vec![false; b.len()];
+            for (i, pair) in pairs.into_iter().enumerate() {
+                match pair {
+                    Some(j) => paired[j] = true,
+                    None => diffs.push(YamlDiff::Removed {
+                        path: path.join(PathSegment::Index(i)),
+                        value: a[i].clone(),
+                    }),
+                }
+            }
+            for (j, value) in b.iter().enumerate() {
+                if !paired[j] {
+                    diffs.push(YamlDiff::Added {
+                        path: path.join(PathSegment::Index(j)),
+                        value: value.clone(),
+                    });
+                }
+            }
+        }
+        (Yaml::Array(a), Yaml::Array(b)) => {
+            for i in 0..a.len().max(b.len()) {
+                path.push(PathSegment::Index(i));
+                match (a.get(i), b.get(i)) {
+                    (Some(a), Some(b)) => diff(a, b, options, path, diffs),
+                    (Some(a), None) => diffs.push(YamlDiff::Removed {
+                        path: path.clone(),
+                        value: a.clone(),
+                    }),
+                    (None, Some(b)) => diffs.push(YamlDiff::Added {
+                        path: path.clone(),
+                        value: b.clone(),
+                    }),
+                    (None, None) => unreachable!(),
+                }
+                path.pop();
+            }
+        }
+        (Yaml::Hash(a), Yaml::Hash(b)) => {
+            for (key, value) in a {
+                path.push(PathSegment::Key(key.clone()));
+                match find_key(b, key, options) {
+                    Some(other) => diff(value, other, options, path, diffs),
+                    None if is_missing(value, options) => {}
+                    None => diffs.push(YamlDiff::Removed {
+                        path: path.clone(),
+                        value: value.clone(),
+                    }),
+                }
+                path.pop();
+            }
+            for (key, value) in b {
+                if find_key(a, key, options).is_none() && !is_missing(value, options) {
+                    diffs.push(YamlDiff::Added {
+                        path: path.join(PathSegment::Key(key.clone())),
+                        value: value.clone(),
+                    });
+                }
+            }
+        }
+        _ => {
+            if !a.semantic_eq_with(b, options) {
+                diffs.push(YamlDiff::Changed {
+                    path: path.clone(),
+                    from: a.clone(),
+                    to: b.clone(),
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! The `%YAML` and `%TAG` directives of documents.
+
+use alloc::{borrow::ToOwned, collections::BTreeMap, string::String, vec::Vec};
+
+use crate::Schema;
+
+/// The directives a document was declared with, and whether it started with a `---` marker.
+///
+/// See [`Yaml::load_from_str_with_metadata`](crate::Yaml::load_from_str_with_metadata) and
+/// [`YamlLoader::document_metadata`](crate::YamlLoader::document_metadata).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DocumentMetadata {
+    /// The version of YAML declared with `%YAML`, as `(major, minor)`, e.g. `(1, 1)`.
+    pub version: Option<(u32, u32)>,
+    /// The tag handles declared with `%TAG` (e.g. `!e!`), with their prefix (e.g.
+    /// `tag:example.com,2020:`).
+    pub tag_handles: BTreeMap<String, String>,
+    /// Whether the document started with a `---` marker, which documents with directives always
+    /// do.
+    pub explicit_start: bool,
+}
+
+impl DocumentMetadata {
+    /// Return the schema of the declared version, if it has one.
+    pub(crate) fn schema(&self) -> Option<Schema> {
+        match self.version? {
+            (1, 1) => Some(Schema::Yaml11),
+            (1, 2) => Some(Schema::Core),
+            _ => None,
+        }
+    }
+}
+
+/// Read the directives of the documents of `source` which have some, along with the line
+/// (starting at 1) of the `---` marker which ends them.
+///
+/// This only looks at lines, and relies on the parser to reject invalid streams: directives are
+/// the lines starting with `%` at the start of the stream or after a `...` marker, up to the next
+/// `---` marker.
+pub(crate) fn scan_directives(source: &str) -> Vec<(usize, DocumentMetadata)> {
+    let mut documents = vec![];
+    let mut in_prefix = true;
+    let mut current: Option<DocumentMetadata> = None;
+    for (index, line) in source.lines().enumerate() {
+        if is_marker(line, "...") {
+            in_prefix = true;
+            current = None;
+        } else if is_marker(line, "---") {
+            if let Some(metadata) = current.take() {
+                documents.push((index + 1, metadata));
+            }
+            in_prefix = false;
+        } else if in_prefix {
+            if let Some(directive) = line.strip_prefix('%') {
+                read_directive(directive, current.get_or_insert_with(Default::default));
+            } else if !line.trim_start().is_empty() && !line.trim_start().starts_with('#') {
+                // The document starts without a `---` marker, so it cannot have directives.
+                in_prefix = false;
+                current = None;
+            }
+        }
+    }
+    documents
+}
+
+/// Return whether `line` is the document marker `marker`, i.e. `---` or `...`.
+fn is_marker(line: &str, marker: &str) -> bool {
+    line.strip_prefix(marker)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t']))
+}
+
+/// Add the directive `directive`, which follows a `%`, to `metadata`.
+///
+/// Reserved directives are ignored.
+fn read_directive(directive: &str, metadata: &mut DocumentMetadata) {
+    let mut words = directive
+        .split_whitespace()
+        .take_while(|word| !word.starts_with('#'));
+    match words.next() {
+        Some("YAML") => {
+            metadata.version = words
+                .next()
+                .and_then(|version| version.split_once('.'))
+                .and_then(|(major, minor)| Some((major.parse().ok()?, minor.parse().ok()?)));
+        }
+        Some("TAG") => {
+            if let (Some(handle), Some(prefix)) = (words.next(), words.next()) {
+                metadata
+                    .tag_handles
+                    .insert(handle.to_owned(), prefix.to_owned());
+            }
+        }
+        _ => {}
+    }
+}
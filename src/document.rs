@@ -0,0 +1,706 @@
+//! Editing documents while keeping their formatting.
+
+use alloc::{string::String, vec::Vec};
+use core::fmt::{self, Display};
+
+use crate::annotated::marked_yaml::byte_index;
+use crate::emitter::{escape_str, tag_text};
+use crate::{
+    ByteRange, Hash, LoadError, MarkedYaml, PathSegment, Yaml, YamlData, YamlEmitter, YamlKind,
+    YamlPath,
+};
+
+/// A YAML document which can be edited while keeping the formatting of its source.
+///
+/// The document keeps its source and only rewrites the text of the nodes that are edited:
+/// comments, blank lines, indentation, key order, anchors and the styles of the other nodes are
+/// kept as they were. Edited strings keep their quotes if they can, edited flow collections stay
+/// in flow style, and new entries are written in the style of the collection they are added to.
+///
+/// After each edit, the source is loaded again and checked against the expected document. Edits
+/// which would change other nodes fail with [`EditErrorKind::Unsupported`] and leave the
+/// document unchanged, e.g. editing a node within a collection copied from an alias (which
+/// would edit the anchored collection as well) or a node within a complex key.
+///
+/// ```
+/// # use saphyr::{yaml, PathSegment, YamlDocument, YamlPath};
+/// let source = "# Service settings.\nname: 'web'  # Quoted.\nports:\n  - 80\n";
+/// let mut doc = YamlDocument::new(source).unwrap();
+/// let key = |key: &str| YamlPath::from(vec![PathSegment::Key(key.into())]);
+///
+/// doc.set(&key("name"), yaml!("api")).unwrap();
+/// doc.push(&key("ports"), yaml!(443)).unwrap();
+/// doc.insert(&YamlPath::new(), "debug", yaml!(true)).unwrap();
+/// assert_eq!(
+///     doc.source(),
+///     "# Service settings.\nname: 'api'  # Quoted.\nports:\n  - 80\n  - 443\ndebug: true\n"
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct YamlDocument {
+    /// The current source of the document.
+    source: String,
+    /// The document loaded from `source`.
+    root: MarkedYaml,
+}
+
+/// An edit of a [`YamlDocument`] could not be made.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EditError {
+    /// The path given to the edit.
+    pub path: YamlPath,
+    /// What went wrong.
+    pub kind: EditErrorKind,
+}
+
+/// The reason why an edit of a [`YamlDocument`] could not be made.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EditErrorKind {
+    /// There is no node at the path.
+    NotFound,
+    /// The node is not of the type the edit requires, e.g. when pushing to a mapping.
+    WrongType {
+        /// The type that was expected, e.g. [`YamlKind::Array`].
+        expected: YamlKind,
+        /// The type of the node, e.g. [`YamlKind::Hash`].
+        actual: YamlKind,
+    },
+    /// The new node contains a [`Yaml::Alias`] or a [`Yaml::BadValue`], which cannot be written.
+    InvalidValue,
+    /// The edit cannot be made without changing other nodes, e.g. to remove the root node.
+    Unsupported,
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EditError {}
+
+impl Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            EditErrorKind::NotFound => f.write_str("no such node")?,
+            EditErrorKind::WrongType { expected, actual } => {
+                write!(f, "expected {expected}, found {actual}")?;
+            }
+            EditErrorKind::InvalidValue => f.write_str("the new node cannot be written")?,
+            EditErrorKind::Unsupported => f.write_str("the edit would change other nodes")?,
+        }
+        if self.path.is_root() {
+            f.write_str(" at the root of the document")
+        } else {
+            write!(f, " at `{}`", self.path)
+        }
+    }
+}
+
+/// Where a node is within its document.
+struct Site<'a> {
+    node: &'a MarkedYaml,
+    /// The collection containing the node, the position of the node within it, and the key of
+    /// the node if the collection is a mapping.
+    parent: Option<(&'a MarkedYaml, usize, Option<&'a MarkedYaml>)>,
+    /// Whether the node is within a flow collection.
+    in_flow: bool,
+}
+
+impl YamlDocument {
+    /// Load the document of the given source.
+    ///
+    /// # Errors
+    /// Returns `LoadError` if the source could not be loaded, or does not contain exactly one
+    /// document.
+    pub fn new<S: Into<String>>(source: S) -> Result<Self, LoadError> {
+        let source = source.into();
+        let root = MarkedYaml::load_one_from_str(&source)?;
+        Ok(Self { source, root })
+    }
+
+    /// Return the current source of the document.
+    #[must_use]
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Return the current source of the document, consuming `self` in the process.
+    #[must_use]
+    pub fn into_source(self) -> String {
+        self.source
+    }
+
+    /// Return the document loaded from the current source.
+    #[must_use]
+    pub fn root(&self) -> &MarkedYaml {
+        &self.root
+    }
+
+    /// Return the node at `path`, if any.
+    #[must_use]
+    pub fn get(&self, path: &YamlPath) -> Option<&MarkedYaml> {
+        path.segments()
+            .iter()
+            .try_fold(&self.root, |node, segment| match (&node.data, segment) {
+                (YamlData::Array(items), PathSegment::Index(i)) => items.get(*i),
+                (YamlData::Hash(mapping), PathSegment::Key(key)) => {
+                    mapping.iter().find(|(k, _)| is_key(k, key)).map(|(_, v)| v)
+                }
+                (YamlData::Hash(mapping), PathSegment::EntryKey(i)) => mapping.keys().nth(*i),
+                _ => None,
+            })
+    }
+
+    /// Replace the node at `path` with `value`, and return the node it replaces.
+    ///
+    /// Strings written in place of quoted scalars keep their quotes if they can, and nodes
+    /// within flow collections or replacing flow collections are written in flow style.
+    ///
+    /// # Errors
+    /// Returns `EditError` if there is no node at `path`, or if the edit cannot be made (see
+    /// [`EditErrorKind`]). `self` is then left unchanged.
+    pub fn set(&mut self, path: &YamlPath, value: Yaml) -> Result<Yaml, EditError> {
+        check_value(&value, path)?;
+        let site = self.find(path)?;
+        let previous = Yaml::from(site.node.clone());
+        let (range, text) = self.replacement(&site, &value, path)?;
+        let mut expected = Yaml::from(self.root.clone());
+        if let Some(node) = node_mut(&mut expected, path.segments()) {
+            *node = untagged_deep(value);
+        }
+        self.apply(range, &text, &expected, path)?;
+        Ok(previous)
+    }
+
+    /// Set the value of `key` in the mapping at `path` without moving its entry, or insert it at
+    /// the end of the mapping if it is not there yet. Returns the previous value, if any.
+    ///
+    /// New entries of block mappings are written on their own lines, at the indentation of the
+    /// other entries.
+    ///
+    /// # Errors
+    /// Returns `EditError` if there is no mapping at `path`, or if the edit cannot be made (see
+    /// [`EditErrorKind`]). `self` is then left unchanged.
+    pub fn insert(
+        &mut self,
+        path: &YamlPath,
+        key: impl Into<Yaml>,
+        value: Yaml,
+    ) -> Result<Option<Yaml>, EditError> {
+        let key = key.into();
+        check_value(&key, path)?;
+        check_value(&value, path)?;
+        let site = self.find(path)?;
+        let YamlData::Hash(mapping) = &site.node.data else {
+            return Err(wrong_type(path, YamlKind::Hash, site.node));
+        };
+        if mapping.keys().any(|k| is_key(k, &key)) {
+            return self.set(&path.join(PathSegment::Key(key)), value).map(Some);
+        }
+
+        // Block mappings are never empty.
+        let (range, text) = match (mapping.front(), mapping.back()) {
+            (Some((first_key, _)), Some((last_key, last_value))) if !self.is_flow(site.node) => {
+                let indent = first_key.span.start.col();
+                let mut entry = Hash::new();
+                entry.insert(key.clone(), value.clone());
+                let end = self.entry_end(Some(last_key), last_value);
+                self.append_line(end, indent, &block_text(&Yaml::Hash(entry), indent))
+            }
+            (_, last) => {
+                let end = last.map(|(k, v)| self.entry_end(Some(k), v));
+                let entry = format!("{}: {}", flow_text(&key), flow_text(&value));
+                self.append_flow(site.node, end, entry, path)?
+            }
+        };
+        let mut expected = Yaml::from(self.root.clone());
+        if let Some(Yaml::Hash(mapping)) = node_mut(&mut expected, path.segments()) {
+            mapping.insert(untagged_deep(key), untagged_deep(value));
+        }
+        self.apply(range, &text, &expected, path)?;
+        Ok(None)
+    }
+
+    /// Append `value` to the sequence at `path`.
+    ///
+    /// New items of block sequences are written on their own lines, at the indentation of the
+    /// other items.
+    ///
+    /// # Errors
+    /// Returns `EditError` if there is no sequence at `path`, or if the edit cannot be made (see
+    /// [`EditErrorKind`]). `self` is then left unchanged.
+    pub fn push(&mut self, path: &YamlPath, value: Yaml) -> Result<(), EditError> {
+        check_value(&value, path)?;
+        let site = self.find(path)?;
+        let YamlData::Array(items) = &site.node.data else {
+            return Err(wrong_type(path, YamlKind::Array, site.node));
+        };
+
+        // Block sequences are never empty.
+        let (range, text) = match items.last() {
+            Some(last) if !self.is_flow(site.node) => {
+                let indent = site.node.span.start.col();
+                let end = self.entry_end(None, last);
+                let item = Yaml::Array(vec![value.clone()]);
+                self.append_line(end, indent, &block_text(&item, indent))
+            }
+            last => {
+                let end = last.map(|item| self.entry_end(None, item));
+                self.append_flow(site.node, end, flow_text(&value), path)?
+            }
+        };
+        let mut expected = Yaml::from(self.root.clone());
+        if let Some(Yaml::Array(items)) = node_mut(&mut expected, path.segments()) {
+            items.push(untagged_deep(value));
+        }
+        self.apply(range, &text, &expected, path)
+    }
+
+    /// Remove the node at `path` from its collection, and return it.
+    ///
+    /// The entries of block collections are removed along with their lines, including their
+    /// end-of-line comments. Removing the only entry of a collection leaves it empty, in flow
+    /// style.
+    ///
+    /// # Errors
+    /// Returns `EditError` if there is no node at `path`, or if the edit cannot be made (see
+    /// [`EditErrorKind`]), e.g. for the root node. `self` is then left unchanged.
+    pub fn remove(&mut self, path: &YamlPath) -> Result<Yaml, EditError> {
+        let site = self.find(path)?;
+        let Some((parent, index, _)) = site.parent else {
+            return Err(unsupported(path));
+        };
+        let removed = Yaml::from(site.node.clone());
+        let count = match &parent.data {
+            YamlData::Array(items) => items.len(),
+            YamlData::Hash(mapping) => mapping.len(),
+            _ => unreachable!("the parent of a node is a collection"),
+        };
+        let parent_path = YamlPath::from(path.segments()[..path.segments().len() - 1].to_vec());
+        if count == 1 {
+            let empty = if parent.data.is_hash() {
+                Yaml::Hash(Hash::new())
+            } else {
+                Yaml::Array(vec![])
+            };
+            return self
+                .set(&parent_path, empty)
+                .map(|_| removed)
+                .map_err(|error| EditError {
+                    path: path.clone(),
+                    kind: error.kind,
+                });
+        }
+
+        let (start, end) = self.entry_bounds(parent, index, path)?;
+        let next = if index + 1 < count {
+            Some(self.entry_bounds(parent, index + 1, path)?.0)
+        } else {
+            None
+        };
+        let range = if self.is_flow(parent) {
+            match next {
+                Some(next) => start..next,
+                None => self.entry_bounds(parent, index - 1, path)?.1..end,
+            }
+        } else {
+            let line_start = self.line_start(start);
+            if self.source[line_start..start].trim().is_empty() {
+                line_start..self.after_line(end)
+            } else {
+                // The entry follows the indicator of its collection, e.g. `- a: 1`.
+                start..next.ok_or_else(|| unsupported(path))?
+            }
+        };
+        let mut expected = Yaml::from(self.root.clone());
+        match (node_mut(&mut expected, parent_path.segments()), site.parent) {
+            (Some(Yaml::Array(items)), _) => {
+                items.remove(index);
+            }
+            (Some(Yaml::Hash(mapping)), Some((_, _, Some(key)))) => {
+                mapping.remove(&Yaml::from(key.clone()));
+            }
+            _ => {}
+        }
+        self.apply(range, "", &expected, path)?;
+        Ok(removed)
+    }
+
+    /// Find the node at `path`, for an edit.
+    fn find(&self, path: &YamlPath) -> Result<Site<'_>, EditError> {
+        let mut site = Site {
+            node: &self.root,
+            parent: None,
+            in_flow: false,
+        };
+        for segment in path.segments() {
+            let in_flow = site.in_flow || self.is_flow(site.node);
+            let (index, key, node) = match (&site.node.data, segment) {
+                (YamlData::Array(items), PathSegment::Index(i)) => {
+                    items.get(*i).map(|item| (*i, None, item))
+                }
+                (YamlData::Hash(mapping), PathSegment::Key(key)) => mapping
+                    .iter()
+                    .enumerate()
+                    .find(|(_, (k, _))| is_key(k, key))
+                    .map(|(i, (k, v))| (i, Some(k), v)),
+                // Keys cannot be edited in place.
+                (YamlData::Hash(_), PathSegment::EntryKey(_)) => return Err(unsupported(path)),
+                _ => None,
+            }
+            .ok_or_else(|| EditError {
+                path: path.clone(),
+                kind: EditErrorKind::NotFound,
+            })?;
+            site = Site {
+                node,
+                parent: Some((site.node, index, key)),
+                in_flow,
+            };
+        }
+        Ok(site)
+    }
+
+    /// Return the range to replace and the text to replace it with, to set the node of `site`
+    /// to `value`.
+    fn replacement(
+        &self,
+        site: &Site,
+        value: &Yaml,
+        path: &YamlPath,
+    ) -> Result<(ByteRange, String), EditError> {
+        let node = site.node;
+        let range = node.byte_range(&self.source);
+        let quoted = self.quoted_text(node, value);
+        if site.in_flow || self.is_flow(node) {
+            return Ok((range, quoted.unwrap_or_else(|| flow_text(value))));
+        }
+        let is_block_collection = matches!(node.data, YamlData::Array(_) | YamlData::Hash(_));
+        let is_empty = node.span.start == node.span.end;
+        match site.parent {
+            None => {
+                let text = quoted.unwrap_or_else(|| block_text(value, node.span.start.col()));
+                Ok((range, text))
+            }
+            Some((_, _, Some(key))) => {
+                // Block collections start on the line after their key.
+                let is_block_value = matches!(
+                    value.untagged(),
+                    Yaml::Array(items) if !items.is_empty()
+                ) || matches!(value.untagged(), Yaml::Hash(mapping) if !mapping.is_empty());
+                let text = quoted.unwrap_or_else(|| block_text(value, 0));
+                if !is_block_value && !is_block_collection && !is_empty {
+                    return Ok((range, text));
+                }
+                // Write the value after the `:` of its key, or on the following lines.
+                let colon = self.colon_after(key).ok_or_else(|| unsupported(path))?;
+                let end = if is_empty { colon } else { range.end };
+                if !is_block_value {
+                    return Ok((colon..end, format!(" {text}")));
+                }
+                let indent =
+                    if is_block_collection && node.span.start.line() > key.span.start.line() {
+                        node.span.start.col()
+                    } else {
+                        key.span.start.col() + 2
+                    };
+                let text = format!("\n{}{}", " ".repeat(indent), block_text(value, indent));
+                Ok((colon..end, text))
+            }
+            Some((_, _, None)) => {
+                let text = quoted.unwrap_or_else(|| block_text(value, 0));
+                if !text.contains('\n') && !is_block_collection && !is_empty {
+                    return Ok((range, text));
+                }
+                // Write the whole item again, from its `-`.
+                let dash = self.dash_before(node).ok_or_else(|| unsupported(path))?;
+                let indent = self.source[self.line_start(dash)..dash].chars().count();
+                let text = block_text(&Yaml::Array(vec![value.clone()]), indent);
+                Ok((dash..range.end.max(dash), text))
+            }
+        }
+    }
+
+    /// Return the range to replace and the text to replace it with, to append `entry` to the
+    /// flow collection `collection`, whose last entry ends at `last_end` if it is not empty.
+    fn append_flow(
+        &self,
+        collection: &MarkedYaml,
+        last_end: Option<usize>,
+        entry: String,
+        path: &YamlPath,
+    ) -> Result<(ByteRange, String), EditError> {
+        if let Some(end) = last_end {
+            return Ok((end..end, format!(", {entry}")));
+        }
+        // Write the entry before the closing delimiter.
+        let end = collection.byte_range(&self.source).end;
+        if !self.source[..end].ends_with([']', '}']) {
+            return Err(unsupported(path));
+        }
+        Ok((end - 1..end - 1, entry))
+    }
+
+    /// Return the range to replace and the text to replace it with, to write `text` on a new
+    /// line after the one ending at `end`, indented by `indent` columns.
+    fn append_line(&self, end: usize, indent: usize, text: &str) -> (ByteRange, String) {
+        let indent = " ".repeat(indent);
+        if self.line_start(end) == end {
+            // The previous entry (e.g. a block scalar) ends with its line break.
+            (end..end, format!("{indent}{text}\n"))
+        } else {
+            let end = self.line_end(end);
+            (end..end, format!("\n{indent}{text}"))
+        }
+    }
+
+    /// Return the byte range of the text of entry `index` of the collection `parent`.
+    ///
+    /// Mapping entries start at their key and end at their value. Items of block sequences start
+    /// at their `-`.
+    fn entry_bounds(
+        &self,
+        parent: &MarkedYaml,
+        index: usize,
+        path: &YamlPath,
+    ) -> Result<(usize, usize), EditError> {
+        match &parent.data {
+            YamlData::Hash(mapping) => {
+                let (key, value) = mapping.iter().nth(index).unwrap();
+                let start = key.byte_range(&self.source).start;
+                Ok((start, self.entry_end(Some(key), value)))
+            }
+            YamlData::Array(items) => {
+                let item = &items[index];
+                let start = if self.is_flow(parent) {
+                    item.byte_range(&self.source).start
+                } else {
+                    self.dash_before(item).ok_or_else(|| unsupported(path))?
+                };
+                Ok((start, self.entry_end(None, item)))
+            }
+            _ => unreachable!("the parent of a node is a collection"),
+        }
+    }
+
+    /// Return the byte offset of the end of the entry made of `key` (if any) and `value`.
+    ///
+    /// Empty values end with their key, since their span is where the next token starts.
+    fn entry_end(&self, key: Option<&MarkedYaml>, value: &MarkedYaml) -> usize {
+        match key {
+            Some(key) if value.span.start == value.span.end => key.byte_range(&self.source).end,
+            _ => value.byte_range(&self.source).end,
+        }
+    }
+
+    /// Return whether `node` is a flow collection.
+    fn is_flow(&self, node: &MarkedYaml) -> bool {
+        let start = byte_index(&self.source, node.span.start);
+        let text = skip_properties(&self.source[start..]);
+        match &node.data {
+            YamlData::Array(_) => text.starts_with('['),
+            // A block mapping may start with a flow mapping as its first key.
+            YamlData::Hash(mapping) => {
+                let brace = self.source.len() - text.len();
+                text.starts_with('{')
+                    && mapping
+                        .keys()
+                        .next()
+                        .map_or(true, |key| key.byte_range(&self.source).start > brace)
+            }
+            _ => false,
+        }
+    }
+
+    /// Return `value` written with the quotes of the scalar `node`, if `node` is quoted and
+    /// `value` is a string that can be written with them.
+    fn quoted_text(&self, node: &MarkedYaml, value: &Yaml) -> Option<String> {
+        let Yaml::String(value) = value else {
+            return None;
+        };
+        if matches!(node.data, YamlData::Array(_) | YamlData::Hash(_)) {
+            return None;
+        }
+        let start = byte_index(&self.source, node.span.start);
+        match skip_properties(&self.source[start..]).chars().next() {
+            Some('\'') if !value.contains(char::is_control) => {
+                Some(format!("'{}'", value.replace('\'', "''")))
+            }
+            Some('"') => {
+                let mut text = String::new();
+                escape_str(&mut text, value).unwrap();
+                Some(text)
+            }
+            _ => None,
+        }
+    }
+
+    /// Return the byte offset right after the `:` following `key`, if it is on the same line.
+    fn colon_after(&self, key: &MarkedYaml) -> Option<usize> {
+        let end = key.byte_range(&self.source).end;
+        let after = self.source[end..].trim_start_matches([' ', '\t']);
+        after
+            .starts_with(':')
+            .then(|| self.source.len() - after.len() + 1)
+    }
+
+    /// Return the byte offset of the `-` introducing the block sequence item `node`, if any.
+    fn dash_before(&self, node: &MarkedYaml) -> Option<usize> {
+        let start = byte_index(&self.source, node.span.start);
+        let before = self.source[..start].trim_end();
+        before.ends_with('-').then(|| before.len() - 1)
+    }
+
+    /// Return the byte offset of the start of the line containing `byte`.
+    fn line_start(&self, byte: usize) -> usize {
+        self.source[..byte].rfind('\n').map_or(0, |i| i + 1)
+    }
+
+    /// Return the byte offset of the line break ending the line containing `byte`, or of the
+    /// end of the source.
+    fn line_end(&self, byte: usize) -> usize {
+        self.source[byte..]
+            .find('\n')
+            .map_or(self.source.len(), |i| byte + i)
+    }
+
+    /// Return the byte offset of the start of the line after the one containing `byte`, unless
+    /// `byte` is already at the start of a line.
+    fn after_line(&self, byte: usize) -> usize {
+        if self.line_start(byte) == byte {
+            byte
+        } else {
+            (self.line_end(byte) + 1).min(self.source.len())
+        }
+    }
+
+    /// Replace `range` of the source with `text`, if the edited source loads as `expected`.
+    fn apply(
+        &mut self,
+        range: ByteRange,
+        text: &str,
+        expected: &Yaml,
+        path: &YamlPath,
+    ) -> Result<(), EditError> {
+        let mut source = self.source.clone();
+        source.replace_range(range, text);
+        match MarkedYaml::load_one_from_str(&source) {
+            Ok(root) if Yaml::from(root.clone()) == *expected => {
+                self.source = source;
+                self.root = root;
+                Ok(())
+            }
+            _ => Err(unsupported(path)),
+        }
+    }
+}
+
+impl Display for YamlDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Return whether the mapping key `node` is `key`.
+fn is_key(node: &MarkedYaml, key: &Yaml) -> bool {
+    Yaml::from(node.clone()) == *key.untagged()
+}
+
+/// Return the node at the end of `segments` from `node`, for modification.
+fn node_mut<'a>(node: &'a mut Yaml, segments: &[PathSegment]) -> Option<&'a mut Yaml> {
+    segments
+        .iter()
+        .try_fold(node, |node, segment| match (node, segment) {
+            (Yaml::Array(items), PathSegment::Index(i)) => items.get_mut(*i),
+            (Yaml::Hash(mapping), PathSegment::Key(key)) => mapping.get_mut(key.untagged()),
+            _ => None,
+        })
+}
+
+/// Remove the tags of `node` and its children, which documents do not keep.
+fn untagged_deep(node: Yaml) -> Yaml {
+    match node.into_untagged() {
+        Yaml::Array(items) => Yaml::Array(items.into_iter().map(untagged_deep).collect()),
+        Yaml::Hash(mapping) => Yaml::Hash(
+            mapping
+                .into_iter()
+                .map(|(k, v)| (untagged_deep(k), untagged_deep(v)))
+                .collect(),
+        ),
+        node => node,
+    }
+}
+
+/// Check that `value` can be written.
+fn check_value(value: &Yaml, path: &YamlPath) -> Result<(), EditError> {
+    let valid = match value {
+        Yaml::Alias(_) | Yaml::BadValue => false,
+        Yaml::Array(items) => items.iter().all(|item| check_value(item, path).is_ok()),
+        Yaml::Hash(mapping) => mapping
+            .iter()
+            .all(|(k, v)| check_value(k, path).is_ok() && check_value(v, path).is_ok()),
+        Yaml::Tagged(_, node) => check_value(node, path).is_ok(),
+        _ => true,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(EditError {
+            path: path.clone(),
+            kind: EditErrorKind::InvalidValue,
+        })
+    }
+}
+
+/// Skip the tag and anchor at the start of `text`, if any.
+fn skip_properties(mut text: &str) -> &str {
+    while text.starts_with(['!', '&']) {
+        let end = text.find(char::is_whitespace).unwrap_or(text.len());
+        text = text[end..].trim_start();
+    }
+    text
+}
+
+/// Write `value` in block style, as the emitter does, with the lines after the first indented by
+/// `indent` columns.
+fn block_text(value: &Yaml, indent: usize) -> String {
+    let mut text = String::new();
+    let mut emitter = YamlEmitter::new(&mut text);
+    emitter.explicit_start(false);
+    emitter.dump(value).unwrap();
+    text.replace('\n', &format!("\n{}", " ".repeat(indent)))
+}
+
+/// Write `value` in flow style, on a single line.
+fn flow_text(value: &Yaml) -> String {
+    match value {
+        Yaml::Array(items) => {
+            let items: Vec<String> = items.iter().map(flow_text).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Yaml::Hash(mapping) => {
+            let entries: Vec<String> = mapping
+                .iter()
+                .map(|(k, v)| format!("{}: {}", flow_text(k), flow_text(v)))
+                .collect();
+            format!("{{{}}}", entries.join(", "))
+        }
+        Yaml::Tagged(tag, node) => format!("{} {}", tag_text(tag), flow_text(node)),
+        scalar => block_text(scalar, 0),
+    }
+}
+
+/// Return an [`EditErrorKind::WrongType`] error for `node`.
+fn wrong_type(path: &YamlPath, expected: YamlKind, node: &MarkedYaml) -> EditError {
+    EditError {
+        path: path.clone(),
+        kind: EditErrorKind::WrongType {
+            expected,
+            actual: Yaml::from(node.clone()).kind(),
+        },
+    }
+}
+
+/// Return an [`EditErrorKind::Unsupported`] error.
+fn unsupported(path: &YamlPath) -> EditError {
+    EditError {
+        path: path.clone(),
+        kind: EditErrorKind::Unsupported,
+    }
+}
@@ -1,28 +1,65 @@
 //! YAML serialization helpers.
 
+use crate::annotated::commented_yaml::{CommentedYaml, Comments};
 use crate::char_traits;
+use crate::loader::parse_f64;
 use crate::yaml::{Hash, Yaml};
-use std::convert::From;
+use crate::{schema, CancelToken, PathSegment, Schema, YamlPath};
+use alloc::{
+    borrow::ToOwned,
+    collections::{BTreeMap, BTreeSet},
+    rc::Rc,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::cmp::Ordering;
+use core::convert::From;
+use core::fmt::{self, Display, Write as _};
+use saphyr_parser::Tag;
+#[cfg(feature = "std")]
 use std::error::Error;
-use std::fmt::{self, Display};
+#[cfg(feature = "std")]
+use std::io::{self, Write as _};
+
+/// The maximum length of an implicit key, in characters (see [`YamlEmitter::dump`]).
+const MAX_IMPLICIT_KEY_LENGTH: usize = 1024;
 
 /// An error when emitting YAML.
-#[derive(Copy, Clone, Debug)]
+#[derive(Debug)]
 pub enum EmitError {
     /// A formatting error.
     FmtError(fmt::Error),
+    /// An error of the [`io::Write`] given to [`YamlEmitter::new_io`].
+    #[cfg(feature = "std")]
+    Io(io::Error),
+    /// Emission was cancelled through [`YamlEmitter::cancel_token`].
+    Cancelled,
+    /// An indentation given to [`YamlEmitter::indent`] is not between 1 and 9.
+    InvalidIndent(usize),
 }
 
+#[cfg(feature = "std")]
 impl Error for EmitError {
-    fn cause(&self) -> Option<&dyn Error> {
-        None
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            EmitError::FmtError(err) => Some(err),
+            EmitError::Io(err) => Some(err),
+            EmitError::Cancelled | EmitError::InvalidIndent(_) => None,
+        }
     }
 }
 
 impl Display for EmitError {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            EmitError::FmtError(ref err) => Display::fmt(err, formatter),
+        match self {
+            EmitError::FmtError(err) => Display::fmt(err, formatter),
+            #[cfg(feature = "std")]
+            EmitError::Io(err) => Display::fmt(err, formatter),
+            EmitError::Cancelled => formatter.write_str("emission was cancelled"),
+            EmitError::InvalidIndent(indent) => write!(
+                formatter,
+                "the indentation must be between 1 and 9, not {indent}"
+            ),
         }
     }
 }
@@ -33,6 +70,378 @@ impl From<fmt::Error> for EmitError {
     }
 }
 
+/// How the emitter aligns the values of a block mapping.
+///
+/// ```
+/// # use saphyr::{AlignPolicy, Yaml, YamlEmitter};
+/// let yaml = Yaml::load_from_str("a: 1\nlong_key: 2").unwrap();
+///
+/// let mut output = String::new();
+/// let mut emitter = YamlEmitter::new(&mut output);
+/// emitter.align_values(AlignPolicy::PerMapping { max_pad: 16 });
+/// emitter.dump(&yaml[0]).unwrap();
+///
+/// assert_eq!(output, "---\na:        1\nlong_key: 2");
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum AlignPolicy {
+    /// Values immediately follow the `:` of their key.
+    #[default]
+    None,
+    /// Values of each block mapping are padded to a common column, right after its longest key.
+    ///
+    /// Entries which would need more than `max_pad` spaces of padding are left unaligned.
+    /// Values that are written on their own lines (non-empty collections, block scalars) and
+    /// complex keys are exempt from alignment.
+    PerMapping {
+        /// The maximum number of spaces inserted after the `:` of a key.
+        max_pad: usize,
+    },
+}
+
+/// How the emitter writes multiline strings, when [`YamlEmitter::multiline_strings`] is set.
+///
+/// ```
+/// # use saphyr::{BlockStyle, Yaml, YamlEmitter};
+/// let mut output = String::new();
+/// let mut emitter = YamlEmitter::new(&mut output);
+/// emitter.multiline_strings(true);
+/// emitter.block_style(BlockStyle::Folded);
+/// emitter.dump(&Yaml::String("one\ntwo\n".into())).unwrap();
+///
+/// assert_eq!(output, "---\n>\none\n\ntwo");
+/// assert_eq!(Yaml::load_from_str(&output).unwrap()[0].as_str(), Some("one\ntwo\n"));
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum BlockStyle {
+    /// Literal block scalars (`|`), whose lines are kept as they are.
+    #[default]
+    Literal,
+    /// Folded block scalars (`>`), whose lines are separated by blank lines and, with a
+    /// [`YamlEmitter::line_width`], broken at spaces.
+    ///
+    /// Strings with lines starting with a blank are still written as literal block scalars,
+    /// since folding does not apply to them.
+    Folded,
+}
+
+impl BlockStyle {
+    /// Return the name of the style in `snake_case`, as used by [`YamlEmitter::describe`].
+    fn name(self) -> &'static str {
+        match self {
+            BlockStyle::Literal => "literal",
+            BlockStyle::Folded => "folded",
+        }
+    }
+}
+
+/// How the emitter writes null values.
+///
+/// ```
+/// # use saphyr::{NullStyle, Yaml, YamlEmitter};
+/// let yaml = Yaml::load_from_str("a: ~\nb: [~]").unwrap();
+///
+/// let mut output = String::new();
+/// let mut emitter = YamlEmitter::new(&mut output);
+/// emitter.null_style(NullStyle::Empty);
+/// emitter.dump(&yaml[0]).unwrap();
+///
+/// assert_eq!(output, "---\na:\nb:\n  -");
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum NullStyle {
+    /// `~`.
+    #[default]
+    Tilde,
+    /// `null`.
+    Lowercase,
+    /// `Null`.
+    Capitalized,
+    /// Nothing, e.g. `key:`.
+    ///
+    /// Only the values of block mappings and the entries of block sequences can be empty.
+    /// Other nulls (e.g. keys, documents or entries of flow collections) are written `null`.
+    Empty,
+}
+
+impl NullStyle {
+    /// Return the name of the style in `snake_case`, as used by [`YamlEmitter::describe`].
+    fn name(self) -> &'static str {
+        match self {
+            NullStyle::Tilde => "tilde",
+            NullStyle::Lowercase => "lowercase",
+            NullStyle::Capitalized => "capitalized",
+            NullStyle::Empty => "empty",
+        }
+    }
+}
+
+/// When the emitter quotes strings, and how.
+///
+/// Whatever the policy, strings which would not load back as the same string unquoted (e.g.
+/// `true`, `1.0` or `a: b`) are quoted, and strings which need escapes (e.g. control characters)
+/// are double-quoted. Canonical output (see [`YamlEmitter::canonical`]) is always double-quoted.
+///
+/// ```
+/// # use saphyr::{QuoteStyle, Yaml, YamlEmitter};
+/// let yaml = Yaml::load_from_str("[plain, 'true', 'say \"hi\"']").unwrap();
+///
+/// let mut output = String::new();
+/// let mut emitter = YamlEmitter::new(&mut output);
+/// emitter.quote_style(QuoteStyle::PreferSingle);
+/// emitter.dump(&yaml[0]).unwrap();
+///
+/// assert_eq!(output, "---\n- plain\n- 'true'\n- 'say \"hi\"'");
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum QuoteStyle {
+    /// Strings are only quoted when needed, in double quotes.
+    #[default]
+    Minimal,
+    /// Strings are only quoted when needed, in single quotes where possible.
+    PreferSingle,
+    /// Strings are only quoted when needed, in double quotes unless they contain `"` or `\`,
+    /// which single quotes need not escape.
+    PreferDouble,
+    /// All strings are double-quoted, including keys. Multiline strings are not written as
+    /// block scalars (see [`YamlEmitter::multiline_strings`]), and long strings are not folded
+    /// (see [`YamlEmitter::line_width`]).
+    AlwaysDouble,
+}
+
+impl QuoteStyle {
+    /// Return the name of the style in `snake_case`, as used by [`YamlEmitter::describe`].
+    fn name(self) -> &'static str {
+        match self {
+            QuoteStyle::Minimal => "minimal",
+            QuoteStyle::PreferSingle => "prefer_single",
+            QuoteStyle::PreferDouble => "prefer_double",
+            QuoteStyle::AlwaysDouble => "always_double",
+        }
+    }
+}
+
+/// The style in which the emitter writes a collection.
+///
+/// See [`YamlEmitter::collection_style`] to choose the style of a single collection.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum CollectionStyle {
+    /// Entries are written on their own lines, e.g. `- 1` or `a: 1`. Collections within a flow
+    /// collection are still written in flow style.
+    #[default]
+    Block,
+    /// Entries are written on a single line, e.g. `[1, 2]` or `{a: 1, b: [2, 3]}`. Collections
+    /// within the collection are written in flow style as well.
+    Flow,
+}
+
+/// The order in which the emitter writes the keys of mappings.
+///
+/// ```
+/// # use saphyr::{KeyOrder, Yaml, YamlEmitter};
+/// let yaml = Yaml::load_from_str("b: 1\n10: 2\na: {z: 3, y: 4}\n2: 5").unwrap();
+///
+/// let mut output = String::new();
+/// let mut emitter = YamlEmitter::new(&mut output);
+/// emitter.key_order(KeyOrder::Sorted);
+/// emitter.dump(&yaml[0]).unwrap();
+///
+/// assert_eq!(output, "---\n2: 5\n10: 2\na:\n  y: 4\n  z: 3\nb: 1");
+/// ```
+#[derive(Copy, Clone, Debug, Default)]
+pub enum KeyOrder {
+    /// Keys are written in the order of the [`Hash`], i.e. in insertion order.
+    #[default]
+    Insertion,
+    /// Keys are sorted, so that the output does not depend on the order in which they were
+    /// inserted. The document itself is left untouched.
+    ///
+    /// Keys are ordered by type first (null, booleans, integers, floats, timestamps, strings,
+    /// binaries, sequences, then mappings), then by value. Floats are compared numerically, strings
+    /// byte-wise, and sequences and mappings by their serialized (and sorted) form. This applies
+    /// to nested mappings as well.
+    Sorted,
+    /// Keys are sorted with the given comparison function, e.g. to put some keys first. The
+    /// sort is stable, so keys which compare equal stay in insertion order.
+    ///
+    /// ```
+    /// # use saphyr::{KeyOrder, Yaml, YamlEmitter};
+    /// let yaml = Yaml::load_from_str("spec: {}\nname: web\nkind: Pod").unwrap();
+    ///
+    /// let mut output = String::new();
+    /// let mut emitter = YamlEmitter::new(&mut output);
+    /// emitter.key_order(KeyOrder::Custom(|a, b| {
+    ///     let rank = |key: &Yaml| ["kind", "name"].iter().position(|k| key.as_str() == Some(*k));
+    ///     rank(a).unwrap_or(usize::MAX).cmp(&rank(b).unwrap_or(usize::MAX))
+    /// }));
+    /// emitter.dump(&yaml[0]).unwrap();
+    ///
+    /// assert_eq!(output, "---\nkind: Pod\nname: web\nspec: {}");
+    /// ```
+    Custom(KeyOrderFn),
+}
+
+impl KeyOrder {
+    /// Return the name of the order in `snake_case`, as used by [`YamlEmitter::describe`].
+    fn name(self) -> &'static str {
+        match self {
+            KeyOrder::Insertion => "insertion",
+            KeyOrder::Sorted => "sorted",
+            KeyOrder::Custom(_) => "custom",
+        }
+    }
+}
+
+/// The comparison function of [`KeyOrder::Custom`], given two keys of a mapping.
+pub type KeyOrderFn = fn(&Yaml, &Yaml) -> Ordering;
+
+/// The signature of the callback of [`AnchorNaming::Custom`].
+///
+/// It is given a node and the path to its first occurrence and returns the name of its anchor.
+pub type AnchorNamingFn = dyn Fn(&Yaml, &YamlPath) -> String;
+
+/// How the emitter names the anchors it generates when emitting aliases.
+///
+/// See [`YamlEmitter::emit_aliases`]. Whatever the strategy, names are sanitized so that they only
+/// contain ASCII alphanumeric characters, `_` and `-`. If two anchors end up with the same name,
+/// the second one is suffixed with `_2`, the third one with `_3`, and so on, in document order.
+#[derive(Clone, Default)]
+pub enum AnchorNaming {
+    /// Name anchors after the path to the first occurrence of the node, e.g. `spec_template`
+    /// for `/spec/template`.
+    ///
+    /// Names do not change when unrelated mapping keys are added or removed.
+    #[default]
+    ByFirstPath,
+    /// Name anchors with `prefix` followed by a hash of the content of the node.
+    ///
+    /// Names do not change when the document changes, as long as the content of the node does
+    /// not.
+    ByContentHash {
+        /// The prefix of each anchor name.
+        prefix: String,
+    },
+    /// Name anchors with a user callback, given the node and the path to its first occurrence.
+    Custom(Rc<AnchorNamingFn>),
+}
+
+impl fmt::Debug for AnchorNaming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ByFirstPath => f.write_str("ByFirstPath"),
+            Self::ByContentHash { prefix } => f
+                .debug_struct("ByContentHash")
+                .field("prefix", prefix)
+                .finish(),
+            Self::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+/// The size of the buffer of [`YamlEmitter::new_io`].
+#[cfg(feature = "std")]
+const IO_BUFFER_SIZE: usize = 8 * 1024;
+
+/// Where a [`YamlEmitter`] writes its output, and the column it is at.
+struct Output<'a> {
+    target: Target<'a>,
+    /// The number of characters written since the last line break, to wrap flow collections
+    /// (see [`YamlEmitter::line_width`]).
+    column: usize,
+}
+
+/// What an [`Output`] writes to.
+enum Target<'a> {
+    /// A [`fmt::Write`], written to directly.
+    Fmt(&'a mut dyn fmt::Write),
+    /// An [`io::Write`], written to by chunks of up to [`IO_BUFFER_SIZE`] bytes.
+    ///
+    /// [`fmt::Write`] errors cannot carry an [`io::Error`], so the first one is kept aside until
+    /// the end of the [`YamlEmitter::dump`] that failed. The buffer is not a [`io::BufWriter`],
+    /// which would flush on drop and thus keep `writer` borrowed for as long as the emitter is in
+    /// scope.
+    #[cfg(feature = "std")]
+    Io {
+        writer: &'a mut dyn io::Write,
+        buffer: Vec<u8>,
+        error: Option<io::Error>,
+    },
+}
+
+impl<'a> Output<'a> {
+    fn new(target: Target<'a>) -> Self {
+        Output { target, column: 0 }
+    }
+
+    /// Flush the output at the end of a dump, replacing a failing `result` with the I/O error
+    /// which caused it, if any.
+    fn finish(&mut self, result: EmitResult) -> EmitResult {
+        match &mut self.target {
+            Target::Fmt(_) => result,
+            #[cfg(feature = "std")]
+            Target::Io {
+                writer,
+                buffer,
+                error,
+            } => {
+                if let Some(err) = error.take() {
+                    buffer.clear();
+                    return Err(EmitError::Io(err));
+                }
+                writer.write_all(buffer).map_err(EmitError::Io)?;
+                buffer.clear();
+                result?;
+                writer.flush().map_err(EmitError::Io)
+            }
+        }
+    }
+}
+
+impl fmt::Write for Output<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        match &mut self.target {
+            Target::Fmt(writer) => writer.write_str(s)?,
+            #[cfg(feature = "std")]
+            Target::Io { error: Some(_), .. } => return Err(fmt::Error),
+            #[cfg(feature = "std")]
+            Target::Io {
+                writer,
+                buffer,
+                error,
+            } => write_buffered(*writer, buffer, s.as_bytes()).map_err(|err| {
+                *error = Some(err);
+                fmt::Error
+            })?,
+        }
+        match s.rfind('\n') {
+            Some(i) => self.column = s[i + 1..].chars().count(),
+            None => self.column += s.chars().count(),
+        }
+        Ok(())
+    }
+}
+
+/// Append `bytes` to `buffer`, first writing `buffer` to `writer` if it would get too large.
+///
+/// `bytes` are written directly if they would not fit in the buffer anyway.
+#[cfg(feature = "std")]
+fn write_buffered(
+    writer: &mut dyn io::Write,
+    buffer: &mut Vec<u8>,
+    bytes: &[u8],
+) -> io::Result<()> {
+    if buffer.len() + bytes.len() > IO_BUFFER_SIZE {
+        writer.write_all(buffer)?;
+        buffer.clear();
+    }
+    if bytes.len() >= IO_BUFFER_SIZE {
+        writer.write_all(bytes)
+    } else {
+        buffer.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
 /// The YAML serializer.
 ///
 /// ```
@@ -47,20 +456,49 @@ impl From<fmt::Error> for EmitError {
 /// a: b
 /// c: d"#);
 /// ```
-#[allow(clippy::module_name_repetitions)]
+#[allow(clippy::module_name_repetitions, clippy::struct_excessive_bools)]
 pub struct YamlEmitter<'a> {
-    writer: &'a mut dyn fmt::Write,
+    writer: Output<'a>,
     best_indent: usize,
     compact: bool,
     level: isize,
     multiline_strings: bool,
+    block_style: BlockStyle,
+    line_width: Option<usize>,
+    schema: Schema,
+    quote_style: QuoteStyle,
+    null_style: NullStyle,
+    canonical: bool,
+    explicit_start: bool,
+    explicit_end: bool,
+    /// The number of documents dumped so far, to separate them.
+    documents: usize,
+    align_values: AlignPolicy,
+    blank_lines_between_top_level: u8,
+    key_order: KeyOrder,
+    flow_depth: Option<usize>,
+    /// The tag handles declared with `%TAG` directives, with their prefix.
+    tag_handles: BTreeMap<String, String>,
+    /// The styles of specific collections, by path.
+    collection_styles: BTreeMap<YamlPath, CollectionStyle>,
+    cancel: Option<CancelToken>,
+    /// The comments to write, by path, while in [`Self::dump_commented`].
+    comments: BTreeMap<YamlPath, Comments>,
+    /// The path to the node being emitted. Only tracked if there are `comments` or
+    /// `collection_styles`.
+    path: YamlPath,
+    emit_aliases: bool,
+    anchor_naming: AnchorNaming,
+    /// The anchor name of each collection that is emitted more than once, and whether its anchor
+    /// has already been emitted. Only set while in [`Self::dump`].
+    anchors: BTreeMap<Yaml, (String, bool)>,
 }
 
 /// A convenience alias for emitter functions that may fail without returning a value.
 pub type EmitResult = Result<(), EmitError>;
 
 // from serialize::json
-fn escape_str(wr: &mut dyn fmt::Write, v: &str) -> Result<(), fmt::Error> {
+pub(crate) fn escape_str(wr: &mut dyn fmt::Write, v: &str) -> Result<(), fmt::Error> {
     wr.write_str("\"")?;
 
     let mut start = 0;
@@ -125,13 +563,140 @@ fn escape_str(wr: &mut dyn fmt::Write, v: &str) -> Result<(), fmt::Error> {
 impl<'a> YamlEmitter<'a> {
     /// Create a new emitter serializing into `writer`.
     pub fn new(writer: &'a mut dyn fmt::Write) -> YamlEmitter {
+        Self::with_output(Output::new(Target::Fmt(writer)))
+    }
+
+    /// Create a new emitter serializing into `writer`, e.g. a [`File`](std::fs::File).
+    ///
+    /// The output is buffered and flushed at the end of each [`dump`](Self::dump), so that it is
+    /// streamed without being built in memory first. Errors of `writer` are returned as
+    /// [`EmitError::Io`].
+    ///
+    /// ```
+    /// # use saphyr::{Yaml, YamlEmitter};
+    /// let yaml = Yaml::load_from_str("a: [1, 2]").unwrap();
+    ///
+    /// let mut output = Vec::new();
+    /// YamlEmitter::new_io(&mut output).dump(&yaml[0]).unwrap();
+    ///
+    /// assert_eq!(output, b"---\na:\n  - 1\n  - 2");
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn new_io(writer: &'a mut dyn io::Write) -> YamlEmitter {
+        Self::with_output(Output::new(Target::Io {
+            writer,
+            buffer: Vec::with_capacity(IO_BUFFER_SIZE),
+            error: None,
+        }))
+    }
+
+    fn with_output(writer: Output<'a>) -> YamlEmitter {
         YamlEmitter {
             writer,
             best_indent: 2,
             compact: true,
             level: -1,
             multiline_strings: false,
+            block_style: BlockStyle::Literal,
+            line_width: None,
+            schema: Schema::Core,
+            quote_style: QuoteStyle::Minimal,
+            null_style: NullStyle::Tilde,
+            canonical: false,
+            explicit_start: true,
+            explicit_end: false,
+            documents: 0,
+            align_values: AlignPolicy::None,
+            blank_lines_between_top_level: 0,
+            key_order: KeyOrder::Insertion,
+            flow_depth: None,
+            tag_handles: BTreeMap::new(),
+            collection_styles: BTreeMap::new(),
+            cancel: None,
+            comments: BTreeMap::new(),
+            path: YamlPath::new(),
+            emit_aliases: false,
+            anchor_naming: AnchorNaming::ByFirstPath,
+            anchors: BTreeMap::new(),
+        }
+    }
+
+    /// Create an emitter writing into `writer` with the same settings as `self`.
+    ///
+    /// This is used to render a node in a temporary buffer, e.g. to measure it.
+    fn fork<'b>(&self, writer: &'b mut dyn fmt::Write) -> YamlEmitter<'b> {
+        YamlEmitter {
+            writer: Output::new(Target::Fmt(writer)),
+            best_indent: self.best_indent,
+            compact: self.compact,
+            level: self.level,
+            multiline_strings: self.multiline_strings,
+            block_style: self.block_style,
+            line_width: self.line_width,
+            schema: self.schema,
+            quote_style: self.quote_style,
+            null_style: self.null_style,
+            canonical: self.canonical,
+            explicit_start: self.explicit_start,
+            explicit_end: self.explicit_end,
+            documents: 0,
+            align_values: self.align_values,
+            blank_lines_between_top_level: self.blank_lines_between_top_level,
+            key_order: self.key_order,
+            flow_depth: self.flow_depth,
+            tag_handles: self.tag_handles.clone(),
+            // Forks render nodes out of the document, to which the paths do not apply.
+            collection_styles: BTreeMap::new(),
+            cancel: self.cancel.clone(),
+            comments: BTreeMap::new(),
+            path: YamlPath::new(),
+            emit_aliases: self.emit_aliases,
+            anchor_naming: self.anchor_naming.clone(),
+            anchors: BTreeMap::new(),
+        }
+    }
+
+    /// Set the number of spaces by which nested block collections are indented. Defaults to 2.
+    ///
+    /// Entries of sequences are indented too, with their `-`. In [`Self::compact`] notation, the
+    /// `-` of an entry is followed by enough spaces for the first entry of a nested collection to
+    /// line up with the next ones. An indentation of 1 leaves no room for them, so nested
+    /// collections then start on the line after the `-`.
+    ///
+    /// # Errors
+    /// Returns [`EmitError::InvalidIndent`], leaving the indentation unchanged, if `indent` is not
+    /// between 1 and 9, the indentations which the indentation indicator of block scalars can
+    /// express.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use saphyr::{Yaml, YamlEmitter};
+    ///
+    /// let parsed = Yaml::load_from_str("a: [{b: 1, c: 2}]").unwrap();
+    ///
+    /// let mut output = String::new();
+    /// let mut emitter = YamlEmitter::new(&mut output);
+    /// emitter.indent(4).unwrap();
+    /// emitter.dump(&parsed[0]).unwrap();
+    /// assert_eq!(output.as_str(), "\
+    /// ---
+    /// a:
+    ///     -   b: 1
+    ///         c: 2");
+    /// ```
+    pub fn indent(&mut self, indent: usize) -> EmitResult {
+        if !(1..=9).contains(&indent) {
+            return Err(EmitError::InvalidIndent(indent));
         }
+        self.best_indent = indent;
+        Ok(())
+    }
+
+    /// Determine the number of spaces by which this emitter indents nested block collections.
+    #[must_use]
+    pub fn get_indent(&self) -> usize {
+        self.best_indent
     }
 
     /// Set 'compact inline notation' on or off, as described for block
@@ -188,170 +753,1875 @@ impl<'a> YamlEmitter<'a> {
         self.multiline_strings
     }
 
-    /// Dump Yaml to an output stream.
-    /// # Errors
-    /// Returns `EmitError` when an error occurs.
-    pub fn dump(&mut self, doc: &Yaml) -> EmitResult {
-        // write DocumentStart
-        writeln!(self.writer, "---")?;
-        self.level = -1;
-        self.emit_node(doc)
+    /// Set the style of the block scalars of [`Self::multiline_strings`]. Defaults to
+    /// [`BlockStyle::Literal`].
+    ///
+    /// Either way, the chomping indicator follows the string: `|-` (or `>-`) if it does not end
+    /// with a line break, and `|` (or `>`) if it ends with one. Strings ending with several line
+    /// breaks are quoted instead.
+    pub fn block_style(&mut self, style: BlockStyle) {
+        self.block_style = style;
     }
 
-    fn write_indent(&mut self) -> EmitResult {
-        if self.level <= 0 {
-            return Ok(());
-        }
-        for _ in 0..self.level {
-            for _ in 0..self.best_indent {
-                write!(self.writer, " ")?;
-            }
-        }
-        Ok(())
+    /// Determine the style of the block scalars this emitter writes.
+    #[must_use]
+    pub fn get_block_style(&self) -> BlockStyle {
+        self.block_style
     }
 
-    fn emit_node(&mut self, node: &Yaml) -> EmitResult {
-        match *node {
-            Yaml::Array(ref v) => self.emit_array(v),
-            Yaml::Hash(ref h) => self.emit_hash(h),
-            Yaml::String(ref v) => {
-                if self.multiline_strings
-                    && v.contains('\n')
-                    && char_traits::is_valid_literal_block_scalar(v)
-                {
-                    self.emit_literal_block(v)?;
-                } else if need_quotes(v) {
-                    escape_str(self.writer, v)?;
-                } else {
-                    write!(self.writer, "{v}")?;
-                }
-                Ok(())
-            }
-            Yaml::Boolean(v) => {
-                if v {
-                    self.writer.write_str("true")?;
-                } else {
-                    self.writer.write_str("false")?;
-                }
-                Ok(())
-            }
-            Yaml::Integer(v) => {
-                write!(self.writer, "{v}")?;
-                Ok(())
-            }
-            Yaml::Real(ref v) => {
-                write!(self.writer, "{v}")?;
-                Ok(())
-            }
-            Yaml::Null | Yaml::BadValue => {
-                write!(self.writer, "~")?;
-                Ok(())
-            }
-            // XXX(chenyh) Alias
-            Yaml::Alias(_) => Ok(()),
-        }
+    /// Set the width past which string values are split across lines, or `None` not to split
+    /// them (the default).
+    ///
+    /// Strings without line breaks are written as folded block scalars (`>-`), broken at single
+    /// spaces. Other strings, including those that cannot be folded without changing them (e.g.
+    /// with leading or trailing spaces), are written double-quoted on the following lines, with
+    /// escaped line breaks. Either way, the document loads back to the same value. Lines are only
+    /// broken where allowed, so they may still exceed the width, e.g. for very long words.
+    ///
+    /// Keys and the literal blocks of [`Self::multiline_strings`] are never split, but the lines of
+    /// its folded blocks (see [`Self::block_style`]) are. Flow collections (see
+    /// [`Self::flow_depth`]) are broken between their entries, each line holding as many entries
+    /// as fit. Strings within them cannot be block scalars, so they are folded as multi-line plain
+    /// or single-quoted scalars, or split with escaped line breaks if they are double-quoted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use saphyr::{Yaml, YamlEmitter};
+    ///
+    /// let input = "a: one two three four five six\nb: [' one two three four']";
+    /// let parsed = Yaml::load_from_str(input).unwrap();
+    ///
+    /// let mut output = String::new();
+    /// let mut emitter = YamlEmitter::new(&mut output);
+    /// emitter.line_width(Some(20));
+    /// emitter.dump(&parsed[0]).unwrap();
+    /// assert_eq!(output.as_str(), r#"---
+    /// a: >-
+    ///   one two three four
+    ///   five six
+    /// b:
+    ///   -
+    ///     " one two \
+    ///     three four""#);
+    /// assert_eq!(Yaml::load_from_str(&output).unwrap(), parsed);
+    /// ```
+    pub fn line_width(&mut self, width: Option<usize>) {
+        self.line_width = width;
     }
 
-    fn emit_literal_block(&mut self, v: &str) -> EmitResult {
-        let ends_with_newline = v.ends_with('\n');
-        if ends_with_newline {
-            self.writer.write_str("|")?;
-        } else {
-            self.writer.write_str("|-")?;
-        }
+    /// Set the width past which lines are broken, as [`Self::line_width`] does with
+    /// `Some(width)`.
+    ///
+    /// This is the `best_width` setting of libyaml and `PyYAML`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use saphyr::{yaml, YamlEmitter};
+    ///
+    /// let doc = yaml!({"tags": ["alpha", "beta", "gamma"], "note": "a rather long note"});
+    ///
+    /// let mut output = String::new();
+    /// let mut emitter = YamlEmitter::new(&mut output);
+    /// emitter.flow_depth(Some(1));
+    /// emitter.set_best_width(16);
+    /// emitter.dump(&doc).unwrap();
+    /// assert_eq!(output, "---\ntags: [alpha,\n  beta, gamma]\nnote: >-\n  a rather long\n  note");
+    /// ```
+    pub fn set_best_width(&mut self, width: usize) {
+        self.line_width = Some(width);
+    }
 
-        self.level += 1;
-        // lines() will omit the last line if it is empty.
-        for line in v.lines() {
-            writeln!(self.writer)?;
-            self.write_indent()?;
-            // It's literal text, so don't escape special chars.
-            self.writer.write_str(line)?;
-        }
-        self.level -= 1;
-        Ok(())
+    /// Determine the width past which this emitter splits string values, if any.
+    #[must_use]
+    pub fn get_line_width(&self) -> Option<usize> {
+        self.line_width
     }
 
-    fn emit_array(&mut self, v: &[Yaml]) -> EmitResult {
-        if v.is_empty() {
-            write!(self.writer, "[]")?;
-        } else {
-            self.level += 1;
-            for (cnt, x) in v.iter().enumerate() {
-                if cnt > 0 {
-                    writeln!(self.writer)?;
-                    self.write_indent()?;
-                }
-                write!(self.writer, "-")?;
-                self.emit_val(true, x)?;
-            }
-            self.level -= 1;
-        }
-        Ok(())
+    /// Set the schema the output is meant to be loaded with. Defaults to [`Schema::Core`].
+    ///
+    /// Strings which the schema would resolve to another type if they were written unquoted are
+    /// quoted. Strings that look like booleans, nulls or numbers in either YAML 1.1 or YAML 1.2
+    /// are quoted whatever the schema, except those which only [`Schema::Yaml11`] resolves (e.g.
+    /// `y`, `0b101` or `1_000`). With [`Schema::Json`], all strings are quoted, and nulls are written
+    /// `null` rather than `~`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use saphyr::{yaml, Schema, YamlEmitter};
+    ///
+    /// let mut output = String::new();
+    /// let mut emitter = YamlEmitter::new(&mut output);
+    /// emitter.schema(Schema::Yaml11);
+    /// emitter.dump(&yaml!(["y", "n", "yes", "0b101", "plain"])).unwrap();
+    /// assert_eq!(output, "---\n- \"y\"\n- \"n\"\n- \"yes\"\n- \"0b101\"\n- plain");
+    /// ```
+    pub fn schema(&mut self, schema: Schema) {
+        self.schema = schema;
     }
 
-    fn emit_hash(&mut self, h: &Hash) -> EmitResult {
+    /// Determine the schema the output of this emitter is meant to be loaded with.
+    #[must_use]
+    pub fn get_schema(&self) -> Schema {
+        self.schema
+    }
+
+    /// Set when and how strings are quoted. Defaults to [`QuoteStyle::Minimal`].
+    ///
+    /// See [`QuoteStyle`] for details.
+    pub fn quote_style(&mut self, style: QuoteStyle) {
+        self.quote_style = style;
+    }
+
+    /// Determine when and how this emitter quotes strings.
+    #[must_use]
+    pub fn get_quote_style(&self) -> QuoteStyle {
+        self.quote_style
+    }
+
+    /// Set how null values are written. Defaults to [`NullStyle::Tilde`].
+    ///
+    /// With [`Schema::Json`], nulls are always written `null`.
+    pub fn null_style(&mut self, style: NullStyle) {
+        self.null_style = style;
+    }
+
+    /// Determine how this emitter writes null values.
+    #[must_use]
+    pub fn get_null_style(&self) -> NullStyle {
+        self.null_style
+    }
+
+    /// Write documents in canonical form, as `libyaml` does with its `canonical` setting.
+    ///
+    /// Every node is explicitly tagged, collections are written in flow style with one item per
+    /// line, mapping keys follow the `?` indicator and scalars are double-quoted. This is meant
+    /// for debugging and for comparing outputs, e.g. with those of the YAML test suite.
+    ///
+    /// The anchors of [`Self::emit_aliases`] are written before the tag, and [`Self::key_order`]
+    /// is respected. The layout settings (e.g. [`Self::compact`] or [`Self::line_width`]) and
+    /// comments are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use saphyr::{yaml, YamlEmitter};
+    ///
+    /// let mut output = String::new();
+    /// let mut emitter = YamlEmitter::new(&mut output);
+    /// emitter.canonical(true);
+    /// emitter.dump(&yaml!({"a": 1, "b": ["x", null]})).unwrap();
+    /// assert_eq!(output, r#"---
+    /// !!map {
+    ///   ? !!str "a"
+    ///   : !!int "1",
+    ///   ? !!str "b"
+    ///   : !!seq [
+    ///     !!str "x",
+    ///     !!null "null",
+    ///   ],
+    /// }"#);
+    /// ```
+    pub fn canonical(&mut self, canonical: bool) {
+        self.canonical = canonical;
+    }
+
+    /// Determine if this emitter writes documents in canonical form.
+    #[must_use]
+    pub fn is_canonical(&self) -> bool {
+        self.canonical
+    }
+
+    /// Set whether documents start with a `---` marker, which they do by default.
+    ///
+    /// Without it, the first document is written bare. The documents after it still start with
+    /// `---` unless the previous one was ended with [`Self::explicit_end`]: documents dumped by
+    /// the same emitter are separated so that they load back as a stream. A canonical document
+    /// always starts with `---`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use saphyr::{yaml, YamlEmitter};
+    ///
+    /// let mut output = String::new();
+    /// let mut emitter = YamlEmitter::new(&mut output);
+    /// emitter.explicit_start(false);
+    /// emitter.dump(&yaml!({"a": 1})).unwrap();
+    /// emitter.dump(&yaml!(2)).unwrap();
+    /// assert_eq!(output, "a: 1\n---\n2");
+    /// ```
+    pub fn explicit_start(&mut self, explicit_start: bool) {
+        self.explicit_start = explicit_start;
+    }
+
+    /// Determine if this emitter starts documents with a `---` marker.
+    #[must_use]
+    pub fn is_explicit_start(&self) -> bool {
+        self.explicit_start
+    }
+
+    /// Set whether documents end with a `...` marker.
+    ///
+    /// A document ended this way can be followed by directives, or by a bare document.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use saphyr::{yaml, YamlEmitter};
+    ///
+    /// let mut output = String::new();
+    /// let mut emitter = YamlEmitter::new(&mut output);
+    /// emitter.explicit_start(false);
+    /// emitter.explicit_end(true);
+    /// emitter.dump(&yaml!("a")).unwrap();
+    /// emitter.dump(&yaml!("b")).unwrap();
+    /// assert_eq!(output, "a\n...\nb\n...");
+    /// ```
+    pub fn explicit_end(&mut self, explicit_end: bool) {
+        self.explicit_end = explicit_end;
+    }
+
+    /// Determine if this emitter ends documents with a `...` marker.
+    #[must_use]
+    pub fn is_explicit_end(&self) -> bool {
+        self.explicit_end
+    }
+
+    /// Set how values of block mappings are aligned.
+    ///
+    /// See [`AlignPolicy`] for details. Alignment is purely cosmetic: the emitted document loads
+    /// to the same [`Yaml`] as an unaligned one.
+    pub fn align_values(&mut self, policy: AlignPolicy) {
+        self.align_values = policy;
+    }
+
+    /// Determine how this emitter aligns values of block mappings.
+    #[must_use]
+    pub fn get_align_values(&self) -> AlignPolicy {
+        self.align_values
+    }
+
+    /// Set the number of blank lines to insert between the keys of a top-level mapping.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use saphyr::{Yaml, YamlEmitter};
+    ///
+    /// let parsed = Yaml::load_from_str("a: 1\nb: [2, 3]").unwrap();
+    ///
+    /// let mut output = String::new();
+    /// let mut emitter = YamlEmitter::new(&mut output);
+    /// emitter.blank_lines_between_top_level(1);
+    /// emitter.dump(&parsed[0]).unwrap();
+    /// assert_eq!(output.as_str(), "\
+    /// ---
+    /// a: 1
+    ///
+    /// b:
+    ///   - 2
+    ///   - 3");
+    /// ```
+    pub fn blank_lines_between_top_level(&mut self, count: u8) {
+        self.blank_lines_between_top_level = count;
+    }
+
+    /// Determine the number of blank lines inserted between the keys of a top-level mapping.
+    #[must_use]
+    pub fn get_blank_lines_between_top_level(&self) -> u8 {
+        self.blank_lines_between_top_level
+    }
+
+    /// Set the order in which the keys of mappings are emitted.
+    ///
+    /// See [`KeyOrder`] for details. Defaults to [`KeyOrder::Insertion`].
+    pub fn key_order(&mut self, order: KeyOrder) {
+        self.key_order = order;
+    }
+
+    /// Determine the order in which this emitter emits the keys of mappings.
+    #[must_use]
+    pub fn get_key_order(&self) -> KeyOrder {
+        self.key_order
+    }
+
+    /// Set the depth from which collections are written in flow style, or `None` to write them
+    /// in block style (the default).
+    ///
+    /// The root of a document is at depth 0, so `Some(0)` writes whole documents in flow style,
+    /// and `Some(1)` writes the entries of the root collection on their own lines, but the
+    /// collections they contain on a single line. [`Self::collection_style`] takes precedence.
+    ///
+    /// Flow collections are only split across lines between their entries, to respect
+    /// [`Self::line_width`], and strings within them are never written as block scalars. The
+    /// comments of [`Self::dump_commented`] within them are dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use saphyr::{Yaml, YamlEmitter};
+    ///
+    /// let parsed = Yaml::load_from_str("a: {b: 1, c: [2, 3]}\nd: [4]").unwrap();
+    ///
+    /// let mut output = String::new();
+    /// let mut emitter = YamlEmitter::new(&mut output);
+    /// emitter.flow_depth(Some(1));
+    /// emitter.dump(&parsed[0]).unwrap();
+    /// assert_eq!(output.as_str(), "---\na: {b: 1, c: [2, 3]}\nd: [4]");
+    /// ```
+    pub fn flow_depth(&mut self, depth: Option<usize>) {
+        self.flow_depth = depth;
+    }
+
+    /// Determine the depth from which this emitter writes collections in flow style, if any.
+    #[must_use]
+    pub fn get_flow_depth(&self) -> Option<usize> {
+        self.flow_depth
+    }
+
+    /// Set the style of the collection at `path` in the dumped documents, whatever
+    /// [`Self::flow_depth`].
+    ///
+    /// The style applies to the collection at `path` in every document dumped afterwards, if
+    /// there is one. It is ignored within flow collections, which only contain flow collections.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use saphyr::{CollectionStyle, PathSegment, Yaml, YamlEmitter, YamlPath};
+    ///
+    /// let parsed = Yaml::load_from_str("labels: {app: web}\nports: [80, 443]").unwrap();
+    ///
+    /// let mut output = String::new();
+    /// let mut emitter = YamlEmitter::new(&mut output);
+    /// let labels = YamlPath::from(vec![PathSegment::Key("labels".into())]);
+    /// emitter.collection_style(labels, CollectionStyle::Flow);
+    /// emitter.dump(&parsed[0]).unwrap();
+    /// assert_eq!(output.as_str(), "---\nlabels: {app: web}\nports:\n  - 80\n  - 443");
+    /// ```
+    pub fn collection_style(&mut self, path: YamlPath, style: CollectionStyle) {
+        self.collection_styles.insert(path, style);
+    }
+
+    /// Determine the style set for the collection at `path` with [`Self::collection_style`], if
+    /// any.
+    #[must_use]
+    pub fn get_collection_style(&self, path: &YamlPath) -> Option<CollectionStyle> {
+        self.collection_styles.get(path).copied()
+    }
+
+    /// Declare the tag handle `handle` (e.g. `!e!`) for the tags starting with `prefix` (e.g.
+    /// `tag:example.com,2020:`).
+    ///
+    /// Each document then starts with a `%TAG` directive for each declared handle, and tags
+    /// starting with one of their prefixes are written with its handle, e.g. `!e!foo` rather than
+    /// `!<tag:example.com,2020:foo>`. Directives can only follow a `...` marker, so documents
+    /// after the first are ended by one if [`Self::explicit_end`] is not set.
+    ///
+    /// # Panics
+    /// Panics if `handle` is not a named handle, i.e. `!`, a name of alphanumeric characters or
+    /// `-`, and `!`. The primary (`!`) and secondary (`!!`) handles cannot be redefined.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use saphyr::{Tag, Yaml, YamlEmitter};
+    ///
+    /// let tag = Tag {
+    ///     handle: "tag:example.com,2020:".into(),
+    ///     suffix: "point".into(),
+    /// };
+    /// let mut output = String::new();
+    /// let mut emitter = YamlEmitter::new(&mut output);
+    /// emitter.tag_handle("!e!", "tag:example.com,2020:");
+    /// emitter.dump(&Yaml::Tagged(tag, Box::new(Yaml::Integer(1)))).unwrap();
+    /// assert_eq!(output, "%TAG !e! tag:example.com,2020:\n---\n!e!point 1");
+    /// ```
+    pub fn tag_handle(&mut self, handle: impl Into<String>, prefix: impl Into<String>) {
+        let handle = handle.into();
+        let name = handle
+            .strip_prefix('!')
+            .and_then(|handle| handle.strip_suffix('!'))
+            .unwrap_or_default();
+        assert!(
+            !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'),
+            "`{handle}` is not a named tag handle"
+        );
+        self.tag_handles.insert(handle, prefix.into());
+    }
+
+    /// Return the tag handles declared with [`Self::tag_handle`], with their prefix.
+    #[must_use]
+    pub fn get_tag_handles(&self) -> &BTreeMap<String, String> {
+        &self.tag_handles
+    }
+
+    /// Set a token to cancel emission with, from another thread.
+    ///
+    /// The token is checked before each entry of a collection. Once it is cancelled, emission
+    /// fails with [`EmitError::Cancelled`]. The output may then be truncated, but what was written
+    /// is always a prefix of the complete output.
+    pub fn cancel_token(&mut self, token: CancelToken) {
+        self.cancel = Some(token);
+    }
+
+    /// Return the cancellation token of this emitter, if any.
+    #[must_use]
+    pub fn get_cancel_token(&self) -> Option<&CancelToken> {
+        self.cancel.as_ref()
+    }
+
+    /// Fail if emission was cancelled.
+    fn check_cancelled(&self) -> EmitResult {
+        if self.cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+            Err(EmitError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Emit collections which appear more than once in a document as aliases.
+    ///
+    /// The first occurrence of the collection is given an anchor, named after the strategy set
+    /// with [`Self::anchor_naming`], and the following ones are emitted as aliases to it. Scalars
+    /// and empty collections are always emitted as-is.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use saphyr::{Yaml, YamlEmitter};
+    ///
+    /// let parsed = Yaml::load_from_str("a: {x: 1}\nb: {x: 1}").unwrap();
+    ///
+    /// let mut output = String::new();
+    /// let mut emitter = YamlEmitter::new(&mut output);
+    /// emitter.emit_aliases(true);
+    /// emitter.dump(&parsed[0]).unwrap();
+    /// assert_eq!(output.as_str(), "\
+    /// ---
+    /// a: &a
+    ///   x: 1
+    /// b: *a");
+    /// ```
+    pub fn emit_aliases(&mut self, emit_aliases: bool) {
+        self.emit_aliases = emit_aliases;
+    }
+
+    /// Determine if this emitter emits aliases for repeated collections.
+    #[must_use]
+    pub fn is_emit_aliases(&self) -> bool {
+        self.emit_aliases
+    }
+
+    /// Set how the anchors generated by [`Self::emit_aliases`] are named.
+    ///
+    /// See [`AnchorNaming`] for details. Defaults to [`AnchorNaming::ByFirstPath`].
+    pub fn anchor_naming(&mut self, naming: AnchorNaming) {
+        self.anchor_naming = naming;
+    }
+
+    /// Determine how this emitter names the anchors it generates.
+    #[must_use]
+    pub fn get_anchor_naming(&self) -> &AnchorNaming {
+        &self.anchor_naming
+    }
+
+    /// Return a summary of the settings of the emitter, e.g. for diagnostics or support bundles.
+    ///
+    /// The summary is a mapping from setting names (in `snake_case`) to their values.
+    ///
+    /// ```
+    /// # use saphyr::YamlEmitter;
+    /// let mut output = String::new();
+    /// let emitter = YamlEmitter::new(&mut output);
+    /// let settings = emitter.describe();
+    /// assert_eq!(settings["compact"].as_bool(), Some(true));
+    /// assert_eq!(settings["align_values"].as_str(), Some("none"));
+    /// ```
+    #[must_use]
+    pub fn describe(&self) -> Yaml {
+        fn entry(hash: &mut Hash, key: &str, value: Yaml) {
+            hash.insert(Yaml::String(key.to_owned()), value);
+        }
+        fn tagged(tag: &str, fields: Hash) -> Yaml {
+            let mut hash = Hash::new();
+            entry(&mut hash, tag, Yaml::Hash(fields));
+            Yaml::Hash(hash)
+        }
+        fn string(value: &str) -> Yaml {
+            Yaml::String(value.into())
+        }
+        fn integer(value: usize) -> Yaml {
+            Yaml::Integer(i64::try_from(value).unwrap_or(i64::MAX))
+        }
+
+        let mut settings = Hash::new();
+        entry(&mut settings, "indent", integer(self.best_indent));
+        entry(&mut settings, "compact", Yaml::Boolean(self.compact));
+        entry(
+            &mut settings,
+            "multiline_strings",
+            Yaml::Boolean(self.multiline_strings),
+        );
+        entry(
+            &mut settings,
+            "block_style",
+            string(self.block_style.name()),
+        );
+        entry(
+            &mut settings,
+            "line_width",
+            self.line_width.map_or(Yaml::Null, integer),
+        );
+        entry(&mut settings, "schema", string(self.schema.name()));
+        entry(
+            &mut settings,
+            "quote_style",
+            string(self.quote_style.name()),
+        );
+        entry(&mut settings, "null_style", string(self.null_style.name()));
+        entry(&mut settings, "canonical", Yaml::Boolean(self.canonical));
+        entry(
+            &mut settings,
+            "explicit_start",
+            Yaml::Boolean(self.explicit_start),
+        );
+        entry(
+            &mut settings,
+            "explicit_end",
+            Yaml::Boolean(self.explicit_end),
+        );
+        let align_values = match self.align_values {
+            AlignPolicy::None => string("none"),
+            AlignPolicy::PerMapping { max_pad } => {
+                let mut fields = Hash::new();
+                entry(&mut fields, "max_pad", integer(max_pad));
+                tagged("per_mapping", fields)
+            }
+        };
+        entry(&mut settings, "align_values", align_values);
+        entry(
+            &mut settings,
+            "blank_lines_between_top_level",
+            Yaml::Integer(self.blank_lines_between_top_level.into()),
+        );
+        entry(&mut settings, "key_order", string(self.key_order.name()));
+        entry(
+            &mut settings,
+            "flow_depth",
+            self.flow_depth.map_or(Yaml::Null, integer),
+        );
+        let mut tag_handles = Hash::new();
+        for (handle, prefix) in &self.tag_handles {
+            entry(&mut tag_handles, handle, string(prefix));
+        }
+        entry(&mut settings, "tag_handles", Yaml::Hash(tag_handles));
+        entry(
+            &mut settings,
+            "cancellable",
+            Yaml::Boolean(self.cancel.is_some()),
+        );
+        entry(
+            &mut settings,
+            "emit_aliases",
+            Yaml::Boolean(self.emit_aliases),
+        );
+        let anchor_naming = match &self.anchor_naming {
+            AnchorNaming::ByFirstPath => string("by_first_path"),
+            AnchorNaming::ByContentHash { prefix } => {
+                let mut fields = Hash::new();
+                entry(&mut fields, "prefix", Yaml::String(prefix.clone()));
+                tagged("by_content_hash", fields)
+            }
+            AnchorNaming::Custom(_) => string("custom"),
+        };
+        entry(&mut settings, "anchor_naming", anchor_naming);
+        Yaml::Hash(settings)
+    }
+
+    /// Dump Yaml to an output stream.
+    ///
+    /// Mapping keys are written after the `?` indicator when they cannot be implicit keys: if
+    /// they are sequences or mappings, literal block scalars, or longer than 1024 characters.
+    ///
+    /// ```
+    /// use saphyr::{yaml, Hash, Yaml, YamlEmitter};
+    ///
+    /// let mut hash = Hash::new();
+    /// hash.insert(Yaml::key(["a", "b"]), yaml!("pair"));
+    /// let mut output = String::new();
+    /// YamlEmitter::new(&mut output).dump(&Yaml::Hash(hash)).unwrap();
+    /// assert_eq!(output, "---\n? - a\n  - b\n: pair");
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `EmitError` when an error occurs.
+    pub fn dump(&mut self, doc: &Yaml) -> EmitResult {
+        if self.emit_aliases {
+            self.anchors = self.name_anchors(doc)?;
+        }
+        self.path = YamlPath::new();
+        let result = self.dump_document(doc);
+        self.anchors.clear();
+        self.writer.finish(result)
+    }
+
+    /// Dump a stream of documents to an output stream.
+    ///
+    /// This is the same as dumping each document in turn: documents after the first always start
+    /// with `---` unless the previous one ends with `...` (see [`Self::explicit_start`] and
+    /// [`Self::explicit_end`]), so that the output loads back as the same stream. An empty stream
+    /// writes nothing.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use saphyr::{yaml, YamlEmitter};
+    ///
+    /// let mut output = String::new();
+    /// YamlEmitter::new(&mut output).dump_all(&[yaml!({"a": 1}), yaml!([2])]).unwrap();
+    /// assert_eq!(output, "---\na: 1\n---\n- 2");
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `EmitError` when an error occurs, after writing the documents before it.
+    pub fn dump_all(&mut self, docs: &[Yaml]) -> EmitResult {
+        for doc in docs {
+            self.dump(doc)?;
+        }
+        Ok(())
+    }
+
+    fn dump_document(&mut self, doc: &Yaml) -> EmitResult {
+        let directives = !self.tag_handles.is_empty();
+        // A bare document can only follow the start of the stream or a `...` marker, and
+        // directives can only be followed by `---`.
+        let needs_start =
+            directives || self.explicit_start || (self.documents > 0 && !self.explicit_end);
+        if self.documents > 0 {
+            // The previous document did not end with a line break.
+            writeln!(self.writer)?;
+            // Directives can only follow the end of the previous document.
+            if directives && !self.explicit_end {
+                writeln!(self.writer, "...")?;
+            }
+        }
+        self.documents += 1;
+        if self.canonical {
+            self.write_directives()?;
+            writeln!(self.writer, "---")?;
+            self.level = 0;
+            self.emit_canonical(doc)?;
+        } else {
+            self.write_leading_comments()?;
+            self.write_directives()?;
+            // The content always starts on its own line, so that a scalar cannot be mistaken for
+            // a part of the marker.
+            if needs_start {
+                writeln!(self.writer, "---")?;
+            }
+            self.level = -1;
+            self.emit_node(doc)?;
+            if !matches!(doc, Yaml::Array(_) | Yaml::Hash(_)) {
+                self.write_trailing_comment()?;
+            }
+        }
+        if self.explicit_end {
+            write!(self.writer, "\n...")?;
+        }
+        Ok(())
+    }
+
+    /// Write a `%TAG` directive for each of [`Self::tag_handles`].
+    fn write_directives(&mut self) -> EmitResult {
+        for (handle, prefix) in &self.tag_handles {
+            writeln!(self.writer, "%TAG {handle} {prefix}")?;
+        }
+        Ok(())
+    }
+
+    /// Find the collections of `doc` that are emitted more than once and name their anchors.
+    fn name_anchors(&self, doc: &Yaml) -> Result<BTreeMap<Yaml, (String, bool)>, EmitError> {
+        let mut first_paths = BTreeMap::new();
+        let mut repeated = vec![];
+        find_repeated(
+            doc,
+            self.key_order,
+            &mut YamlPath::new(),
+            &mut first_paths,
+            &mut repeated,
+        );
+
+        let mut anchors = BTreeMap::new();
+        let mut names = BTreeSet::new();
+        for node in repeated {
+            let path = &first_paths[node];
+            let name = match &self.anchor_naming {
+                AnchorNaming::ByFirstPath => path.to_string(),
+                AnchorNaming::ByContentHash { prefix } => {
+                    let mut rendered = String::new();
+                    let mut emitter = self.fork(&mut rendered);
+                    emitter.emit_aliases = false;
+                    emitter.emit_node(node)?;
+                    format!("{prefix}{:08x}", fnv1a(rendered.as_bytes()) >> 32)
+                }
+                AnchorNaming::Custom(callback) => callback(node, path),
+            };
+            let name = sanitize_anchor(&name);
+            let mut unique = name.clone();
+            for i in 2.. {
+                if names.insert(unique.clone()) {
+                    break;
+                }
+                unique = format!("{name}_{i}");
+            }
+            anchors.insert(node.clone(), (unique, false));
+        }
+        Ok(anchors)
+    }
+
+    /// Return the anchor of `node` and whether it has already been emitted, if it has one.
+    ///
+    /// The anchor is marked as emitted.
+    fn take_anchor(&mut self, node: &Yaml) -> Option<(String, bool)> {
+        if self.anchors.is_empty() {
+            return None;
+        }
+        let (name, emitted) = self.anchors.get_mut(node)?;
+        Some((name.clone(), core::mem::replace(emitted, true)))
+    }
+
+    /// Dump a [`CommentedYaml`] to an output stream, writing its comments back.
+    ///
+    /// Leading comments are written on their own lines before the node, at its indentation.
+    /// Trailing comments are written at the end of the line introducing the node. A few comments
+    /// cannot be written back and are dropped, namely:
+    ///   * Leading comments of scalar mapping values.
+    ///   * Trailing comments of mapping keys and of literal block scalars.
+    ///
+    /// See [`CommentedYaml`] for an example.
+    ///
+    /// # Errors
+    /// Returns `EmitError` when an error occurs.
+    pub fn dump_commented(&mut self, doc: &CommentedYaml) -> EmitResult {
+        let mut comments = BTreeMap::new();
+        doc.collect_comments(&mut YamlPath::new(), &mut comments);
+        self.comments = comments;
+        let result = self.dump(&doc.clone().into());
+        self.comments.clear();
+        result
+    }
+
+    /// Append `segment` to the path of the node being emitted, if needed.
+    fn enter(&mut self, segment: impl FnOnce() -> PathSegment) {
+        if !self.comments.is_empty() || !self.collection_styles.is_empty() {
+            self.path.push(segment());
+        }
+    }
+
+    /// Remove the last segment of the path of the node being emitted, if needed.
+    fn leave(&mut self) {
+        if !self.comments.is_empty() || !self.collection_styles.is_empty() {
+            self.path.pop();
+        }
+    }
+
+    /// Write the leading comments of the current node, each followed by a new indented line.
+    fn write_leading_comments(&mut self) -> EmitResult {
+        let Some(comments) = self.comments.get(&self.path) else {
+            return Ok(());
+        };
+        let lines: Vec<String> = comments
+            .leading
+            .iter()
+            .flat_map(|comment| comment.split('\n'))
+            .map(str::to_owned)
+            .collect();
+        for line in lines {
+            writeln!(self.writer, "#{line}")?;
+            self.write_indent()?;
+        }
+        Ok(())
+    }
+
+    /// Write the trailing comment of the current node, if any.
+    fn write_trailing_comment(&mut self) -> EmitResult {
+        if let Some(comment) = self
+            .comments
+            .get(&self.path)
+            .and_then(|comments| comments.trailing.as_ref())
+        {
+            write!(self.writer, " #{}", comment.replace('\n', " "))?;
+        }
+        Ok(())
+    }
+
+    fn write_indent(&mut self) -> EmitResult {
+        if self.level <= 0 {
+            return Ok(());
+        }
+        for _ in 0..self.level {
+            for _ in 0..self.best_indent {
+                write!(self.writer, " ")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn emit_node(&mut self, node: &Yaml) -> EmitResult {
+        match *node {
+            Yaml::Array(ref v) => self.emit_array(v),
+            Yaml::Hash(ref h) => self.emit_hash(h),
+            Yaml::String(ref v) => self.emit_string(v),
+            Yaml::SharedString(ref v) => self.emit_string(v),
+            Yaml::Boolean(v) => {
+                if v {
+                    self.writer.write_str("true")?;
+                } else {
+                    self.writer.write_str("false")?;
+                }
+                Ok(())
+            }
+            Yaml::Integer(v) => {
+                write!(self.writer, "{v}")?;
+                Ok(())
+            }
+            Yaml::Real(ref v) => {
+                write!(self.writer, "{v}")?;
+                Ok(())
+            }
+            Yaml::Float(ref v) => {
+                self.writer.write_str(&v.text())?;
+                Ok(())
+            }
+            // The tag keeps the timestamp when it is loaded without resolving untagged ones.
+            Yaml::Timestamp(ref v) => {
+                write!(self.writer, "!!timestamp {v}")?;
+                Ok(())
+            }
+            Yaml::Binary(ref v) => {
+                let text = crate::binary::encode(v);
+                if text.is_empty() {
+                    self.writer.write_str("!!binary \"\"")?;
+                } else {
+                    write!(self.writer, "!!binary {text}")?;
+                }
+                Ok(())
+            }
+            Yaml::Null | Yaml::BadValue => {
+                let null = match self.null_style {
+                    _ if self.schema == Schema::Json => "null",
+                    NullStyle::Tilde => "~",
+                    NullStyle::Lowercase | NullStyle::Empty => "null",
+                    NullStyle::Capitalized => "Null",
+                };
+                self.writer.write_str(null)?;
+                Ok(())
+            }
+            Yaml::Tagged(ref tag, ref node) => {
+                self.writer.write_str(&self.tag_text(tag))?;
+                match **node {
+                    // Block collections start on the next line.
+                    Yaml::Array(ref v) if !v.is_empty() && !self.is_flow() => {
+                        self.begin_block_after_properties()?;
+                    }
+                    Yaml::Hash(ref h) if !h.is_empty() && !self.is_flow() => {
+                        self.begin_block_after_properties()?;
+                    }
+                    _ => self.writer.write_str(" ")?,
+                }
+                self.emit_node(node)
+            }
+            // XXX(chenyh) Alias
+            Yaml::Alias(_) => Ok(()),
+        }
+    }
+
+    /// Return how `tag` is written, with the handle of [`Self::tag_handles`] matching it, if any.
+    fn tag_text(&self, tag: &Tag) -> String {
+        let text = tag_text(tag);
+        if !text.starts_with("!<") {
+            return text;
+        }
+        let uri = format!("{}{}", tag.handle, tag.suffix);
+        self.tag_handles
+            .iter()
+            .filter_map(|(handle, prefix)| Some((handle, uri.strip_prefix(prefix.as_str())?)))
+            // Unlike verbatim tags, shorthands cannot contain `!` or flow indicators.
+            .filter(|(_, suffix)| {
+                !suffix.is_empty()
+                    && suffix
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || "-#;/?:@&=+$_.~*'()%".contains(c))
+            })
+            .min_by_key(|(_, suffix)| suffix.len())
+            .map_or(text, |(handle, suffix)| format!("{handle}{suffix}"))
+    }
+
+    /// Whether the collection about to be emitted, at [`Self::path`] and whose entries would be
+    /// one level deeper than [`Self::level`], is written in flow style.
+    fn is_flow(&self) -> bool {
+        self.is_flow_at(&self.path, self.level + 1)
+    }
+
+    /// Whether the collection at `path`, at nesting `depth`, is written in flow style.
+    fn is_flow_at(&self, path: &YamlPath, depth: isize) -> bool {
+        match self.collection_styles.get(path) {
+            Some(style) => *style == CollectionStyle::Flow,
+            None => self
+                .flow_depth
+                .is_some_and(|flow_depth| usize::try_from(depth).is_ok_and(|d| d >= flow_depth)),
+        }
+    }
+
+    /// Write `node` in flow style, with its properties.
+    fn emit_flow(&mut self, node: &Yaml) -> EmitResult {
+        match self.take_anchor(node) {
+            Some((name, true)) => {
+                write!(self.writer, "*{name}")?;
+                return Ok(());
+            }
+            Some((name, false)) => write!(self.writer, "&{name} ")?,
+            None => {}
+        }
+        match node {
+            Yaml::Array(items) => self.emit_flow_array(items),
+            Yaml::Hash(entries) => self.emit_flow_hash(entries),
+            Yaml::Tagged(tag, node) => {
+                write!(self.writer, "{} ", self.tag_text(tag))?;
+                self.emit_flow(node)
+            }
+            // Block scalars cannot be written within flow collections.
+            Yaml::String(v) => self.emit_flow_str(v),
+            Yaml::SharedString(v) => self.emit_flow_str(v),
+            node => self.emit_node(node),
+        }
+    }
+
+    /// Write the entries of a sequence in flow style, e.g. `[1, 2]`.
+    fn emit_flow_array(&mut self, items: &[Yaml]) -> EmitResult {
+        self.writer.write_str("[")?;
+        for (i, item) in items.iter().enumerate() {
+            self.check_cancelled()?;
+            if i > 0 {
+                self.write_flow_separator(|emitter| emitter.emit_flow(item))?;
+            }
+            self.emit_flow(item)?;
+        }
+        self.writer.write_str("]")?;
+        Ok(())
+    }
+
+    /// Write the entries of a mapping in flow style, e.g. `{a: 1, b: 2}`.
+    fn emit_flow_hash(&mut self, entries: &Hash) -> EmitResult {
+        self.writer.write_str("{")?;
+        for (i, (_, (key, value))) in ordered_entries(entries, self.key_order)
+            .into_iter()
+            .enumerate()
+        {
+            self.check_cancelled()?;
+            if i > 0 {
+                self.write_flow_separator(|emitter| emitter.emit_flow_entry(key, value))?;
+            }
+            self.emit_flow_entry(key, value)?;
+        }
+        self.writer.write_str("}")?;
+        Ok(())
+    }
+
+    /// Write an entry of a flow mapping, e.g. `a: 1`.
+    fn emit_flow_entry(&mut self, key: &Yaml, value: &Yaml) -> EmitResult {
+        // Aliases may end with a `:`, so they are followed by a space.
+        let alias = self.anchors.get(key).is_some_and(|(_, emitted)| *emitted);
+        let explicit = alias || self.needs_explicit_key(key)?;
+        if explicit {
+            self.writer.write_str("? ")?;
+            self.emit_flow(key)?;
+        } else {
+            // Implicit keys cannot span lines.
+            let line_width = self.line_width.take();
+            let result = self.emit_flow(key);
+            self.line_width = line_width;
+            result?;
+        }
+        self.writer.write_str(if explicit { " : " } else { ": " })?;
+        self.emit_flow(value)
+    }
+
+    /// Write a string within a flow collection, folded across lines if it would make the current
+    /// one exceed [`Self::line_width`].
+    fn emit_flow_str(&mut self, v: &str) -> EmitResult {
+        let Some(width) = self.line_width else {
+            return self.emit_str(v);
+        };
+        let mut rendered = String::new();
+        self.fork(&mut rendered).emit_str(v)?;
+        if self.writer.column + rendered.chars().count() <= width {
+            self.writer.write_str(&rendered)?;
+            return Ok(());
+        }
+        let indent = self.flow_indent();
+        let width = width.saturating_sub(indent).max(1);
+        let offset = self.writer.column.saturating_sub(indent);
+        // Plain and single-quoted scalars fold line breaks into spaces, as block scalars do.
+        let double_quoted = rendered.starts_with('"');
+        let lines = if double_quoted {
+            wrap_escaped(&rendered, width, offset)
+        } else {
+            fold_lines(&rendered, width, offset)
+        };
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                if double_quoted {
+                    self.writer.write_str("\\")?;
+                }
+                writeln!(self.writer)?;
+                write!(self.writer, "{:indent$}", "")?;
+            }
+            self.writer.write_str(line)?;
+        }
+        Ok(())
+    }
+
+    /// Return the indentation of the continuation lines of flow collections.
+    ///
+    /// They are indented one level deeper than the block collection containing the flow
+    /// collection, as they must be indented more than it.
+    fn flow_indent(&self) -> usize {
+        usize::try_from(self.level + 1).unwrap_or(0).max(1) * self.best_indent
+    }
+
+    /// Write the `,` between two entries of a flow collection, and what separates it from the
+    /// next entry, which `emit` writes.
+    ///
+    /// The next entry goes on a new line, at [`Self::flow_indent`], if it would make the current
+    /// one exceed [`Self::line_width`].
+    fn write_flow_separator(
+        &mut self,
+        emit: impl FnOnce(&mut YamlEmitter) -> EmitResult,
+    ) -> EmitResult {
+        self.writer.write_str(",")?;
+        let fits = match self.line_width {
+            Some(width) => {
+                let mut rendered = String::new();
+                let mut fork = self.fork(&mut rendered);
+                fork.line_width = None;
+                emit(&mut fork)?;
+                // The entry is followed by a `,` or the end of the collection.
+                self.writer.column + rendered.chars().count() + 2 <= width
+            }
+            None => true,
+        };
+        if fits {
+            self.writer.write_str(" ")?;
+        } else {
+            writeln!(self.writer)?;
+            let indent = self.flow_indent();
+            write!(self.writer, "{:indent$}", "")?;
+        }
+        Ok(())
+    }
+
+    /// Go to the line of the first entry of a block collection, after its properties.
+    fn begin_block_after_properties(&mut self) -> EmitResult {
+        writeln!(self.writer)?;
+        self.level += 1;
+        self.write_indent()?;
+        self.level -= 1;
+        Ok(())
+    }
+
+    /// Write `node` in canonical form (see [`Self::canonical`]).
+    fn emit_canonical(&mut self, node: &Yaml) -> EmitResult {
+        match self.take_anchor(node) {
+            Some((name, true)) => {
+                write!(self.writer, "*{name}")?;
+                return Ok(());
+            }
+            Some((name, false)) => write!(self.writer, "&{name} ")?,
+            None => {}
+        }
+        // A kept tag replaces the tag of the type of the node.
+        let (custom_tag, node) = match node {
+            Yaml::Tagged(tag, node) => (Some(self.tag_text(tag)), &**node),
+            node => (None, node),
+        };
+        let tag = |standard: &str| {
+            custom_tag
+                .clone()
+                .unwrap_or_else(|| format!("!!{standard}"))
+        };
+        match node {
+            Yaml::Array(items) => {
+                write!(self.writer, "{} [", tag("seq"))?;
+                self.level += 1;
+                for item in items {
+                    self.check_cancelled()?;
+                    writeln!(self.writer)?;
+                    self.write_indent()?;
+                    self.emit_canonical(item)?;
+                    self.writer.write_str(",")?;
+                }
+                self.level -= 1;
+                if !items.is_empty() {
+                    writeln!(self.writer)?;
+                    self.write_indent()?;
+                }
+                self.writer.write_str("]")?;
+            }
+            Yaml::Hash(entries) => {
+                write!(self.writer, "{} {{", tag("map"))?;
+                self.level += 1;
+                for (_, (key, value)) in ordered_entries(entries, self.key_order) {
+                    self.check_cancelled()?;
+                    writeln!(self.writer)?;
+                    self.write_indent()?;
+                    self.writer.write_str("? ")?;
+                    self.emit_canonical(key)?;
+                    writeln!(self.writer)?;
+                    self.write_indent()?;
+                    self.writer.write_str(": ")?;
+                    self.emit_canonical(value)?;
+                    self.writer.write_str(",")?;
+                }
+                self.level -= 1;
+                if !entries.is_empty() {
+                    writeln!(self.writer)?;
+                    self.write_indent()?;
+                }
+                self.writer.write_str("}")?;
+            }
+            Yaml::String(v) | Yaml::Real(v) => {
+                let standard = if node.is_string() { "str" } else { "float" };
+                write!(self.writer, "{} ", tag(standard))?;
+                escape_str(&mut self.writer, v)?;
+            }
+            Yaml::SharedString(v) => {
+                write!(self.writer, "{} ", tag("str"))?;
+                escape_str(&mut self.writer, v)?;
+            }
+            Yaml::Float(v) => {
+                write!(self.writer, "{} ", tag("float"))?;
+                escape_str(&mut self.writer, &v.text())?;
+            }
+            Yaml::Integer(v) => write!(self.writer, "{} \"{v}\"", tag("int"))?,
+            Yaml::Boolean(v) => write!(self.writer, "{} \"{v}\"", tag("bool"))?,
+            Yaml::Timestamp(v) => write!(self.writer, "{} \"{v}\"", tag("timestamp"))?,
+            Yaml::Binary(v) => write!(
+                self.writer,
+                "{} \"{}\"",
+                tag("binary"),
+                crate::binary::encode(v)
+            )?,
+            // A node has at most one tag.
+            Yaml::Tagged(_, node) => self.emit_canonical(node)?,
+            Yaml::Null | Yaml::BadValue | Yaml::Alias(_) => {
+                write!(self.writer, "{} \"null\"", tag("null"))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write a string node, as a block scalar if it should be one.
+    fn emit_string(&mut self, v: &str) -> EmitResult {
+        if self.is_block_scalar(v) {
+            self.emit_block_scalar(v)
+        } else {
+            self.emit_str(v)
+        }
+    }
+
+    /// Write `v` as a plain or quoted scalar, as [`Self::quote_style`] requires.
+    fn emit_str(&mut self, v: &str) -> EmitResult {
+        if !self.is_quoted(v) {
+            self.writer.write_str(v)?;
+        } else if self.is_single_quoted(v) {
+            write!(self.writer, "'{}'", v.replace('\'', "''"))?;
+        } else {
+            escape_str(&mut self.writer, v)?;
+        }
+        Ok(())
+    }
+
+    /// Whether `v` is written quoted, rather than as a plain scalar.
+    fn is_quoted(&self, v: &str) -> bool {
+        self.quote_style == QuoteStyle::AlwaysDouble || self.need_quotes(v)
+    }
+
+    /// Whether `v` is written in single quotes if it is quoted.
+    ///
+    /// Single quotes cannot escape anything but themselves, so strings with characters that
+    /// [`escape_str`] escapes (other than `"` and `\`) are double-quoted.
+    fn is_single_quoted(&self, v: &str) -> bool {
+        let needs_escapes = || v.bytes().any(|b| matches!(b, b'\x00'..=b'\x1f' | b'\x7f'));
+        match self.quote_style {
+            QuoteStyle::PreferSingle => !needs_escapes(),
+            QuoteStyle::PreferDouble => v.contains(['"', '\\']) && !needs_escapes(),
+            QuoteStyle::Minimal | QuoteStyle::AlwaysDouble => false,
+        }
+    }
+
+    /// Write `v` as a block scalar, in the style of [`Self::block_style`].
+    ///
+    /// The chomping indicator keeps a single trailing line break (`|`) or none (`|-`).
+    fn emit_block_scalar(&mut self, v: &str) -> EmitResult {
+        let folded = self.block_style == BlockStyle::Folded && !has_spaced_lines(v);
+        let content = v.strip_suffix('\n').unwrap_or(v);
+        self.writer.write_str(if folded { ">" } else { "|" })?;
+        if content.len() == v.len() {
+            self.writer.write_str("-")?;
+        }
+
+        self.level += 1;
+        let width = self.line_width.map(|width| {
+            let indent = usize::try_from(self.level).unwrap_or(0) * self.best_indent;
+            width.saturating_sub(indent).max(1)
+        });
+        let mut lines = content.split('\n').peekable();
+        while let Some(line) = lines.next() {
+            let parts = match width {
+                Some(width) if folded => fold_lines(line, width, 0),
+                _ => vec![line],
+            };
+            for part in parts {
+                writeln!(self.writer)?;
+                self.write_indent()?;
+                // It's literal text, so don't escape special chars.
+                self.writer.write_str(part)?;
+            }
+            // Folding turns a single line break between two lines into a space, and drops the
+            // first of several, so each one is written twice.
+            if folded && !line.is_empty() && lines.peek().is_some() {
+                writeln!(self.writer)?;
+            }
+        }
+        self.level -= 1;
+        Ok(())
+    }
+
+    fn emit_array(&mut self, v: &[Yaml]) -> EmitResult {
+        if v.is_empty() {
+            write!(self.writer, "[]")?;
+        } else if self.is_flow() {
+            self.emit_flow_array(v)?;
+        } else {
+            self.level += 1;
+            for (cnt, x) in v.iter().enumerate() {
+                self.check_cancelled()?;
+                if cnt > 0 {
+                    writeln!(self.writer)?;
+                    self.write_indent()?;
+                }
+                self.enter(|| PathSegment::Index(cnt));
+                self.write_leading_comments()?;
+                write!(self.writer, "-")?;
+                self.emit_val(true, x)?;
+                self.leave();
+            }
+            self.level -= 1;
+        }
+        Ok(())
+    }
+
+    fn emit_hash(&mut self, h: &Hash) -> EmitResult {
         if h.is_empty() {
             self.writer.write_str("{}")?;
+        } else if self.is_flow() {
+            self.emit_flow_hash(h)?;
         } else {
             self.level += 1;
-            for (cnt, (k, v)) in h.iter().enumerate() {
-                let complex_key = matches!(*k, Yaml::Hash(_) | Yaml::Array(_));
-                if cnt > 0 {
+            let key_widths = self.aligned_key_widths(h)?;
+            let align_column = key_widths.iter().flatten().max().copied().unwrap_or(0);
+            for (pos, (cnt, (k, v))) in ordered_entries(h, self.key_order).into_iter().enumerate() {
+                self.check_cancelled()?;
+                let complex_key = self.needs_explicit_key(k)?;
+                if pos > 0 {
                     writeln!(self.writer)?;
+                    if self.level == 0 {
+                        for _ in 0..self.blank_lines_between_top_level {
+                            writeln!(self.writer)?;
+                        }
+                    }
                     self.write_indent()?;
                 }
+                self.enter(|| PathSegment::EntryKey(cnt));
+                self.write_leading_comments()?;
                 if complex_key {
                     write!(self.writer, "?")?;
                     self.emit_val(true, k)?;
+                    self.leave();
                     writeln!(self.writer)?;
                     self.write_indent()?;
                     write!(self.writer, ":")?;
+                    self.enter(|| PathSegment::Key(k.clone()));
                     self.emit_val(true, v)?;
                 } else {
                     self.emit_node(k)?;
+                    self.leave();
                     write!(self.writer, ":")?;
+                    if let Some(width) = key_widths[cnt] {
+                        for _ in width..align_column {
+                            write!(self.writer, " ")?;
+                        }
+                    }
+                    self.enter(|| PathSegment::Key(k.clone()));
                     self.emit_val(false, v)?;
                 }
+                self.leave();
             }
             self.level -= 1;
         }
         Ok(())
     }
 
+    /// Compute the width of each key of `h` whose value takes part in alignment.
+    ///
+    /// Entries that are exempt from alignment yield `None`. If alignment is disabled, the returned
+    /// `Vec` is filled with `None`.
+    fn aligned_key_widths(&self, h: &Hash) -> Result<Vec<Option<usize>>, EmitError> {
+        let AlignPolicy::PerMapping { max_pad } = self.align_values else {
+            return Ok(vec![None; h.len()]);
+        };
+
+        let mut widths = Vec::with_capacity(h.len());
+        for (k, v) in h {
+            let exempt = match (k, v.untagged()) {
+                _ if self.needs_explicit_key(k)? => true,
+                (_, Yaml::Array(v)) if !v.is_empty() => !self.is_value_flow(k),
+                (_, Yaml::Hash(h)) if !h.is_empty() => !self.is_value_flow(k),
+                (_, v) => v.as_str().is_some_and(|v| self.is_block_scalar(v)),
+            };
+            if exempt {
+                widths.push(None);
+            } else {
+                let mut rendered = String::new();
+                self.fork(&mut rendered).emit_node(k)?;
+                widths.push(Some(rendered.chars().count()));
+            }
+        }
+
+        // Entries needing too much padding do not push the column further and stay unaligned.
+        let mut candidates: Vec<usize> = widths.iter().flatten().copied().collect();
+        candidates.sort_unstable();
+        let shortest = candidates.first().copied().unwrap_or(0);
+        let column = candidates
+            .into_iter()
+            .filter(|width| width - shortest <= max_pad)
+            .max()
+            .unwrap_or(0);
+        for width in &mut widths {
+            if width.is_some_and(|width| width > column) {
+                *width = None;
+            }
+        }
+        Ok(widths)
+    }
+
+    /// Whether the collection value of `key`, in the mapping being emitted, is written in flow
+    /// style.
+    fn is_value_flow(&self, key: &Yaml) -> bool {
+        let mut path = self.path.clone();
+        if !self.collection_styles.is_empty() {
+            path.push(PathSegment::Key(key.clone()));
+        }
+        self.is_flow_at(&path, self.level + 1)
+    }
+
+    /// Whether `key` cannot be written as an implicit key (`key: value`), and must follow the `?`
+    /// indicator instead.
+    ///
+    /// Implicit keys must fit on a single line of at most 1024 characters.
+    fn needs_explicit_key(&self, key: &Yaml) -> Result<bool, EmitError> {
+        match key {
+            Yaml::Array(_) | Yaml::Hash(_) => Ok(true),
+            Yaml::Tagged(_, node) => self.needs_explicit_key(node),
+            // Base64 takes 4 characters for every 3 bytes, after the tag.
+            Yaml::Binary(v) => Ok(9 + (v.len() + 2) / 3 * 4 > MAX_IMPLICIT_KEY_LENGTH),
+            _ => match key.as_str() {
+                Some(v) if self.is_block_scalar(v) => Ok(true),
+                // Escaping at most multiplies the length by 6, so short keys need not be rendered.
+                Some(v) if v.len() > MAX_IMPLICIT_KEY_LENGTH / 6 => {
+                    let mut rendered = String::new();
+                    self.fork(&mut rendered).emit_node(key)?;
+                    Ok(rendered.chars().count() > MAX_IMPLICIT_KEY_LENGTH)
+                }
+                _ => Ok(false),
+            },
+        }
+    }
+
+    /// Whether the given string must be quoted, for it to load back as a string with the schema
+    /// of the emitter.
+    fn need_quotes(&self, v: &str) -> bool {
+        need_quotes(v)
+            || self.schema == Schema::Json
+            || (self.schema == Schema::Yaml11
+                && (schema::is_null_11(v)
+                    || schema::parse_bool_11(v).is_some()
+                    || schema::parse_int_11(v).is_some()))
+    }
+
+    /// Whether the given string would be emitted as a block scalar.
+    ///
+    /// The lines of a top-level block scalar are not indented, so lines which could be read as
+    /// document markers are not written this way. Neither are strings whose first line starts
+    /// with a space, which would be read as indentation, nor those ending with several line
+    /// breaks, which the emitted chomping indicators do not keep.
+    fn is_block_scalar(&self, v: &str) -> bool {
+        // The lines of folded blocks may also start after a space, if they are too long.
+        let splits_lines = self.block_style == BlockStyle::Folded && self.line_width.is_some();
+        self.multiline_strings
+            && self.quote_style != QuoteStyle::AlwaysDouble
+            && v.contains('\n')
+            && char_traits::is_valid_literal_block_scalar(v)
+            && !v
+                .lines()
+                .find(|line| !line.is_empty())
+                .is_some_and(|line| line.starts_with(' '))
+            && !v.ends_with("\n\n")
+            && !v
+                .split(|c| c == '\n' || (splits_lines && c == ' '))
+                .any(|line| line.starts_with("---") || line.starts_with("..."))
+    }
+
     /// Emit a yaml as a hash or array value: i.e., which should appear
     /// following a ":" or "-", either after a space, or on a new line.
     /// If `inline` is true, then the preceding characters are distinct
     /// and short enough to respect the compact flag.
     fn emit_val(&mut self, inline: bool, val: &Yaml) -> EmitResult {
+        let anchor = match self.take_anchor(val) {
+            Some((name, true)) => {
+                write!(self.writer, " *{name}")?;
+                return self.write_trailing_comment();
+            }
+            Some((name, false)) => Some(name),
+            None => None,
+        };
+        let anchor = anchor.as_deref();
+        let (tag, val) = match val {
+            Yaml::Tagged(tag, node) => (Some(self.tag_text(tag)), &**node),
+            val => (None, val),
+        };
+        let tag = tag.as_deref();
         match *val {
             Yaml::Array(ref v) => {
-                if (inline && self.compact) || v.is_empty() {
-                    write!(self.writer, " ")?;
-                } else {
-                    writeln!(self.writer)?;
-                    self.level += 1;
-                    self.write_indent()?;
-                    self.level -= 1;
+                let one_line = v.is_empty() || self.is_flow();
+                self.begin_collection_val(inline, one_line, anchor, tag)?;
+                self.emit_array(v)?;
+                if one_line {
+                    self.write_trailing_comment()?;
                 }
-                self.emit_array(v)
+                Ok(())
             }
             Yaml::Hash(ref h) => {
-                if (inline && self.compact) || h.is_empty() {
-                    write!(self.writer, " ")?;
-                } else {
-                    writeln!(self.writer)?;
-                    self.level += 1;
-                    self.write_indent()?;
-                    self.level -= 1;
+                let one_line = h.is_empty() || self.is_flow();
+                self.begin_collection_val(inline, one_line, anchor, tag)?;
+                self.emit_hash(h)?;
+                if one_line {
+                    self.write_trailing_comment()?;
                 }
-                self.emit_hash(h)
+                Ok(())
             }
             _ => {
+                if let Some(tag) = tag {
+                    write!(self.writer, " {tag}")?;
+                }
+                if let Some(wrapped) = self.wrap_string(val)? {
+                    return self.emit_wrapped(&wrapped);
+                }
+                if self.is_empty_null(val) {
+                    return self.write_trailing_comment();
+                }
                 write!(self.writer, " ")?;
-                self.emit_node(val)
+                self.emit_node(val)?;
+                if !val.as_str().is_some_and(|v| self.is_block_scalar(v)) {
+                    self.write_trailing_comment()?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Whether `val`, as a block collection entry, is written as nothing at all.
+    fn is_empty_null(&self, val: &Yaml) -> bool {
+        self.null_style == NullStyle::Empty
+            && self.schema != Schema::Json
+            && matches!(val, Yaml::Null | Yaml::BadValue)
+    }
+
+    /// Split `val` across lines if it is a string longer than [`Self::line_width`] allows.
+    fn wrap_string(&self, val: &Yaml) -> Result<Option<WrappedString>, EmitError> {
+        let (Some(width), Some(v)) = (self.line_width, val.as_str()) else {
+            return Ok(None);
+        };
+        if self.is_block_scalar(v) {
+            return Ok(None);
+        }
+        // Wrapped strings are written on their own lines, one level deeper.
+        let indent = usize::try_from(self.level + 1).unwrap_or(0) * self.best_indent;
+        let width = width.saturating_sub(indent).max(1);
+        let mut rendered = String::new();
+        self.fork(&mut rendered).emit_str(v)?;
+        if rendered.chars().count() <= width {
+            return Ok(None);
+        }
+
+        if self.quote_style != QuoteStyle::AlwaysDouble && is_foldable(v) {
+            let lines = fold_lines(v, width, 0);
+            if lines.len() > 1 {
+                return Ok(Some(WrappedString::Folded(
+                    lines.into_iter().map(str::to_owned).collect(),
+                )));
+            }
+        }
+        let mut escaped = String::new();
+        escape_str(&mut escaped, v)?;
+        let lines = wrap_escaped(&escaped, width, 0);
+        Ok((lines.len() > 1)
+            .then(|| WrappedString::Quoted(lines.into_iter().map(str::to_owned).collect())))
+    }
+
+    /// Write a string value split by [`Self::wrap_string`], after the preceding `:` or `-`.
+    fn emit_wrapped(&mut self, wrapped: &WrappedString) -> EmitResult {
+        let (lines, continuation) = match wrapped {
+            WrappedString::Folded(lines) => {
+                self.writer.write_str(" >-")?;
+                (lines, "")
+            }
+            WrappedString::Quoted(lines) => (lines, "\\"),
+        };
+        self.level += 1;
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                self.writer.write_str(continuation)?;
+            }
+            writeln!(self.writer)?;
+            self.write_indent()?;
+            self.writer.write_str(line)?;
+        }
+        self.level -= 1;
+        // As with literal blocks, comments cannot follow a block scalar.
+        if matches!(wrapped, WrappedString::Quoted(_)) {
+            self.write_trailing_comment()?;
+        }
+        Ok(())
+    }
+
+    /// Write what separates a collection value from the preceding `:` or `-`.
+    ///
+    /// See [`Self::emit_val`] for the meaning of `inline`. Collections with an anchor or a tag
+    /// cannot be written in compact inline notation. `one_line` collections (empty or in flow
+    /// style) always follow on the same line.
+    fn begin_collection_val(
+        &mut self,
+        inline: bool,
+        one_line: bool,
+        anchor: Option<&str>,
+        tag: Option<&str>,
+    ) -> EmitResult {
+        if let Some(name) = anchor {
+            write!(self.writer, " &{name}")?;
+        }
+        if let Some(tag) = tag {
+            write!(self.writer, " {tag}")?;
+        }
+        // Compact collections cannot start right after `-` or `?`, so need an indent of 2 or more.
+        let compact = inline && self.compact && tag.is_none() && self.best_indent > 1;
+        if (anchor.is_none() && compact) || one_line {
+            // The first entry of a compact collection is aligned with the next ones.
+            let spaces = if one_line { 1 } else { self.best_indent - 1 };
+            write!(self.writer, "{:spaces$}", "")?;
+        } else {
+            self.write_trailing_comment()?;
+            writeln!(self.writer)?;
+            self.level += 1;
+            self.write_indent()?;
+            // The leading comments of sequence items and keys are written by their parent.
+            if !inline {
+                self.write_leading_comments()?;
+            }
+            self.level -= 1;
+        }
+        Ok(())
+    }
+}
+
+/// A string value split across lines to respect [`YamlEmitter::line_width`].
+enum WrappedString {
+    /// The lines of a folded block scalar.
+    Folded(Vec<String>),
+    /// The lines of a double-quoted scalar, quotes included, to be joined by escaped line breaks.
+    Quoted(Vec<String>),
+}
+
+/// Whether some line of `v` starts with a blank.
+///
+/// The line breaks around such "more-indented" lines are not folded, so strings with them are
+/// written as literal block scalars whatever [`YamlEmitter::block_style`].
+fn has_spaced_lines(v: &str) -> bool {
+    v.split('\n').any(|line| line.starts_with([' ', '\t']))
+}
+
+/// Whether `v` can be written as a folded block scalar which loads back to `v`.
+///
+/// Line breaks and control characters cannot be folded, and leading or trailing blanks would be
+/// mistaken for indentation or lost.
+fn is_foldable(v: &str) -> bool {
+    let is_blank = |c: char| c == ' ' || c == '\t';
+    !v.starts_with(is_blank)
+        && !v.ends_with(is_blank)
+        && !v.chars().any(|c| c.is_control() && c != '\t')
+}
+
+/// Split `v` into the lines of a folded block scalar, of at most `width` characters where
+/// possible, the first of which follows `offset` characters.
+///
+/// Lines are only broken at single spaces between two non-blank characters, which folding turns
+/// back into a space. Other blanks are significant and are kept, e.g. double spaces.
+fn fold_lines(v: &str, width: usize, offset: usize) -> Vec<&str> {
+    let is_blank = |c: char| c == ' ' || c == '\t';
+    let chars: Vec<(usize, char)> = v.char_indices().collect();
+    let mut lines = vec![];
+    // The byte and char offsets of the start of the current line and of the last space it can
+    // be broken at.
+    let mut start = (0, 0);
+    let mut last_break: Option<(usize, usize)> = None;
+    for (k, &(i, c)) in chars.iter().enumerate() {
+        let breakable = c == ' '
+            && k > 0
+            && !is_blank(chars[k - 1].1)
+            && chars.get(k + 1).is_some_and(|&(_, next)| !is_blank(next));
+        if !breakable {
+            continue;
+        }
+        let lead = if lines.is_empty() { offset } else { 0 };
+        if k - start.1 + lead > width {
+            if let Some((byte, char)) = last_break {
+                lines.push(&v[start.0..byte]);
+                start = (byte + 1, char + 1);
+            }
+        }
+        last_break = Some((i, k));
+    }
+    let lead = if lines.is_empty() { offset } else { 0 };
+    if chars.len() - start.1 + lead > width {
+        if let Some((byte, char)) = last_break.filter(|&(_, char)| char >= start.1) {
+            lines.push(&v[start.0..byte]);
+            start = (byte + 1, char + 1);
+        }
+    }
+    lines.push(&v[start.0..]);
+    lines
+}
+
+/// Split `escaped`, a double-quoted scalar as written by `escape_str`, into lines of at most
+/// `width` characters (counting the `\` escaping the line break) where possible, the first of
+/// which follows `offset` characters.
+///
+/// Lines are broken after a space if possible, and between escape sequences otherwise. They are
+/// never broken before a space, which would be discarded as indentation of the next line.
+fn wrap_escaped(escaped: &str, width: usize, offset: usize) -> Vec<&str> {
+    // Split into characters and escape sequences, by byte offset.
+    let mut units = vec![];
+    let mut chars = escaped.char_indices();
+    while let Some((i, c)) = chars.next() {
+        units.push(i);
+        if c == '\\' {
+            let escape = chars.next().map(|(_, c)| c);
+            if escape == Some('u') {
+                chars.nth(3);
+            }
+        }
+    }
+    units.push(escaped.len());
+
+    let unit = |j: usize| &escaped[units[j]..units[j + 1]];
+    let unit_width = |j: usize| unit(j).chars().count();
+    let mut lines = vec![];
+    let mut start = 0;
+    let mut len = offset;
+    let mut soft_break = None;
+    for j in 0..units.len() - 1 {
+        // Do not break right after the opening quote, or right before the closing one.
+        let breakable = j >= 2 && j + 2 < units.len() && unit(j) != " ";
+        if breakable && len > 0 && len + unit_width(j) + 1 > width {
+            let at = soft_break.filter(|&at| at > start).unwrap_or(j);
+            lines.push(&escaped[units[start]..units[at]]);
+            len = (at..j).map(unit_width).sum();
+            start = at;
+            soft_break = None;
+        }
+        if breakable && unit(j - 1) == " " {
+            soft_break = Some(j);
+        }
+        len += unit_width(j);
+    }
+    lines.push(&escaped[units[start]..]);
+    lines
+}
+
+/// Walk `node` in emission order and list the collections that are emitted more than once.
+///
+/// `key_order` is the setting of [`YamlEmitter::key_order`].
+/// `first_paths` maps every non-empty collection to the path of its first occurrence. Repeated
+/// collections are listed in `repeated` in the order of their first occurrence. Children of
+/// repeated occurrences are not visited, since they are emitted as an alias.
+fn find_repeated<'y>(
+    node: &'y Yaml,
+    key_order: KeyOrder,
+    path: &mut YamlPath,
+    first_paths: &mut BTreeMap<&'y Yaml, YamlPath>,
+    repeated: &mut Vec<&'y Yaml>,
+) {
+    let mut visit_child = |child: &'y Yaml, path: &mut YamlPath| {
+        let is_candidate = match child {
+            Yaml::Array(v) => !v.is_empty(),
+            Yaml::Hash(h) => !h.is_empty(),
+            _ => false,
+        };
+        if !is_candidate {
+            // The collections within tagged ones may be repeated as well.
+            if child.is_tagged() {
+                find_repeated(child, key_order, path, first_paths, repeated);
+            }
+            return;
+        }
+        if first_paths.contains_key(child) {
+            if !repeated.contains(&child) {
+                repeated.push(child);
+            }
+        } else {
+            first_paths.insert(child, path.clone());
+            find_repeated(child, key_order, path, first_paths, repeated);
+        }
+    };
+    match node.untagged() {
+        Yaml::Array(v) => {
+            for (i, item) in v.iter().enumerate() {
+                path.push(PathSegment::Index(i));
+                visit_child(item, path);
+                path.pop();
+            }
+        }
+        Yaml::Hash(h) => {
+            for (i, (k, v)) in ordered_entries(h, key_order) {
+                path.push(PathSegment::EntryKey(i));
+                visit_child(k, path);
+                path.pop();
+                path.push(PathSegment::Key(k.clone()));
+                visit_child(v, path);
+                path.pop();
             }
         }
+        _ => {}
+    }
+}
+
+/// The entries of `h` in the order they are emitted, along with their index in `h`.
+fn ordered_entries(h: &Hash, order: KeyOrder) -> Vec<(usize, (&Yaml, &Yaml))> {
+    let mut entries: Vec<_> = h.iter().enumerate().collect();
+    match order {
+        KeyOrder::Insertion => {}
+        KeyOrder::Sorted => entries.sort_by(|(_, (a, _)), (_, (b, _))| compare_keys(a, b)),
+        KeyOrder::Custom(compare) => entries.sort_by(|(_, (a, _)), (_, (b, _))| compare(a, b)),
+    }
+    entries
+}
+
+/// The order of mapping keys for [`KeyOrder::Sorted`].
+pub(crate) fn compare_keys(a: &Yaml, b: &Yaml) -> Ordering {
+    fn rank(key: &Yaml) -> u8 {
+        match key {
+            Yaml::Null => 0,
+            Yaml::Boolean(_) => 1,
+            Yaml::Integer(_) => 2,
+            Yaml::Real(_) | Yaml::Float(_) => 3,
+            Yaml::Timestamp(_) => 4,
+            Yaml::String(_) | Yaml::SharedString(_) => 5,
+            Yaml::Binary(_) => 6,
+            Yaml::Array(_) => 7,
+            Yaml::Hash(_) => 8,
+            Yaml::Tagged(..) => 9,
+            Yaml::Alias(_) => 10,
+            Yaml::BadValue => 11,
+        }
+    }
+    // Use the default settings, so that the order does not depend on those of the emitter.
+    fn serialized(key: &Yaml) -> String {
+        let mut output = String::new();
+        let mut emitter = YamlEmitter::new(&mut output);
+        emitter.key_order = KeyOrder::Sorted;
+        // Writing to a `String` cannot fail.
+        let _ = emitter.emit_node(key);
+        output
+    }
+
+    match (a, b) {
+        (Yaml::Boolean(a), Yaml::Boolean(b)) => a.cmp(b),
+        (Yaml::Integer(a), Yaml::Integer(b)) => a.cmp(b),
+        (Yaml::Real(a), Yaml::Real(b)) => match (parse_f64(a), parse_f64(b)) {
+            (Some(x), Some(y)) => x.total_cmp(&y).then_with(|| a.cmp(b)),
+            _ => a.cmp(b),
+        },
+        (Yaml::Float(a), Yaml::Float(b)) => a.cmp(b),
+        (Yaml::Timestamp(a), Yaml::Timestamp(b)) => a.cmp(b),
+        (Yaml::Binary(a), Yaml::Binary(b)) => a.cmp(b),
+        (Yaml::Array(_), Yaml::Array(_))
+        | (Yaml::Hash(_), Yaml::Hash(_))
+        | (Yaml::Tagged(..), Yaml::Tagged(..)) => serialized(a).cmp(&serialized(b)),
+        (Yaml::Alias(a), Yaml::Alias(b)) => a.cmp(b),
+        _ => match (a.as_str(), b.as_str()) {
+            (Some(a), Some(b)) => a.cmp(b),
+            _ => rank(a).cmp(&rank(b)),
+        },
+    }
+}
+
+/// Turn `name` into a valid anchor name.
+fn sanitize_anchor(name: &str) -> String {
+    let name: String = name
+        .trim_start_matches('/')
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.is_empty() {
+        "anchor".to_owned()
+    } else {
+        name
+    }
+}
+
+/// The 64-bit FNV-1a hash of `bytes`.
+///
+/// Unlike the hashers of the standard library, it is guaranteed to be stable across platforms
+/// and Rust versions, so it can be used in emitted documents.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Return how `tag` is written before a node, e.g. `!Ref` or `!!set`.
+///
+/// Tags whose handle was expanded from a `%TAG` directive are written verbatim (e.g.
+/// `!<tag:example.com,2020:config>`). This is the fallback used when no handle declared with
+/// [`YamlEmitter::tag_handle`] matches the tag.
+pub(crate) fn tag_text(tag: &Tag) -> String {
+    if tag.handle == "tag:yaml.org,2002:" {
+        format!("!!{}", tag.suffix)
+    } else if tag.handle.starts_with('!') {
+        format!("{}{}", tag.handle, tag.suffix)
+    } else {
+        format!("!<{}{}>", tag.handle, tag.suffix)
     }
 }
 
@@ -370,7 +2640,7 @@ impl<'a> YamlEmitter<'a> {
 /// * When the string looks like a number, such as integers (e.g. 2, 14, etc.), floats (e.g. 2.6, 14.9) and exponential numbers (e.g. 12e7, etc.) (otherwise, it would be treated as a numeric value);
 /// * When the string looks like a date (e.g. 2014-12-31) (otherwise it would be automatically converted into a Unix timestamp).
 #[allow(clippy::doc_markdown)]
-fn need_quotes(string: &str) -> bool {
+pub(crate) fn need_quotes(string: &str) -> bool {
     fn need_quotes_spaces(string: &str) -> bool {
         string.starts_with(' ') || string.ends_with(' ')
     }
@@ -417,10 +2687,24 @@ fn need_quotes(string: &str) -> bool {
         || string.starts_with("0x")
         || string.parse::<i64>().is_ok()
         || string.parse::<f64>().is_ok()
+        || is_timestamp(string)
+}
+
+/// Return whether `string` would be loaded as a timestamp if it were written unquoted.
+#[cfg(feature = "timestamps")]
+fn is_timestamp(string: &str) -> bool {
+    crate::Timestamp::parse(string).is_some()
+}
+
+#[cfg(not(feature = "timestamps"))]
+fn is_timestamp(_: &str) -> bool {
+    false
 }
 
 #[cfg(test)]
 mod test {
+    use alloc::string::String;
+
     use crate::Yaml;
 
     use super::YamlEmitter;
@@ -1,5 +1,6 @@
 //! Encoding utilities. Available only with the `encoding` feature.
 
+use alloc::{string::String, vec::Vec};
 use std::{borrow::Cow, ops::ControlFlow};
 
 use encoding_rs::{Decoder, DecoderResult, Encoding};
@@ -197,6 +198,8 @@ fn detect_utf16_endianness(b: &[u8]) -> &'static Encoding {
 
 #[cfg(test)]
 mod test {
+    use std::println;
+
     use super::{YAMLDecodingTrap, Yaml, YamlDecoder};
 
     #[test]
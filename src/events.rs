@@ -0,0 +1,158 @@
+//! Conversion of [`Yaml`] nodes to parser events.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use saphyr_parser::{Event, TScalarStyle};
+
+use crate::{emitter::need_quotes, Array, Hash, Yaml};
+
+/// An iterator over the events of a [`Yaml`] document, as returned by [`Yaml::into_events`].
+pub struct YamlEvents {
+    /// The events to yield before the root node, in reverse order.
+    prologue: Vec<Event>,
+    /// The root node, until its first event is yielded.
+    root: Option<Yaml>,
+    /// The collections that are being iterated over, innermost last.
+    stack: Vec<Frame>,
+    /// The events to yield after the root node, in reverse order.
+    epilogue: Vec<Event>,
+}
+
+/// A collection that is being iterated over by [`YamlEvents`].
+enum Frame {
+    Array(<Array as IntoIterator>::IntoIter),
+    /// The entries of the mapping, and the value of the last key yielded, if it has not been
+    /// yielded yet.
+    Hash(<Hash as IntoIterator>::IntoIter, Option<Yaml>),
+}
+
+impl Yaml {
+    /// Convert `self` to the events the parser would produce for a stream holding it as its only
+    /// document.
+    ///
+    /// The events can be transformed and loaded back with [`YamlLoader::from_events`]. Nodes are
+    /// yielded as they are reached, without copying the document. Strings which would not be read
+    /// back as strings if unquoted (e.g. `"true"`) are double-quoted, as the emitter does.
+    /// Aliases are yielded as [`Event::Alias`], without any anchor. The tag of a [`Yaml::Tagged`]
+    /// node is that of the event of the node it tags.
+    ///
+    /// ```
+    /// # use saphyr::{Event, Yaml, YamlLoader};
+    /// let doc = Yaml::load_one_from_str("a: [1, '2']").unwrap();
+    /// let events = doc.clone().into_events();
+    /// let reloaded = YamlLoader::<Yaml>::from_events(events.map(|ev| (ev, Default::default())));
+    /// assert_eq!(reloaded.unwrap(), [doc]);
+    /// ```
+    ///
+    /// [`YamlLoader::from_events`]: crate::YamlLoader::from_events
+    #[must_use]
+    pub fn into_events(self) -> YamlEvents {
+        YamlEvents {
+            prologue: vec![Event::DocumentStart(true), Event::StreamStart],
+            root: Some(self),
+            stack: vec![],
+            epilogue: vec![Event::StreamEnd, Event::DocumentEnd],
+        }
+    }
+}
+
+impl YamlEvents {
+    /// Return the first event of `node`, and push it onto the stack if it is a collection.
+    fn enter(&mut self, node: Yaml) -> Event {
+        match node {
+            Yaml::Array(array) => {
+                self.stack.push(Frame::Array(array.into_iter()));
+                Event::SequenceStart(0, None)
+            }
+            Yaml::Hash(hash) => {
+                self.stack.push(Frame::Hash(hash.into_iter(), None));
+                Event::MappingStart(0, None)
+            }
+            Yaml::Tagged(tag, node) => {
+                let mut event = self.enter(*node);
+                if let Event::SequenceStart(_, node_tag)
+                | Event::MappingStart(_, node_tag)
+                | Event::Scalar(_, _, _, node_tag) = &mut event
+                {
+                    *node_tag = Some(tag);
+                }
+                event
+            }
+            scalar => scalar_event(scalar),
+        }
+    }
+}
+
+impl Iterator for YamlEvents {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Event> {
+        if let Some(event) = self.prologue.pop() {
+            return Some(event);
+        }
+        if let Some(root) = self.root.take() {
+            return Some(self.enter(root));
+        }
+        let next = match self.stack.last_mut() {
+            None => return self.epilogue.pop(),
+            Some(Frame::Array(items)) => items.next(),
+            Some(Frame::Hash(entries, value)) => value.take().or_else(|| {
+                entries.next().map(|(key, next_value)| {
+                    *value = Some(next_value);
+                    key
+                })
+            }),
+        };
+        Some(match next {
+            Some(node) => self.enter(node),
+            None => match self.stack.pop() {
+                Some(Frame::Array(_)) => Event::SequenceEnd,
+                _ => Event::MappingEnd,
+            },
+        })
+    }
+}
+
+/// Return the event of a scalar node.
+fn scalar_event(node: Yaml) -> Event {
+    let plain = |v: String| Event::Scalar(v, TScalarStyle::Plain, 0, None);
+    match node {
+        Yaml::SharedString(v) => scalar_event(Yaml::String(String::from(&*v))),
+        Yaml::String(v) => {
+            let style = if need_quotes(&v) {
+                TScalarStyle::DoubleQuoted
+            } else {
+                TScalarStyle::Plain
+            };
+            Event::Scalar(v, style, 0, None)
+        }
+        Yaml::Real(v) => plain(v),
+        Yaml::Float(v) => plain(v.text().into_owned()),
+        Yaml::Integer(v) => plain(v.to_string()),
+        Yaml::Boolean(v) => plain(v.to_string()),
+        Yaml::Timestamp(v) => Event::Scalar(
+            v.to_string(),
+            TScalarStyle::Plain,
+            0,
+            Some(saphyr_parser::Tag {
+                handle: "tag:yaml.org,2002:".into(),
+                suffix: "timestamp".into(),
+            }),
+        ),
+        Yaml::Binary(v) => Event::Scalar(
+            crate::binary::encode(&v),
+            TScalarStyle::Plain,
+            0,
+            Some(saphyr_parser::Tag {
+                handle: "tag:yaml.org,2002:".into(),
+                suffix: "binary".into(),
+            }),
+        ),
+        Yaml::Alias(id) => Event::Alias(id),
+        Yaml::Null | Yaml::BadValue => plain("~".into()),
+        Yaml::Array(_) | Yaml::Hash(_) | Yaml::Tagged(..) => unreachable!("not a scalar"),
+    }
+}
@@ -0,0 +1,121 @@
+//! Floats stored as their value.
+
+use alloc::{
+    borrow::{Cow, ToOwned},
+    string::{String, ToString},
+};
+use core::cmp::Ordering;
+use core::fmt::{self, Display};
+use core::hash::{Hash, Hasher};
+
+/// A float stored as an `f64`, and optionally the text it was loaded from.
+///
+/// Unlike a [`Yaml::Real`](crate::Yaml::Real), a `Float` is compared and hashed by value, bit by
+/// bit: `1.0` and `1.00` are equal, `.nan` is equal to itself, and `0.0` and `-0.0` are
+/// different. The `source` is ignored by comparisons.
+///
+/// A `Float` is emitted as its `source` if there is one, or in its canonical form otherwise (see
+/// the [`Display`] implementation). Floats are only loaded as such with the `floats` feature and
+/// `LoaderOptions::parse_floats`.
+///
+/// ```
+/// # use saphyr::Float;
+/// assert_eq!(Float::parse("1.00"), Some(Float::new(1.0)));
+/// assert_eq!(Float::parse("1.00").unwrap().source.as_deref(), Some("1.00"));
+/// assert_eq!(Float::new(f64::NAN), Float::new(f64::NAN));
+/// assert_ne!(Float::new(0.0), Float::new(-0.0));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Float {
+    /// The value of the float.
+    pub value: f64,
+    /// The text the float was loaded from, if it was kept. It should denote `value`.
+    pub source: Option<String>,
+}
+
+impl Float {
+    /// Create a float without a source.
+    #[must_use]
+    pub fn new(value: f64) -> Self {
+        Self {
+            value,
+            source: None,
+        }
+    }
+
+    /// Parse a YAML float, keeping `v` as its source.
+    ///
+    /// Returns `None` if `v` is not a valid float, as for [`Yaml::as_f64`](crate::Yaml::as_f64).
+    #[must_use]
+    pub fn parse(v: &str) -> Option<Self> {
+        crate::loader::parse_f64(v).map(|value| Self {
+            value,
+            source: Some(v.to_owned()),
+        })
+    }
+
+    /// Return the text the float is emitted as: its source if any, or its canonical form.
+    pub(crate) fn text(&self) -> Cow<'_, str> {
+        match &self.source {
+            Some(source) => Cow::Borrowed(source),
+            None => Cow::Owned(self.to_string()),
+        }
+    }
+}
+
+impl PartialEq for Float {
+    fn eq(&self, other: &Self) -> bool {
+        self.value.to_bits() == other.value.to_bits()
+    }
+}
+
+impl Eq for Float {}
+
+impl Hash for Float {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.to_bits().hash(state);
+    }
+}
+
+impl PartialOrd for Float {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Float {
+    /// Order floats with [`f64::total_cmp`], which is consistent with equality.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.value.total_cmp(&other.value)
+    }
+}
+
+impl Display for Float {
+    /// Write the canonical form of the value, ignoring the source.
+    ///
+    /// This is the shortest form which reads back as the value, with a `.` so that it is a float
+    /// in YAML 1.1 as well (e.g. `1.0e100`). Non-finite values are written `.nan`, `.inf` and
+    /// `-.inf`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self.value;
+        if value.is_nan() {
+            f.write_str(".nan")
+        } else if value.is_infinite() {
+            f.write_str(if value > 0.0 { ".inf" } else { "-.inf" })
+        } else {
+            let shortest = format!("{value:?}");
+            match shortest.split_once('e') {
+                Some((mantissa, exponent)) if !mantissa.contains('.') => {
+                    write!(f, "{mantissa}.0e{exponent}")
+                }
+                _ => f.write_str(&shortest),
+            }
+        }
+    }
+}
+
+impl From<f64> for Float {
+    fn from(value: f64) -> Self {
+        Self::new(value)
+    }
+}
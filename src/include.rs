@@ -0,0 +1,157 @@
+//! Splicing other sources into documents with `!include` tags.
+
+use alloc::{string::String, sync::Arc};
+use core::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::LoadError;
+
+/// Where the loader gets the sources of the scalars tagged `!include` (see
+/// [`IncludeOptions`]).
+///
+/// The loader calls [`Self::locate`] to identify the included source, then [`Self::resolve`] to
+/// get its text. Resolvers need not read files: tests and sandboxed environments can serve
+/// sources from memory.
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # use std::path::Path;
+/// # use saphyr::{IncludeOptions, IncludeResolver, LoadError, LoaderOptions, Yaml};
+/// struct InMemory(HashMap<&'static str, &'static str>);
+///
+/// impl IncludeResolver for InMemory {
+///     fn resolve(&self, path: &str, _: Option<&Path>) -> Result<String, LoadError> {
+///         let source = self.0.get(path).ok_or_else(|| {
+///             std::io::Error::new(std::io::ErrorKind::NotFound, path.to_owned())
+///         })?;
+///         Ok((*source).to_owned())
+///     }
+/// }
+///
+/// let resolver = InMemory(HashMap::from([("db.yml", "host: localhost")]));
+/// let options = LoaderOptions {
+///     includes: Some(IncludeOptions::new(resolver)),
+///     ..LoaderOptions::default()
+/// };
+/// let docs = Yaml::load_from_str_with_options("db: !include db.yml", options).unwrap();
+/// assert_eq!(docs[0]["db"]["host"].as_str(), Some("localhost"));
+/// ```
+pub trait IncludeResolver: Send + Sync {
+    /// Return the text of the source at `path`, as written after the tag.
+    ///
+    /// `from` is where the including source is, as returned by [`Self::locate`], or
+    /// [`IncludeOptions::root`] for the including document itself.
+    ///
+    /// # Errors
+    /// Returns an error if the source cannot be read, e.g. [`LoadError::IO`]. Loading fails with
+    /// that error, within a [`LoadError::Include`].
+    fn resolve(&self, path: &str, from: Option<&Path>) -> Result<String, LoadError>;
+
+    /// Return where the source at `path` is, to tell it apart from other sources.
+    ///
+    /// Include cycles are found by comparing these locations, and the location of a source is
+    /// the `from` of the sources it includes. By default, `path` is relative to the directory of
+    /// `from`.
+    fn locate(&self, path: &str, from: Option<&Path>) -> PathBuf {
+        match from.and_then(Path::parent) {
+            Some(directory) => directory.join(path),
+            None => PathBuf::from(path),
+        }
+    }
+}
+
+/// An [`IncludeResolver`] reading files.
+///
+/// Paths are relative to the directory of the including file, or to the base directory for
+/// the document being loaded if [`IncludeOptions::root`] is not set.
+#[derive(Clone, Debug, Default)]
+pub struct FsIncludeResolver {
+    base: PathBuf,
+}
+
+impl FsIncludeResolver {
+    /// Create a resolver reading files relative to `base` from the document being loaded.
+    ///
+    /// The [`Default`] resolver reads them relative to the current directory.
+    #[must_use]
+    pub fn new(base: impl Into<PathBuf>) -> Self {
+        Self { base: base.into() }
+    }
+}
+
+impl IncludeResolver for FsIncludeResolver {
+    fn resolve(&self, path: &str, from: Option<&Path>) -> Result<String, LoadError> {
+        Ok(std::fs::read_to_string(self.locate(path, from))?)
+    }
+
+    /// Return the canonical path of the file, so that different paths to the same file are
+    /// identified, or the path relative to the including file if it does not exist.
+    fn locate(&self, path: &str, from: Option<&Path>) -> PathBuf {
+        let directory = from.and_then(Path::parent).unwrap_or(&self.base);
+        let path = directory.join(path);
+        std::fs::canonicalize(&path).unwrap_or(path)
+    }
+}
+
+/// How the loader splices the sources of `!include` scalars into documents (see
+/// [`LoaderOptions::includes`]).
+///
+/// A scalar with [`Self::tag`] is replaced by the only document of the source it names,
+/// which is loaded with the same options, recursively. Loading fails with
+/// [`LoadError::IncludeCycle`] if a source includes itself, directly or not, and with
+/// [`LoadError::LimitExceeded`] if includes are nested deeper than [`Self::max_depth`].
+///
+/// Limits on the whole stream (e.g. [`LoaderOptions::max_nodes`]) count the included nodes as
+/// well. The node filter of the loader (see [`YamlLoader::node_filter`]) is only called on the
+/// included document as a whole.
+///
+/// [`LoaderOptions::includes`]: crate::LoaderOptions::includes
+/// [`LoaderOptions::max_nodes`]: crate::LoaderOptions::max_nodes
+/// [`YamlLoader::node_filter`]: crate::YamlLoader::node_filter
+#[derive(Clone)]
+pub struct IncludeOptions {
+    /// Where the included sources come from.
+    pub resolver: Arc<dyn IncludeResolver>,
+    /// The tag of the scalars to replace. Defaults to `!include`.
+    pub tag: String,
+    /// The maximum number of nested includes. Defaults to 16.
+    pub max_depth: usize,
+    /// Where the document being loaded is, if it is a file. Sources included by the document
+    /// are located relative to it, and including it again is a cycle.
+    pub root: Option<PathBuf>,
+}
+
+impl IncludeOptions {
+    /// Create options resolving includes with `resolver`, and the default settings.
+    #[must_use]
+    pub fn new(resolver: impl IncludeResolver + 'static) -> Self {
+        Self {
+            resolver: Arc::new(resolver),
+            tag: "!include".into(),
+            max_depth: 16,
+            root: None,
+        }
+    }
+}
+
+impl fmt::Debug for IncludeOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IncludeOptions")
+            .field("tag", &self.tag)
+            .field("max_depth", &self.max_depth)
+            .field("root", &self.root)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for IncludeOptions {
+    /// Options are equal if their settings are, and they share the same resolver.
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.resolver, &other.resolver)
+            && self.tag == other.tag
+            && self.max_depth == other.max_depth
+            && self.root == other.root
+    }
+}
+
+impl Eq for IncludeOptions {}
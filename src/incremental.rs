@@ -0,0 +1,300 @@
+//! Incremental loading, for editors re-loading documents after each edit.
+
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
+use core::ops::Range;
+
+use hashlink::LinkedHashMap;
+use saphyr_parser::{BufferedInput, Marker, Parser, ScanError};
+
+use crate::{loader::LoadError, MarkedYaml, YamlData, YamlLoader};
+
+/// A range of bytes within the source of an [`IncrementalLoader`].
+pub type ByteRange = Range<usize>;
+
+/// A loader which keeps its source and documents around so they can be updated after an edit.
+///
+/// The source is split into regions, each of which is made of one (or seldom more) documents and
+/// can be parsed independently of the others. When the source is edited, only the regions around
+/// the edit are re-parsed and the markers of the documents that follow are shifted. If the
+/// re-parsed regions cannot be loaded on their own, the whole source is loaded again. In both
+/// cases, the documents are the same as they would be after loading the edited source from
+/// scratch.
+///
+/// ```
+/// # use saphyr::IncrementalLoader;
+/// let mut loader = IncrementalLoader::new("a: 1\n---\nb: 2").unwrap();
+/// loader.apply_edit(3..4, "42").unwrap();
+///
+/// assert_eq!(loader.source(), "a: 42\n---\nb: 2");
+/// assert_eq!(loader.documents()[0].data["a"].data.as_i64(), Some(42));
+/// assert_eq!(loader.documents()[1].span.start.line(), 3);
+/// ```
+pub struct IncrementalLoader {
+    /// The current source of the stream.
+    source: String,
+    /// The documents loaded from `source`.
+    documents: Vec<MarkedYaml>,
+    /// The regions `source` is split into, in order.
+    regions: Vec<Region>,
+}
+
+/// A part of the source that can be parsed independently of the others.
+#[derive(Clone, Copy, Debug)]
+struct Region {
+    /// The byte offset of the start of the region.
+    ///
+    /// Regions always start at the beginning of a line.
+    start: usize,
+    /// The number of documents in the region.
+    documents: usize,
+}
+
+/// The position of a region within the source.
+#[derive(Clone, Copy, Default)]
+struct Offset {
+    bytes: usize,
+    chars: usize,
+    lines: usize,
+}
+
+/// A shift to apply to markers located after an edit.
+///
+/// Since regions start at the beginning of a line, columns never need to be shifted.
+struct Shift {
+    remove_chars: usize,
+    add_chars: usize,
+    remove_lines: usize,
+    add_lines: usize,
+}
+
+impl Shift {
+    fn apply(&self, marker: Marker) -> Marker {
+        Marker::new(
+            marker.index() - self.remove_chars + self.add_chars,
+            marker.line() - self.remove_lines + self.add_lines,
+            marker.col(),
+        )
+    }
+}
+
+impl IncrementalLoader {
+    /// Load the given source.
+    ///
+    /// # Errors
+    /// Returns `LoadError` if the source could not be loaded.
+    pub fn new<S: Into<String>>(source: S) -> Result<Self, LoadError> {
+        let source = source.into();
+        let (documents, regions) =
+            load_regions(&source, Offset::default()).map_err(LoadError::Scan)?;
+        Ok(Self {
+            source,
+            documents,
+            regions,
+        })
+    }
+
+    /// Return the current source of the stream.
+    #[must_use]
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Return the documents loaded from the current source.
+    #[must_use]
+    pub fn documents(&self) -> &[MarkedYaml] {
+        &self.documents
+    }
+
+    /// Return the documents loaded from the current source, consuming `self` in the process.
+    #[must_use]
+    pub fn into_documents(self) -> Vec<MarkedYaml> {
+        self.documents
+    }
+
+    /// Replace the given range of the source with `replacement` and update the documents.
+    ///
+    /// The documents the edit touches and their immediate neighbours are re-parsed. If that
+    /// fails, the whole source is re-parsed.
+    ///
+    /// # Errors
+    /// Returns `LoadError` if the edited source cannot be loaded. In that case, the edit is
+    /// reverted and `self` is left unchanged.
+    ///
+    /// # Panics
+    /// This function panics if `range` is out of bounds or does not lie on UTF-8 character
+    /// boundaries, as per [`String::replace_range`].
+    pub fn apply_edit(&mut self, range: ByteRange, replacement: &str) -> Result<(), LoadError> {
+        let removed = self.source[range.clone()].to_owned();
+        let shift = Shift {
+            remove_chars: removed.chars().count(),
+            add_chars: replacement.chars().count(),
+            remove_lines: removed.matches('\n').count(),
+            add_lines: replacement.matches('\n').count(),
+        };
+
+        let first = self.region_at(range.start).saturating_sub(1);
+        let last = (self.region_at(range.end) + 1).min(self.regions.len() - 1);
+        let chunk_start = self.regions[first].start;
+        let chunk_end = self
+            .regions
+            .get(last + 1)
+            .map_or(self.source.len(), |region| region.start)
+            - removed.len()
+            + replacement.len();
+
+        self.source.replace_range(range.clone(), replacement);
+
+        let offset = Offset {
+            bytes: chunk_start,
+            chars: self.source[..chunk_start].chars().count(),
+            lines: self.source[..chunk_start].matches('\n').count(),
+        };
+        match load_regions(&self.source[chunk_start..chunk_end], offset) {
+            Ok((documents, regions)) => {
+                let docs_before: usize = self.regions[..first].iter().map(|r| r.documents).sum();
+                let docs_edited: usize =
+                    self.regions[first..=last].iter().map(|r| r.documents).sum();
+
+                for document in &mut self.documents[docs_before + docs_edited..] {
+                    shift_node(document, &shift);
+                }
+                for region in &mut self.regions[last + 1..] {
+                    region.start = region.start - removed.len() + replacement.len();
+                }
+
+                self.documents
+                    .splice(docs_before..docs_before + docs_edited, documents);
+                self.regions.splice(first..=last, regions);
+                Ok(())
+            }
+            Err(_) => match load_regions(&self.source, Offset::default()) {
+                Ok((documents, regions)) => {
+                    self.documents = documents;
+                    self.regions = regions;
+                    Ok(())
+                }
+                Err(error) => {
+                    self.source
+                        .replace_range(range.start..range.start + replacement.len(), &removed);
+                    Err(LoadError::Scan(error))
+                }
+            },
+        }
+    }
+
+    /// Return the index of the region containing the given byte.
+    fn region_at(&self, byte: usize) -> usize {
+        self.regions
+            .partition_point(|region| region.start <= byte)
+            .saturating_sub(1)
+    }
+}
+
+/// Load the documents in `text` and split it into regions.
+///
+/// `text` must start at the beginning of a line (i.e.: at the beginning of a region). Markers and
+/// regions are shifted by `offset`.
+fn load_regions(text: &str, offset: Offset) -> Result<(Vec<MarkedYaml>, Vec<Region>), ScanError> {
+    let mut loader = YamlLoader::<MarkedYaml>::new();
+    let mut parser = Parser::new(BufferedInput::new(text.chars()));
+    parser.load(&mut loader, true)?;
+    loader.take_scan_error()?;
+    let starts = loader
+        .document_starts()
+        .iter()
+        .map(Marker::index)
+        .collect::<Vec<_>>();
+    let mut documents = loader.into_documents();
+
+    let mut regions = vec![Region {
+        start: 0,
+        documents: 0,
+    }];
+    if starts.len() == documents.len() {
+        for (i, byte) in char_to_byte_indices(text, &starts).into_iter().enumerate() {
+            let start = region_start(text, byte);
+            let last = regions.last_mut().unwrap();
+            if i == 0 || start <= last.start {
+                last.documents += 1;
+            } else {
+                regions.push(Region {
+                    start,
+                    documents: 1,
+                });
+            }
+        }
+    } else {
+        regions[0].documents = documents.len();
+    }
+
+    for region in &mut regions {
+        region.start += offset.bytes;
+    }
+    if offset.chars > 0 {
+        let shift = Shift {
+            remove_chars: 0,
+            add_chars: offset.chars,
+            remove_lines: 0,
+            add_lines: offset.lines,
+        };
+        for document in &mut documents {
+            shift_node(document, &shift);
+        }
+    }
+    Ok((documents, regions))
+}
+
+/// Return the byte offset at which the region of the document starting at `byte` starts.
+///
+/// This is the beginning of the line of the document start, or of the directives preceding it.
+fn region_start(text: &str, byte: usize) -> usize {
+    let mut start = text[..byte].rfind('\n').map_or(0, |i| i + 1);
+    while start > 0 {
+        let previous = text[..start - 1].rfind('\n').map_or(0, |i| i + 1);
+        if text[previous..].starts_with('%') {
+            start = previous;
+        } else {
+            break;
+        }
+    }
+    start
+}
+
+/// Convert the given sorted character indices to byte offsets within `text`.
+fn char_to_byte_indices(text: &str, chars: &[usize]) -> Vec<usize> {
+    let mut bytes = Vec::with_capacity(chars.len());
+    let mut targets = chars.iter().peekable();
+    for (char_idx, (byte_idx, _)) in text.char_indices().enumerate() {
+        while targets.next_if(|&&target| target <= char_idx).is_some() {
+            bytes.push(byte_idx);
+        }
+        if targets.peek().is_none() {
+            break;
+        }
+    }
+    bytes.resize(chars.len(), text.len());
+    bytes
+}
+
+/// Shift the markers of `node` and all of its children.
+fn shift_node(node: &mut MarkedYaml, shift: &Shift) {
+    node.span.start = shift.apply(node.span.start);
+    node.span.end = shift.apply(node.span.end);
+    match &mut node.data {
+        YamlData::Array(items) => {
+            for item in items {
+                shift_node(item, shift);
+            }
+        }
+        YamlData::Hash(mapping) => {
+            // Keys cannot be mutated in place. Their hash does not depend on their markers.
+            let entries = core::mem::replace(mapping, LinkedHashMap::new());
+            for (mut key, mut value) in entries {
+                shift_node(&mut key, shift);
+                shift_node(&mut value, shift);
+                mapping.insert(key, value);
+            }
+        }
+        _ => {}
+    }
+}
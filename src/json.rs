@@ -0,0 +1,266 @@
+//! Conversions between [`Yaml`] and [`serde_json::Value`].
+
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::{self, Display};
+
+use serde_json::{Map, Number, Value};
+
+use crate::{loader::parse_f64, Hash, PathSegment, Yaml, YamlPath};
+
+/// What to do with mapping keys that are not strings when converting to JSON.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NonStringKeys {
+    /// Convert the key to a string.
+    ///
+    /// Scalars are written as they would be in YAML (e.g. `1`, `true`, `null`). Collections are
+    /// converted to JSON and serialized.
+    #[default]
+    Stringify,
+    /// Fail the conversion.
+    Error,
+}
+
+/// What to do with floats that JSON cannot represent (`.inf`, `-.inf`, `.nan`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NonFiniteFloats {
+    /// Fail the conversion.
+    #[default]
+    Error,
+    /// Convert the float to `null`.
+    Null,
+}
+
+/// Options governing the conversion of a [`Yaml`] to a [`serde_json::Value`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct JsonOptions {
+    /// What to do with mapping keys that are not strings.
+    pub non_string_keys: NonStringKeys,
+    /// What to do with floats that JSON cannot represent.
+    pub non_finite_floats: NonFiniteFloats,
+}
+
+/// An error when converting a [`Yaml`] to a [`serde_json::Value`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JsonError {
+    /// The path to the node that could not be converted.
+    pub path: YamlPath,
+    /// What went wrong.
+    pub kind: JsonErrorKind,
+}
+
+/// The reason why a [`Yaml`] node could not be converted to JSON.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum JsonErrorKind {
+    /// A mapping key is not a string, and [`NonStringKeys::Error`] was requested.
+    NonStringKey(Yaml),
+    /// Two keys of a mapping are the same once converted to strings (e.g. `1` and `"1"`).
+    DuplicateKey(String),
+    /// A float cannot be represented in JSON, and [`NonFiniteFloats::Error`] was requested.
+    NonFiniteFloat(String),
+    /// A [`Yaml::Real`] does not contain a valid float.
+    InvalidReal(String),
+    /// The node is a [`Yaml::BadValue`].
+    BadValue,
+    /// The node is an unresolved [`Yaml::Alias`].
+    Alias(usize),
+}
+
+impl std::error::Error for JsonError {}
+
+impl Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            JsonErrorKind::NonStringKey(key) => write!(f, "mapping key {key:?} is not a string")?,
+            JsonErrorKind::DuplicateKey(key) => write!(f, "duplicate mapping key `{key}`")?,
+            JsonErrorKind::NonFiniteFloat(v) => write!(f, "`{v}` cannot be represented in JSON")?,
+            JsonErrorKind::InvalidReal(v) => write!(f, "`{v}` is not a valid float")?,
+            JsonErrorKind::BadValue => f.write_str("invalid value")?,
+            JsonErrorKind::Alias(_) => f.write_str("unresolved alias")?,
+        }
+        if self.path.is_root() {
+            f.write_str(" at the root of the document")
+        } else {
+            write!(f, " at `{}`", self.path)
+        }
+    }
+}
+
+impl Yaml {
+    /// Convert `self` to a [`serde_json::Value`].
+    ///
+    /// The order of mapping keys is preserved. Integers and floats are converted to JSON numbers.
+    /// Tags are dropped: a [`Yaml::Tagged`] node is converted as the node it tags.
+    ///
+    /// ```
+    /// # use saphyr::{JsonOptions, NonFiniteFloats, Yaml};
+    /// let doc = Yaml::load_one_from_str("a: 1\nb: .inf").unwrap();
+    /// assert!(doc.to_json(JsonOptions::default()).is_err());
+    ///
+    /// let options = JsonOptions {
+    ///     non_finite_floats: NonFiniteFloats::Null,
+    ///     ..JsonOptions::default()
+    /// };
+    /// let json = doc.to_json(options).unwrap();
+    /// assert_eq!(json.to_string(), r#"{"a":1,"b":null}"#);
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `JsonError` if `self` contains nodes that cannot be represented in JSON with the
+    /// given options, or `BadValue`s or aliases.
+    pub fn to_json(&self, options: JsonOptions) -> Result<Value, JsonError> {
+        to_json(self, options, &mut YamlPath::new())
+    }
+
+    /// Convert a [`serde_json::Value`] to a [`Yaml`].
+    ///
+    /// Integers which do not fit in an `i64` and floats are converted to [`Yaml::Real`]. The former
+    /// are converted back to exact integers by [`Yaml::to_json`].
+    #[must_use]
+    pub fn from_json(value: &Value) -> Self {
+        match value {
+            Value::Null => Yaml::Null,
+            Value::Bool(v) => Yaml::Boolean(*v),
+            Value::Number(v) => from_json_number(v),
+            Value::String(v) => Yaml::String(v.clone()),
+            Value::Array(v) => Yaml::Array(v.iter().map(Yaml::from_json).collect()),
+            Value::Object(v) => Yaml::Hash(
+                v.iter()
+                    .map(|(k, v)| (Yaml::String(k.clone()), Yaml::from_json(v)))
+                    .collect::<Hash>(),
+            ),
+        }
+    }
+}
+
+impl TryFrom<&Yaml> for Value {
+    type Error = JsonError;
+
+    /// Convert a [`Yaml`] with the default [`JsonOptions`]. See [`Yaml::to_json`].
+    fn try_from(value: &Yaml) -> Result<Self, Self::Error> {
+        value.to_json(JsonOptions::default())
+    }
+}
+
+impl TryFrom<Yaml> for Value {
+    type Error = JsonError;
+
+    /// Convert a [`Yaml`] with the default [`JsonOptions`]. See [`Yaml::to_json`].
+    fn try_from(value: Yaml) -> Result<Self, Self::Error> {
+        value.to_json(JsonOptions::default())
+    }
+}
+
+impl From<&Value> for Yaml {
+    /// See [`Yaml::from_json`].
+    fn from(value: &Value) -> Self {
+        Yaml::from_json(value)
+    }
+}
+
+impl From<Value> for Yaml {
+    /// See [`Yaml::from_json`].
+    fn from(value: Value) -> Self {
+        Yaml::from_json(&value)
+    }
+}
+
+fn to_json(yaml: &Yaml, options: JsonOptions, path: &mut YamlPath) -> Result<Value, JsonError> {
+    let error = |path: &YamlPath, kind| {
+        Err(JsonError {
+            path: path.clone(),
+            kind,
+        })
+    };
+    Ok(match yaml {
+        Yaml::Null => Value::Null,
+        Yaml::Boolean(v) => Value::Bool(*v),
+        Yaml::Integer(v) => Value::from(*v),
+        Yaml::String(v) => Value::String(v.clone()),
+        Yaml::SharedString(v) => Value::String(String::from(&**v)),
+        Yaml::Timestamp(v) => Value::String(v.to_string()),
+        Yaml::Binary(v) => Value::String(crate::binary::encode(v)),
+        Yaml::Real(v) => match (v.parse::<u64>(), parse_f64(v)) {
+            // Integers too large for a `Yaml::Integer` are stored as reals. Keep them exact.
+            (Ok(n), _) => Value::from(n),
+            (Err(_), None) => return error(path, JsonErrorKind::InvalidReal(v.clone())),
+            (Err(_), Some(f)) => match (Number::from_f64(f), options.non_finite_floats) {
+                (Some(number), _) => Value::Number(number),
+                (None, NonFiniteFloats::Null) => Value::Null,
+                (None, NonFiniteFloats::Error) => {
+                    return error(path, JsonErrorKind::NonFiniteFloat(v.clone()))
+                }
+            },
+        },
+        Yaml::Float(v) => match (Number::from_f64(v.value), options.non_finite_floats) {
+            (Some(number), _) => Value::Number(number),
+            (None, NonFiniteFloats::Null) => Value::Null,
+            (None, NonFiniteFloats::Error) => {
+                return error(path, JsonErrorKind::NonFiniteFloat(v.text().into_owned()))
+            }
+        },
+        Yaml::Array(v) => {
+            let mut array = Vec::with_capacity(v.len());
+            for (i, item) in v.iter().enumerate() {
+                path.push(PathSegment::Index(i));
+                array.push(to_json(item, options, path)?);
+                path.pop();
+            }
+            Value::Array(array)
+        }
+        Yaml::Hash(h) => {
+            let mut object = Map::new();
+            for (i, (k, v)) in h.iter().enumerate() {
+                path.push(PathSegment::EntryKey(i));
+                let key = key_to_json(k, options, path)?;
+                if object.contains_key(&key) {
+                    return error(path, JsonErrorKind::DuplicateKey(key));
+                }
+                path.pop();
+                path.push(PathSegment::Key(k.clone()));
+                object.insert(key, to_json(v, options, path)?);
+                path.pop();
+            }
+            Value::Object(object)
+        }
+        Yaml::Tagged(_, node) => return to_json(node, options, path),
+        Yaml::Alias(id) => return error(path, JsonErrorKind::Alias(*id)),
+        Yaml::BadValue => return error(path, JsonErrorKind::BadValue),
+    })
+}
+
+fn key_to_json(key: &Yaml, options: JsonOptions, path: &mut YamlPath) -> Result<String, JsonError> {
+    match (key, options.non_string_keys) {
+        (Yaml::Tagged(_, node), _) => key_to_json(node, options, path),
+        (Yaml::String(v), _) | (Yaml::Real(v), NonStringKeys::Stringify) => Ok(v.clone()),
+        (Yaml::SharedString(v), _) => Ok(String::from(&**v)),
+        (Yaml::Alias(_) | Yaml::BadValue, _) => to_json(key, options, path).map(|_| String::new()),
+        (_, NonStringKeys::Error) => Err(JsonError {
+            path: path.clone(),
+            kind: JsonErrorKind::NonStringKey(key.clone()),
+        }),
+        (Yaml::Integer(v), NonStringKeys::Stringify) => Ok(v.to_string()),
+        (Yaml::Boolean(v), NonStringKeys::Stringify) => Ok(v.to_string()),
+        (Yaml::Float(v), NonStringKeys::Stringify) => Ok(v.text().into_owned()),
+        (Yaml::Timestamp(v), NonStringKeys::Stringify) => Ok(v.to_string()),
+        (Yaml::Binary(v), NonStringKeys::Stringify) => Ok(crate::binary::encode(v)),
+        (Yaml::Null, NonStringKeys::Stringify) => Ok("null".to_owned()),
+        (Yaml::Array(_) | Yaml::Hash(_), NonStringKeys::Stringify) => {
+            Ok(to_json(key, options, path)?.to_string())
+        }
+    }
+}
+
+fn from_json_number(number: &Number) -> Yaml {
+    if let Some(v) = number.as_i64() {
+        Yaml::Integer(v)
+    } else if let Some(v) = number.as_u64() {
+        Yaml::Real(v.to_string())
+    } else {
+        // `{:?}` always writes a `.` or an exponent, so that the value is read back as a float.
+        Yaml::Real(format!("{:?}", number.as_f64().unwrap_or(f64::NAN)))
+    }
+}
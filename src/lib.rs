@@ -35,37 +35,174 @@
 //! # Features
 //! **Note:** With all features disabled, this crate's MSRV is `1.65.0`.
 //!
+//! #### `std` (_enabled by default_)
+//! Enables the parts of the crate which need the standard library: loading from readers
+//! ([`Yaml::load_from_reader`]) and emitting to writers ([`YamlEmitter::new_io`]), including other
+//! files (see [`LoaderOptions::includes`]), and implementations of [`std::error::Error`].
+//! Without it, saphyr itself only uses `core` and `alloc`: loading from strings, the [`Yaml`]
+//! type and emitting to strings work as they do with it. The `encoding`, `json`, `serde` and
+//! `parallel` features enable it.
+//!
 //! #### `encoding` (_enabled by default_)
 //! Enables encoding-aware decoding of Yaml documents.
 //!
 //! The MSRV for this feature is `1.70.0`.
+//!
+//! #### `json`
+//! Enables conversions between [`Yaml`] and [`serde_json::Value`] (see [`Yaml::to_json`] and
+//! [`Yaml::from_json`]).
+//!
+//! #### `serde`
+//! Enables deserializing Rust values from YAML with [`serde`], through [`from_str`] and
+//! [`Deserializer`], and serializing them to YAML, through [`to_string`] and [`to_yaml_value`].
+//!
+//! #### `timestamps`
+//! Makes the loader resolve scalars tagged `!!timestamp` to [`Yaml::Timestamp`] and, if
+//! `LoaderOptions::resolve_timestamps` is set, untagged plain scalars that look like timestamps.
+//!
+//! #### `binary`
+//! Makes the loader decode the base64 of scalars tagged `!!binary` to [`Yaml::Binary`].
+//!
+//! #### `floats`
+//! Makes the loader resolve floats to [`Yaml::Float`], which holds them as `f64` values compared
+//! bit by bit rather than as strings, if `LoaderOptions::parse_floats` is set.
+//!
+//! #### `number`
+//! Adds [`Number`], the value of an integer or float node as an `i64`, a `u64` or an `f64`,
+//! returned by [`Yaml::as_number`].
+//!
+//! The variants of [`Yaml`] these features load exist whether they are enabled or not, so that
+//! enabling them in one crate does not break exhaustive matches in another. Without them, such
+//! nodes can still be built, emitted and converted.
+//!
+//! #### `parallel`
+//! Enables `Yaml::load_from_str_parallel`, which loads the documents of a stream on several
+//! threads.
 
+#![no_std]
 #![warn(missing_docs, clippy::pedantic)]
 
+#[macro_use]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
 #[macro_use]
 mod macros;
 
+mod access;
 mod annotated;
+mod arena;
+mod binary;
+mod borrowed;
+mod cancel;
+mod capabilities;
 mod char_traits;
+mod cursor;
+mod diff;
+mod directives;
+mod document;
 mod emitter;
+mod events;
+mod float;
+#[cfg(feature = "std")]
+mod include;
+mod incremental;
 mod loader;
+mod merge;
+mod patch;
+mod path;
+mod position;
+mod preview;
+mod report;
+mod schema;
+mod stream;
+mod tag_resolver;
+mod timestamp;
+mod walk;
 mod yaml;
 
 // Re-export main components.
+pub use crate::access::{HashExt, IndexError};
 pub use crate::annotated::{
-    marked_yaml::MarkedYaml, AnnotatedArray, AnnotatedHash, AnnotatedYamlIter, YamlData,
+    commented_yaml::{CommentedYaml, Comments},
+    marked_yaml::MarkedYaml,
+    AnnotatedArray, AnnotatedHash, AnnotatedYamlIter, YamlData,
+};
+pub use crate::arena::{ArenaNode, NodeId, YamlArena};
+pub use crate::borrowed::BorrowedYaml;
+pub use crate::cancel::CancelToken;
+pub use crate::capabilities::{capabilities, Capabilities};
+pub use crate::cursor::{ExpectedType, YamlCursor};
+pub use crate::diff::{CompareOptions, YamlDiff};
+pub use crate::directives::DocumentMetadata;
+pub use crate::document::{EditError, EditErrorKind, YamlDocument};
+pub use crate::emitter::{
+    AlignPolicy, AnchorNaming, AnchorNamingFn, BlockStyle, CollectionStyle, EmitError, KeyOrder,
+    KeyOrderFn, NullStyle, QuoteStyle, YamlEmitter,
+};
+pub use crate::events::YamlEvents;
+pub use crate::float::Float;
+#[cfg(feature = "std")]
+pub use crate::include::{FsIncludeResolver, IncludeOptions, IncludeResolver};
+pub use crate::incremental::{ByteRange, IncrementalLoader};
+pub use crate::loader::{
+    AnchorReport, AnchorSite, AnchorUsage, DuplicateKeys, FilterAction, KeyOccurrence, LoadError,
+    LoadErrorKind, LoadableYamlNode, LoaderLimit, LoaderOptions, NodeContext, NodeFilter,
+    UnknownAliases, YamlLoader,
 };
-pub use crate::emitter::YamlEmitter;
-pub use crate::loader::{LoadableYamlNode, YamlLoader};
-pub use crate::yaml::{Array, Hash, Yaml, YamlIter};
+pub use crate::merge::{
+    ArrayMerge, ConflictPolicy, MappingMerge, MergeError, MergeStrategy, NullOverlay,
+};
+pub use crate::patch::{PatchError, PatchErrorKind, PatchOp};
+pub use crate::path::{PathSegment, YamlPath};
+pub use crate::position::LineIndex;
+pub use crate::report::{DisplayWithSource, ErrorReport};
+pub use crate::schema::Schema;
+pub use crate::stream::{parse_events, DocumentIter};
+pub use crate::tag_resolver::{SharedTagResolver, TagResolver};
+pub use crate::timestamp::{TimeOfDay, Timestamp};
+pub use crate::walk::{YamlEntries, YamlWalk};
+pub use crate::yaml::{Array, Hash, HashEntry, UnwrapRootError, Yaml, YamlIter, YamlKind};
 
 #[cfg(feature = "encoding")]
 mod encoding;
 #[cfg(feature = "encoding")]
 pub use crate::encoding::{YAMLDecodingTrap, YAMLDecodingTrapFn, YamlDecoder};
 
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "json")]
+pub use crate::json::{JsonError, JsonErrorKind, JsonOptions, NonFiniteFloats, NonStringKeys};
+
+#[cfg(feature = "number")]
+mod number;
+#[cfg(feature = "number")]
+pub use crate::number::Number;
+
+#[cfg(feature = "serde")]
+mod de;
+#[cfg(feature = "serde")]
+pub use crate::de::{from_str, DeserializeError, Deserializer};
+#[cfg(feature = "serde")]
+mod ser;
+#[cfg(feature = "serde")]
+pub use crate::ser::{to_string, to_string_with, to_yaml_value, SerializeError};
+
+#[cfg(feature = "parallel")]
+mod parallel;
+
+// Used by the `yaml!` macro, which cannot refer to `alloc` in crates without `extern crate alloc`.
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::vec;
+}
+
 // Re-export `ScanError` as it is used as part of our public API and we want consumers to be able
 // to inspect it (e.g. perform a `match`). They wouldn't be able without it.
 pub use saphyr_parser::ScanError;
 // Re-export [`Marker`] which is used for annotated YAMLs.
 pub use saphyr_parser::Marker;
+// Re-export the types of parser events, so that they can be produced and consumed without
+// depending on the parser (see [`Yaml::into_events`] and [`YamlLoader::from_events`]).
+pub use saphyr_parser::{Event, Span, TScalarStyle, Tag};
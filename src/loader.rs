@@ -1,11 +1,30 @@
 //! The default loader.
 
-use std::collections::BTreeMap;
+use alloc::{
+    borrow::ToOwned,
+    boxed::Box,
+    collections::{BTreeMap, BTreeSet},
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+#[cfg(feature = "std")]
+use std::io::Read;
+#[cfg(feature = "std")]
+use std::path::PathBuf;
 
 use hashlink::LinkedHashMap;
-use saphyr_parser::{Event, ScanError, Span, SpannedEventReceiver, TScalarStyle, Tag};
+use saphyr_parser::{
+    BufferedInput, Event, Input, Marker, Parser, ScanError, Span, SpannedEventReceiver,
+    TScalarStyle, Tag,
+};
 
-use crate::{Hash, Yaml};
+use crate::directives::{scan_directives, DocumentMetadata};
+use crate::emitter::tag_text;
+use crate::merge::resolve_merge_keys;
+#[cfg(feature = "std")]
+use crate::IncludeOptions;
+use crate::{schema, CancelToken, Hash, PathSegment, Schema, SharedTagResolver, Yaml, YamlPath};
 
 /// Main structure for parsing YAML.
 ///
@@ -21,11 +40,758 @@ where
 {
     /// The different YAML documents that are loaded.
     docs: Vec<Node>,
+    /// The marker at which each of `docs` starts.
+    doc_starts: Vec<Marker>,
+    /// The directives of each of `docs`.
+    metadata: Vec<DocumentMetadata>,
+    /// The directives of the document that is being loaded.
+    current_metadata: DocumentMetadata,
+    /// The directives read with [`Self::read_directives`], by the line of the `---` marker of
+    /// their document.
+    directives: Vec<(usize, DocumentMetadata)>,
+    /// The options of the document that is being loaded, if its `%YAML` directive changes them
+    /// (see [`LoaderOptions::schema_from_version`]).
+    document_options: Option<LoaderOptions>,
     // states
     // (current node, anchor_id) tuple
     doc_stack: Vec<(Node, usize)>,
     key_stack: Vec<Node>,
-    anchor_map: BTreeMap<usize, Node>,
+    anchor_map: BTreeMap<usize, Anchored<Node>>,
+    /// Whether some of the nodes in `anchor_map` are [`Anchored::Location`]s.
+    has_locations: bool,
+    /// How documents are loaded.
+    options: LoaderOptions,
+    /// Anchor bookkeeping, if requested through [`Self::record_anchors`].
+    anchor_recorder: Option<AnchorRecorder>,
+    /// Where each collection that is being built starts.
+    collection_starts: Vec<Marker>,
+    /// The tag of each collection that is being loaded, if it is to be resolved with
+    /// [`LoaderOptions::tag_resolver`] or kept (see [`LoaderOptions::keep_tags`]).
+    collection_tags: Vec<Option<Tag>>,
+    /// Where the last node that was completed ends.
+    last_end: Marker,
+    /// The keys of each mapping that is being built. Only filled if duplicate keys are rejected.
+    key_sites: Vec<Vec<KeyOccurrence>>,
+    /// How the next node to be inserted was written and where it is. Only set if duplicate keys
+    /// are rejected.
+    next_site: Option<(String, Span)>,
+    /// The name of the alias about to be loaded, read from the source of the stream. Only set if
+    /// unknown aliases are rejected.
+    alias_name: Option<String>,
+    /// The first error found while loading, if any.
+    error: Option<LoadError>,
+    /// The filter set with [`Self::node_filter`], if any.
+    node_filter: Option<Box<NodeFilter<Node>>>,
+    /// If the value of a dropped mapping key is being skipped, the number of collections of that
+    /// value that have been entered and not left yet.
+    skipping: Option<usize>,
+    /// What has been loaded since the loader was created or reset, for the limits of `options`.
+    totals: Totals,
+    /// The number of documents started since the loader was created or reset.
+    document_count: usize,
+    /// The anchor of each collection that is being loaded, with `totals` at its start and the
+    /// depth of the deepest alias expansion within it so far.
+    collection_totals: Vec<(usize, Totals, usize)>,
+    /// What each anchored node amounts to, i.e. what an alias to it adds to `totals`, with the
+    /// depth of the deepest alias expansion within it.
+    anchor_totals: BTreeMap<usize, (Totals, usize)>,
+    /// The mapping keys interned since the loader was created or reset, if
+    /// [`LoaderOptions::intern_keys`] is set.
+    interned_keys: BTreeSet<Arc<str>>,
+    /// The locations of the sources that include the one being loaded, and of that source if it
+    /// is known, outermost first (see [`LoaderOptions::includes`]).
+    #[cfg(feature = "std")]
+    include_chain: Vec<PathBuf>,
+    /// The number of includes the source being loaded is nested in.
+    #[cfg(feature = "std")]
+    include_depth: usize,
+}
+
+/// Counts of what has been loaded, checked against [`LoaderOptions::max_nodes`],
+/// [`LoaderOptions::max_total_string_bytes`] and [`LoaderOptions::max_alias_expansions`].
+#[derive(Clone, Copy, Debug, Default)]
+struct Totals {
+    nodes: usize,
+    string_bytes: usize,
+    aliases: usize,
+}
+
+impl Totals {
+    fn since(self, start: Totals) -> Totals {
+        Totals {
+            nodes: self.nodes - start.nodes,
+            string_bytes: self.string_bytes - start.string_bytes,
+            aliases: self.aliases - start.aliases,
+        }
+    }
+}
+
+/// A node with an anchor, as recorded for aliases to refer to.
+enum Anchored<Node> {
+    /// A copy of the node.
+    Copy(Node),
+    /// Where the node is, so that it is only copied if it is aliased.
+    ///
+    /// `positions` are the indices of the items (or entries, for mappings) to follow from the
+    /// root of the document to reach the node. Nodes are only located this way as long as these
+    /// positions cannot change: mapping keys and nodes seen by a node filter are copied instead.
+    Location {
+        document: usize,
+        positions: Vec<usize>,
+    },
+}
+
+/// The signature of the callback of [`YamlLoader::node_filter`].
+pub type NodeFilter<Node> = dyn FnMut(&mut Node, &NodeContext<Node>) -> FilterAction<Node>;
+
+/// Where a node given to a [`NodeFilter`] is about to be inserted.
+#[derive(Debug)]
+pub struct NodeContext<'a, Node> {
+    /// The key of the node, if it is a mapping value.
+    pub key: Option<&'a Node>,
+    /// Whether the node is a mapping key.
+    pub is_key: bool,
+    /// The number of collections enclosing the node. The root of a document has a depth of 0.
+    pub depth: usize,
+}
+
+/// What to do with a node given to a [`NodeFilter`].
+#[derive(Debug)]
+pub enum FilterAction<Node> {
+    /// Insert the node, including any change the filter made to it.
+    Keep,
+    /// Insert the given node instead.
+    Replace(Node),
+    /// Do not insert the node.
+    ///
+    /// If the node is a mapping key, its value is skipped without being built. If it is a mapping
+    /// value, its key is dropped as well. If it is the root of a document, the document is empty
+    /// (i.e. `BadValue`).
+    Drop,
+}
+
+/// What the loader does when a mapping contains the same key twice.
+///
+/// Keys are compared once resolved, i.e. as [`Yaml`] values. With the YAML 1.2 Core schema, the
+/// quoting style of a key only matters insofar as it changes its type: `a` and `"a"` are the same
+/// (string) key, `1` (integer) and `'1'` (string) are different keys, and `0x10` and `16` are the
+/// same integer key.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicateKeys {
+    /// The value of the last occurrence of the key is kept.
+    #[default]
+    Overwrite,
+    /// Loading fails with [`LoadError::DuplicateKey`].
+    Error,
+    /// The value of the first occurrence of the key is kept, and those of the others are dropped.
+    ///
+    /// Anchors defined within a dropped value can still be referred to by later aliases.
+    KeepFirst,
+}
+
+/// What the loader does with an alias it cannot resolve.
+///
+/// The parser rejects aliases to anchors it has not seen. The loader can still meet aliases it
+/// cannot resolve: aliases to a collection from within itself (e.g. `&a [*a]`), to a node dropped
+/// by a [`NodeFilter`], or in a stream of events given to [`YamlLoader::from_events`].
+///
+/// Redefining an anchor is not an error: as the YAML specification mandates, an alias refers to
+/// the last node with its anchor.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnknownAliases {
+    /// The alias is loaded as [`Yaml::BadValue`].
+    #[default]
+    BadValue,
+    /// Loading fails with [`LoadError::UnknownAlias`].
+    Error,
+}
+
+/// Options governing how a [`YamlLoader`] loads documents.
+///
+/// ```
+/// # use saphyr::{LoaderOptions, Yaml, YamlLoader};
+/// let loader = YamlLoader::<Yaml>::with_options(LoaderOptions {
+///     record_anchors: true,
+///     ..LoaderOptions::default()
+/// });
+/// assert!(loader.options().record_anchors);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[allow(clippy::module_name_repetitions, clippy::struct_excessive_bools)]
+pub struct LoaderOptions {
+    /// Whether to record how anchors are used. See [`YamlLoader::record_anchors`].
+    pub record_anchors: bool,
+    /// What to do with duplicate mapping keys.
+    pub duplicate_keys: DuplicateKeys,
+    /// What to do with aliases that cannot be resolved.
+    pub unknown_aliases: UnknownAliases,
+    /// Whether anchors and aliases fail loading with [`LoadError::AnchorForbidden`]. Defaults to
+    /// `false`.
+    ///
+    /// Aliases let a small input repeat nodes many times (see also [`Self::max_nodes`]), and
+    /// some guidelines for untrusted input disallow them altogether.
+    pub forbid_anchors: bool,
+    /// How untagged plain scalars are resolved. Defaults to the YAML 1.2 core schema.
+    ///
+    /// The schema also applies to scalars tagged `!!bool`, `!!int`, `!!float` or `!!null`, e.g.
+    /// `!!bool yes` is only a boolean with [`Schema::Yaml11`].
+    pub schema: Schema,
+    /// Whether documents declaring `%YAML 1.1` are loaded with [`Schema::Yaml11`], and those
+    /// declaring `%YAML 1.2` with [`Schema::Core`], whatever [`Self::schema`] is.
+    ///
+    /// Directives are only known to the loader if they were read with
+    /// [`YamlLoader::read_directives`], as the loading functions of [`Yaml`] do.
+    pub schema_from_version: bool,
+    /// Whether a tag with a named handle (e.g. `!e!foo`) which was not declared by a `%TAG`
+    /// directive fails loading with [`LoadError::UnknownTagHandle`]. Otherwise, the tag is kept
+    /// as it was written.
+    ///
+    /// As for [`Self::schema_from_version`], this requires the directives to be read.
+    pub strict_tag_handles: bool,
+    /// The maximum length of a scalar mapping key, in bytes. Defaults to 64 KiB.
+    ///
+    /// Loading fails with [`LoadError::LimitExceeded`] if a key is longer.
+    pub max_key_length: usize,
+    /// The maximum length of a tag (handle and suffix), in bytes. Defaults to 64 KiB.
+    ///
+    /// Loading fails with [`LoadError::LimitExceeded`] if a tag is longer.
+    pub max_tag_length: usize,
+    /// The maximum length of an anchor name, in bytes. Defaults to 64 KiB.
+    ///
+    /// Loading fails with [`LoadError::LimitExceeded`] if an anchor or an alias has a longer
+    /// name. The parser does not forward anchor names to the loader, so they are read from the
+    /// source: the limit only applies when loading a string with
+    /// [`Yaml::load_from_str_with_options`] or the functions like it, and to included sources.
+    pub max_anchor_length: usize,
+    /// The maximum number of nodes in the stream, if any. Defaults to no limit.
+    ///
+    /// Every scalar and collection counts, including those in mapping keys and those dropped by
+    /// a node filter, and an alias counts as all the nodes it refers to. Loading fails with
+    /// [`LoadError::LimitExceeded`] as soon as the limit is exceeded, before the offending node is
+    /// loaded.
+    pub max_nodes: Option<usize>,
+    /// The maximum total length of the scalars in the stream, in bytes, if any. Defaults to no
+    /// limit.
+    ///
+    /// As for [`Self::max_nodes`], the scalars an alias refers to count again.
+    pub max_total_string_bytes: Option<usize>,
+    /// The maximum number of documents in the stream, if any. Defaults to no limit.
+    pub max_documents: Option<usize>,
+    /// The maximum number of aliases expanded in the stream, if any. Defaults to no limit.
+    ///
+    /// An alias counts once, and again for every alias within the node it refers to, since they
+    /// are all copied: in `a: &a [*x, *x]\nb: *a`, `*a` counts 3. Loading fails with
+    /// [`LoadError::LimitExceeded`] before the offending alias is copied. Together with
+    /// [`Self::max_alias_depth`], this rejects "billion laughs" documents, which expand a few
+    /// aliases into many nodes.
+    pub max_alias_expansions: Option<usize>,
+    /// The maximum depth of alias expansions, if any. Defaults to no limit.
+    ///
+    /// An alias to a node without aliases has a depth of 1, an alias to a node with aliases of
+    /// depth 1 has a depth of 2, and so on. Loading fails with [`LoadError::LimitExceeded`]
+    /// before an alias is copied if its depth is greater.
+    pub max_alias_depth: Option<usize>,
+    /// The maximum nesting depth of collections, if any. Defaults to no limit.
+    ///
+    /// A collection at the root of a document is at depth 1, its child collections at depth 2,
+    /// and so on. Loading fails with [`LoadError::LimitExceeded`] when a collection starts
+    /// deeper.
+    pub max_depth: Option<usize>,
+    /// The maximum length of a scalar, in bytes, if any. Defaults to no limit.
+    ///
+    /// Unlike [`Self::max_total_string_bytes`], this applies to each scalar on its own, as
+    /// written: the scalars an alias refers to are not checked again. Mapping keys are also
+    /// checked against [`Self::max_key_length`].
+    pub max_scalar_length: Option<usize>,
+    /// A token to cancel loading with, from another thread.
+    ///
+    /// The token is checked for every event. Once it is cancelled, loading fails with
+    /// [`LoadError::Cancelled`].
+    pub cancel: Option<CancelToken>,
+    /// Whether to resolve untagged plain scalars that look like timestamps (e.g. `2002-12-14`)
+    /// to [`Yaml::Timestamp`], as in the YAML 1.1 schema.
+    ///
+    /// Scalars tagged `!!timestamp` are always resolved.
+    #[cfg(feature = "timestamps")]
+    pub resolve_timestamps: bool,
+    /// Whether a plain scalar tagged `!!timestamp` which is not a valid timestamp fails loading
+    /// with [`LoadError::InvalidTimestamp`]. Otherwise, it is loaded as a string.
+    #[cfg(feature = "timestamps")]
+    pub strict_timestamps: bool,
+    /// Whether to load floats as [`Yaml::Float`], stored as their `f64` value, rather than as
+    /// [`Yaml::Real`].
+    ///
+    /// This applies to the scalars tagged `!!float` and to the untagged plain scalars resolved as
+    /// floats by the [`Self::schema`]. Integers too large for an `i64`, which are also loaded as
+    /// [`Yaml::Real`], are kept as they are, so that e.g. [`Yaml::as_u64`] does not round them.
+    #[cfg(feature = "floats")]
+    pub parse_floats: bool,
+    /// Whether to keep the text of floats loaded as [`Yaml::Float`] (see [`Self::parse_floats`]),
+    /// so that they are emitted exactly as they were written (e.g. `0.1000000000000000000001`).
+    /// Otherwise, they are emitted in their canonical form (e.g. `0.1`).
+    #[cfg(feature = "floats")]
+    pub keep_float_sources: bool,
+    /// How to splice other sources into documents with `!include` tags, if at all. Defaults to
+    /// not doing so: the tagged scalars are loaded as they are.
+    #[cfg(feature = "std")]
+    pub includes: Option<IncludeOptions>,
+    /// Whether to resolve merge keys (`<<`) once each document is loaded. Defaults to `false`:
+    /// `<<` is then an ordinary key. See [`Yaml::resolve_merge_keys`].
+    pub merge_keys: bool,
+    /// Whether to keep the tags which the loader does not resolve (e.g. `!Ref` or `!!set`), by
+    /// wrapping the tagged nodes in [`Yaml::Tagged`]. Defaults to `false`: the tags are dropped.
+    ///
+    /// Either way, a tagged node is loaded as it would be without its tag, except for plain
+    /// scalars, which are strings. Node types other than [`Yaml`] keep the tags if they implement
+    /// [`LoadableYamlNode::with_tag`].
+    pub keep_tags: bool,
+    /// How to build the nodes of some tags (e.g. `!secret`), if at all. Defaults to resolving
+    /// tagged nodes with the [`Self::schema`] only. See [`TagResolver`].
+    ///
+    /// [`TagResolver`]: crate::TagResolver
+    pub tag_resolver: Option<SharedTagResolver>,
+    /// Whether to fail loading rather than load a [`Yaml::BadValue`]. Defaults to `false`.
+    ///
+    /// In strict mode, scalars which are invalid for their tag (e.g. `!!int foo`) or for the
+    /// [`Self::schema`] fail with [`LoadError::InvalidScalar`], aliases which cannot be resolved
+    /// with [`LoadError::UnknownAlias`] (whatever [`Self::unknown_aliases`] is), and documents
+    /// without a root node (which only event streams can have) with
+    /// [`LoadError::EmptyDocument`].
+    pub strict: bool,
+    /// Whether mapping keys with the same text share their storage. Defaults to `false`: each key
+    /// is a string of its own.
+    ///
+    /// Documents which repeat the same keys many times (e.g. infrastructure templates) then take
+    /// less memory. String keys are loaded as [`Yaml::SharedString`]s, which are otherwise the same
+    /// as [`Yaml::String`]s. Keys are shared across the documents of a stream, until the loader is
+    /// reset. Node types other than [`Yaml`] share keys if they implement
+    /// [`LoadableYamlNode::intern`].
+    pub intern_keys: bool,
+}
+
+impl Default for LoaderOptions {
+    fn default() -> Self {
+        Self {
+            record_anchors: false,
+            duplicate_keys: DuplicateKeys::Overwrite,
+            unknown_aliases: UnknownAliases::BadValue,
+            forbid_anchors: false,
+            schema: Schema::Core,
+            schema_from_version: false,
+            strict_tag_handles: false,
+            max_key_length: 64 * 1024,
+            max_tag_length: 64 * 1024,
+            max_anchor_length: 64 * 1024,
+            max_nodes: None,
+            max_total_string_bytes: None,
+            max_documents: None,
+            max_alias_expansions: None,
+            max_alias_depth: None,
+            max_depth: None,
+            max_scalar_length: None,
+            cancel: None,
+            #[cfg(feature = "timestamps")]
+            resolve_timestamps: false,
+            #[cfg(feature = "timestamps")]
+            strict_timestamps: false,
+            #[cfg(feature = "floats")]
+            parse_floats: false,
+            #[cfg(feature = "floats")]
+            keep_float_sources: false,
+            #[cfg(feature = "std")]
+            includes: None,
+            merge_keys: false,
+            keep_tags: false,
+            tag_resolver: None,
+            strict: false,
+            intern_keys: false,
+        }
+    }
+}
+
+/// A limit of [`LoaderOptions`] that a document can exceed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoaderLimit {
+    /// [`LoaderOptions::max_key_length`].
+    KeyLength,
+    /// [`LoaderOptions::max_tag_length`].
+    TagLength,
+    /// [`LoaderOptions::max_anchor_length`].
+    AnchorLength,
+    /// [`LoaderOptions::max_nodes`].
+    Nodes,
+    /// [`LoaderOptions::max_total_string_bytes`].
+    TotalStringBytes,
+    /// [`LoaderOptions::max_documents`].
+    Documents,
+    /// [`LoaderOptions::max_alias_expansions`].
+    AliasExpansions,
+    /// [`LoaderOptions::max_alias_depth`].
+    AliasDepth,
+    /// [`LoaderOptions::max_depth`].
+    Depth,
+    /// [`LoaderOptions::max_scalar_length`].
+    ScalarLength,
+    /// [`IncludeOptions::max_depth`].
+    IncludeDepth,
+}
+
+impl LoaderLimit {
+    /// Return the unit of the limit, e.g. `bytes`.
+    fn unit(self) -> &'static str {
+        match self {
+            LoaderLimit::KeyLength
+            | LoaderLimit::TagLength
+            | LoaderLimit::AnchorLength
+            | LoaderLimit::TotalStringBytes
+            | LoaderLimit::ScalarLength => "bytes",
+            LoaderLimit::Nodes => "nodes",
+            LoaderLimit::Documents => "documents",
+            LoaderLimit::AliasExpansions => "aliases",
+            LoaderLimit::Depth | LoaderLimit::AliasDepth | LoaderLimit::IncludeDepth => "levels",
+        }
+    }
+}
+
+impl core::fmt::Display for LoaderLimit {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            LoaderLimit::KeyLength => "mapping key length",
+            LoaderLimit::TagLength => "tag length",
+            LoaderLimit::AnchorLength => "anchor name length",
+            LoaderLimit::Nodes => "node count",
+            LoaderLimit::TotalStringBytes => "total string length",
+            LoaderLimit::Documents => "document count",
+            LoaderLimit::AliasExpansions => "alias expansion count",
+            LoaderLimit::AliasDepth => "alias expansion depth",
+            LoaderLimit::Depth => "nesting depth",
+            LoaderLimit::ScalarLength => "scalar length",
+            LoaderLimit::IncludeDepth => "include depth",
+        })
+    }
+}
+
+impl LoaderOptions {
+    /// Resolve a scalar to a [`Yaml`] node, as the loader does with these options.
+    ///
+    /// Quoted and block scalars are strings. Plain scalars are resolved according to their tag
+    /// if it is one of the YAML core tags (e.g. `!!int`), and to [`Self::schema`] otherwise.
+    ///
+    /// ```
+    /// # use saphyr::{LoaderOptions, TScalarStyle, Yaml};
+    /// let options = LoaderOptions::default();
+    /// assert_eq!(
+    ///     options.resolve_scalar("42".into(), TScalarStyle::Plain, None),
+    ///     Yaml::Integer(42)
+    /// );
+    /// assert_eq!(
+    ///     options.resolve_scalar("42".into(), TScalarStyle::SingleQuoted, None),
+    ///     Yaml::String("42".into())
+    /// );
+    /// ```
+    #[must_use]
+    pub fn resolve_scalar(&self, value: String, style: TScalarStyle, tag: Option<&Tag>) -> Yaml {
+        resolve_scalar(value, style, tag, self)
+    }
+
+    /// Return a summary of the options, e.g. for diagnostics or support bundles.
+    ///
+    /// The summary is a mapping from option names (in `snake_case`) to their values.
+    #[must_use]
+    pub fn describe(&self) -> Yaml {
+        let mut options = Hash::new();
+        options.insert(
+            Yaml::String("record_anchors".into()),
+            Yaml::Boolean(self.record_anchors),
+        );
+        let duplicate_keys = match self.duplicate_keys {
+            DuplicateKeys::Overwrite => "overwrite",
+            DuplicateKeys::Error => "error",
+            DuplicateKeys::KeepFirst => "keep_first",
+        };
+        options.insert(
+            Yaml::String("duplicate_keys".into()),
+            Yaml::String(duplicate_keys.into()),
+        );
+        let unknown_aliases = match self.unknown_aliases {
+            UnknownAliases::BadValue => "bad_value",
+            UnknownAliases::Error => "error",
+        };
+        options.insert(
+            Yaml::String("unknown_aliases".into()),
+            Yaml::String(unknown_aliases.into()),
+        );
+        options.insert(
+            Yaml::String("schema".into()),
+            Yaml::String(self.schema.name().into()),
+        );
+        for (key, value) in [
+            ("forbid_anchors", self.forbid_anchors),
+            ("schema_from_version", self.schema_from_version),
+            ("strict_tag_handles", self.strict_tag_handles),
+            ("merge_keys", self.merge_keys),
+            ("keep_tags", self.keep_tags),
+            ("strict", self.strict),
+            ("intern_keys", self.intern_keys),
+        ] {
+            options.insert(Yaml::String(key.into()), Yaml::Boolean(value));
+        }
+        for (key, value) in [
+            ("max_key_length", self.max_key_length),
+            ("max_tag_length", self.max_tag_length),
+            ("max_anchor_length", self.max_anchor_length),
+        ] {
+            options.insert(
+                Yaml::String(key.into()),
+                Yaml::Integer(i64::try_from(value).unwrap_or(i64::MAX)),
+            );
+        }
+        for (key, value) in [
+            ("max_nodes", self.max_nodes),
+            ("max_total_string_bytes", self.max_total_string_bytes),
+            ("max_documents", self.max_documents),
+            ("max_alias_expansions", self.max_alias_expansions),
+            ("max_alias_depth", self.max_alias_depth),
+            ("max_depth", self.max_depth),
+            ("max_scalar_length", self.max_scalar_length),
+        ] {
+            options.insert(
+                Yaml::String(key.into()),
+                value.map_or(Yaml::Null, |value| {
+                    Yaml::Integer(i64::try_from(value).unwrap_or(i64::MAX))
+                }),
+            );
+        }
+        options.insert(
+            Yaml::String("cancellable".into()),
+            Yaml::Boolean(self.cancel.is_some()),
+        );
+        options.insert(
+            Yaml::String("tag_resolver".into()),
+            Yaml::Boolean(self.tag_resolver.is_some()),
+        );
+        #[cfg(feature = "timestamps")]
+        for (key, value) in [
+            ("resolve_timestamps", self.resolve_timestamps),
+            ("strict_timestamps", self.strict_timestamps),
+        ] {
+            options.insert(Yaml::String(key.into()), Yaml::Boolean(value));
+        }
+        #[cfg(feature = "floats")]
+        for (key, value) in [
+            ("parse_floats", self.parse_floats),
+            ("keep_float_sources", self.keep_float_sources),
+        ] {
+            options.insert(Yaml::String(key.into()), Yaml::Boolean(value));
+        }
+        #[cfg(feature = "std")]
+        options.insert(Yaml::String("includes".into()), self.describe_includes());
+        Yaml::Hash(options)
+    }
+
+    /// Describe [`Self::includes`], as [`Self::describe`] does.
+    #[cfg(feature = "std")]
+    fn describe_includes(&self) -> Yaml {
+        self.includes.as_ref().map_or(Yaml::Null, |includes| {
+            let mut fields = Hash::new();
+            fields.insert(
+                Yaml::String("tag".into()),
+                Yaml::String(includes.tag.clone()),
+            );
+            fields.insert(
+                Yaml::String("max_depth".into()),
+                Yaml::Integer(i64::try_from(includes.max_depth).unwrap_or(i64::MAX)),
+            );
+            fields.insert(
+                Yaml::String("root".into()),
+                includes
+                    .root
+                    .as_ref()
+                    .map_or(Yaml::Null, |root| Yaml::String(root.display().to_string())),
+            );
+            Yaml::Hash(fields)
+        })
+    }
+}
+
+/// A mapping key involved in a [`LoadError::DuplicateKey`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyOccurrence {
+    /// The key as it was written, with its quotes if any. Collections are written `[...]` and
+    /// `{...}`, aliases `*alias`.
+    pub source: String,
+    /// The key once resolved, which is what is compared to detect duplicates.
+    pub resolved: Yaml,
+    /// Where the key starts.
+    pub start: Marker,
+    /// Where the key ends.
+    pub end: Marker,
+}
+
+/// The location of an anchor or of one of its aliases.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AnchorSite {
+    /// The path to the node within its document.
+    pub path: YamlPath,
+    /// The span of the event which introduced the node.
+    pub span: Span,
+}
+
+/// How an anchor is used within a document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AnchorUsage {
+    /// Where the anchored node is defined.
+    pub definition: AnchorSite,
+    /// Every alias referring to the anchor, in document order.
+    pub aliases: Vec<AnchorSite>,
+}
+
+/// The anchors of a document, indexed by the anchor id the parser assigned them.
+///
+/// The parser does not forward anchor names to the loader. The span of each site can be used to
+/// retrieve them from the source, as [`AnchorUsage::name`] does.
+pub type AnchorReport = BTreeMap<usize, AnchorUsage>;
+
+impl AnchorUsage {
+    /// Return the name of the anchor, e.g. `default-job` for `&default-job`.
+    ///
+    /// The name is read from `source`, which must be the input the document was loaded from: at
+    /// the first alias if there is one, or before the anchored node otherwise. Returns `None` if
+    /// it cannot be found there.
+    ///
+    /// ```
+    /// # use saphyr::{Yaml, YamlLoader};
+    /// # use saphyr_parser::{BufferedInput, Parser};
+    /// let source = "defaults: &default-job {image: rust}\nbuild: *default-job\nlint: &lint !!map {}";
+    /// let mut loader = YamlLoader::<Yaml>::default();
+    /// loader.record_anchors(true);
+    /// Parser::new(BufferedInput::new(source.chars())).load(&mut loader, true).unwrap();
+    ///
+    /// let names: Vec<_> = loader.anchor_report()[0]
+    ///     .values()
+    ///     .map(|usage| usage.name(source))
+    ///     .collect();
+    /// assert_eq!(names, [Some("default-job"), Some("lint")]);
+    /// ```
+    #[must_use]
+    pub fn name<'a>(&self, source: &'a str) -> Option<&'a str> {
+        let Some(alias) = self.aliases.first() else {
+            return anchor_property(source, byte_offset(source, self.definition.span.start));
+        };
+        let start = byte_offset(source, alias.span.start);
+        let end = byte_offset(source, alias.span.end);
+        source
+            .get(start..end)?
+            .strip_prefix('*')
+            .filter(|name| !name.is_empty())
+    }
+}
+
+/// Return the byte offset of `marker` within `source`, or its length if `marker` is beyond it.
+fn byte_offset(source: &str, marker: Marker) -> usize {
+    source
+        .char_indices()
+        .nth(marker.index())
+        .map_or(source.len(), |(i, _)| i)
+}
+
+/// The byte offsets of the markers of a source, looked up in the order of its events.
+///
+/// Events come roughly in source order, so each lookup only walks from the previous one.
+struct SourceOffsets<'a> {
+    source: &'a str,
+    /// The character index and byte offset of the last lookup.
+    index: usize,
+    offset: usize,
+}
+
+impl<'a> SourceOffsets<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            index: 0,
+            offset: 0,
+        }
+    }
+
+    /// Return the byte offset of the character at `index`, or the length of the source if it is
+    /// beyond it.
+    fn offset(&mut self, index: usize) -> usize {
+        while self.index < index && self.offset < self.source.len() {
+            self.offset += self.source[self.offset..]
+                .chars()
+                .next()
+                .map_or(0, char::len_utf8);
+            self.index += 1;
+        }
+        while self.index > index {
+            self.offset -= self.source[..self.offset]
+                .chars()
+                .next_back()
+                .map_or(0, char::len_utf8);
+            self.index -= 1;
+        }
+        self.offset
+    }
+
+    /// Return the name of the anchor or alias of `ev`, at `span`, if it has one.
+    fn anchor_name(&mut self, ev: &Event, span: Span) -> Option<&'a str> {
+        match ev {
+            Event::Alias(_) => {
+                let start = self.offset(span.start.index());
+                let end = self.offset(span.end.index());
+                self.source.get(start..end)?.strip_prefix('*')
+            }
+            Event::Scalar(_, _, aid, _)
+            | Event::SequenceStart(aid, _)
+            | Event::MappingStart(aid, _)
+                if *aid > 0 =>
+            {
+                anchor_property(self.source, self.offset(span.start.index()))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Return the name of the anchor of the node starting at byte `start` of `source`.
+///
+/// The span of the node may start at its anchor, or after it and the tag that may follow it.
+fn anchor_property(source: &str, start: usize) -> Option<&str> {
+    let is_delimiter = |c: char| c.is_whitespace() || matches!(c, ',' | '[' | ']' | '{' | '}');
+    let rest = &source[start..];
+    if let Some(name) = rest[..rest.find(is_delimiter).unwrap_or(rest.len())].strip_prefix('&') {
+        return Some(name).filter(|name| !name.is_empty());
+    }
+    let mut end = start;
+    loop {
+        let before = source[..end].trim_end();
+        let token_start = before
+            .char_indices()
+            .rev()
+            .find(|&(_, c)| is_delimiter(c))
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        let token = &before[token_start..];
+        if let Some(name) = token.strip_prefix('&') {
+            return Some(name).filter(|name| !name.is_empty());
+        }
+        // Skip the tag of the node, which may come after the anchor.
+        if !token.starts_with('!') {
+            return None;
+        }
+        end = token_start;
+    }
+}
+
+/// State needed by the loader to produce [`AnchorReport`]s.
+#[derive(Default)]
+struct AnchorRecorder {
+    /// Path to the collection that is being built.
+    path: YamlPath,
+    /// The report of the document that is being loaded.
+    current: AnchorReport,
+    /// The reports of the documents that have been loaded.
+    reports: Vec<AnchorReport>,
 }
 
 // For some reason, rustc wants `Node: Default` if I `#[derive(Default)]`.
@@ -36,9 +802,35 @@ where
     fn default() -> Self {
         Self {
             docs: vec![],
+            doc_starts: vec![],
+            metadata: vec![],
+            current_metadata: DocumentMetadata::default(),
+            directives: vec![],
+            document_options: None,
             doc_stack: vec![],
             key_stack: vec![],
             anchor_map: BTreeMap::new(),
+            has_locations: false,
+            options: LoaderOptions::default(),
+            anchor_recorder: None,
+            collection_starts: vec![],
+            collection_tags: vec![],
+            key_sites: vec![],
+            next_site: None,
+            alias_name: None,
+            error: None,
+            node_filter: None,
+            skipping: None,
+            last_end: Marker::default(),
+            totals: Totals::default(),
+            document_count: 0,
+            collection_totals: vec![],
+            anchor_totals: BTreeMap::new(),
+            interned_keys: BTreeSet::new(),
+            #[cfg(feature = "std")]
+            include_chain: vec![],
+            #[cfg(feature = "std")]
+            include_depth: 0,
         }
     }
 }
@@ -48,163 +840,2303 @@ where
     Node: LoadableYamlNode,
 {
     fn on_event(&mut self, ev: Event, span: Span) {
+        if self.error.is_some() {
+            return;
+        }
+        if self
+            .options
+            .cancel
+            .as_ref()
+            .is_some_and(CancelToken::is_cancelled)
+        {
+            self.error = Some(LoadError::Cancelled);
+            return;
+        }
+        let ev = self.expand_tag_handles(ev);
+        if let Err(error) = self
+            .check_event(&ev, span)
+            .and_then(|()| self.count_event(&ev, span))
+        {
+            self.error = Some(error);
+            return;
+        }
+        if let Some(nesting) = self.skipping {
+            self.skipping = match ev {
+                Event::SequenceStart(..) | Event::MappingStart(..) => Some(nesting + 1),
+                Event::SequenceEnd | Event::MappingEnd if nesting > 1 => Some(nesting - 1),
+                Event::Scalar(..) | Event::Alias(_) if nesting > 0 => Some(nesting),
+                _ => None,
+            };
+            return;
+        }
+        if let Err(reason) = self.check_structure(&ev) {
+            self.error = Some(LoadError::InvalidEventStream {
+                reason,
+                marker: span.start,
+                end: span.end,
+                path: self.current_path(false),
+            });
+            return;
+        }
         match ev {
-            Event::DocumentStart(_) | Event::Nothing | Event::StreamStart | Event::StreamEnd => {
+            Event::DocumentStart(explicit) => self.start_document(span.start, explicit),
+            Event::Nothing | Event::StreamStart | Event::StreamEnd => {
                 // do nothing
             }
-            Event::DocumentEnd => {
-                match self.doc_stack.len() {
-                    // empty document
-                    0 => self
-                        .docs
-                        .push(Node::from_bare_yaml(Yaml::BadValue).with_span(span)),
-                    1 => self.docs.push(self.doc_stack.pop().unwrap().0),
-                    _ => unreachable!(),
-                }
-            }
-            Event::SequenceStart(aid, _) => {
-                self.doc_stack.push((
-                    Node::from_bare_yaml(Yaml::Array(Vec::new())).with_span(span),
-                    aid,
-                ));
+            Event::DocumentEnd => self.end_document(span),
+            Event::SequenceStart(aid, tag) => {
+                self.enter_collection(aid, span);
+                self.collection_starts.push(span.start);
+                self.collection_tags.push(self.collection_tag(tag.as_ref()));
+                self.doc_stack
+                    .push((Node::from_sequence_start(tag.as_ref()).with_span(span), aid));
             }
             Event::SequenceEnd => {
-                let node = self.doc_stack.pop().unwrap();
-                self.insert_new_node(node);
-            }
-            Event::MappingStart(aid, _) => {
-                self.doc_stack.push((
-                    Node::from_bare_yaml(Yaml::Hash(Hash::new())).with_span(span),
-                    aid,
-                ));
+                let span = self.collection_span(span);
+                self.set_next_site(|| "[...]".into(), span);
+                let (node, aid) = self.doc_stack.pop().unwrap();
+                self.leave_collection();
+                let Some(node) = self.tag_collection(node, span) else {
+                    return;
+                };
+                self.insert_new_node((node.with_span(span), aid));
+            }
+            Event::MappingStart(aid, tag) => {
+                self.enter_collection(aid, span);
+                self.collection_starts.push(span.start);
+                self.collection_tags.push(self.collection_tag(tag.as_ref()));
+                self.key_sites.push(vec![]);
+                self.doc_stack
+                    .push((Node::from_mapping_start(tag.as_ref()).with_span(span), aid));
                 self.key_stack.push(Node::from_bare_yaml(Yaml::BadValue));
             }
             Event::MappingEnd => {
                 self.key_stack.pop().unwrap();
-                let node = self.doc_stack.pop().unwrap();
-                self.insert_new_node(node);
+                self.key_sites.pop();
+                let span = self.collection_span(span);
+                self.set_next_site(|| "{...}".into(), span);
+                let (node, aid) = self.doc_stack.pop().unwrap();
+                self.leave_collection();
+                let Some(node) = self.tag_collection(node, span) else {
+                    return;
+                };
+                self.insert_new_node((node.with_span(span), aid));
             }
             Event::Scalar(v, style, aid, tag) => {
-                let node = if style != TScalarStyle::Plain {
-                    Yaml::String(v)
-                } else if let Some(Tag {
-                    ref handle,
-                    ref suffix,
-                }) = tag
-                {
-                    if handle == "tag:yaml.org,2002:" {
-                        match suffix.as_ref() {
-                            "bool" => {
-                                // "true" or "false"
-                                match v.parse::<bool>() {
-                                    Err(_) => Yaml::BadValue,
-                                    Ok(v) => Yaml::Boolean(v),
-                                }
-                            }
-                            "int" => match v.parse::<i64>() {
-                                Err(_) => Yaml::BadValue,
-                                Ok(v) => Yaml::Integer(v),
-                            },
-                            "float" => match parse_f64(&v) {
-                                Some(_) => Yaml::Real(v),
-                                None => Yaml::BadValue,
-                            },
-                            "null" => match v.as_ref() {
-                                "~" | "null" => Yaml::Null,
-                                _ => Yaml::BadValue,
-                            },
-                            _ => Yaml::String(v),
-                        }
-                    } else {
-                        Yaml::String(v)
-                    }
+                self.load_scalar(v, style, aid, tag.as_ref(), span);
+            }
+            Event::Alias(id) => self.load_alias(id, span),
+        }
+    }
+}
+
+impl<Node> YamlLoader<Node>
+where
+    Node: LoadableYamlNode,
+{
+    /// Insert a scalar node.
+    fn load_scalar(
+        &mut self,
+        v: String,
+        style: TScalarStyle,
+        aid: usize,
+        tag: Option<&Tag>,
+        span: Span,
+    ) {
+        if aid > 0 {
+            self.record_anchor(aid, span);
+        }
+        self.set_next_site(|| written_scalar(&v, style), span);
+        self.last_end = span.end;
+        #[cfg(feature = "std")]
+        if self.is_include(tag) {
+            self.load_include(&v, aid, span);
+            return;
+        }
+        match self.resolve_tagged_scalar(tag, &v, style, span) {
+            Ok(Some(node)) => {
+                self.insert_new_node((node.with_span(span), aid));
+                return;
+            }
+            Ok(None) => {}
+            Err(error) => {
+                self.error = Some(error);
+                return;
+            }
+        }
+        let kept_tag = self.kept_tag(tag);
+        let written = self.options.strict.then(|| written_scalar(&v, style));
+        let options = self.document_options.as_ref().unwrap_or(&self.options);
+        let mut node = Node::from_scalar(v, style, tag, options);
+        if let (Some(value), true) = (written, node.is_badvalue()) {
+            self.error = Some(LoadError::InvalidScalar {
+                value,
+                tag: tag.map(tag_text),
+                marker: span.start,
+                end: span.end,
+                path: self.current_path(true),
+            });
+            return;
+        }
+        if let Some(tag) = kept_tag {
+            node = node.with_tag(tag);
+        }
+        self.insert_new_node((node.with_span(span), aid));
+    }
+
+    /// Insert the node an alias refers to.
+    fn load_alias(&mut self, id: usize, span: Span) {
+        let name = self.alias_name.take();
+        let node = match self.anchored_node(id) {
+            Some(node) => node,
+            None if self.options.unknown_aliases == UnknownAliases::Error
+                || self.options.strict =>
+            {
+                self.error = Some(LoadError::UnknownAlias {
+                    id,
+                    name,
+                    marker: span.start,
+                    end: span.end,
+                    path: self.current_path(true),
+                });
+                return;
+            }
+            None => Node::from_bare_yaml(Yaml::BadValue),
+        };
+        self.record_alias(id, span);
+        self.set_next_site(|| "*alias".into(), span);
+        self.last_end = span.end;
+        self.insert_new_node((node.with_span(span), 0));
+    }
+
+    /// Return `tag` if the node it tags is to keep it, as per [`LoaderOptions::keep_tags`].
+    fn kept_tag(&self, tag: Option<&Tag>) -> Option<Tag> {
+        tag.filter(|tag| self.options.keep_tags && !is_resolved_tag(tag))
+            .cloned()
+    }
+
+    /// Return `tag` if the collection it tags needs it once complete, to resolve it with
+    /// [`LoaderOptions::tag_resolver`] or to keep it.
+    fn collection_tag(&self, tag: Option<&Tag>) -> Option<Tag> {
+        if self.options.tag_resolver.is_some() {
+            tag.cloned()
+        } else {
+            self.kept_tag(tag)
+        }
+    }
+
+    /// Resolve the collection `node`, which was just completed, with its tag, or give it the tag
+    /// it keeps, if any.
+    ///
+    /// Return `None` if the tag resolver failed, in which case the error is set.
+    fn tag_collection(&mut self, mut node: Node, span: Span) -> Option<Node> {
+        let Some(tag) = self.collection_tags.pop().flatten() else {
+            return Some(node);
+        };
+        if let Some(resolver) = &self.options.tag_resolver {
+            match resolver.resolve_collection(&tag, node.to_bare_yaml()) {
+                Ok(Some(yaml)) => {
+                    // The anchored nodes within `node` are dropped with it.
+                    self.copy_anchored_nodes(Some(&mut node));
+                    return Some(node_from_yaml(yaml));
+                }
+                Ok(None) => {}
+                Err(error) => {
+                    self.error = Some(self.tag_resolution_error(&tag, error, span));
+                    return None;
+                }
+            }
+        }
+        Some(match self.kept_tag(Some(&tag)) {
+            Some(tag) => node.with_tag(tag),
+            None => node,
+        })
+    }
+
+    /// Return the node [`LoaderOptions::tag_resolver`] builds for a scalar with `tag`, if any.
+    fn resolve_tagged_scalar(
+        &mut self,
+        tag: Option<&Tag>,
+        value: &str,
+        style: TScalarStyle,
+        span: Span,
+    ) -> Result<Option<Node>, LoadError> {
+        let (Some(resolver), Some(tag)) = (&self.options.tag_resolver, tag) else {
+            return Ok(None);
+        };
+        match resolver.resolve_scalar(tag, value, style) {
+            Ok(yaml) => Ok(yaml.map(node_from_yaml)),
+            Err(error) => Err(self.tag_resolution_error(tag, error, span)),
+        }
+    }
+
+    /// Wrap the `error` of [`LoaderOptions::tag_resolver`] on the node with `tag` at `span`.
+    fn tag_resolution_error(&mut self, tag: &Tag, error: LoadError, span: Span) -> LoadError {
+        LoadError::TagResolution {
+            tag: format!("{}{}", tag.handle, tag.suffix),
+            error: Box::new(error),
+            marker: span.start,
+            end: span.end,
+            path: self.current_path(true),
+        }
+    }
+
+    /// Whether a scalar with `tag` is to be replaced by the source it names.
+    #[cfg(feature = "std")]
+    fn is_include(&self, tag: Option<&Tag>) -> bool {
+        match (&self.options.includes, tag) {
+            (Some(includes), Some(tag)) => {
+                includes.tag.strip_prefix(tag.handle.as_str()) == Some(tag.suffix.as_str())
+            }
+            _ => false,
+        }
+    }
+
+    /// Insert the document of the source included at `path`.
+    #[cfg(feature = "std")]
+    fn load_include(&mut self, path: &str, aid: usize, span: Span) {
+        match self.included_document(path, span) {
+            Ok(node) => self.insert_new_node((node.with_span(span), aid)),
+            Err(error) => self.error = Some(error),
+        }
+    }
+
+    /// Load the only document of the source included at `path`, with the same options.
+    #[cfg(feature = "std")]
+    fn included_document(&mut self, path: &str, span: Span) -> Result<Node, LoadError> {
+        let includes = self.options.includes.clone().expect("includes are enabled");
+        let from = self.include_chain.last().cloned();
+        let file = includes.resolver.locate(path, from.as_deref());
+        if let Some(start) = self.include_chain.iter().position(|source| *source == file) {
+            let mut chain = self.include_chain[start..].to_vec();
+            chain.push(file);
+            return Err(LoadError::IncludeCycle {
+                chain,
+                marker: span.start,
+                end: span.end,
+                path: self.current_path(true),
+            });
+        }
+        if self.include_depth >= includes.max_depth {
+            return Err(self.limit_exceeded(
+                LoaderLimit::IncludeDepth,
+                self.include_depth + 1,
+                includes.max_depth,
+                span,
+            ));
+        }
+
+        let mut loader = YamlLoader::with_options(self.options.clone());
+        loader.include_chain.clone_from(&self.include_chain);
+        loader.include_chain.push(file.clone());
+        loader.include_depth = self.include_depth + 1;
+        // Included nodes count towards the limits on the whole stream.
+        loader.totals = self.totals;
+        let result = includes
+            .resolver
+            .resolve(path, from.as_deref())
+            .and_then(|source| load_into(loader, &source))
+            .and_then(|mut loader| {
+                self.totals = loader.totals;
+                if loader.docs.len() == 1 {
+                    Ok(loader.docs.pop().unwrap())
+                } else {
+                    Err(LoadError::ExpectedSingleDocument {
+                        found: loader.docs.len(),
+                        second_document: loader.doc_starts.get(1).copied(),
+                    })
+                }
+            });
+        match result {
+            Err(error) if !matches!(error, LoadError::IncludeCycle { .. }) => {
+                Err(LoadError::Include {
+                    file,
+                    error: Box::new(error),
+                    marker: span.start,
+                    end: span.end,
+                    path: self.current_path(true),
+                })
+            }
+            result => result,
+        }
+    }
+
+    fn insert_new_node(&mut self, mut node: (Node, usize)) {
+        if self.node_filter.is_some() && !self.filter_node(&mut node.0) {
+            self.next_site = None;
+            return;
+        }
+        if self.options.intern_keys && self.next_is_key() {
+            node.0.intern(&mut self.interned_keys);
+        }
+        if let Some((source, span)) = self.next_site.take() {
+            self.check_duplicate_key(&node.0, source, span);
+        }
+        // valid anchor id starts from 1
+        if node.1 > 0 {
+            self.anchor_node(&mut node.0, node.1);
+        } else if self.has_locations && self.overwrites_key() {
+            self.copy_anchored_nodes(Some(&mut node.0));
+        }
+        if let Some(parent) = self.doc_stack.last_mut() {
+            let parent_node = &mut parent.0;
+            if parent_node.is_array() {
+                parent_node.array_mut().push(node.0);
+            } else if parent_node.is_hash() {
+                let cur_key = self.key_stack.last_mut().unwrap();
+                // current node is a key
+                if cur_key.is_badvalue() {
+                    *cur_key = node.0;
+                // current node is a value
                 } else {
-                    // Datatype is not specified, or unrecognized
-                    Yaml::from_str(&v)
+                    let key = cur_key.take();
+                    let hash = parent_node.hash_mut();
+                    if self.options.duplicate_keys != DuplicateKeys::KeepFirst
+                        || !hash.contains_key(&key)
+                    {
+                        hash.insert(key, node.0);
+                    }
+                }
+            }
+        } else {
+            self.doc_stack.push(node);
+        }
+    }
+
+    /// Record `node`, which is about to be inserted, as the node with anchor `id`.
+    fn anchor_node(&mut self, node: &mut Node, id: usize) {
+        let anchored = if self.node_filter.is_some() || self.in_mapping_key() {
+            Anchored::Copy(node.clone())
+        } else if self.overwrites_key() {
+            // Replacing a value may move the entries of its mapping.
+            self.copy_anchored_nodes(Some(node));
+            Anchored::Copy(node.clone())
+        } else {
+            self.has_locations = true;
+            Anchored::Location {
+                document: self.docs.len(),
+                // Open collections are inserted in their parent after the children it already
+                // has, as is the node.
+                positions: self
+                    .doc_stack
+                    .iter_mut()
+                    .map(|(parent, _)| child_count(parent))
+                    .collect(),
+            }
+        };
+        self.anchor_map.insert(id, anchored);
+    }
+
+    /// Return a copy of the node with anchor `id`, if it is complete.
+    fn anchored_node(&mut self, id: usize) -> Option<Node> {
+        match self.anchor_map.get(&id)? {
+            Anchored::Copy(node) => Some(node.clone()),
+            Anchored::Location {
+                document,
+                positions,
+            } => locate(
+                &mut self.docs,
+                &mut self.doc_stack,
+                None,
+                *document,
+                positions,
+            )
+            .cloned(),
+        }
+    }
+
+    /// Replace the locations of anchored nodes with copies, before they become invalid.
+    ///
+    /// `pending` is the node about to be inserted, if any: it is no longer in `doc_stack`, but
+    /// anchored nodes may be within it.
+    fn copy_anchored_nodes(&mut self, mut pending: Option<&mut Node>) {
+        if !self.has_locations {
+            return;
+        }
+        self.has_locations = false;
+        for anchored in self.anchor_map.values_mut() {
+            if let Anchored::Location {
+                document,
+                positions,
+            } = anchored
+            {
+                let node = locate(
+                    &mut self.docs,
+                    &mut self.doc_stack,
+                    pending.as_deref_mut(),
+                    *document,
+                    positions,
+                )
+                .expect("anchored nodes are in the documents")
+                .clone();
+                *anchored = Anchored::Copy(node);
+            }
+        }
+    }
+
+    /// Whether the next node to be inserted replaces the value of an existing mapping key.
+    fn overwrites_key(&mut self) -> bool {
+        if self.next_is_key() {
+            return false;
+        }
+        match (self.doc_stack.last_mut(), self.key_stack.last()) {
+            (Some((parent, _)), Some(key)) if parent.is_hash() => {
+                parent.hash_mut().contains_key(key)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the next node to be inserted is a mapping key or is within one.
+    fn in_mapping_key(&self) -> bool {
+        // Each open mapping has its pending key in `key_stack`, in the same order.
+        let mut keys = self.key_stack.iter();
+        self.doc_stack
+            .iter()
+            .any(|(node, _)| node.is_hash() && keys.next().is_some_and(Node::is_badvalue))
+    }
+
+    /// Run the node filter on `node`, which is about to be inserted.
+    ///
+    /// Returns `false` if the node must be dropped, in which case the state of the loader is
+    /// updated accordingly.
+    fn filter_node(&mut self, node: &mut Node) -> bool {
+        let is_key = self.next_is_key();
+        let parent_is_hash = self
+            .doc_stack
+            .last()
+            .is_some_and(|(parent, _)| parent.is_hash());
+        let context = NodeContext {
+            key: self.key_stack.last().filter(|_| parent_is_hash && !is_key),
+            is_key,
+            depth: self.doc_stack.len(),
+        };
+        let Some(filter) = &mut self.node_filter else {
+            return true;
+        };
+        match filter(node, &context) {
+            FilterAction::Keep => true,
+            FilterAction::Replace(replacement) => {
+                *node = replacement;
+                true
+            }
+            FilterAction::Drop => {
+                if is_key {
+                    self.skipping = Some(0);
+                } else if parent_is_hash {
+                    let _ = self.key_stack.last_mut().unwrap().take();
+                }
+                false
+            }
+        }
+    }
+
+    /// Return the span of the collection that `end_span`, the span of its end event, closes.
+    ///
+    /// Flow collections end with their closing delimiter. The end event of block collections is
+    /// empty and placed at the next token, possibly lines below; they end with their last child
+    /// instead.
+    fn collection_span(&mut self, end_span: Span) -> Span {
+        let start = self.collection_starts.pop().unwrap();
+        if end_span.start != end_span.end {
+            self.last_end = end_span.end;
+        }
+        Span::new(start, self.last_end)
+    }
+
+    /// Return whether the next node to be inserted is a mapping key.
+    fn next_is_key(&self) -> bool {
+        self.doc_stack
+            .last()
+            .is_some_and(|(parent, _)| parent.is_hash())
+            && self.key_stack.last().is_some_and(Node::is_badvalue)
+    }
+
+    /// Add what `ev` loads to the totals, and check them against the limits on the whole stream.
+    fn count_event(&mut self, ev: &Event, span: Span) -> Result<(), LoadError> {
+        let added = match ev {
+            Event::DocumentStart(_) => {
+                self.document_count += 1;
+                if let Some(max) = self.options.max_documents {
+                    if self.document_count > max {
+                        return Err(self.limit_exceeded(
+                            LoaderLimit::Documents,
+                            self.document_count,
+                            max,
+                            span,
+                        ));
+                    }
+                }
+                return Ok(());
+            }
+            Event::Scalar(v, _, aid, _) => {
+                let added = Totals {
+                    nodes: 1,
+                    string_bytes: v.len(),
+                    aliases: 0,
                 };
-                self.insert_new_node((Node::from_bare_yaml(node).with_span(span), aid));
+                if *aid > 0 {
+                    self.anchor_totals.insert(*aid, (added, 0));
+                }
+                added
+            }
+            Event::SequenceStart(aid, _) | Event::MappingStart(aid, _) => {
+                self.collection_totals.push((*aid, self.totals, 0));
+                Totals {
+                    nodes: 1,
+                    string_bytes: 0,
+                    aliases: 0,
+                }
+            }
+            Event::SequenceEnd | Event::MappingEnd => {
+                if let Some((aid, start, alias_depth)) = self.collection_totals.pop() {
+                    if aid > 0 {
+                        self.anchor_totals
+                            .insert(aid, (self.totals.since(start), alias_depth));
+                    }
+                    if let Some((_, _, parent_depth)) = self.collection_totals.last_mut() {
+                        *parent_depth = (*parent_depth).max(alias_depth);
+                    }
+                }
+                return Ok(());
             }
             Event::Alias(id) => {
-                let n = match self.anchor_map.get(&id) {
-                    Some(v) => v.clone(),
-                    None => Node::from_bare_yaml(Yaml::BadValue),
-                };
-                self.insert_new_node((n.with_span(span), 0));
+                let (mut added, depth) = self.anchor_totals.get(id).copied().unwrap_or_default();
+                added.nodes = added.nodes.max(1);
+                added.aliases += 1;
+                let depth = depth + 1;
+                if let Some(max) = self.options.max_alias_depth.filter(|&max| depth > max) {
+                    return Err(self.limit_exceeded(LoaderLimit::AliasDepth, depth, max, span));
+                }
+                if let Some((_, _, parent_depth)) = self.collection_totals.last_mut() {
+                    *parent_depth = (*parent_depth).max(depth);
+                }
+                added
+            }
+            _ => return Ok(()),
+        };
+        self.totals.nodes += added.nodes;
+        self.totals.string_bytes += added.string_bytes;
+        self.totals.aliases += added.aliases;
+        for (limit, total, max) in [
+            (
+                LoaderLimit::Nodes,
+                self.totals.nodes,
+                self.options.max_nodes,
+            ),
+            (
+                LoaderLimit::TotalStringBytes,
+                self.totals.string_bytes,
+                self.options.max_total_string_bytes,
+            ),
+            (
+                LoaderLimit::AliasExpansions,
+                self.totals.aliases,
+                self.options.max_alias_expansions,
+            ),
+        ] {
+            if let Some(max) = max.filter(|&max| total > max) {
+                return Err(self.limit_exceeded(limit, total, max, span));
+            }
+        }
+        Ok(())
+    }
+
+    /// Build the error for `limit` being exceeded by the node or document at `span`.
+    fn limit_exceeded(
+        &mut self,
+        limit: LoaderLimit,
+        length: usize,
+        max: usize,
+        span: Span,
+    ) -> LoadError {
+        LoadError::LimitExceeded {
+            limit,
+            length,
+            max,
+            marker: span.start,
+            end: span.end,
+            path: self.current_path(true),
+        }
+    }
+
+    /// Check that `ev` does not exceed the limits set in the options, nor is otherwise rejected
+    /// by them.
+    fn check_event(&mut self, ev: &Event, span: Span) -> Result<(), LoadError> {
+        let mut exceeded = None;
+        if let Event::Scalar(_, _, _, Some(tag))
+        | Event::SequenceStart(_, Some(tag))
+        | Event::MappingStart(_, Some(tag)) = ev
+        {
+            let length = tag.handle.len() + tag.suffix.len();
+            if length > self.options.max_tag_length {
+                exceeded = Some((LoaderLimit::TagLength, length, self.options.max_tag_length));
+            }
+        }
+        if let Event::Scalar(v, ..) = ev {
+            if exceeded.is_none() && v.len() > self.options.max_key_length && self.next_is_key() {
+                exceeded = Some((LoaderLimit::KeyLength, v.len(), self.options.max_key_length));
+            }
+            if let Some(max) = self.options.max_scalar_length {
+                if exceeded.is_none() && v.len() > max {
+                    exceeded = Some((LoaderLimit::ScalarLength, v.len(), max));
+                }
+            }
+        }
+        if let Event::SequenceStart(..) | Event::MappingStart(..) = ev {
+            // Collections in the value of a dropped key are skipped rather than built, but count.
+            let depth = self.collection_starts.len() + self.skipping.unwrap_or(0) + 1;
+            if let Some(max) = self
+                .options
+                .max_depth
+                .filter(|&max| exceeded.is_none() && depth > max)
+            {
+                exceeded = Some((LoaderLimit::Depth, depth, max));
+            }
+        }
+        if let Some((limit, length, max)) = exceeded {
+            return Err(self.limit_exceeded(limit, length, max, span));
+        }
+        if let Event::Scalar(_, _, _, Some(tag))
+        | Event::SequenceStart(_, Some(tag))
+        | Event::MappingStart(_, Some(tag)) = ev
+        {
+            // Declared handles have been expanded, so a named handle left is unknown.
+            let handle = &tag.handle;
+            if self.options.strict_tag_handles
+                && handle.len() > 2
+                && handle.starts_with('!')
+                && handle.ends_with('!')
+            {
+                return Err(LoadError::UnknownTagHandle {
+                    handle: handle.clone(),
+                    marker: span.start,
+                    end: span.end,
+                    path: self.current_path(true),
+                });
+            }
+        }
+        if self.options.forbid_anchors {
+            let alias = matches!(ev, Event::Alias(_));
+            if let Event::Scalar(_, _, aid, _)
+            | Event::SequenceStart(aid, _)
+            | Event::MappingStart(aid, _)
+            | Event::Alias(aid) = ev
+            {
+                if *aid > 0 || alias {
+                    return Err(LoadError::AnchorForbidden {
+                        alias,
+                        marker: span.start,
+                        end: span.end,
+                        path: self.current_path(true),
+                    });
+                }
+            }
+        }
+        #[cfg(feature = "timestamps")]
+        if let Event::Scalar(v, TScalarStyle::Plain, _, Some(tag)) = ev {
+            if self.options.strict_timestamps
+                && tag.handle == "tag:yaml.org,2002:"
+                && tag.suffix == "timestamp"
+                && crate::Timestamp::parse(v).is_none()
+            {
+                return Err(LoadError::InvalidTimestamp {
+                    value: v.clone(),
+                    marker: span.start,
+                    end: span.end,
+                    path: self.current_path(true),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the name of the anchor or alias of `ev`, read from the source of the stream, against
+    /// [`LoaderOptions::max_anchor_length`].
+    fn check_anchor_length(
+        &mut self,
+        ev: &Event,
+        span: Span,
+        source: &mut SourceOffsets,
+    ) -> Result<(), LoadError> {
+        let max = self.options.max_anchor_length;
+        match source.anchor_name(ev, span) {
+            Some(name) if name.len() > max => {
+                Err(self.limit_exceeded(LoaderLimit::AnchorLength, name.len(), max, span))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Remember the name of the alias `ev`, read from the source of the stream, in case it does
+    /// not refer to a complete anchored node.
+    fn read_alias_name(&mut self, ev: &Event, span: Span, source: &mut SourceOffsets) {
+        if matches!(ev, Event::Alias(_))
+            && (self.options.unknown_aliases == UnknownAliases::Error || self.options.strict)
+        {
+            self.alias_name = source.anchor_name(ev, span).map(ToOwned::to_owned);
+        }
+    }
+
+    /// Check that `ev` can follow the events received so far, so that the stacks of the loader
+    /// stay consistent.
+    ///
+    /// Returns why `ev` cannot follow them otherwise. The parser only produces well-formed
+    /// streams, but events may come from elsewhere (see [`Self::from_events`]).
+    fn check_structure(&self, ev: &Event) -> Result<(), &'static str> {
+        let open = self.collection_starts.len();
+        // The root of the document is complete if it is the only node left and not open.
+        let root_complete = self.doc_stack.len() > open;
+        let innermost = self
+            .doc_stack
+            .last()
+            .filter(|_| open > 0)
+            .map(|(node, _)| node);
+        match ev {
+            Event::DocumentStart(_) if !self.doc_stack.is_empty() => {
+                Err("a document starts before the previous one ends")
+            }
+            Event::DocumentEnd if open > 0 => Err("a document ends within a collection"),
+            Event::SequenceEnd if !innermost.is_some_and(Node::is_array) => {
+                Err("the end of a sequence does not close a sequence")
+            }
+            Event::MappingEnd if !innermost.is_some_and(Node::is_hash) => {
+                Err("the end of a mapping does not close a mapping")
+            }
+            Event::MappingEnd if !self.key_stack.last().is_some_and(Node::is_badvalue) => {
+                Err("a mapping ends after a key without a value")
+            }
+            Event::Scalar(..)
+            | Event::Alias(_)
+            | Event::SequenceStart(..)
+            | Event::MappingStart(..)
+                if root_complete =>
+            {
+                Err("a document has more than one root node")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Remember how the next node was written, if needed to report duplicate keys.
+    fn set_next_site(&mut self, source: impl FnOnce() -> String, span: Span) {
+        if self.options.duplicate_keys == DuplicateKeys::Error {
+            self.next_site = Some((source(), span));
+        }
+    }
+
+    /// If `node` is about to be inserted as a key, check that its mapping does not contain it.
+    fn check_duplicate_key(&mut self, node: &Node, source: String, span: Span) {
+        if !self.next_is_key() || self.error.is_some() {
+            return;
+        }
+        let (Some((parent, _)), Some(sites)) =
+            (self.doc_stack.last_mut(), self.key_sites.last_mut())
+        else {
+            return;
+        };
+        let occurrence = KeyOccurrence {
+            source,
+            resolved: node.to_bare_yaml(),
+            start: span.start,
+            end: span.end,
+        };
+        let hash = parent.hash_mut();
+        if !hash.contains_key(node) {
+            sites.push(occurrence);
+            return;
+        }
+        let position = hash.keys().position(|key| key == node).unwrap();
+        let first = Box::new(sites[position].clone());
+        self.error = Some(LoadError::DuplicateKey {
+            first,
+            second: Box::new(occurrence),
+            path: self.current_path(false),
+            schema: self.options.schema,
+        });
+    }
+
+    /// Return the path to the collection being built or, if `next` is set, to the next node to be
+    /// inserted in it.
+    fn current_path(&mut self, next: bool) -> YamlPath {
+        let mut path = YamlPath::new();
+        let mut hashes = 0;
+        let depth = self.doc_stack.len();
+        for (i, (parent, _)) in self.doc_stack.iter_mut().enumerate() {
+            if i + 1 == depth && !next {
+                break;
+            }
+            if parent.is_array() {
+                path.push(PathSegment::Index(parent.array_mut().len()));
+            } else if parent.is_hash() {
+                let cur_key = &self.key_stack[hashes];
+                hashes += 1;
+                path.push(if cur_key.is_badvalue() {
+                    PathSegment::EntryKey(parent.hash_mut().len())
+                } else {
+                    PathSegment::Key(cur_key.to_bare_yaml())
+                });
+            }
+        }
+        path
+    }
+
+    /// Create a new loader, with no document loaded.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new loader with the given options.
+    #[must_use]
+    pub fn with_options(options: LoaderOptions) -> Self {
+        let mut loader = Self::default();
+        loader.record_anchors(options.record_anchors);
+        #[cfg(feature = "std")]
+        {
+            loader.include_chain = options
+                .includes
+                .as_ref()
+                .and_then(|includes| includes.root.clone())
+                .into_iter()
+                .collect();
+        }
+        loader.options = options;
+        loader
+    }
+
+    /// Return the options of the loader.
+    #[must_use]
+    pub fn options(&self) -> &LoaderOptions {
+        &self.options
+    }
+
+    /// Return the document nodes from `self`, consuming it in the process.
+    #[must_use]
+    pub fn into_documents(self) -> Vec<Node> {
+        self.docs
+    }
+
+    /// Return the document nodes loaded so far.
+    #[must_use]
+    pub fn documents(&self) -> &[Node] {
+        &self.docs
+    }
+
+    /// Remove the document nodes loaded so far from `self` and return them.
+    ///
+    /// Unlike [`Self::reset`], this leaves the rest of the state untouched and can be called
+    /// between documents of a stream.
+    pub fn take_documents(&mut self) -> Vec<Node> {
+        self.copy_anchored_nodes(None);
+        self.doc_starts.clear();
+        self.metadata.clear();
+        core::mem::take(&mut self.docs)
+    }
+
+    /// Move the document nodes loaded so far from `self` to the end of `docs`.
+    ///
+    /// This is the same as [`Self::take_documents`], except that neither `docs` nor the buffer
+    /// of `self` is reallocated if they are large enough. Together with [`Self::reset`], this
+    /// lets a loader be reused for many small streams without allocating its state every time:
+    ///
+    /// ```
+    /// # use saphyr::{Yaml, YamlLoader};
+    /// # use saphyr_parser::{BufferedInput, Parser};
+    /// let mut loader = YamlLoader::<Yaml>::new();
+    /// let mut docs = Vec::with_capacity(3);
+    /// for source in ["a: 1", "b: 2", "c: 3"] {
+    ///     loader.reset();
+    ///     let mut parser = Parser::new(BufferedInput::new(source.chars()));
+    ///     parser.load(&mut loader, true).unwrap();
+    ///     loader.append_documents(&mut docs);
+    /// }
+    /// assert_eq!(docs[2]["c"].as_i64(), Some(3));
+    /// ```
+    pub fn append_documents(&mut self, docs: &mut Vec<Node>) {
+        self.copy_anchored_nodes(None);
+        self.doc_starts.clear();
+        self.metadata.clear();
+        docs.append(&mut self.docs);
+    }
+
+    /// Return the marker at which each of [`Self::documents`] starts.
+    pub(crate) fn document_starts(&self) -> &[Marker] {
+        &self.doc_starts
+    }
+
+    /// Read the `%YAML` and `%TAG` directives of `source`, the stream about to be loaded.
+    ///
+    /// The parser does not forward directives to the loader. With them, the loader fills in the
+    /// [`Self::document_metadata`] of each document and expands the tag handles they declare
+    /// before giving tags to nodes (see [`LoadableYamlNode::from_scalar`]): with
+    /// `%TAG !e! tag:example.com,2020:`, `!e!foo` becomes `tag:example.com,2020:foo`.
+    ///
+    /// ```
+    /// # use saphyr::{Yaml, YamlLoader};
+    /// # use saphyr_parser::{BufferedInput, Parser};
+    /// let source = "%YAML 1.1\n%TAG !e! tag:example.com,2020:\n--- !e!foo 1";
+    /// let mut loader = YamlLoader::<Yaml>::new();
+    /// loader.read_directives(source);
+    /// Parser::new(BufferedInput::new(source.chars())).load(&mut loader, true).unwrap();
+    ///
+    /// let metadata = &loader.document_metadata()[0];
+    /// assert_eq!(metadata.version, Some((1, 1)));
+    /// assert_eq!(metadata.tag_handles["!e!"], "tag:example.com,2020:");
+    /// ```
+    pub fn read_directives(&mut self, source: &str) {
+        // Skip the scan in the common case of a stream without directives.
+        if source.starts_with('%') || source.contains("\n%") {
+            self.directives = scan_directives(source);
+        }
+    }
+
+    /// Return the directives of each of [`Self::documents`], and whether they started with a
+    /// `---` marker.
+    ///
+    /// Documents have no directives unless [`Self::read_directives`] was called.
+    #[must_use]
+    pub fn document_metadata(&self) -> &[DocumentMetadata] {
+        &self.metadata
+    }
+
+    /// Start a document at `marker`, with the directives read for it, if any. `explicit` is
+    /// whether it starts with a `---` marker.
+    fn start_document(&mut self, marker: Marker, explicit: bool) {
+        self.doc_starts.push(marker);
+        self.current_metadata = self
+            .directives
+            .binary_search_by_key(&marker.line(), |(line, _)| *line)
+            .map(|index| self.directives[index].1.clone())
+            .unwrap_or_default();
+        self.current_metadata.explicit_start = explicit;
+        self.document_options = match self.current_metadata.schema() {
+            Some(schema) if self.options.schema_from_version && schema != self.options.schema => {
+                Some(LoaderOptions {
+                    schema,
+                    ..self.options.clone()
+                })
+            }
+            _ => None,
+        };
+    }
+
+    /// End the document being loaded, whose end event is at `span`.
+    fn end_document(&mut self, span: Span) {
+        self.metadata
+            .push(core::mem::take(&mut self.current_metadata));
+        self.document_options = None;
+        if let Some(recorder) = &mut self.anchor_recorder {
+            let report = core::mem::take(&mut recorder.current);
+            recorder.reports.push(report);
+        }
+        // The root is complete, as checked by `check_structure`.
+        match self.doc_stack.pop() {
+            Some((mut root, _)) => {
+                if self.options.merge_keys {
+                    // Merged entries move the anchored nodes of the document.
+                    self.copy_anchored_nodes(Some(&mut root));
+                    resolve_merge_keys(&mut root);
+                }
+                self.docs.push(root);
+            }
+            None if self.options.strict => {
+                self.error = Some(LoadError::EmptyDocument {
+                    marker: span.start,
+                    end: span.end,
+                });
+            }
+            // empty document
+            None => self
+                .docs
+                .push(Node::from_bare_yaml(Yaml::BadValue).with_span(span)),
+        }
+    }
+
+    /// Expand the tag handle of the node of `ev`, if it was declared by the current document.
+    fn expand_tag_handles(&self, mut ev: Event) -> Event {
+        if let Event::Scalar(_, _, _, Some(tag))
+        | Event::SequenceStart(_, Some(tag))
+        | Event::MappingStart(_, Some(tag)) = &mut ev
+        {
+            if let Some(prefix) = self.current_metadata.tag_handles.get(&tag.handle) {
+                tag.handle.clone_from(prefix);
+            }
+        }
+        ev
+    }
+
+    /// Clear all the state of the loader, so it can be reused for another stream.
+    ///
+    /// Loaded documents, anchors and anchor reports are dropped, but allocations are kept.
+    /// Settings (e.g. [`Self::record_anchors`]) are kept as well.
+    ///
+    /// ```
+    /// # use saphyr::{Yaml, YamlLoader};
+    /// # use saphyr_parser::{BufferedInput, Parser};
+    /// let mut loader = YamlLoader::<Yaml>::new();
+    /// for source in ["a: 1", "b: 2"] {
+    ///     let mut parser = Parser::new(BufferedInput::new(source.chars()));
+    ///     parser.load(&mut loader, true).unwrap();
+    ///     assert_eq!(loader.documents().len(), 1);
+    ///     loader.reset();
+    /// }
+    /// ```
+    pub fn reset(&mut self) {
+        self.docs.clear();
+        self.doc_starts.clear();
+        self.metadata.clear();
+        self.current_metadata = DocumentMetadata::default();
+        self.directives.clear();
+        self.document_options = None;
+        self.doc_stack.clear();
+        self.key_stack.clear();
+        self.anchor_map.clear();
+        self.has_locations = false;
+        self.collection_starts.clear();
+        self.collection_tags.clear();
+        self.last_end = Marker::default();
+        self.key_sites.clear();
+        self.next_site = None;
+        self.error = None;
+        self.skipping = None;
+        self.totals = Totals::default();
+        self.document_count = 0;
+        self.collection_totals.clear();
+        self.anchor_totals.clear();
+        self.interned_keys.clear();
+        if let Some(recorder) = &mut self.anchor_recorder {
+            recorder.path = YamlPath::new();
+            recorder.current.clear();
+            recorder.reports.clear();
+        }
+    }
+
+    /// Set a filter called on every node before it is inserted in a document.
+    ///
+    /// The filter is given the node and where it is about to be inserted, and can modify it,
+    /// replace it or drop it (see [`FilterAction`]). Collections are given to the filter once all
+    /// of their children went through it. Aliases are filtered after they are resolved, and
+    /// anchors refer to the filtered node, so that an alias cannot reveal what a filter redacted.
+    /// Anchors defined in a dropped node cannot be aliased.
+    ///
+    /// Dropping a mapping key skips its value without ever building it, which keeps the memory
+    /// use of the loader in line with what is kept.
+    ///
+    /// ```
+    /// # use saphyr::{FilterAction, Yaml, YamlLoader};
+    /// # use saphyr_parser::{BufferedInput, Parser};
+    /// let mut loader = YamlLoader::<Yaml>::new();
+    /// loader.node_filter(|node, context| match (context.key, context.is_key) {
+    ///     (Some(Yaml::String(key)), _) if key == "password" => {
+    ///         FilterAction::Replace(Yaml::String("<redacted>".into()))
+    ///     }
+    ///     (_, true) if node.as_str().is_some_and(|key| key.starts_with('_')) => FilterAction::Drop,
+    ///     _ => FilterAction::Keep,
+    /// });
+    /// let source = "user: admin\npassword: hunter2\n_internal: [1, 2]";
+    /// let mut parser = Parser::new(BufferedInput::new(source.chars()));
+    /// parser.load(&mut loader, true).unwrap();
+    ///
+    /// let doc = &loader.documents()[0];
+    /// assert_eq!(doc["password"].as_str(), Some("<redacted>"));
+    /// assert_eq!(doc.as_hash().unwrap().len(), 2);
+    /// ```
+    pub fn node_filter(
+        &mut self,
+        filter: impl FnMut(&mut Node, &NodeContext<Node>) -> FilterAction<Node> + 'static,
+    ) {
+        self.node_filter = Some(Box::new(filter));
+    }
+
+    /// Return the first error the loader itself encountered, if any.
+    ///
+    /// Errors of the parser are returned by [`Parser::load`]. The loader reports the errors that
+    /// depend on its options (e.g. [`LoadError::DuplicateKey`]) here instead. The rest of the
+    /// stream is ignored after such an error, so that nothing more is copied into the documents.
+    #[must_use]
+    pub fn error(&self) -> Option<&LoadError> {
+        self.error.as_ref()
+    }
+
+    /// Fill in the name of the anchor of a [`LoadError::UnknownAlias`] reported by
+    /// [`Self::error`], from `source`, the text the events were parsed from.
+    ///
+    /// The parser only gives the ids of anchors to the loader, so their names are only known
+    /// beforehand when loading from a string, e.g. with [`Yaml::load_from_str_with_options`].
+    /// This reads them back when the loader was fed by a [`Parser`] instead.
+    pub fn name_unknown_alias(&mut self, source: &str) {
+        if let Some(LoadError::UnknownAlias {
+            name: name @ None,
+            marker,
+            end,
+            ..
+        }) = &mut self.error
+        {
+            let span = Span::new(*marker, *end);
+            *name = SourceOffsets::new(source)
+                .anchor_name(&Event::Alias(0), span)
+                .map(ToOwned::to_owned);
+        }
+    }
+
+    /// Take the error the loader encountered, if any.
+    pub(crate) fn take_error(&mut self) -> Result<(), LoadError> {
+        self.error.take().map_or(Ok(()), Err)
+    }
+
+    /// Take the error the loader encountered, if any, as a `ScanError`.
+    ///
+    /// This is for the loading functions which only return `ScanError`s.
+    pub(crate) fn take_scan_error(&mut self) -> Result<(), ScanError> {
+        let Some(error) = self.error.take() else {
+            return Ok(());
+        };
+        let marker = match &error {
+            LoadError::Scan(error) => return Err(error.clone()),
+            LoadError::DuplicateKey { second, .. } => second.start,
+            LoadError::LimitExceeded { marker, .. }
+            | LoadError::InvalidEventStream { marker, .. }
+            | LoadError::UnknownAlias { marker, .. }
+            | LoadError::AnchorForbidden { marker, .. }
+            | LoadError::UnknownTagHandle { marker, .. }
+            | LoadError::TagResolution { marker, .. }
+            | LoadError::InvalidScalar { marker, .. }
+            | LoadError::EmptyDocument { marker, .. } => *marker,
+            #[cfg(feature = "std")]
+            LoadError::Include { marker, .. } | LoadError::IncludeCycle { marker, .. } => *marker,
+            LoadError::InvalidTimestamp { marker, .. } => *marker,
+            #[cfg(feature = "std")]
+            LoadError::IO(_) => unreachable!("not an error of the default loader"),
+            LoadError::Decode(_)
+            | LoadError::ExpectedSingleDocument { .. }
+            | LoadError::EmptyStream
+            | LoadError::Cancelled => unreachable!("not an error of the default loader"),
+        };
+        Err(ScanError::new(marker, error.to_string()))
+    }
+
+    /// Record how anchors and aliases are used in each loaded document.
+    ///
+    /// This is metadata only: aliased nodes are still copied into the loaded documents. Once
+    /// loading is done, the reports can be retrieved with [`Self::anchor_report`].
+    ///
+    /// ```
+    /// # use saphyr::{PathSegment, Yaml, YamlLoader};
+    /// # use saphyr_parser::{BufferedInput, Parser};
+    /// let mut loader = YamlLoader::<Yaml>::default();
+    /// loader.record_anchors(true);
+    /// let mut parser = Parser::new(BufferedInput::new("a: &x 1\nb: *x".chars()));
+    /// parser.load(&mut loader, true).unwrap();
+    ///
+    /// let report = &loader.anchor_report()[0];
+    /// let usage = report.values().next().unwrap();
+    /// assert_eq!(usage.definition.path.to_string(), "/a");
+    /// assert_eq!(usage.aliases[0].path.to_string(), "/b");
+    /// ```
+    pub fn record_anchors(&mut self, record: bool) {
+        self.options.record_anchors = record;
+        self.anchor_recorder = record.then(AnchorRecorder::default);
+    }
+
+    /// Return the anchor reports of the documents loaded so far, in document order.
+    ///
+    /// This is empty unless [`Self::record_anchors`] was enabled before loading.
+    #[must_use]
+    pub fn anchor_report(&self) -> &[AnchorReport] {
+        self.anchor_recorder
+            .as_ref()
+            .map_or(&[], |recorder| recorder.reports.as_slice())
+    }
+
+    /// Return the anchors of each document loaded so far, by name, in document order.
+    ///
+    /// The names are read from `source`, which must be the input the documents were loaded from
+    /// (see [`AnchorUsage::name`]). When a document defines an anchor several times, the last
+    /// definition is kept, as it is the one aliases after it refer to. This is empty unless
+    /// [`Self::record_anchors`] was enabled before loading.
+    #[must_use]
+    pub fn anchors<'a>(&'a self, source: &'a str) -> Vec<BTreeMap<&'a str, &'a AnchorUsage>> {
+        self.anchor_report()
+            .iter()
+            .map(|report| {
+                report
+                    .values()
+                    .filter_map(|usage| Some((usage.name(source)?, usage)))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Return the path segment of the next node to be inserted in the current collection.
+    ///
+    /// Returns `None` if there is no current collection, i.e. if the node is the document root.
+    fn next_child_segment(&mut self) -> Option<PathSegment> {
+        let (parent, _) = self.doc_stack.last_mut()?;
+        if parent.is_array() {
+            Some(PathSegment::Index(parent.array_mut().len()))
+        } else if parent.is_hash() {
+            let cur_key = self.key_stack.last()?;
+            if cur_key.is_badvalue() {
+                Some(PathSegment::EntryKey(parent.hash_mut().len()))
+            } else {
+                Some(PathSegment::Key(cur_key.to_bare_yaml()))
+            }
+        } else {
+            None
+        }
+    }
+
+    /// Return the path of the next node to be inserted in the current collection.
+    ///
+    /// Returns `None` if anchors are not being recorded.
+    fn next_child_path(&mut self) -> Option<YamlPath> {
+        let segment = self.next_child_segment();
+        let path = &self.anchor_recorder.as_ref()?.path;
+        Some(match segment {
+            Some(segment) => path.join(segment),
+            None => path.clone(),
+        })
+    }
+
+    /// Record the definition of an anchor on the next node to be inserted.
+    fn record_anchor(&mut self, aid: usize, span: Span) {
+        if self.anchor_recorder.is_none() {
+            return;
+        }
+        let Some(path) = self.next_child_path() else {
+            return;
+        };
+        if let Some(recorder) = &mut self.anchor_recorder {
+            recorder.current.insert(
+                aid,
+                AnchorUsage {
+                    definition: AnchorSite { path, span },
+                    aliases: vec![],
+                },
+            );
+        }
+    }
+
+    /// Record an alias to the given anchor as the next node to be inserted.
+    fn record_alias(&mut self, aid: usize, span: Span) {
+        if self.anchor_recorder.is_none() {
+            return;
+        }
+        let Some(path) = self.next_child_path() else {
+            return;
+        };
+        if let Some(usage) = self
+            .anchor_recorder
+            .as_mut()
+            .and_then(|recorder| recorder.current.get_mut(&aid))
+        {
+            usage.aliases.push(AnchorSite { path, span });
+        }
+    }
+
+    /// Update anchor bookkeeping when a collection starts.
+    ///
+    /// This must be called before the collection is pushed onto `doc_stack`.
+    fn enter_collection(&mut self, aid: usize, span: Span) {
+        if self.anchor_recorder.is_none() {
+            return;
+        }
+        if aid > 0 {
+            self.record_anchor(aid, span);
+        }
+        let segment = self.next_child_segment();
+        if let (Some(recorder), Some(segment)) = (&mut self.anchor_recorder, segment) {
+            recorder.path.push(segment);
+        }
+    }
+
+    /// Update anchor bookkeeping when a collection ends.
+    ///
+    /// This must be called after the collection is popped from `doc_stack`.
+    fn leave_collection(&mut self) {
+        if let Some(recorder) = &mut self.anchor_recorder {
+            if !self.doc_stack.is_empty() {
+                recorder.path.pop();
             }
         }
     }
 }
 
-impl<Node> YamlLoader<Node>
-where
-    Node: LoadableYamlNode,
-{
-    fn insert_new_node(&mut self, node: (Node, usize)) {
-        // valid anchor id starts from 1
-        if node.1 > 0 {
-            self.anchor_map.insert(node.1, node.0.clone());
+/// Convert `yaml` to a `Node`, with its items.
+fn node_from_yaml<Node: LoadableYamlNode>(yaml: Yaml) -> Node {
+    match yaml {
+        Yaml::Array(items) => {
+            let mut node = Node::from_bare_yaml(Yaml::Array(vec![]));
+            node.array_mut()
+                .extend(items.into_iter().map(node_from_yaml));
+            node
         }
-        if let Some(parent) = self.doc_stack.last_mut() {
-            let parent_node = &mut parent.0;
-            if parent_node.is_array() {
-                parent_node.array_mut().push(node.0);
-            } else if parent_node.is_hash() {
-                let cur_key = self.key_stack.last_mut().unwrap();
-                // current node is a key
-                if cur_key.is_badvalue() {
-                    *cur_key = node.0;
-                // current node is a value
-                } else {
-                    let hash = parent_node.hash_mut();
-                    hash.insert(cur_key.take(), node.0);
-                }
-            }
-        } else {
-            self.doc_stack.push(node);
+        Yaml::Hash(entries) => {
+            let mut node = Node::from_bare_yaml(Yaml::Hash(Hash::new()));
+            node.hash_mut().extend(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (node_from_yaml(key), node_from_yaml(value))),
+            );
+            node
         }
+        Yaml::Tagged(tag, node) => node_from_yaml::<Node>(*node).with_tag(tag),
+        yaml => Node::from_bare_yaml(yaml),
     }
+}
 
-    /// Return the document nodes from `self`, consuming it in the process.
-    #[must_use]
-    pub fn into_documents(self) -> Vec<Node> {
-        self.docs
+/// Return the number of children of `node`.
+fn child_count<Node: LoadableYamlNode>(node: &mut Node) -> usize {
+    if node.is_array() {
+        node.array_mut().len()
+    } else if node.is_hash() {
+        node.hash_mut().len()
+    } else {
+        0
+    }
+}
+
+/// Return the child of `node` at `position`: an item, or the value of an entry.
+fn child<Node: LoadableYamlNode>(node: &mut Node, position: usize) -> Option<&mut Node> {
+    if node.is_array() {
+        node.array_mut().get_mut(position)
+    } else if node.is_hash() {
+        node.hash_mut()
+            .iter_mut()
+            .nth(position)
+            .map(|(_, value)| value)
+    } else {
+        None
+    }
+}
+
+/// Find the node at `positions` in `document`, which is either in `docs` or being built in
+/// `doc_stack` (the last child of which may be `pending`). See [`Anchored::Location`].
+fn locate<'n, Node: LoadableYamlNode>(
+    docs: &'n mut [Node],
+    doc_stack: &'n mut [(Node, usize)],
+    pending: Option<&'n mut Node>,
+    document: usize,
+    positions: &[usize],
+) -> Option<&'n mut Node> {
+    let (mut node, positions) = if let Some(root) = docs.get_mut(document) {
+        (root, positions)
+    } else if doc_stack.is_empty() {
+        (pending?, positions)
+    } else {
+        // Skip the collections which are still open, and thus not in their parent yet.
+        let mut level = 0;
+        while level < doc_stack.len()
+            && positions.get(level) == Some(&child_count(&mut doc_stack[level].0))
+        {
+            level += 1;
+        }
+        if level == doc_stack.len() {
+            (pending?, &positions[level..])
+        } else {
+            (&mut doc_stack[level].0, &positions[level..])
+        }
+    };
+    for &position in positions {
+        node = child(node, position)?;
     }
+    Some(node)
 }
 
 /// An error that happened when loading a YAML document.
+///
+/// Besides the human-readable message given by [`Display`](core::fmt::Display), the error can be
+/// inspected with [`Self::kind`], [`Self::span`] and [`Self::path`], e.g. to report diagnostics in
+/// an editor.
+///
+/// ```
+/// # use saphyr::{DuplicateKeys, LoadErrorKind, LoaderOptions, Yaml};
+/// let options = LoaderOptions {
+///     duplicate_keys: DuplicateKeys::Error,
+///     ..LoaderOptions::default()
+/// };
+/// let error = Yaml::load_from_str_with_options("a:\n  b: 1\n  b: 2", options).unwrap_err();
+/// assert_eq!(error.kind(), LoadErrorKind::DuplicateKey);
+/// assert_eq!(error.kind().code(), "duplicate-key");
+/// assert_eq!(error.path().unwrap().to_string(), "/a");
+/// let span = error.span().unwrap();
+/// assert_eq!((span.start.line(), span.start.col(), span.end.col()), (3, 2, 3));
+/// ```
 #[derive(Debug)]
 pub enum LoadError {
     /// An I/O error.
+    #[cfg(feature = "std")]
     IO(std::io::Error),
     /// An error within the scanner. This indicates a malformed YAML input.
     Scan(ScanError),
     /// A decoding error (e.g.: Invalid UTF-8).
-    Decode(std::borrow::Cow<'static, str>),
+    Decode(alloc::borrow::Cow<'static, str>),
+    /// The stream did not contain exactly one document.
+    ExpectedSingleDocument {
+        /// The number of documents in the stream.
+        found: usize,
+        /// Where the second document of the stream starts, if any.
+        second_document: Option<Marker>,
+    },
+    /// The stream did not contain any document.
+    EmptyStream,
+    /// A mapping contains the same key twice and [`DuplicateKeys::Error`] was requested.
+    DuplicateKey {
+        /// The first occurrence of the key.
+        first: Box<KeyOccurrence>,
+        /// The occurrence of the key that collides with `first`.
+        second: Box<KeyOccurrence>,
+        /// The path to the mapping.
+        path: YamlPath,
+        /// The schema under which the two keys resolved equal.
+        schema: Schema,
+    },
+    /// A node exceeds one of the limits set in the [`LoaderOptions`].
+    LimitExceeded {
+        /// The limit that was exceeded.
+        limit: LoaderLimit,
+        /// The length of the offending node, in bytes, or for the limits on the whole stream (e.g.
+        /// [`LoaderLimit::Nodes`]), the total it would reach.
+        length: usize,
+        /// The maximum allowed by the options.
+        max: usize,
+        /// Where the offending node starts.
+        marker: Marker,
+        /// Where the event of the offending node ends. For collections, this is the end of their
+        /// tag rather than of the whole collection.
+        end: Marker,
+        /// The path to the offending node.
+        path: YamlPath,
+    },
+    /// Loading was cancelled through [`LoaderOptions::cancel`].
+    Cancelled,
+    /// The events given to the loader do not form a well-formed stream, e.g. a mapping ends
+    /// within a sequence. The parser never produces such events, but other sources of events
+    /// may (see [`YamlLoader::from_events`]).
+    InvalidEventStream {
+        /// What is wrong with the event.
+        reason: &'static str,
+        /// Where the offending event starts.
+        marker: Marker,
+        /// Where the offending event ends.
+        end: Marker,
+        /// The path to the collection the event is in.
+        path: YamlPath,
+    },
+    /// An alias cannot be resolved and [`UnknownAliases::Error`] was requested.
+    UnknownAlias {
+        /// The id the parser assigned to the anchor.
+        id: usize,
+        /// The name of the anchor, if the source is known (e.g. with
+        /// [`Yaml::load_from_str_with_options`]). The parser only gives ids to the loader (see
+        /// [`YamlLoader::name_unknown_alias`]).
+        name: Option<String>,
+        /// Where the alias starts.
+        marker: Marker,
+        /// Where the alias ends.
+        end: Marker,
+        /// The path to the alias.
+        path: YamlPath,
+    },
+    /// A node has an anchor, or is an alias, and [`LoaderOptions::forbid_anchors`] was
+    /// requested.
+    AnchorForbidden {
+        /// Whether the node is an alias rather than an anchored node.
+        alias: bool,
+        /// Where the node starts.
+        marker: Marker,
+        /// Where the event of the node ends. For collections, this is the end of their anchor
+        /// and tag rather than of the whole collection.
+        end: Marker,
+        /// The path to the node.
+        path: YamlPath,
+    },
+    /// A tag has a named handle which was not declared by a `%TAG` directive, and
+    /// [`LoaderOptions::strict_tag_handles`] was requested.
+    UnknownTagHandle {
+        /// The handle, e.g. `!e!`.
+        handle: String,
+        /// Where the tagged node starts.
+        marker: Marker,
+        /// Where the event of the tagged node ends.
+        end: Marker,
+        /// The path to the tagged node.
+        path: YamlPath,
+    },
+    /// A source included with [`LoaderOptions::includes`] could not be resolved or loaded.
+    #[cfg(feature = "std")]
+    Include {
+        /// The location of the included source (see [`IncludeResolver::locate`]).
+        ///
+        /// [`IncludeResolver::locate`]: crate::IncludeResolver::locate
+        file: PathBuf,
+        /// The error resolving or loading the source. Its position is within that source.
+        error: Box<LoadError>,
+        /// Where the include starts, in the including source.
+        marker: Marker,
+        /// Where the include ends.
+        end: Marker,
+        /// The path to the include.
+        path: YamlPath,
+    },
+    /// A source included with [`LoaderOptions::includes`] includes itself, directly or not.
+    ///
+    /// Unlike other errors in included sources, this error is not wrapped in
+    /// [`LoadError::Include`]s: it is returned as it is, since `chain` lists the sources.
+    #[cfg(feature = "std")]
+    IncludeCycle {
+        /// The locations of the sources in the cycle, starting and ending with the same one.
+        chain: Vec<PathBuf>,
+        /// Where the include closing the cycle starts, in the last source of `chain` but one.
+        marker: Marker,
+        /// Where the include ends.
+        end: Marker,
+        /// The path to the include.
+        path: YamlPath,
+    },
+    /// The [`LoaderOptions::tag_resolver`] failed to build a tagged node.
+    TagResolution {
+        /// The tag of the node, with its handle expanded.
+        tag: String,
+        /// The error returned by the resolver.
+        error: Box<LoadError>,
+        /// Where the tagged node starts.
+        marker: Marker,
+        /// Where the tagged node ends.
+        end: Marker,
+        /// The path to the tagged node.
+        path: YamlPath,
+    },
+    /// A scalar is invalid for its tag or for the schema, and [`LoaderOptions::strict`] was
+    /// requested.
+    InvalidScalar {
+        /// The scalar as it was written, with its quotes if any.
+        value: String,
+        /// The tag of the scalar, if any, e.g. `!!int`.
+        tag: Option<String>,
+        /// Where the scalar starts.
+        marker: Marker,
+        /// Where the scalar ends.
+        end: Marker,
+        /// The path to the scalar.
+        path: YamlPath,
+    },
+    /// A document has no root node, and [`LoaderOptions::strict`] was requested.
+    EmptyDocument {
+        /// Where the document starts.
+        marker: Marker,
+        /// Where the document ends.
+        end: Marker,
+    },
+    /// A plain scalar tagged `!!timestamp` is not a valid timestamp, and
+    /// [`LoaderOptions::strict_timestamps`] was requested.
+    InvalidTimestamp {
+        /// The contents of the scalar.
+        value: String,
+        /// Where the scalar starts.
+        marker: Marker,
+        /// Where the scalar ends.
+        end: Marker,
+        /// The path to the scalar.
+        path: YamlPath,
+    },
+}
+
+/// The kind of a [`LoadError`], as returned by [`LoadError::kind`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LoadErrorKind {
+    /// [`LoadError::IO`].
+    Io,
+    /// [`LoadError::Scan`]: the input is not valid YAML. The parser does not categorize these
+    /// errors further; see the message of the [`ScanError`] for details.
+    Syntax,
+    /// [`LoadError::Decode`].
+    Decode,
+    /// [`LoadError::ExpectedSingleDocument`].
+    ExpectedSingleDocument,
+    /// [`LoadError::EmptyStream`].
+    EmptyStream,
+    /// [`LoadError::DuplicateKey`].
+    DuplicateKey,
+    /// [`LoadError::LimitExceeded`].
+    LimitExceeded,
+    /// [`LoadError::Cancelled`].
+    Cancelled,
+    /// [`LoadError::InvalidEventStream`].
+    InvalidEventStream,
+    /// [`LoadError::UnknownAlias`].
+    UnknownAlias,
+    /// [`LoadError::AnchorForbidden`].
+    AnchorForbidden,
+    /// [`LoadError::UnknownTagHandle`].
+    UnknownTagHandle,
+    /// [`LoadError::Include`].
+    Include,
+    /// [`LoadError::IncludeCycle`].
+    IncludeCycle,
+    /// [`LoadError::TagResolution`].
+    TagResolution,
+    /// [`LoadError::InvalidScalar`].
+    InvalidScalar,
+    /// [`LoadError::EmptyDocument`].
+    EmptyDocument,
+    /// [`LoadError::InvalidTimestamp`].
+    InvalidTimestamp,
+}
+
+impl LoadErrorKind {
+    /// Return a stable, `kebab-case` identifier of the kind, e.g. `duplicate-key`.
+    #[must_use]
+    pub fn code(self) -> &'static str {
+        match self {
+            LoadErrorKind::Io => "io",
+            LoadErrorKind::Syntax => "syntax",
+            LoadErrorKind::Decode => "decode",
+            LoadErrorKind::ExpectedSingleDocument => "expected-single-document",
+            LoadErrorKind::EmptyStream => "empty-stream",
+            LoadErrorKind::DuplicateKey => "duplicate-key",
+            LoadErrorKind::LimitExceeded => "limit-exceeded",
+            LoadErrorKind::Cancelled => "cancelled",
+            LoadErrorKind::InvalidEventStream => "invalid-event-stream",
+            LoadErrorKind::UnknownAlias => "unknown-alias",
+            LoadErrorKind::AnchorForbidden => "anchor-forbidden",
+            LoadErrorKind::UnknownTagHandle => "unknown-tag-handle",
+            LoadErrorKind::Include => "include",
+            LoadErrorKind::IncludeCycle => "include-cycle",
+            LoadErrorKind::TagResolution => "tag-resolution",
+            LoadErrorKind::InvalidScalar => "invalid-scalar",
+            LoadErrorKind::EmptyDocument => "empty-document",
+            LoadErrorKind::InvalidTimestamp => "invalid-timestamp",
+        }
+    }
+}
+
+impl LoadError {
+    /// Return the kind of the error.
+    #[must_use]
+    pub fn kind(&self) -> LoadErrorKind {
+        match self {
+            #[cfg(feature = "std")]
+            LoadError::IO(_) => LoadErrorKind::Io,
+            LoadError::Scan(_) => LoadErrorKind::Syntax,
+            LoadError::Decode(_) => LoadErrorKind::Decode,
+            LoadError::ExpectedSingleDocument { .. } => LoadErrorKind::ExpectedSingleDocument,
+            LoadError::EmptyStream => LoadErrorKind::EmptyStream,
+            LoadError::DuplicateKey { .. } => LoadErrorKind::DuplicateKey,
+            LoadError::LimitExceeded { .. } => LoadErrorKind::LimitExceeded,
+            LoadError::Cancelled => LoadErrorKind::Cancelled,
+            LoadError::InvalidEventStream { .. } => LoadErrorKind::InvalidEventStream,
+            LoadError::UnknownAlias { .. } => LoadErrorKind::UnknownAlias,
+            LoadError::AnchorForbidden { .. } => LoadErrorKind::AnchorForbidden,
+            LoadError::UnknownTagHandle { .. } => LoadErrorKind::UnknownTagHandle,
+            #[cfg(feature = "std")]
+            LoadError::Include { .. } => LoadErrorKind::Include,
+            #[cfg(feature = "std")]
+            LoadError::IncludeCycle { .. } => LoadErrorKind::IncludeCycle,
+            LoadError::TagResolution { .. } => LoadErrorKind::TagResolution,
+            LoadError::InvalidScalar { .. } => LoadErrorKind::InvalidScalar,
+            LoadError::EmptyDocument { .. } => LoadErrorKind::EmptyDocument,
+            LoadError::InvalidTimestamp { .. } => LoadErrorKind::InvalidTimestamp,
+        }
+    }
+
+    /// Return the range of the input the error is about, if it is known.
+    ///
+    /// Scan errors only have a position, which is both the start and the end of the span. For
+    /// [`LoadError::ExpectedSingleDocument`], this is the start of the second document. For
+    /// [`LoadError::DuplicateKey`], this is the second occurrence of the key.
+    #[must_use]
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            LoadError::Scan(error) => Some(Span::new(*error.marker(), *error.marker())),
+            LoadError::ExpectedSingleDocument {
+                second_document, ..
+            } => second_document.map(|marker| Span::new(marker, marker)),
+            LoadError::DuplicateKey { second, .. } => Some(Span::new(second.start, second.end)),
+            LoadError::LimitExceeded { marker, end, .. }
+            | LoadError::InvalidEventStream { marker, end, .. }
+            | LoadError::UnknownAlias { marker, end, .. }
+            | LoadError::AnchorForbidden { marker, end, .. }
+            | LoadError::UnknownTagHandle { marker, end, .. }
+            | LoadError::TagResolution { marker, end, .. }
+            | LoadError::InvalidScalar { marker, end, .. }
+            | LoadError::EmptyDocument { marker, end } => Some(Span::new(*marker, *end)),
+            #[cfg(feature = "std")]
+            LoadError::Include { marker, end, .. }
+            | LoadError::IncludeCycle { marker, end, .. } => Some(Span::new(*marker, *end)),
+            LoadError::InvalidTimestamp { marker, end, .. } => Some(Span::new(*marker, *end)),
+            #[cfg(feature = "std")]
+            LoadError::IO(_) => None,
+            LoadError::Decode(_) | LoadError::EmptyStream | LoadError::Cancelled => None,
+        }
+    }
+
+    /// Return the path to the node the error is about, within its document, if it is known.
+    ///
+    /// For [`LoadError::DuplicateKey`], this is the path to the mapping.
+    #[must_use]
+    pub fn path(&self) -> Option<&YamlPath> {
+        match self {
+            LoadError::DuplicateKey { path, .. }
+            | LoadError::LimitExceeded { path, .. }
+            | LoadError::InvalidEventStream { path, .. }
+            | LoadError::UnknownAlias { path, .. }
+            | LoadError::AnchorForbidden { path, .. }
+            | LoadError::UnknownTagHandle { path, .. }
+            | LoadError::TagResolution { path, .. }
+            | LoadError::InvalidScalar { path, .. } => Some(path),
+            #[cfg(feature = "std")]
+            LoadError::Include { path, .. } | LoadError::IncludeCycle { path, .. } => Some(path),
+            LoadError::InvalidTimestamp { path, .. } => Some(path),
+            #[cfg(feature = "std")]
+            LoadError::IO(_) => None,
+            LoadError::Scan(_)
+            | LoadError::Decode(_)
+            | LoadError::ExpectedSingleDocument { .. }
+            | LoadError::EmptyStream
+            | LoadError::EmptyDocument { .. }
+            | LoadError::Cancelled => None,
+        }
+    }
 }
 
+#[cfg(feature = "std")]
 impl From<std::io::Error> for LoadError {
     fn from(error: std::io::Error) -> Self {
         LoadError::IO(error)
     }
 }
 
+#[cfg(feature = "std")]
 impl std::error::Error for LoadError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         Some(match &self {
             LoadError::IO(e) => e,
             LoadError::Scan(e) => e,
-            LoadError::Decode(_) => return None,
+            LoadError::Include { error, .. } | LoadError::TagResolution { error, .. } => {
+                error.as_ref()
+            }
+            LoadError::Decode(_)
+            | LoadError::ExpectedSingleDocument { .. }
+            | LoadError::EmptyStream
+            | LoadError::DuplicateKey { .. }
+            | LoadError::LimitExceeded { .. }
+            | LoadError::Cancelled
+            | LoadError::InvalidEventStream { .. }
+            | LoadError::UnknownAlias { .. }
+            | LoadError::AnchorForbidden { .. }
+            | LoadError::UnknownTagHandle { .. }
+            | LoadError::IncludeCycle { .. }
+            | LoadError::InvalidScalar { .. }
+            | LoadError::EmptyDocument { .. }
+            | LoadError::InvalidTimestamp { .. } => return None,
         })
     }
 }
 
-impl std::fmt::Display for LoadError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
+            #[cfg(feature = "std")]
             LoadError::IO(e) => e.fmt(f),
             LoadError::Scan(e) => e.fmt(f),
             LoadError::Decode(e) => e.fmt(f),
+            LoadError::ExpectedSingleDocument {
+                found,
+                second_document,
+            } => {
+                write!(f, "expected a single document, found {found}")?;
+                if let Some(marker) = second_document {
+                    write!(
+                        f,
+                        ", second document at byte {} line {} column {}",
+                        marker.index(),
+                        marker.line(),
+                        marker.col() + 1,
+                    )?;
+                }
+                Ok(())
+            }
+            LoadError::EmptyStream => f.write_str("expected at least one document, found none"),
+            LoadError::Cancelled => f.write_str("loading was cancelled"),
+            LoadError::InvalidEventStream { reason, marker, .. } => write!(
+                f,
+                "invalid event stream at line {} column {}: {reason}",
+                marker.line(),
+                marker.col() + 1,
+            ),
+            LoadError::DuplicateKey {
+                first,
+                second,
+                schema,
+                ..
+            } => write!(
+                f,
+                "key `{}` ({}) at line {} column {} collides with key `{}` ({}) at line {} \
+                 column {}, resolved equal under the `{}` schema",
+                second.source,
+                second.resolved.kind(),
+                second.start.line(),
+                second.start.col() + 1,
+                first.source,
+                first.resolved.kind(),
+                first.start.line(),
+                first.start.col() + 1,
+                schema.name(),
+            ),
+            LoadError::LimitExceeded {
+                limit,
+                length,
+                max,
+                marker,
+                ..
+            } => write!(
+                f,
+                "{limit} of {length} {} at line {} column {} exceeds the maximum of {max}",
+                limit.unit(),
+                marker.line(),
+                marker.col() + 1,
+            ),
+            LoadError::UnknownAlias { .. } | LoadError::AnchorForbidden { .. } => {
+                self.fmt_anchor(f)
+            }
+            LoadError::UnknownTagHandle { handle, marker, .. } => write!(
+                f,
+                "tag handle `{handle}` at line {} column {} was not declared by a `%TAG` \
+                 directive",
+                marker.line(),
+                marker.col() + 1,
+            ),
+            #[cfg(feature = "std")]
+            LoadError::Include { .. } | LoadError::IncludeCycle { .. } => self.fmt_include(f),
+            LoadError::TagResolution {
+                tag, error, marker, ..
+            } => write!(
+                f,
+                "could not resolve the node tagged `{tag}` at line {} column {}: {error}",
+                marker.line(),
+                marker.col() + 1,
+            ),
+            LoadError::InvalidScalar { .. } | LoadError::EmptyDocument { .. } => self.fmt_strict(f),
+            LoadError::InvalidTimestamp { value, marker, .. } => write!(
+                f,
+                "`{value}` at line {} column {} is tagged `!!timestamp` but is not a valid \
+                 timestamp",
+                marker.line(),
+                marker.col() + 1,
+            ),
+        }
+    }
+}
+
+impl LoadError {
+    /// Write the message of [`LoadError::Include`] and [`LoadError::IncludeCycle`].
+    #[cfg(feature = "std")]
+    fn fmt_include(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            LoadError::Include {
+                file,
+                error,
+                marker,
+                ..
+            } => write!(
+                f,
+                "in `{}` included at line {} column {}: {error}",
+                file.display(),
+                marker.line(),
+                marker.col() + 1,
+            ),
+            LoadError::IncludeCycle { chain, marker, .. } => write!(
+                f,
+                "include at line {} column {} forms a cycle: {}",
+                marker.line(),
+                marker.col() + 1,
+                chain
+                    .iter()
+                    .map(|file| format!("`{}`", file.display()))
+                    .collect::<Vec<_>>()
+                    .join(" -> "),
+            ),
+            _ => unreachable!("not an include error"),
+        }
+    }
+
+    /// Write the message of [`LoadError::UnknownAlias`] and [`LoadError::AnchorForbidden`].
+    fn fmt_anchor(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            LoadError::UnknownAlias { name, marker, .. } => {
+                f.write_str("alias ")?;
+                if let Some(name) = name {
+                    write!(f, "`*{name}` ")?;
+                }
+                write!(
+                    f,
+                    "at line {} column {} does not refer to a complete anchored node",
+                    marker.line(),
+                    marker.col() + 1,
+                )
+            }
+            LoadError::AnchorForbidden { alias, marker, .. } => write!(
+                f,
+                "{} at line {} column {} is not allowed",
+                if *alias { "alias" } else { "anchor" },
+                marker.line(),
+                marker.col() + 1,
+            ),
+            _ => unreachable!("not an anchor error"),
+        }
+    }
+
+    /// Write the message of the errors of [`LoaderOptions::strict`].
+    fn fmt_strict(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            LoadError::InvalidScalar {
+                value, tag, marker, ..
+            } => {
+                write!(
+                    f,
+                    "`{value}` at line {} column {} ",
+                    marker.line(),
+                    marker.col() + 1,
+                )?;
+                match tag {
+                    Some(tag) => write!(f, "is not a valid `{tag}`"),
+                    None => f.write_str("is not valid for the schema"),
+                }
+            }
+            LoadError::EmptyDocument { marker, .. } => write!(
+                f,
+                "document at line {} column {} has no root node",
+                marker.line(),
+                marker.col() + 1,
+            ),
+            _ => unreachable!("not an error of the strict mode"),
+        }
+    }
+}
+
+/// Load the documents of `source` with the given options, returning the loader holding them.
+pub(crate) fn load_with_options<Node>(
+    source: &str,
+    options: LoaderOptions,
+) -> Result<YamlLoader<Node>, LoadError>
+where
+    Node: LoadableYamlNode,
+{
+    load_into(YamlLoader::with_options(options), source)
+}
+
+/// Load the documents of `source` with `loader`, returning it.
+fn load_into<Node>(
+    mut loader: YamlLoader<Node>,
+    source: &str,
+) -> Result<YamlLoader<Node>, LoadError>
+where
+    Node: LoadableYamlNode,
+{
+    loader.read_directives(source);
+    load_chars(&mut loader, source.chars(), Some(source))?;
+    Ok(loader)
+}
+
+/// Load the documents of the stream of `chars` with `loader`.
+///
+/// `source` is the text of `chars`, if it is known, to read anchor names from.
+fn load_chars<Node>(
+    loader: &mut YamlLoader<Node>,
+    chars: impl Iterator<Item = char>,
+    source: Option<&str>,
+) -> Result<(), LoadError>
+where
+    Node: LoadableYamlNode,
+{
+    let mut offsets = source.map(SourceOffsets::new);
+    // Drive the parser here rather than with `Parser::load`, to stop at the first error.
+    for event in Parser::new(BufferedInput::new(chars)) {
+        let (event, span) = event.map_err(LoadError::Scan)?;
+        if let Some(offsets) = &mut offsets {
+            loader.check_anchor_length(&event, span, offsets)?;
+            loader.read_alias_name(&event, span, offsets);
+        }
+        loader.on_event(event, span);
+        loader.take_error()?;
+    }
+    Ok(())
+}
+
+/// Load the documents of the stream read from `reader`, decoding it as it is read.
+#[cfg(feature = "std")]
+pub(crate) fn load_from_reader<R: Read>(reader: R) -> Result<Vec<Yaml>, LoadError> {
+    let mut chars = ReaderChars::new(reader)?;
+    let mut loader = YamlLoader::default();
+    let result = load_chars(&mut loader, &mut chars, None);
+    // Reading stops at the first read or decoding error, which may leave the parser with
+    // truncated input: report the cause rather than the syntax error.
+    if let Some(error) = chars.error {
+        return Err(error);
+    }
+    result?;
+    Ok(loader.into_documents())
+}
+
+/// The Unicode encodings of YAML streams.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum StreamEncoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Utf32Le,
+    Utf32Be,
+}
+
+#[cfg(feature = "std")]
+impl StreamEncoding {
+    /// Detect the encoding of a stream from its first bytes, as the YAML specification describes,
+    /// and return it along with the length of the byte order mark, if any.
+    ///
+    /// Without a byte order mark, the encoding is deduced from the pattern of null bytes of the
+    /// first character, which must be ASCII. See
+    /// <https://yaml.org/spec/1.2.2/#52-character-encodings>.
+    fn detect(prefix: &[u8]) -> (Self, usize) {
+        match prefix {
+            [0, 0, 0xFE, 0xFF, ..] => (Self::Utf32Be, 4),
+            [0, 0, 0, _, ..] => (Self::Utf32Be, 0),
+            [0xFF, 0xFE, 0, 0, ..] => (Self::Utf32Le, 4),
+            [_, 0, 0, 0, ..] => (Self::Utf32Le, 0),
+            [0xFE, 0xFF, ..] => (Self::Utf16Be, 2),
+            [0, _, ..] => (Self::Utf16Be, 0),
+            [0xFF, 0xFE, ..] => (Self::Utf16Le, 2),
+            [_, 0, ..] => (Self::Utf16Le, 0),
+            [0xEF, 0xBB, 0xBF, ..] => (Self::Utf8, 3),
+            _ => (Self::Utf8, 0),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Utf8 => "UTF-8",
+            Self::Utf16Le => "UTF-16LE",
+            Self::Utf16Be => "UTF-16BE",
+            Self::Utf32Le => "UTF-32LE",
+            Self::Utf32Be => "UTF-32BE",
+        }
+    }
+}
+
+/// The characters of a stream read from a reader, in the encoding detected from its first bytes.
+///
+/// The iterator ends at the first read or decoding error, which is then kept in `error`.
+#[cfg(feature = "std")]
+struct ReaderChars<R: Read> {
+    bytes: std::io::Bytes<std::io::BufReader<std::io::Chain<std::io::Cursor<Vec<u8>>, R>>>,
+    encoding: StreamEncoding,
+    /// The number of bytes read, byte order mark included.
+    offset: usize,
+    error: Option<LoadError>,
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> ReaderChars<R> {
+    /// Read the first bytes of `reader` to detect its encoding, and skip its byte order mark.
+    fn new(mut reader: R) -> Result<Self, LoadError> {
+        let mut prefix = Vec::with_capacity(4);
+        reader.by_ref().take(4).read_to_end(&mut prefix)?;
+        let (encoding, bom) = StreamEncoding::detect(&prefix);
+        prefix.drain(..bom);
+        Ok(Self {
+            bytes: std::io::BufReader::new(std::io::Cursor::new(prefix).chain(reader)).bytes(),
+            encoding,
+            offset: bom,
+            error: None,
+        })
+    }
+
+    /// Read the next byte, if the stream has not ended.
+    fn byte(&mut self) -> Result<Option<u8>, LoadError> {
+        let byte = self.bytes.next().transpose()?;
+        self.offset += usize::from(byte.is_some());
+        Ok(byte)
+    }
+
+    /// Read the next code unit of `size` bytes of a UTF-16 or UTF-32 stream, if the stream has
+    /// not ended.
+    fn unit(&mut self, size: usize) -> Result<Option<u32>, LoadError> {
+        let big_endian = matches!(
+            self.encoding,
+            StreamEncoding::Utf16Be | StreamEncoding::Utf32Be
+        );
+        let mut unit = 0;
+        for i in 0..size {
+            let Some(byte) = self.byte()? else {
+                return if i == 0 {
+                    Ok(None)
+                } else {
+                    Err(self.invalid(i))
+                };
+            };
+            unit = if big_endian {
+                unit << 8 | u32::from(byte)
+            } else {
+                unit | u32::from(byte) << (8 * i)
+            };
+        }
+        Ok(Some(unit))
+    }
+
+    /// Decode the next character, if the stream has not ended.
+    fn decode(&mut self) -> Result<Option<char>, LoadError> {
+        match self.encoding {
+            StreamEncoding::Utf8 => {
+                let Some(first) = self.byte()? else {
+                    return Ok(None);
+                };
+                let len = match first {
+                    0..=0x7F => return Ok(Some(char::from(first))),
+                    0xC0..=0xDF => 2,
+                    0xE0..=0xEF => 3,
+                    0xF0..=0xF7 => 4,
+                    _ => return Err(self.invalid(1)),
+                };
+                let mut sequence = [first, 0, 0, 0];
+                for (i, byte) in sequence.iter_mut().enumerate().take(len).skip(1) {
+                    *byte = self.byte()?.ok_or_else(|| self.invalid(i))?;
+                }
+                core::str::from_utf8(&sequence[..len])
+                    .ok()
+                    .and_then(|sequence| sequence.chars().next())
+                    .map(Some)
+                    .ok_or_else(|| self.invalid(len))
+            }
+            StreamEncoding::Utf16Le | StreamEncoding::Utf16Be => {
+                let Some(first) = self.unit(2)? else {
+                    return Ok(None);
+                };
+                let c = if (0xD800..0xDC00).contains(&first) {
+                    // A high surrogate, which must be followed by a low one.
+                    let second = self.unit(2)?.ok_or_else(|| self.invalid(2))?;
+                    if !(0xDC00..0xE000).contains(&second) {
+                        return Err(self.invalid(4));
+                    }
+                    char::from_u32(0x10000 + ((first - 0xD800) << 10) + (second - 0xDC00))
+                } else {
+                    char::from_u32(first)
+                };
+                c.map(Some).ok_or_else(|| self.invalid(2))
+            }
+            StreamEncoding::Utf32Le | StreamEncoding::Utf32Be => match self.unit(4)? {
+                None => Ok(None),
+                Some(unit) => char::from_u32(unit)
+                    .map(Some)
+                    .ok_or_else(|| self.invalid(4)),
+            },
+        }
+    }
+
+    /// Return the error for an invalid sequence, whose last `len` bytes were read.
+    fn invalid(&self, len: usize) -> LoadError {
+        LoadError::Decode(
+            format!(
+                "invalid {} sequence at byte {}",
+                self.encoding.name(),
+                self.offset - len
+            )
+            .into(),
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> Iterator for ReaderChars<R> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        if self.error.is_some() {
+            return None;
+        }
+        self.decode().unwrap_or_else(|error| {
+            self.error = Some(error);
+            None
+        })
+    }
+}
+
+impl<Node> YamlLoader<Node>
+where
+    Node: LoadableYamlNode,
+{
+    /// Load documents from a stream of events, e.g. from another parser or from
+    /// [`Yaml::into_events`], with the default options.
+    ///
+    /// The events must form a well-formed stream, as the parser produces them:
+    /// `StreamStart`, then each document between `DocumentStart` and `DocumentEnd`, with balanced
+    /// collections, then `StreamEnd`.
+    ///
+    /// ```
+    /// # use saphyr::{Event, Yaml, YamlLoader};
+    /// // Add a key to every mapping of the document as it streams through.
+    /// let doc = Yaml::load_one_from_str("a: {b: 1}").unwrap();
+    /// let events = doc.into_events().flat_map(|ev| match ev {
+    ///     Event::MappingStart(..) => {
+    ///         let key = Event::Scalar("seen".into(), saphyr::TScalarStyle::Plain, 0, None);
+    ///         let value = Event::Scalar("true".into(), saphyr::TScalarStyle::Plain, 0, None);
+    ///         vec![ev, key, value]
+    ///     }
+    ///     ev => vec![ev],
+    /// });
+    /// let docs = YamlLoader::<Yaml>::from_events(events.map(|ev| (ev, Default::default())));
+    /// let expected = Yaml::load_one_from_str("{seen: true, a: {seen: true, b: 1}}").unwrap();
+    /// assert_eq!(docs.unwrap(), [expected]);
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error reported by the loader (see [`YamlLoader::error`]), and
+    /// [`LoadError::InvalidEventStream`] if the events do not form a well-formed stream.
+    pub fn from_events(
+        events: impl IntoIterator<Item = (Event, Span)>,
+    ) -> Result<Vec<Node>, LoadError> {
+        let mut loader = YamlLoader::default();
+        for (event, span) in events {
+            loader.on_event(event, span);
+            loader.take_error()?;
         }
+        Ok(loader.into_documents())
+    }
+}
+
+/// Load the documents from `parser`, failing if there are none.
+pub(crate) fn load_non_empty<Node, I>(parser: &mut Parser<I>) -> Result<Vec<Node>, LoadError>
+where
+    Node: LoadableYamlNode,
+    I: Input,
+{
+    let mut loader = YamlLoader::default();
+    parser.load(&mut loader, true).map_err(LoadError::Scan)?;
+    loader.take_error()?;
+    if loader.docs.is_empty() {
+        Err(LoadError::EmptyStream)
+    } else {
+        Ok(loader.into_documents())
+    }
+}
+
+/// Load the only document from `parser`, failing if there is not exactly one.
+pub(crate) fn load_single<Node, I>(parser: &mut Parser<I>) -> Result<Node, LoadError>
+where
+    Node: LoadableYamlNode,
+    I: Input,
+{
+    let mut loader = YamlLoader::default();
+    parser.load(&mut loader, true).map_err(LoadError::Scan)?;
+    loader.take_error()?;
+    if loader.docs.len() == 1 {
+        Ok(loader.docs.pop().unwrap())
+    } else {
+        Err(LoadError::ExpectedSingleDocument {
+            found: loader.docs.len(),
+            second_document: loader.doc_starts.get(1).copied(),
+        })
     }
 }
 
@@ -212,7 +3144,7 @@ impl std::fmt::Display for LoadError {
 ///
 /// This trait must be implemented on YAML node types (i.e.: [`Yaml`] and annotated YAML nodes). It
 /// provides the necessary methods for [`YamlLoader`] to load data into the node.
-pub trait LoadableYamlNode: Clone + std::hash::Hash + Eq {
+pub trait LoadableYamlNode: Clone + core::hash::Hash + Eq {
     /// Create an instance of `Self` from a [`Yaml`].
     ///
     /// Nodes must implement this to be built. The optional metadata that they contain will be
@@ -250,25 +3182,87 @@ pub trait LoadableYamlNode: Clone + std::hash::Hash + Eq {
     #[must_use]
     fn take(&mut self) -> Self;
 
+    /// Create a scalar node, given how it was written.
+    ///
+    /// The default implementation resolves the scalar to a [`Yaml`] node with
+    /// [`LoaderOptions::resolve_scalar`] and converts it with [`Self::from_bare_yaml`]. Node
+    /// types may override this to build scalars directly, e.g. to intern strings, or to keep
+    /// their tag.
+    #[must_use]
+    fn from_scalar(
+        value: String,
+        style: TScalarStyle,
+        tag: Option<&Tag>,
+        options: &LoaderOptions,
+    ) -> Self {
+        Self::from_bare_yaml(options.resolve_scalar(value, style, tag))
+    }
+
+    /// Create an empty sequence, to which the loader then adds the items.
+    ///
+    /// The default implementation converts an empty [`Yaml::Array`] with
+    /// [`Self::from_bare_yaml`]. Node types may override this to keep the tag of the sequence.
+    #[must_use]
+    fn from_sequence_start(_: Option<&Tag>) -> Self {
+        Self::from_bare_yaml(Yaml::Array(Vec::new()))
+    }
+
+    /// Create an empty mapping, to which the loader then adds the entries.
+    ///
+    /// The default implementation converts an empty [`Yaml::Hash`] with
+    /// [`Self::from_bare_yaml`]. Node types may override this to keep the tag of the mapping.
+    #[must_use]
+    fn from_mapping_start(_: Option<&Tag>) -> Self {
+        Self::from_bare_yaml(Yaml::Hash(Hash::new()))
+    }
+
+    /// Return a copy of the node as a [`Yaml`], stripped of any metadata.
+    ///
+    /// The loader uses this to describe paths to nodes (e.g.: in [`AnchorReport`]s). The default
+    /// implementation returns [`Yaml::BadValue`].
+    #[must_use]
+    fn to_bare_yaml(&self) -> Yaml {
+        Yaml::BadValue
+    }
+
     /// Provide the marker for the node (builder-style).
     #[inline]
     #[must_use]
     fn with_span(self, _: Span) -> Self {
         self
     }
+
+    /// Provide the tag of the node, if the loader keeps it (builder-style).
+    ///
+    /// The loader calls this once the node is complete, with the tags it does not resolve itself,
+    /// if [`LoaderOptions::keep_tags`] is set. The default implementation drops the tag.
+    #[inline]
+    #[must_use]
+    fn with_tag(self, _: Tag) -> Self {
+        self
+    }
+
+    /// Make the text of the node shared with that of the other nodes in `strings`, if the node is a
+    /// string, adding it to `strings` if it is not there yet.
+    ///
+    /// The loader calls this on mapping keys if [`LoaderOptions::intern_keys`] is set. The default
+    /// implementation keeps the node as it is.
+    #[inline]
+    fn intern(&mut self, _strings: &mut BTreeSet<Arc<str>>) {}
 }
 
+// Tagged collections are collections to the loader, e.g. to locate the anchors within them.
 impl LoadableYamlNode for Yaml {
     fn from_bare_yaml(yaml: Yaml) -> Self {
         yaml
     }
 
     fn is_array(&self) -> bool {
-        matches!(self, Yaml::Array(_))
+        matches!(self.untagged(), Yaml::Array(_))
     }
 
     fn is_hash(&self) -> bool {
-        matches!(self, Yaml::Hash(_))
+        matches!(self.untagged(), Yaml::Hash(_))
     }
 
     fn is_badvalue(&self) -> bool {
@@ -276,26 +3270,198 @@ impl LoadableYamlNode for Yaml {
     }
 
     fn array_mut(&mut self) -> &mut Vec<Self> {
-        if let Yaml::Array(x) = self {
-            x
-        } else {
-            panic!("Called array_mut on a non-array");
+        match self {
+            Yaml::Array(x) => x,
+            Yaml::Tagged(_, node) => node.array_mut(),
+            _ => panic!("Called array_mut on a non-array"),
         }
     }
 
     fn hash_mut(&mut self) -> &mut LinkedHashMap<Self, Self> {
-        if let Yaml::Hash(x) = self {
-            x
-        } else {
-            panic!("Called hash_mut on a non-hash");
+        match self {
+            Yaml::Hash(x) => x,
+            Yaml::Tagged(_, node) => node.hash_mut(),
+            _ => panic!("Called hash_mut on a non-hash"),
         }
     }
 
     fn take(&mut self) -> Self {
         let mut taken_out = Yaml::BadValue;
-        std::mem::swap(&mut taken_out, self);
+        core::mem::swap(&mut taken_out, self);
         taken_out
     }
+
+    fn to_bare_yaml(&self) -> Yaml {
+        self.clone()
+    }
+
+    fn with_tag(self, tag: Tag) -> Self {
+        Yaml::Tagged(tag, Box::new(self))
+    }
+
+    fn intern(&mut self, strings: &mut BTreeSet<Arc<str>>) {
+        if let Yaml::String(text) = self {
+            let shared = if let Some(shared) = strings.get(text.as_str()) {
+                Arc::clone(shared)
+            } else {
+                let shared: Arc<str> = Arc::from(text.as_str());
+                strings.insert(Arc::clone(&shared));
+                shared
+            };
+            *self = Yaml::SharedString(shared);
+        }
+    }
+}
+
+/// Whether the loader resolves nodes with `tag` itself, rather than keeping the tag (see
+/// [`LoaderOptions::keep_tags`]).
+fn is_resolved_tag(tag: &Tag) -> bool {
+    match tag.handle.as_str() {
+        "tag:yaml.org,2002:" => {
+            matches!(
+                tag.suffix.as_str(),
+                "str" | "int" | "float" | "bool" | "null" | "seq" | "map"
+            ) || (cfg!(feature = "timestamps") && tag.suffix == "timestamp")
+                || (cfg!(feature = "binary") && tag.suffix == "binary")
+        }
+        // The non-specific tag, e.g. `! 12`, makes a string.
+        "!" => tag.suffix.is_empty(),
+        _ => false,
+    }
+}
+
+/// Resolve a scalar to a [`Yaml`] node, according to its style and tag.
+fn resolve_scalar(
+    v: String,
+    style: TScalarStyle,
+    tag: Option<&Tag>,
+    options: &LoaderOptions,
+) -> Yaml {
+    #[cfg(feature = "floats")]
+    let tagged_float =
+        tag.is_some_and(|tag| tag.handle == "tag:yaml.org,2002:" && tag.suffix == "float");
+    let yaml = resolve_scalar_type(v, style, tag, options);
+    #[cfg(feature = "floats")]
+    if let (Yaml::Real(v), true) = (&yaml, options.parse_floats) {
+        // Integers too large for an `i64` keep their exact text, unless tagged as floats.
+        if !tagged_float && schema::is_large_integer(v) {
+            return yaml;
+        }
+        return parse_f64(v).map_or(Yaml::BadValue, |value| {
+            Yaml::Float(crate::Float {
+                value,
+                source: options.keep_float_sources.then(|| v.clone()),
+            })
+        });
+    }
+    yaml
+}
+
+/// Resolve a scalar to a [`Yaml`] node of the type given by its style and tag, or by the schema.
+#[cfg_attr(not(feature = "timestamps"), allow(unused_variables))]
+fn resolve_scalar_type(
+    v: String,
+    style: TScalarStyle,
+    tag: Option<&Tag>,
+    options: &LoaderOptions,
+) -> Yaml {
+    // Binary scalars are usually block scalars, to split them on several lines.
+    #[cfg(feature = "binary")]
+    if let Some(Tag { handle, suffix }) = tag {
+        if handle == "tag:yaml.org,2002:" && suffix == "binary" {
+            return crate::binary::decode(&v).map_or(Yaml::BadValue, Yaml::Binary);
+        }
+    }
+    if style != TScalarStyle::Plain {
+        Yaml::String(v)
+    } else if let Some(Tag { handle, suffix }) = tag {
+        if handle == "tag:yaml.org,2002:" {
+            match suffix.as_ref() {
+                "bool" if options.schema == Schema::Yaml11 => {
+                    schema::parse_bool_11(&v).map_or(Yaml::BadValue, Yaml::Boolean)
+                }
+                "bool" => {
+                    // "true" or "false"
+                    match v.parse::<bool>() {
+                        Err(_) => Yaml::BadValue,
+                        Ok(v) => Yaml::Boolean(v),
+                    }
+                }
+                "int" => {
+                    let integer = match options.schema {
+                        Schema::Yaml11 => schema::parse_int_11(&v),
+                        Schema::Json => schema::parse_int_json(&v),
+                        Schema::Core | Schema::Failsafe => v.parse::<i64>().ok(),
+                    };
+                    match integer {
+                        Some(integer) => Yaml::Integer(integer),
+                        // Keep the text of integers too large for an `i64`, as for untagged ones.
+                        None if schema::is_large_integer(&v)
+                            && (options.schema != Schema::Json || schema::is_float_json(&v)) =>
+                        {
+                            Yaml::Real(v)
+                        }
+                        None => Yaml::BadValue,
+                    }
+                }
+                "null" if options.schema == Schema::Yaml11 => {
+                    if schema::is_null_11(&v) {
+                        Yaml::Null
+                    } else {
+                        Yaml::BadValue
+                    }
+                }
+                "float" if options.schema == Schema::Json => {
+                    if schema::is_float_json(&v) {
+                        Yaml::Real(v)
+                    } else {
+                        Yaml::BadValue
+                    }
+                }
+                "null" if options.schema == Schema::Json => {
+                    if v == "null" {
+                        Yaml::Null
+                    } else {
+                        Yaml::BadValue
+                    }
+                }
+                "float" => match parse_f64(&v) {
+                    Some(_) => Yaml::Real(v),
+                    None => Yaml::BadValue,
+                },
+                "null" => match v.as_ref() {
+                    "~" | "null" => Yaml::Null,
+                    _ => Yaml::BadValue,
+                },
+                #[cfg(feature = "timestamps")]
+                "timestamp" => match crate::Timestamp::parse(&v) {
+                    Some(timestamp) => Yaml::Timestamp(timestamp),
+                    None => Yaml::String(v),
+                },
+                _ => Yaml::String(v),
+            }
+        } else {
+            Yaml::String(v)
+        }
+    } else {
+        #[cfg(feature = "timestamps")]
+        if options.resolve_timestamps {
+            if let Some(timestamp) = crate::Timestamp::parse(&v) {
+                return Yaml::Timestamp(timestamp);
+            }
+        }
+        // Datatype is not specified, or unrecognized
+        options.schema.resolve(&v)
+    }
+}
+
+/// Write back a scalar in the style it was written with, for error messages.
+fn written_scalar(v: &str, style: TScalarStyle) -> String {
+    match style {
+        TScalarStyle::SingleQuoted => format!("'{}'", v.replace('\'', "''")),
+        TScalarStyle::DoubleQuoted => format!("{v:?}"),
+        _ => v.to_owned(),
+    }
 }
 
 // parse f64 as Core schema
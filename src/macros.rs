@@ -83,3 +83,130 @@ pub fn $fn_name(&self) -> bool {
 }
     );
 );
+
+/// Build a [`Yaml`](crate::Yaml) node from a JSON-like literal.
+///
+/// `null` is [`Yaml::Null`](crate::Yaml::Null), `[...]` a sequence and `{...}` a mapping, and
+/// any other value is converted with [`From`] (e.g. `i64`, `f64`, `bool`, `&str` or an existing
+/// `Yaml`), so that literals and Rust expressions can be mixed. Keys are converted the same way.
+///
+/// ```
+/// # use saphyr::{yaml, Yaml};
+/// let name = "web";
+/// let doc = yaml!({
+///     "name": name,
+///     "ports": [80, 443],
+///     "tls": true,
+///     "weight": 0.5,
+///     "backup": null,
+///     "tags": { "env": format!("{name}-prod") },
+/// });
+/// assert_eq!(doc["ports"][1].as_i64(), Some(443));
+/// assert_eq!(doc["weight"], Yaml::Real("0.5".into()));
+/// assert_eq!(doc["tags"]["env"].as_str(), Some("web-prod"));
+/// ```
+///
+/// Entries must be separated by commas, and keys followed by a colon and a value:
+///
+/// ```compile_fail
+/// # use saphyr::yaml;
+/// let doc = yaml!([1 2]);
+/// ```
+///
+/// ```compile_fail
+/// # use saphyr::yaml;
+/// let doc = yaml!({ "a": 1, "b" });
+/// ```
+///
+/// ```compile_fail
+/// # use saphyr::yaml;
+/// let doc = yaml!({ "a": , "b": 2 });
+/// ```
+#[macro_export]
+macro_rules! yaml {
+    (null) => {
+        $crate::Yaml::Null
+    };
+    ([]) => {
+        $crate::Yaml::Array($crate::Array::new())
+    };
+    ([ $($tt:tt)+ ]) => {
+        $crate::Yaml::Array($crate::__yaml_array!([] $($tt)+))
+    };
+    ({}) => {
+        $crate::Yaml::Hash($crate::Hash::new())
+    };
+    ({ $($tt:tt)+ }) => {
+        $crate::Yaml::Hash({
+            let mut hash = $crate::Hash::new();
+            $crate::__yaml_hash!(hash () ($($tt)+));
+            hash
+        })
+    };
+    ($other:expr) => {
+        $crate::Yaml::from($other)
+    };
+}
+
+/// Build the items of a sequence for [`yaml!`], one item at a time.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __yaml_array {
+    ([ $($items:expr,)* ]) => {
+        $crate::__private::vec![$($items,)*]
+    };
+    ([ $($items:expr,)* ] null $(, $($rest:tt)*)?) => {
+        $crate::__yaml_array!([ $($items,)* $crate::Yaml::Null, ] $($($rest)*)?)
+    };
+    ([ $($items:expr,)* ] [ $($array:tt)* ] $(, $($rest:tt)*)?) => {
+        $crate::__yaml_array!([ $($items,)* $crate::yaml!([ $($array)* ]), ] $($($rest)*)?)
+    };
+    ([ $($items:expr,)* ] { $($hash:tt)* } $(, $($rest:tt)*)?) => {
+        $crate::__yaml_array!([ $($items,)* $crate::yaml!({ $($hash)* }), ] $($($rest)*)?)
+    };
+    ([ $($items:expr,)* ] , $($rest:tt)*) => {
+        ::core::compile_error!("expected a value before `,`")
+    };
+    ([ $($items:expr,)* ] $next:expr $(, $($rest:tt)*)?) => {
+        $crate::__yaml_array!([ $($items,)* $crate::Yaml::from($next), ] $($($rest)*)?)
+    };
+}
+
+/// Insert the entries of a mapping for [`yaml!`] into `$hash`, one entry at a time. The tokens
+/// of the current key are accumulated in the first group until its `:` is reached.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __yaml_hash {
+    ($hash:ident () ()) => {};
+    ($hash:ident () (: $($rest:tt)*)) => {
+        ::core::compile_error!("expected a key before `:`")
+    };
+    ($hash:ident ($($key:tt)+) ()) => {
+        ::core::compile_error!("expected `:` and a value after the key")
+    };
+    ($hash:ident ($($key:tt)+) (, $($rest:tt)*)) => {
+        ::core::compile_error!("expected `:` and a value after the key")
+    };
+    ($hash:ident ($($key:tt)+) (: $(, $($rest:tt)*)?)) => {
+        ::core::compile_error!("expected a value after `:`")
+    };
+    ($hash:ident ($($key:tt)+) (: null $(, $($rest:tt)*)?)) => {
+        $hash.insert($crate::yaml!($($key)+), $crate::Yaml::Null);
+        $crate::__yaml_hash!($hash () ($($($rest)*)?));
+    };
+    ($hash:ident ($($key:tt)+) (: [ $($array:tt)* ] $(, $($rest:tt)*)?)) => {
+        $hash.insert($crate::yaml!($($key)+), $crate::yaml!([ $($array)* ]));
+        $crate::__yaml_hash!($hash () ($($($rest)*)?));
+    };
+    ($hash:ident ($($key:tt)+) (: { $($map:tt)* } $(, $($rest:tt)*)?)) => {
+        $hash.insert($crate::yaml!($($key)+), $crate::yaml!({ $($map)* }));
+        $crate::__yaml_hash!($hash () ($($($rest)*)?));
+    };
+    ($hash:ident ($($key:tt)+) (: $value:expr $(, $($rest:tt)*)?)) => {
+        $hash.insert($crate::yaml!($($key)+), $crate::Yaml::from($value));
+        $crate::__yaml_hash!($hash () ($($($rest)*)?));
+    };
+    ($hash:ident ($($key:tt)*) ($next:tt $($rest:tt)*)) => {
+        $crate::__yaml_hash!($hash ($($key)* $next) ($($rest)*));
+    };
+}
@@ -0,0 +1,297 @@
+//! Merging of [`Yaml`] documents, e.g. to layer configuration files.
+
+use alloc::string::String;
+use core::fmt::{self, Display};
+
+use hashlink::LinkedHashMap;
+
+use crate::{Hash, HashExt, LoadableYamlNode, PathSegment, Yaml, YamlKind, YamlPath};
+
+/// How [`Yaml::merge_from`] merges two sequences.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ArrayMerge {
+    /// The sequence of the overlay replaces that of the base.
+    #[default]
+    Replace,
+    /// The items of the overlay are appended to those of the base.
+    Concat,
+    /// Items at the same index are merged. Items beyond the end of the base are appended.
+    ByIndex,
+    /// Mappings with the same value for the given key are merged, e.g. the containers of a
+    /// Kubernetes pod by `name`. The other items of the overlay are appended.
+    ///
+    /// Each item of the overlay is merged into the first item of the base which has the same
+    /// value for the key.
+    ByKey(String),
+}
+
+/// How [`Yaml::merge_from`] merges two mappings below the root of the documents.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MappingMerge {
+    /// Mappings are merged key by key, recursively.
+    #[default]
+    Deep,
+    /// The mapping of the overlay replaces that of the base.
+    ///
+    /// The root mappings are still merged key by key, so that each of their values in the
+    /// overlay replaces that of the base.
+    Replace,
+}
+
+/// What [`Yaml::merge_from`] does with a `null` value in a mapping of the overlay.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NullOverlay {
+    /// The value of the key is set to `null`.
+    #[default]
+    Set,
+    /// The key is removed from the mapping of the base.
+    ///
+    /// `null` still replaces the base at the root of the document and in sequences.
+    Delete,
+}
+
+/// What [`Yaml::merge_from`] does when a collection of one side meets a node of another type on
+/// the other side (e.g. a mapping over a sequence, or a scalar over a mapping).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// The node of the overlay replaces that of the base.
+    #[default]
+    Replace,
+    /// The merge fails with a [`MergeError`].
+    Error,
+}
+
+/// How [`Yaml::merge_from`] merges two documents.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MergeStrategy {
+    /// How mappings are merged.
+    pub mappings: MappingMerge,
+    /// How sequences are merged.
+    pub arrays: ArrayMerge,
+    /// What to do with `null` values in mappings of the overlay.
+    pub nulls: NullOverlay,
+    /// What to do when the types of the two sides conflict.
+    pub conflicts: ConflictPolicy,
+}
+
+/// The types of the two sides conflict, and [`ConflictPolicy::Error`] was requested.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MergeError {
+    /// The path to the conflicting node.
+    pub path: YamlPath,
+    /// The type of the node of the base, e.g. [`YamlKind::Array`].
+    pub base: YamlKind,
+    /// The type of the node of the overlay, e.g. [`YamlKind::Hash`].
+    pub overlay: YamlKind,
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MergeError {}
+
+impl Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot merge {} over {}", self.overlay, self.base)?;
+        if self.path.is_root() {
+            f.write_str(" at the root of the document")
+        } else {
+            write!(f, " at `{}`", self.path)
+        }
+    }
+}
+
+impl Yaml {
+    /// Merge `overlay` into `self`.
+    ///
+    /// Mappings are merged according to `strategy.mappings`, by default recursively, key by key;
+    /// keys which only exist on one side are kept. Sequences are merged according to
+    /// `strategy.arrays`, and scalars of the overlay replace
+    /// those of the base. A base which is `null` or a `BadValue` is replaced by the overlay.
+    ///
+    /// ```
+    /// # use saphyr::{MergeStrategy, NullOverlay, Yaml};
+    /// let mut config = Yaml::load_one_from_str("db: {host: localhost, port: 5432}\ndebug: true")
+    ///     .unwrap();
+    /// let overlay = Yaml::load_one_from_str("db: {host: db.prod}\ndebug: ~").unwrap();
+    /// let strategy = MergeStrategy {
+    ///     nulls: NullOverlay::Delete,
+    ///     ..MergeStrategy::default()
+    /// };
+    /// config.merge_from(overlay, &strategy).unwrap();
+    /// assert_eq!(
+    ///     config,
+    ///     Yaml::load_one_from_str("db: {host: db.prod, port: 5432}").unwrap()
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `MergeError` if the types of the two sides conflict and `strategy.conflicts` is
+    /// [`ConflictPolicy::Error`]. `self` is then left partially merged.
+    pub fn merge_from(
+        &mut self,
+        overlay: Yaml,
+        strategy: &MergeStrategy,
+    ) -> Result<(), MergeError> {
+        merge(self, overlay, strategy, &mut YamlPath::new())
+    }
+
+    /// Resolve the merge keys (`<<`) of the mappings of `self`, as in YAML 1.1.
+    ///
+    /// The value of a merge key is a mapping, or a sequence of mappings, usually given as
+    /// aliases. The merge key is replaced by the entries of these mappings whose keys are not
+    /// already in its own mapping, where it was: the keys of the mapping win over the merged ones,
+    /// and the first mappings of a sequence win over the next ones. Merge keys are resolved from
+    /// the innermost mappings out, so merged mappings may have merge keys of their own.
+    ///
+    /// A `<<` key whose value is neither a mapping nor a sequence of mappings is kept as it is.
+    /// [`LoaderOptions::merge_keys`](crate::LoaderOptions::merge_keys) resolves the merge keys of
+    /// documents as they are loaded, whatever their type of node.
+    ///
+    /// ```
+    /// # use saphyr::Yaml;
+    /// let mut doc = Yaml::load_one_from_str(
+    ///     "base: &base {image: rust, cache: true}\njob:\n  <<: *base\n  cache: false",
+    /// )
+    /// .unwrap();
+    /// doc.resolve_merge_keys();
+    /// assert_eq!(
+    ///     doc["job"],
+    ///     Yaml::load_one_from_str("{image: rust, cache: false}").unwrap()
+    /// );
+    /// ```
+    pub fn resolve_merge_keys(&mut self) {
+        resolve_merge_keys(self);
+    }
+}
+
+/// Resolve the merge keys of the mappings of `node`. See [`Yaml::resolve_merge_keys`].
+pub(crate) fn resolve_merge_keys<Node: LoadableYamlNode>(node: &mut Node) {
+    if node.is_array() {
+        node.array_mut().iter_mut().for_each(resolve_merge_keys);
+        return;
+    }
+    if !node.is_hash() {
+        return;
+    }
+    let hash = node.hash_mut();
+    hash.values_mut().for_each(resolve_merge_keys);
+    let merge_key = Node::from_bare_yaml(Yaml::String("<<".into()));
+    let Some(value) = hash.get_mut(&merge_key) else {
+        return;
+    };
+    let mut sources = if value.is_hash() {
+        vec![value.take()]
+    } else if value.is_array() && value.array_mut().iter().all(Node::is_hash) {
+        core::mem::take(value.array_mut())
+    } else {
+        return;
+    };
+
+    let entries = core::mem::take(hash);
+    let mut merged = LinkedHashMap::with_capacity(entries.len());
+    for (key, value) in entries {
+        if key != merge_key {
+            merged.insert(key, value);
+            continue;
+        }
+        // The keys which follow the merge key replace the merged values.
+        for source in &mut sources {
+            for (key, value) in core::mem::take(source.hash_mut()) {
+                if !merged.contains_key(&key) {
+                    merged.insert(key, value);
+                }
+            }
+        }
+    }
+    *hash = merged;
+}
+
+fn merge(
+    base: &mut Yaml,
+    overlay: Yaml,
+    strategy: &MergeStrategy,
+    path: &mut YamlPath,
+) -> Result<(), MergeError> {
+    match (base, overlay) {
+        (Yaml::Hash(base), Yaml::Hash(overlay))
+            if strategy.mappings == MappingMerge::Replace && !path.is_root() =>
+        {
+            *base = overlay;
+            Ok(())
+        }
+        (Yaml::Hash(base), Yaml::Hash(overlay)) => merge_hashes(base, overlay, strategy, path),
+        (Yaml::Array(base), Yaml::Array(overlay)) => {
+            match &strategy.arrays {
+                ArrayMerge::Replace => *base = overlay,
+                ArrayMerge::Concat => base.extend(overlay),
+                ArrayMerge::ByIndex => {
+                    for (i, item) in overlay.into_iter().enumerate() {
+                        if let Some(existing) = base.get_mut(i) {
+                            path.push(PathSegment::Index(i));
+                            merge(existing, item, strategy, path)?;
+                            path.pop();
+                        } else {
+                            base.push(item);
+                        }
+                    }
+                }
+                ArrayMerge::ByKey(key) => {
+                    let id = |item: &Yaml| {
+                        item.as_hash()
+                            .and_then(|hash| hash.get_by_str(key))
+                            .cloned()
+                    };
+                    for item in overlay {
+                        let position = id(&item).and_then(|value| {
+                            base.iter()
+                                .position(|other| id(other).as_ref() == Some(&value))
+                        });
+                        if let Some(i) = position {
+                            path.push(PathSegment::Index(i));
+                            merge(&mut base[i], item, strategy, path)?;
+                            path.pop();
+                        } else {
+                            base.push(item);
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }
+        (base, overlay) => {
+            let is_collection = |yaml: &Yaml| yaml.is_array() || yaml.is_hash();
+            let conflict = !base.is_null()
+                && !base.is_badvalue()
+                && !overlay.is_null()
+                && (is_collection(base) || is_collection(&overlay));
+            if conflict && strategy.conflicts == ConflictPolicy::Error {
+                return Err(MergeError {
+                    path: path.clone(),
+                    base: base.kind(),
+                    overlay: overlay.kind(),
+                });
+            }
+            *base = overlay;
+            Ok(())
+        }
+    }
+}
+
+fn merge_hashes(
+    base: &mut Hash,
+    overlay: Hash,
+    strategy: &MergeStrategy,
+    path: &mut YamlPath,
+) -> Result<(), MergeError> {
+    for (key, value) in overlay {
+        if value.is_null() && strategy.nulls == NullOverlay::Delete {
+            base.remove(&key);
+        } else if let Some(existing) = base.get_mut(&key) {
+            path.push(PathSegment::Key(key));
+            merge(existing, value, strategy, path)?;
+            path.pop();
+        } else {
+            base.insert(key, value);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,224 @@
+//! Numbers of any of the numeric variants of [`Yaml`].
+
+use alloc::string::{String, ToString};
+use core::fmt::{self, Display};
+use core::hash::{Hash, Hasher};
+
+use crate::yaml::real_text;
+use crate::Yaml;
+
+/// The value of an integer or a float node, whichever variant of [`Yaml`] holds it.
+///
+/// Integers are [`Yaml::Integer`]s, or [`Yaml::Real`]s if they are too large for an `i64` (see
+/// [`Yaml::as_u64`]), and floats are [`Yaml::Real`]s or [`Yaml::Float`]s. A `Number` holds an
+/// `i64`, a `u64` or an `f64` instead, so that it does not need to be parsed again to be used.
+/// The variants themselves are unchanged: `Number` is only an accessor.
+///
+/// A number taken from a `Yaml::Real` or a `Yaml::Float` keeps the text it was loaded from as
+/// its [`source`](Number::source), so that `2.50` is not converted back as `2.5`. Numbers are
+/// compared by value, ignoring the source: integers are equal if they are the same integer,
+/// whatever type they were created from, and floats are compared bit by bit, as
+/// [`Float`](crate::Float)s are. An integer is never equal to a float.
+///
+/// ```
+/// use saphyr::{yaml, Number, Yaml};
+///
+/// let doc = Yaml::load_one_from_str("[8080, 2.50, 18446744073709551615]").unwrap();
+/// assert_eq!(doc[0].as_number(), Some(Number::from(8080)));
+/// assert_eq!(doc[1].as_number().and_then(|n| n.as_f64()), Some(2.5));
+/// assert_eq!(doc[1].as_number().unwrap().source(), Some("2.50"));
+/// assert_eq!(doc[2].as_number().and_then(|n| n.as_u64()), Some(u64::MAX));
+/// assert_eq!(yaml!("8080").as_number(), None);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Number {
+    value: N,
+    source: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum N {
+    /// A non-negative integer.
+    PosInt(u64),
+    /// A negative integer.
+    NegInt(i64),
+    /// A float, which may be integral or not finite.
+    Float(f64),
+}
+
+impl Number {
+    /// Create a number without a source.
+    fn new(value: N) -> Self {
+        Self {
+            value,
+            source: None,
+        }
+    }
+
+    /// Return the text the number was loaded from, if it was kept.
+    #[must_use]
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// Return whether the number is an integer in the range of an `i64`.
+    #[must_use]
+    pub fn is_i64(&self) -> bool {
+        self.as_i64().is_some()
+    }
+
+    /// Return whether the number is an integer in the range of a `u64`.
+    #[must_use]
+    pub fn is_u64(&self) -> bool {
+        matches!(self.value, N::PosInt(_))
+    }
+
+    /// Return whether the number is a float, even an integral one such as `1.0`.
+    #[must_use]
+    pub fn is_f64(&self) -> bool {
+        matches!(self.value, N::Float(_))
+    }
+
+    /// Return the number as an `i64`, if it is an integer in its range.
+    #[must_use]
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.value {
+            N::PosInt(v) => i64::try_from(v).ok(),
+            N::NegInt(v) => Some(v),
+            N::Float(_) => None,
+        }
+    }
+
+    /// Return the number as a `u64`, if it is an integer in its range.
+    #[must_use]
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.value {
+            N::PosInt(v) => Some(v),
+            N::NegInt(_) | N::Float(_) => None,
+        }
+    }
+
+    /// Return the number as an `f64`. Integers are converted, and may lose precision.
+    ///
+    /// This always returns `Some`, as with `as_f64` in `serde_json`.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss, clippy::unnecessary_wraps)]
+    pub fn as_f64(&self) -> Option<f64> {
+        Some(match self.value {
+            N::PosInt(v) => v as f64,
+            N::NegInt(v) => v as f64,
+            N::Float(v) => v,
+        })
+    }
+}
+
+impl Yaml {
+    /// Return the value of `self` if it is an integer or a float, in any of their variants.
+    ///
+    /// Integers too large for an `i64` or a `u64` are returned as floats, which may lose
+    /// precision. The text of a `Yaml::Real`, or the source of a `Yaml::Float`, is kept as the
+    /// [`source`](Number::source) of the number.
+    #[must_use]
+    pub fn as_number(&self) -> Option<Number> {
+        let (number, source) = match self {
+            Yaml::Integer(v) => return Some(Number::from(*v)),
+            Yaml::Real(v) => match v.parse::<u64>() {
+                Ok(value) => (Number::from(value), v),
+                Err(_) => (self.as_f64().map(Number::from)?, v),
+            },
+            Yaml::Float(v) => match &v.source {
+                Some(source) => (Number::from(v.value), source),
+                None => return Some(Number::from(v.value)),
+            },
+            _ => return None,
+        };
+        Some(Number {
+            source: Some(source.clone()),
+            ..number
+        })
+    }
+}
+
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.value, other.value) {
+            (N::PosInt(a), N::PosInt(b)) => a == b,
+            (N::NegInt(a), N::NegInt(b)) => a == b,
+            (N::Float(a), N::Float(b)) => a.to_bits() == b.to_bits(),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Number {}
+
+impl Hash for Number {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self.value {
+            N::PosInt(v) => (0u8, v).hash(state),
+            N::NegInt(v) => (1u8, v).hash(state),
+            N::Float(v) => (2u8, v.to_bits()).hash(state),
+        }
+    }
+}
+
+impl Display for Number {
+    /// Write the number so that it reads back as the same number, ignoring the source.
+    ///
+    /// Floats are written in the shortest form which reads back as their value, as with
+    /// `Yaml::from(f64)`. Non-finite values are written `.nan`, `.inf` and `-.inf`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.value {
+            N::PosInt(v) => write!(f, "{v}"),
+            N::NegInt(v) => write!(f, "{v}"),
+            N::Float(v) => f.write_str(&real_text(v)),
+        }
+    }
+}
+
+macro_rules! impl_from_unsigned {
+    ($($ty:ty),*) => {$(
+        impl From<$ty> for Number {
+            fn from(value: $ty) -> Self {
+                Number::new(N::PosInt(u64::from(value)))
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_from_signed {
+    ($($ty:ty),*) => {$(
+        impl From<$ty> for Number {
+            fn from(value: $ty) -> Self {
+                let value = i64::from(value);
+                Number::new(match u64::try_from(value) {
+                    Ok(v) => N::PosInt(v),
+                    Err(_) => N::NegInt(value),
+                })
+            }
+        }
+    )*};
+}
+
+impl_from_unsigned!(u8, u16, u32, u64);
+impl_from_signed!(i8, i16, i32, i64);
+
+impl From<f64> for Number {
+    fn from(value: f64) -> Self {
+        Number::new(N::Float(value))
+    }
+}
+
+impl From<Number> for Yaml {
+    /// Convert `number` to a [`Yaml::Integer`], or to a [`Yaml::Real`] if it is a float or an
+    /// integer too large for an `i64`. A `Yaml::Real` holds the source of the number, if any.
+    fn from(number: Number) -> Self {
+        match number.as_i64() {
+            Some(v) => Yaml::Integer(v),
+            None => Yaml::Real(match number.source {
+                Some(source) => source,
+                None => number.to_string(),
+            }),
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! Loading the documents of a stream on several threads.
+
+use alloc::vec::Vec;
+use core::num::NonZeroUsize;
+use std::thread;
+
+use saphyr_parser::ScanError;
+
+use crate::{stream::document_boundaries, Yaml};
+
+impl Yaml {
+    /// Load the given string as an array of YAML documents, on up to `threads` threads.
+    ///
+    /// The documents are the same as with [`Self::load_from_str`], in the same order. The source
+    /// is split between documents into one chunk per thread, of about the same size, and each
+    /// chunk is loaded on its own thread. If `threads` is 0, the number of threads is
+    /// [`std::thread::available_parallelism`].
+    ///
+    /// Splitting only needs to look at the start of lines: in YAML, a line starting with a
+    /// document marker (`---` or `...`, followed by a space, a tab or the end of the line) always
+    /// ends the content of the current document, even within a block scalar or a quoted string.
+    /// That is also why documents can be loaded independently of one another: anchors do not
+    /// outlive their document. The directives of a document (e.g. `%YAML 1.1`) are kept with it.
+    ///
+    /// This pays off for streams of many documents. A single document is loaded on the current
+    /// thread.
+    ///
+    /// ```
+    /// use saphyr::Yaml;
+    ///
+    /// let source = "id: 1\nscript: |\n  echo ---\n---\nid: 2\n...\n%YAML 1.1\n--- !!str\nid: 3";
+    /// let docs = Yaml::load_from_str_parallel(source, 2).unwrap();
+    /// assert_eq!(docs, Yaml::load_from_str(source).unwrap());
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `ScanError` when loading fails. The source is then loaded again on the current
+    /// thread, so that the error is the same as with [`Self::load_from_str`], with positions
+    /// relative to the whole source.
+    pub fn load_from_str_parallel(source: &str, threads: usize) -> Result<Vec<Self>, ScanError> {
+        let threads = if threads == 0 {
+            thread::available_parallelism().map_or(1, NonZeroUsize::get)
+        } else {
+            threads
+        };
+        let chunks = split_chunks(source, threads);
+        if chunks.len() <= 1 {
+            return Self::load_from_str(source);
+        }
+
+        let loaded: Vec<_> = thread::scope(|scope| {
+            let workers: Vec<_> = chunks
+                .iter()
+                .map(|chunk| scope.spawn(move || Self::load_from_str(chunk)))
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| {
+                    worker
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+                })
+                .collect()
+        });
+
+        let mut docs = Vec::new();
+        for chunk in loaded {
+            match chunk {
+                Ok(chunk) => docs.extend(chunk),
+                Err(_) => return Self::load_from_str(source),
+            }
+        }
+        Ok(docs)
+    }
+}
+
+/// Split `source` into at most `threads` chunks of whole documents, of about the same size.
+fn split_chunks(source: &str, threads: usize) -> Vec<&str> {
+    let boundaries = document_boundaries(source);
+    let mut chunks = Vec::with_capacity(threads);
+    let mut start = 0;
+    for i in 1..threads {
+        let target = source.len() / threads * i;
+        let end = boundaries[boundaries.partition_point(|&boundary| boundary < target)..]
+            .first()
+            .copied()
+            .unwrap_or(source.len());
+        if end > start {
+            chunks.push(&source[start..end]);
+            start = end;
+        }
+    }
+    if start < source.len() || chunks.is_empty() {
+        chunks.push(&source[start..]);
+    }
+    chunks
+}
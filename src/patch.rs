@@ -0,0 +1,359 @@
+//! Application of JSON Patches to [`Yaml`] documents.
+
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
+use core::fmt::{self, Display};
+
+use crate::access::{key_matches, parse_index, pointer_segments};
+use crate::{Hash, HashExt, Yaml};
+
+/// An operation of a [JSON Patch](https://datatracker.ietf.org/doc/html/rfc6902), as applied by
+/// [`Yaml::apply_json_patch`].
+///
+/// Paths are JSON Pointers, which designate nodes as with [`Yaml::pointer`]. In the path of an
+/// `Add`, the last segment may also be the index just past the end of a sequence, or `-`, to
+/// append to it, or a key the mapping does not have yet, to insert it. New keys are strings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatchOp {
+    /// Insert `value` at `path`, shifting the following items of a sequence, or replacing the
+    /// value of an existing key.
+    Add {
+        /// Where to insert the node.
+        path: String,
+        /// The node to insert.
+        value: Yaml,
+    },
+    /// Remove the node at `path`, which must exist.
+    Remove {
+        /// The node to remove.
+        path: String,
+    },
+    /// Replace the node at `path`, which must exist, with `value`.
+    Replace {
+        /// The node to replace.
+        path: String,
+        /// The new node.
+        value: Yaml,
+    },
+    /// Remove the node at `from` and add it at `path`, which must not be within it.
+    Move {
+        /// The node to move.
+        from: String,
+        /// Where to add the node, as with [`PatchOp::Add`].
+        path: String,
+    },
+    /// Add a copy of the node at `from` at `path`.
+    Copy {
+        /// The node to copy.
+        from: String,
+        /// Where to add the copy, as with [`PatchOp::Add`].
+        path: String,
+    },
+    /// Check that the node at `path` is semantically equal to `value` (see
+    /// [`Yaml::semantic_eq`]).
+    Test {
+        /// The node to check.
+        path: String,
+        /// The node it must equal.
+        value: Yaml,
+    },
+}
+
+/// An operation of a JSON Patch failed, as returned by [`Yaml::apply_json_patch`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PatchError {
+    /// The index of the operation within the patch.
+    pub index: usize,
+    /// The path the operation failed at: its `from` or its `path`.
+    pub path: String,
+    /// What went wrong.
+    pub kind: PatchErrorKind,
+}
+
+/// The reason why an operation of a JSON Patch failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatchErrorKind {
+    /// The path is not a JSON Pointer, there is no node at the path, or, for the path of an
+    /// `add`, there is no collection to add a node to.
+    NotFound,
+    /// The node of a `test` differs from the expected one.
+    TestFailed,
+    /// The operation designates nodes it cannot change: it removes the root of the document, or
+    /// moves a node within itself.
+    Unsupported,
+    /// The operation could not be read from a [`Yaml`] node, for the given reason (see
+    /// [`PatchOp::from_yaml`]).
+    Malformed(&'static str),
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PatchError {}
+
+impl Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "patch operation {}: ", self.index)?;
+        match self.kind {
+            PatchErrorKind::NotFound => f.write_str("no such node")?,
+            PatchErrorKind::TestFailed => f.write_str("the node differs")?,
+            PatchErrorKind::Unsupported => f.write_str("the node cannot be changed")?,
+            PatchErrorKind::Malformed(reason) => return f.write_str(reason),
+        }
+        if self.path.is_empty() {
+            f.write_str(" at the root of the document")
+        } else {
+            write!(f, " at `{}`", self.path)
+        }
+    }
+}
+
+impl PatchOp {
+    /// Read the operations of a JSON Patch, i.e. a sequence of mappings such as
+    /// `{op: replace, path: /spec/replicas, value: 3}`.
+    ///
+    /// ```
+    /// # use saphyr::{PatchOp, Yaml};
+    /// let patch = Yaml::load_one_from_str("- {op: move, from: /a, path: /b}").unwrap();
+    /// assert_eq!(
+    ///     PatchOp::from_yaml(&patch).unwrap(),
+    ///     [PatchOp::Move {
+    ///         from: "/a".to_owned(),
+    ///         path: "/b".to_owned()
+    ///     }]
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// Returns [`PatchErrorKind::Malformed`] if `patch` is not a sequence, or if one of its items
+    /// is not an operation, e.g. because it lacks a member the operation requires.
+    pub fn from_yaml(patch: &Yaml) -> Result<Vec<Self>, PatchError> {
+        let malformed = |index, reason| PatchError {
+            index,
+            path: String::new(),
+            kind: PatchErrorKind::Malformed(reason),
+        };
+        let Some(items) = patch.untagged().as_vec() else {
+            return Err(malformed(0, "a patch is a sequence of operations"));
+        };
+        items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                let item = item
+                    .untagged()
+                    .as_hash()
+                    .ok_or_else(|| malformed(index, "an operation is a mapping"))?;
+                let member = |name, reason| {
+                    item.get_by_str(name)
+                        .ok_or_else(|| malformed(index, reason))
+                };
+                let pointer = |name, reason| {
+                    member(name, reason)?
+                        .untagged()
+                        .as_str()
+                        .map(str::to_owned)
+                        .ok_or_else(|| malformed(index, reason))
+                };
+                let op_path = || pointer("path", "an operation has a string `path`");
+                let from = || pointer("from", "a `move` or `copy` has a string `from`");
+                let value =
+                    || member("value", "an `add`, `replace` or `test` has a `value`").cloned();
+                match member("op", "an operation has an `op`")?
+                    .untagged()
+                    .as_str()
+                {
+                    Some("add") => Ok(PatchOp::Add {
+                        path: op_path()?,
+                        value: value()?,
+                    }),
+                    Some("remove") => Ok(PatchOp::Remove { path: op_path()? }),
+                    Some("replace") => Ok(PatchOp::Replace {
+                        path: op_path()?,
+                        value: value()?,
+                    }),
+                    Some("move") => Ok(PatchOp::Move {
+                        from: from()?,
+                        path: op_path()?,
+                    }),
+                    Some("copy") => Ok(PatchOp::Copy {
+                        from: from()?,
+                        path: op_path()?,
+                    }),
+                    Some("test") => Ok(PatchOp::Test {
+                        path: op_path()?,
+                        value: value()?,
+                    }),
+                    _ => Err(malformed(index, "unknown `op`")),
+                }
+            })
+            .collect()
+    }
+
+    /// Return the path of the operation, i.e. the node it adds, removes, replaces or tests.
+    #[must_use]
+    pub fn path(&self) -> &str {
+        match self {
+            PatchOp::Add { path, .. }
+            | PatchOp::Remove { path }
+            | PatchOp::Replace { path, .. }
+            | PatchOp::Move { path, .. }
+            | PatchOp::Copy { path, .. }
+            | PatchOp::Test { path, .. } => path,
+        }
+    }
+}
+
+impl Yaml {
+    /// Apply the operations of the [JSON Patch](https://datatracker.ietf.org/doc/html/rfc6902)
+    /// `patch` to `self`, in order (see [`PatchOp`]).
+    ///
+    /// The patch is applied as a whole or not at all: the operations are applied to a copy of
+    /// `self`, which replaces it once they have all succeeded.
+    ///
+    /// ```
+    /// # use saphyr::{PatchOp, Yaml};
+    /// let mut doc = Yaml::load_one_from_str("spec: {replicas: 1, ports: [80]}").unwrap();
+    /// let patch = Yaml::load_one_from_str(
+    ///     "- {op: test, path: /spec/replicas, value: 1}
+    ///      - {op: replace, path: /spec/replicas, value: 3}
+    ///      - {op: add, path: /spec/ports/-, value: 443}",
+    /// )
+    /// .unwrap();
+    /// doc.apply_json_patch(&PatchOp::from_yaml(&patch).unwrap()).unwrap();
+    /// assert_eq!(
+    ///     doc,
+    ///     Yaml::load_one_from_str("spec: {replicas: 3, ports: [80, 443]}").unwrap()
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `PatchError` for the first operation which fails. `self` is then left unchanged.
+    pub fn apply_json_patch(&mut self, patch: &[PatchOp]) -> Result<(), PatchError> {
+        let mut doc = self.clone();
+        for (index, op) in patch.iter().enumerate() {
+            apply(&mut doc, op).map_err(|(path, kind)| PatchError {
+                index,
+                path: path.to_owned(),
+                kind,
+            })?;
+        }
+        *self = doc;
+        Ok(())
+    }
+}
+
+/// Apply `op` to `doc`, or return the path it failed at and why.
+fn apply<'a>(doc: &mut Yaml, op: &'a PatchOp) -> Result<(), (&'a str, PatchErrorKind)> {
+    match op {
+        PatchOp::Add { path, value } => {
+            add(doc, path, value.clone()).map_err(|kind| (&**path, kind))
+        }
+        PatchOp::Remove { path } => remove(doc, path).map(drop).map_err(|kind| (&**path, kind)),
+        PatchOp::Replace { path, value } => {
+            let node = doc
+                .pointer_mut(path)
+                .ok_or((&**path, PatchErrorKind::NotFound))?;
+            *node = value.clone();
+            Ok(())
+        }
+        PatchOp::Move { from, path } => {
+            if doc.pointer(from).is_none() {
+                return Err((from, PatchErrorKind::NotFound));
+            }
+            if from == path {
+                return Ok(());
+            }
+            if path
+                .strip_prefix(&**from)
+                .is_some_and(|rest| rest.starts_with('/'))
+            {
+                return Err((path, PatchErrorKind::Unsupported));
+            }
+            let value = remove(doc, from).map_err(|kind| (&**from, kind))?;
+            add(doc, path, value).map_err(|kind| (&**path, kind))
+        }
+        PatchOp::Copy { from, path } => {
+            let value = doc
+                .pointer(from)
+                .cloned()
+                .ok_or((&**from, PatchErrorKind::NotFound))?;
+            add(doc, path, value).map_err(|kind| (&**path, kind))
+        }
+        PatchOp::Test { path, value } => match doc.pointer(path) {
+            Some(node) if node.semantic_eq(value) => Ok(()),
+            Some(_) => Err((path, PatchErrorKind::TestFailed)),
+            None => Err((path, PatchErrorKind::NotFound)),
+        },
+    }
+}
+
+/// Insert `value` at `path`, as with [`PatchOp::Add`].
+fn add(doc: &mut Yaml, path: &str, value: Yaml) -> Result<(), PatchErrorKind> {
+    let Some((parent, last)) = split(doc, path)? else {
+        *doc = value;
+        return Ok(());
+    };
+    match parent {
+        Yaml::Array(items) => {
+            let index = if last == "-" {
+                items.len()
+            } else {
+                parse_index(&last)
+                    .filter(|&index| index <= items.len())
+                    .ok_or(PatchErrorKind::NotFound)?
+            };
+            items.insert(index, value);
+        }
+        Yaml::Hash(hash) => match find_key(hash, &last).and_then(|key| hash.get_mut(&key)) {
+            Some(node) => *node = value,
+            None => {
+                hash.insert(Yaml::String(last), value);
+            }
+        },
+        _ => return Err(PatchErrorKind::NotFound),
+    }
+    Ok(())
+}
+
+/// Remove the node at `path` and return it, as with [`PatchOp::Remove`].
+fn remove(doc: &mut Yaml, path: &str) -> Result<Yaml, PatchErrorKind> {
+    let Some((parent, last)) = split(doc, path)? else {
+        return Err(PatchErrorKind::Unsupported);
+    };
+    match parent {
+        Yaml::Array(items) => parse_index(&last)
+            .filter(|&index| index < items.len())
+            .map(|index| items.remove(index)),
+        Yaml::Hash(hash) => find_key(hash, &last).and_then(|key| hash.remove(&key)),
+        _ => None,
+    }
+    .ok_or(PatchErrorKind::NotFound)
+}
+
+/// Return the collection containing the node at `path`, untagged, and the last segment of
+/// `path`, or `None` if `path` is the root of the document.
+fn split<'a>(
+    doc: &'a mut Yaml,
+    path: &str,
+) -> Result<Option<(&'a mut Yaml, String)>, PatchErrorKind> {
+    let Some(last) = pointer_segments(path)
+        .ok_or(PatchErrorKind::NotFound)?
+        .last()
+    else {
+        return Ok(None);
+    };
+    let parent = path.rfind('/').map_or("", |end| &path[..end]);
+    match doc.pointer_mut(parent).ok_or(PatchErrorKind::NotFound)? {
+        Yaml::Tagged(_, node) => Ok(Some((&mut **node, last))),
+        node => Ok(Some((node, last))),
+    }
+}
+
+/// Return the key of `hash` which the segment `segment` of a JSON Pointer designates, as with
+/// [`Yaml::pointer`].
+fn find_key(hash: &Hash, segment: &str) -> Option<Yaml> {
+    let key = Yaml::String(segment.to_owned());
+    if hash.contains_key(&key) {
+        Some(key)
+    } else {
+        hash.keys().find(|key| key_matches(key, segment)).cloned()
+    }
+}
@@ -0,0 +1,116 @@
+//! Paths to nodes within a YAML document.
+
+use alloc::vec::Vec;
+use core::fmt::{self, Display};
+
+use crate::Yaml;
+
+/// A single step in a [`YamlPath`].
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum PathSegment {
+    /// The element at the given index of a sequence.
+    Index(usize),
+    /// The value associated with the given key in a mapping.
+    Key(Yaml),
+    /// The key of the entry at the given index in a mapping.
+    ///
+    /// This designates the key itself rather than its value. It is used to refer to nodes that
+    /// are nested within complex keys (e.g.: `? [a, b]: c`).
+    EntryKey(usize),
+}
+
+/// A path from the root of a document to one of its nodes.
+///
+/// The path displays as a [JSON Pointer](https://datatracker.ietf.org/doc/html/rfc6901), e.g.
+/// `/spec/containers/0/image`. The root path displays as an empty string.
+///
+/// ```
+/// # use saphyr::{PathSegment, Yaml, YamlPath};
+/// let mut path = YamlPath::new();
+/// path.push(PathSegment::Key(Yaml::String("spec".into())));
+/// path.push(PathSegment::Index(0));
+/// assert_eq!(path.to_string(), "/spec/0");
+/// ```
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+#[allow(clippy::module_name_repetitions)]
+pub struct YamlPath {
+    segments: Vec<PathSegment>,
+}
+
+impl YamlPath {
+    /// Create a path pointing to the root of a document.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a segment to the path.
+    pub fn push(&mut self, segment: PathSegment) {
+        self.segments.push(segment);
+    }
+
+    /// Remove the last segment of the path and return it, if any.
+    pub fn pop(&mut self) -> Option<PathSegment> {
+        self.segments.pop()
+    }
+
+    /// Return a copy of `self` with `segment` appended to it.
+    #[must_use]
+    pub fn join(&self, segment: PathSegment) -> Self {
+        let mut path = self.clone();
+        path.push(segment);
+        path
+    }
+
+    /// Return the segments of the path, from the root to the node.
+    #[must_use]
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    /// Return whether the path points to the root of the document.
+    #[must_use]
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+}
+
+impl From<Vec<PathSegment>> for YamlPath {
+    fn from(segments: Vec<PathSegment>) -> Self {
+        Self { segments }
+    }
+}
+
+impl Display for PathSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathSegment::Index(index) => write!(f, "{index}"),
+            PathSegment::Key(key) => match key {
+                Yaml::String(v) | Yaml::Real(v) => {
+                    f.write_str(&v.replace('~', "~0").replace('/', "~1"))
+                }
+                Yaml::SharedString(v) => f.write_str(&v.replace('~', "~0").replace('/', "~1")),
+                Yaml::Integer(v) => write!(f, "{v}"),
+                Yaml::Boolean(v) => write!(f, "{v}"),
+                Yaml::Float(v) => f.write_str(&v.text()),
+                Yaml::Timestamp(v) => write!(f, "{v}"),
+                Yaml::Binary(v) => f.write_str(&crate::binary::encode(v).replace('/', "~1")),
+                Yaml::Null => f.write_str("null"),
+                Yaml::Array(_) => f.write_str("[...]"),
+                Yaml::Hash(_) => f.write_str("{...}"),
+                Yaml::Tagged(_, node) => PathSegment::Key((**node).clone()).fmt(f),
+                Yaml::Alias(_) | Yaml::BadValue => f.write_str("~"),
+            },
+            PathSegment::EntryKey(index) => write!(f, "?{index}"),
+        }
+    }
+}
+
+impl Display for YamlPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in &self.segments {
+            write!(f, "/{segment}")?;
+        }
+        Ok(())
+    }
+}
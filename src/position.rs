@@ -0,0 +1,127 @@
+//! Conversions of positions within a source, for editor tooling.
+
+use alloc::vec::Vec;
+
+use saphyr_parser::{Marker, Span};
+
+use crate::ByteRange;
+
+/// An index of the lines of a source, to convert [`Marker`]s to byte offsets and UTF-16 columns.
+///
+/// Markers hold a character index, a 1-based line and a column counted in characters. Editors and
+/// the Language Server Protocol count columns in UTF-16 code units instead, and slicing the source
+/// needs byte offsets. Building the index takes a pass over the source, after which each
+/// conversion only reads the line the position is on.
+///
+/// Lines are broken at `\n`, `\r\n` and lone `\r`, as the parser breaks them.
+///
+/// ```
+/// use saphyr::{LineIndex, Marker};
+///
+/// let source = "name: \"Zoë 🦀\"\nage: 7\n";
+/// let index = LineIndex::new(source);
+///
+/// // `ë` is 2 bytes and 1 UTF-16 unit, `🦀` is 4 bytes and 2 UTF-16 units.
+/// let quote = index.marker_at(16);
+/// assert_eq!((quote.index(), quote.line(), quote.col()), (12, 1, 12));
+/// assert_eq!(index.utf16_col(quote), 13);
+/// assert_eq!(index.byte_offset(quote), 16);
+///
+/// let age = Marker::new(14, 2, 0);
+/// assert_eq!(&source[index.byte_offset(age)..], "age: 7\n");
+/// ```
+#[derive(Clone, Debug)]
+pub struct LineIndex<'a> {
+    /// The source positions are converted within.
+    source: &'a str,
+    /// The byte offset and the character index of the start of each line, in order.
+    lines: Vec<(usize, usize)>,
+}
+
+impl<'a> LineIndex<'a> {
+    /// Index the lines of `source`.
+    #[must_use]
+    pub fn new(source: &'a str) -> Self {
+        let mut lines = vec![(0, 0)];
+        let mut chars = source.char_indices().enumerate().peekable();
+        while let Some((char_idx, (byte_idx, c))) = chars.next() {
+            let is_break = match c {
+                '\n' => true,
+                '\r' => chars.peek().map_or(true, |&(_, (_, next))| next != '\n'),
+                _ => false,
+            };
+            if is_break {
+                lines.push((byte_idx + 1, char_idx + 1));
+            }
+        }
+        Self { source, lines }
+    }
+
+    /// Return the source the index was built for.
+    #[must_use]
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+
+    /// Return the byte offset of `marker` within the source.
+    ///
+    /// Markers beyond the end of their line are at its line break, and markers beyond the end of
+    /// the source are at its end.
+    #[must_use]
+    pub fn byte_offset(&self, marker: Marker) -> usize {
+        let (start, text) = self.line(marker.line());
+        start
+            + text
+                .char_indices()
+                .nth(marker.col())
+                .map_or(text.len(), |(i, _)| i)
+    }
+
+    /// Return the byte range of `span` within the source.
+    #[must_use]
+    pub fn byte_range(&self, span: Span) -> ByteRange {
+        self.byte_offset(span.start)..self.byte_offset(span.end)
+    }
+
+    /// Return the column of `marker` counted in UTF-16 code units, as the Language Server
+    /// Protocol counts them by default.
+    ///
+    /// As with [`Marker::col`], the column is 0-based. LSP lines are 0-based as well, while
+    /// [`Marker::line`] is 1-based.
+    #[must_use]
+    pub fn utf16_col(&self, marker: Marker) -> usize {
+        let (_, text) = self.line(marker.line());
+        text.chars().take(marker.col()).map(char::len_utf16).sum()
+    }
+
+    /// Return the marker at byte `offset` of the source.
+    ///
+    /// An offset within a character is rounded down to its start, and an offset beyond the end of
+    /// the source is its end.
+    #[must_use]
+    pub fn marker_at(&self, offset: usize) -> Marker {
+        let mut offset = offset.min(self.source.len());
+        while !self.source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let line = self.lines.partition_point(|&(start, _)| start <= offset);
+        let (start, start_char) = self.lines[line - 1];
+        let col = self.source[start..offset].chars().count();
+        Marker::new(start_char + col, line, col)
+    }
+
+    /// Return the byte offset of the start of the 1-based `line`, and its text without its line
+    /// break.
+    fn line(&self, line: usize) -> (usize, &'a str) {
+        let Some(&(start, _)) = line.checked_sub(1).and_then(|line| self.lines.get(line)) else {
+            return (self.source.len(), "");
+        };
+        let end = self
+            .lines
+            .get(line)
+            .map_or(self.source.len(), |&(end, _)| end);
+        let text = &self.source[start..end];
+        let text = text.strip_suffix('\n').unwrap_or(text);
+        (start, text.strip_suffix('\r').unwrap_or(text))
+    }
+}
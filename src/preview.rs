@@ -0,0 +1,169 @@
+//! Short one-line summaries of [`Yaml`] nodes, for logs and error messages.
+
+use alloc::{
+    borrow::Cow,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::{
+    emitter::{escape_str, need_quotes, tag_text},
+    Yaml,
+};
+
+/// Collections nested deeper than this are summarized by their size.
+const MAX_DEPTH: usize = 2;
+
+impl Yaml {
+    /// Return a one-line summary of `self` in flow style, of about `max_len` characters.
+    ///
+    /// Unlike the [`Debug`] output, the summary of a large document stays short enough for a log
+    /// line or an error message. Long strings are cut short with `…`. A collection only shows
+    /// its first entries that fit, followed by the number of the others. Collections which do
+    /// not fit at all, or which are nested more than two levels deep, only show their size.
+    ///
+    /// ```
+    /// use saphyr::{yaml, Yaml};
+    ///
+    /// let doc = yaml!({"name": "web", "env": {"vars": {"db": {"host": "localhost"}}}});
+    /// assert_eq!(doc.preview(80), "{name: web, env: {vars: {db: {… 1 entry …}}}}");
+    ///
+    /// let ports = Yaml::Array((8000..8040).map(Yaml::Integer).collect());
+    /// let doc = yaml!({"host": "localhost", "ports": ports});
+    /// assert_eq!(doc.preview(40), "{host: localhost, ports: [… 40 items …]}");
+    /// assert_eq!(doc.preview(50), "{host: localhost, ports: [8000, 8001, … 38 more]}");
+    /// assert_eq!(doc.preview(30), "{host: localhost, … 1 more}");
+    /// assert_eq!(doc.preview(10), "{… 2 entries …}");
+    /// ```
+    #[must_use]
+    pub fn preview(&self, max_len: usize) -> String {
+        preview(self, max_len, 0)
+    }
+}
+
+fn preview(node: &Yaml, max_len: usize, depth: usize) -> String {
+    match node {
+        Yaml::Array(items) => {
+            preview_collection(('[', ']'), items.len(), max_len, depth, |i, budget| {
+                preview(&items[i], budget, depth + 1)
+            })
+        }
+        Yaml::Hash(hash) => {
+            preview_collection(('{', '}'), hash.len(), max_len, depth, |i, budget| {
+                // Entries are requested in order, and only as many as fit in `max_len`.
+                let (key, value) = hash.iter().nth(i).unwrap();
+                let key = preview(key, budget, depth + 1);
+                let rest = budget.saturating_sub(key.chars().count() + 2);
+                format!("{key}: {}", preview(value, rest, depth + 1))
+            })
+        }
+        Yaml::String(v) => preview_string(v, max_len),
+        Yaml::SharedString(v) => preview_string(v, max_len),
+        Yaml::Integer(v) => v.to_string(),
+        Yaml::Real(v) => v.clone(),
+        Yaml::Float(v) => v.text().into_owned(),
+        Yaml::Timestamp(v) => v.to_string(),
+        Yaml::Binary(v) => format!("!!binary ({} bytes)", v.len()),
+        Yaml::Boolean(v) => v.to_string(),
+        Yaml::Null => "null".into(),
+        Yaml::Tagged(tag, node) => {
+            let tag = tag_text(tag);
+            let rest = max_len.saturating_sub(tag.chars().count() + 1);
+            format!("{tag} {}", preview(node, rest, depth))
+        }
+        Yaml::Alias(id) => format!("*{id}"),
+        Yaml::BadValue => "<bad value>".into(),
+    }
+}
+
+/// Summarize a collection of `len` entries within `delimiters`, where `entry(i, budget)`
+/// returns the summary of the entry `i`, which fits if it is no longer than `budget`.
+///
+/// Entries are shown in order while they fit, then the last ones shown are shortened or hidden
+/// until the number of the hidden entries fits as well.
+fn preview_collection(
+    (open, close): (char, char),
+    len: usize,
+    max_len: usize,
+    depth: usize,
+    mut entry: impl FnMut(usize, usize) -> String,
+) -> String {
+    if len == 0 {
+        return format!("{open}{close}");
+    }
+    let size = || {
+        let unit = match (open, len) {
+            ('[', 1) => "item",
+            ('[', _) => "items",
+            (_, 1) => "entry",
+            (_, _) => "entries",
+        };
+        format!("{open}… {len} {unit} …{close}")
+    };
+    if depth > MAX_DEPTH {
+        return size();
+    }
+
+    let mut shown: Vec<String> = vec![];
+    // The length of the shown entries and their separators, with the delimiters.
+    let mut used = 2;
+    for i in 0..len {
+        let separator = if i == 0 { 0 } else { 2 };
+        let budget = max_len.saturating_sub(used + separator);
+        let summary = entry(i, budget);
+        let summary_len = summary.chars().count();
+        if summary_len > budget {
+            break;
+        }
+        used += separator + summary_len;
+        shown.push(summary);
+    }
+    if shown.len() < len {
+        loop {
+            let hidden = format!("… {} more", len - shown.len());
+            let separator = if shown.is_empty() { 0 } else { 2 };
+            if !shown.is_empty() && used + separator + hidden.chars().count() <= max_len {
+                shown.push(hidden);
+                break;
+            }
+            // Make room for the number of the hidden entries: show the last entry shorter, or
+            // hide it too.
+            let Some(last) = shown.pop() else {
+                return size();
+            };
+            let separator = if shown.is_empty() { 0 } else { 2 };
+            used -= separator + last.chars().count();
+            let hidden_len = format!("… {} more", len - shown.len() - 1).chars().count();
+            let budget = max_len.saturating_sub(used + separator + 2 + hidden_len);
+            let summary = entry(shown.len(), budget);
+            let summary_len = summary.chars().count();
+            if summary_len <= budget {
+                used += separator + summary_len;
+                shown.push(summary);
+            }
+        }
+    }
+    format!("{open}{}{close}", shown.join(", "))
+}
+
+/// Summarize a string, quoted if it would not be read back as a string, and cut short to fit
+/// in `max_len` characters.
+fn preview_string(v: &str, max_len: usize) -> String {
+    let quoted = need_quotes(v);
+    let room = max_len.saturating_sub(if quoted { 2 } else { 0 });
+    let v: Cow<str> = if v.chars().count() > room {
+        let mut cut: String = v.chars().take(room.saturating_sub(1)).collect();
+        cut.push('…');
+        cut.into()
+    } else {
+        v.into()
+    };
+    if quoted {
+        let mut out = String::new();
+        // Writing to a `String` cannot fail.
+        let _ = escape_str(&mut out, &v);
+        out
+    } else {
+        v.into_owned()
+    }
+}
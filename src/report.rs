@@ -0,0 +1,123 @@
+//! Rendering of errors along with the line of the source they are about.
+
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+};
+use core::fmt::{self, Display};
+
+use saphyr_parser::{ScanError, Span};
+
+use crate::LoadError;
+
+/// An error rendered with the line of the source it is about, as returned by
+/// [`DisplayWithSource::display_with_source`].
+///
+/// It displays as the message of the error, followed by its position and the line of the source
+/// at that position, with carets under the span of the error:
+///
+/// ```text
+/// error: mapping values are not allowed in this context
+///  --> 2:5
+///   |
+/// 2 | b: c: d
+///   |     ^
+/// ```
+///
+/// If the error has no position, or if it is not within the source, only the message is
+/// displayed.
+#[derive(Clone, Debug)]
+pub struct ErrorReport<'a> {
+    message: String,
+    span: Option<Span>,
+    source: &'a str,
+}
+
+/// Errors which can be displayed along with the source they are about (see [`ErrorReport`]).
+///
+/// ```
+/// use saphyr::{DisplayWithSource, Yaml};
+///
+/// let source = "a: 1\nb: c: d\n";
+/// let error = Yaml::load_from_str(source).unwrap_err();
+/// let report = error.display_with_source(source).to_string();
+/// assert!(report.starts_with("error: mapping values are not allowed"));
+/// assert!(report.contains("\n2 | b: c: d\n"));
+/// ```
+pub trait DisplayWithSource {
+    /// Return the error along with the line of `source` it is about, for display.
+    ///
+    /// `source` must be the text the error was reported for, so that its positions match.
+    fn display_with_source<'a>(&self, source: &'a str) -> ErrorReport<'a>;
+}
+
+impl DisplayWithSource for ScanError {
+    fn display_with_source<'a>(&self, source: &'a str) -> ErrorReport<'a> {
+        ErrorReport {
+            message: self.info().to_owned(),
+            span: Some(Span::new(*self.marker(), *self.marker())),
+            source,
+        }
+    }
+}
+
+impl DisplayWithSource for LoadError {
+    fn display_with_source<'a>(&self, source: &'a str) -> ErrorReport<'a> {
+        ErrorReport {
+            message: match self {
+                LoadError::Scan(error) => error.info().to_owned(),
+                error => error.to_string(),
+            },
+            span: self.span(),
+            source,
+        }
+    }
+}
+
+impl LoadError {
+    /// Render the error along with the line of `source` it is about.
+    ///
+    /// This is [`DisplayWithSource::display_with_source`], as a string.
+    #[must_use]
+    pub fn to_report(&self, source: &str) -> String {
+        self.display_with_source(source).to_string()
+    }
+}
+
+impl Display for ErrorReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "error: {}", self.message)?;
+        let Some(span) = self.span else {
+            return Ok(());
+        };
+        let (start, end) = (span.start, span.end);
+        let Some(text) = start
+            .line()
+            .checked_sub(1)
+            .and_then(|line| self.source.split('\n').nth(line))
+        else {
+            return Ok(());
+        };
+        let text = text.strip_suffix('\r').unwrap_or(text);
+        let line = start.line().to_string();
+        let gutter = " ".repeat(line.len());
+        // Tabs are kept before the carets, so that they line up with the text.
+        let indent: String = text
+            .chars()
+            .chain(core::iter::repeat(' '))
+            .take(start.col())
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let width = if end.line() == start.line() && end.col() > start.col() {
+            end.col() - start.col()
+        } else {
+            1
+        };
+        write!(
+            f,
+            "\n{gutter}--> {line}:{}\n{gutter} |\n{line} | {text}\n{gutter} | {indent}{}",
+            start.col() + 1,
+            "^".repeat(width),
+        )
+    }
+}
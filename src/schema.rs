@@ -0,0 +1,228 @@
+//! The schemas untagged plain scalars are resolved with.
+
+use alloc::{borrow::ToOwned, string::String};
+
+use crate::{loader::parse_f64, Yaml};
+
+/// How untagged plain scalars are resolved to types, e.g. whether `yes` is a boolean.
+///
+/// See [`LoaderOptions::schema`](crate::LoaderOptions::schema) and
+/// [`YamlEmitter::schema`](crate::YamlEmitter::schema).
+///
+/// ```
+/// # use saphyr::{Schema, Yaml};
+/// assert_eq!(Schema::Core.resolve("yes"), Yaml::String("yes".into()));
+/// assert_eq!(Schema::Yaml11.resolve("yes"), Yaml::Boolean(true));
+/// assert_eq!(Schema::Yaml11.resolve("0777"), Yaml::Integer(0o777));
+/// assert_eq!(Schema::Yaml11.resolve("1:30:00"), Yaml::Integer(5400));
+/// assert_eq!(Schema::Failsafe.resolve("42"), Yaml::String("42".into()));
+/// assert_eq!(Schema::Json.resolve("0x2A"), Yaml::BadValue);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Schema {
+    /// The YAML 1.2 core schema, as resolved by [`Yaml::scalar_from_str`].
+    #[default]
+    Core,
+    /// The types of YAML 1.1, for documents written for tools which predate YAML 1.2.
+    ///
+    /// The differences with [`Schema::Core`] are:
+    ///   * Booleans are `y`, `yes`, `true`, `on`, `n`, `no`, `false` and `off`, in lowercase,
+    ///     capitalized or uppercase.
+    ///   * Nulls are `~`, `null`, `Null`, `NULL` and the empty scalar.
+    ///   * Integers may contain `_`, be octal with a leading `0` (`0777`), binary (`0b101`) or
+    ///     sexagesimal (`1:30:00`). `0o` is not an octal prefix.
+    ///   * Floats must contain a `.` (e.g. `1e3` is a string), and are otherwise resolved as in
+    ///     [`Schema::Core`].
+    Yaml11,
+    /// The YAML 1.2 failsafe schema: untagged plain scalars are all strings.
+    ///
+    /// Tagged scalars (e.g. `!!int 42`) are resolved as with [`Schema::Core`].
+    Failsafe,
+    /// The YAML 1.2 JSON schema, which only resolves what JSON would.
+    ///
+    /// The differences with [`Schema::Core`] are:
+    ///   * Booleans are `true` and `false`, and nulls are `null`, in lowercase only.
+    ///   * Integers are decimal, without a leading `+` or `0` (e.g. `0x2A` and `007` are invalid).
+    ///   * Floats are written as in JSON (e.g. `-1.5e3`): `.inf` and `.nan` are invalid.
+    ///   * Anything else is invalid, i.e. [`Yaml::BadValue`], rather than a string: strings must
+    ///     be quoted.
+    ///
+    /// Scalars tagged `!!null`, `!!int` or `!!float` are invalid too if they are not written as in
+    /// JSON.
+    Json,
+}
+
+impl Schema {
+    /// Resolve an untagged plain scalar to a [`Yaml`] node.
+    ///
+    /// This falls back to [`Yaml::String`] if nothing else matches, except with [`Schema::Json`].
+    #[must_use]
+    pub fn resolve(self, v: &str) -> Yaml {
+        match self {
+            Schema::Core => Yaml::scalar_from_str(v),
+            Schema::Yaml11 => {
+                if is_null_11(v) {
+                    Yaml::Null
+                } else if let Some(v) = parse_bool_11(v) {
+                    Yaml::Boolean(v)
+                } else if let Some(v) = parse_int_11(v) {
+                    Yaml::Integer(v)
+                } else if (v.contains('.') && parse_f64(v).is_some()) || is_large_integer(v) {
+                    Yaml::Real(v.to_owned())
+                } else {
+                    Yaml::String(v.to_owned())
+                }
+            }
+            Schema::Failsafe => Yaml::String(v.to_owned()),
+            Schema::Json => match v {
+                "null" => Yaml::Null,
+                "true" => Yaml::Boolean(true),
+                "false" => Yaml::Boolean(false),
+                _ => {
+                    if let Some(v) = parse_int_json(v) {
+                        Yaml::Integer(v)
+                    } else if is_float_json(v) {
+                        Yaml::Real(v.to_owned())
+                    } else {
+                        Yaml::BadValue
+                    }
+                }
+            },
+        }
+    }
+
+    /// Return the name of the schema in `snake_case`, as used by `describe` methods and errors.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Schema::Core => "core",
+            Schema::Yaml11 => "yaml_1_1",
+            Schema::Failsafe => "failsafe",
+            Schema::Json => "json",
+        }
+    }
+}
+
+/// Return whether `v` is a decimal integer too large for an `i64`, e.g. `18446744073709551615`.
+///
+/// These are loaded as [`Yaml::Real`]s, which keep their text.
+pub(crate) fn is_large_integer(v: &str) -> bool {
+    let digits = v.strip_prefix(['-', '+']).unwrap_or(v);
+    digits.starts_with(|c: char| c.is_ascii_digit() && c != '0')
+        && digits.bytes().all(|c| c.is_ascii_digit())
+        && v.parse::<i64>().is_err()
+}
+
+/// Return whether `v` is a null in YAML 1.1.
+pub(crate) fn is_null_11(v: &str) -> bool {
+    matches!(v, "" | "~" | "null" | "Null" | "NULL")
+}
+
+/// Parse `v` as a YAML 1.1 boolean.
+pub(crate) fn parse_bool_11(v: &str) -> Option<bool> {
+    match v {
+        "y" | "Y" | "yes" | "Yes" | "YES" | "true" | "True" | "TRUE" | "on" | "On" | "ON" => {
+            Some(true)
+        }
+        "n" | "N" | "no" | "No" | "NO" | "false" | "False" | "FALSE" | "off" | "Off" | "OFF" => {
+            Some(false)
+        }
+        _ => None,
+    }
+}
+
+/// Parse `v` as a YAML 1.1 integer.
+pub(crate) fn parse_int_11(v: &str) -> Option<i64> {
+    let (negative, digits) = if let Some(digits) = v.strip_prefix('-') {
+        (true, digits)
+    } else {
+        (false, v.strip_prefix('+').unwrap_or(v))
+    };
+    if !digits.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let magnitude = if let Some(digits) = digits.strip_prefix("0b") {
+        parse_digits(digits, 2)?
+    } else if let Some(digits) = digits.strip_prefix("0x") {
+        parse_digits(digits, 16)?
+    } else if digits.contains(':') {
+        parse_sexagesimal(digits)?
+    } else if let Some(digits) = digits.strip_prefix('0').filter(|digits| !digits.is_empty()) {
+        parse_digits(digits, 8)?
+    } else {
+        parse_digits(digits, 10)?
+    };
+    if negative {
+        0i64.checked_sub_unsigned(magnitude)
+    } else {
+        i64::try_from(magnitude).ok()
+    }
+}
+
+/// Parse digits in the given radix, which may be separated by `_`.
+fn parse_digits(digits: &str, radix: u32) -> Option<u64> {
+    let digits: String = digits.chars().filter(|&c| c != '_').collect();
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+    u64::from_str_radix(&digits, radix).ok()
+}
+
+/// Parse a base 60 integer, e.g. `1:30:00`.
+fn parse_sexagesimal(digits: &str) -> Option<u64> {
+    let mut parts = digits.split(':');
+    let first = parts.next()?;
+    if first.starts_with('0') {
+        return None;
+    }
+    let mut value = parse_digits(first, 10)?;
+    for part in parts {
+        if part.is_empty() || part.len() > 2 || !part.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let part = part.parse::<u64>().ok().filter(|&part| part < 60)?;
+        value = value.checked_mul(60)?.checked_add(part)?;
+    }
+    Some(value)
+}
+
+/// Parse `v` as a JSON integer, e.g. `-12` but not `+12` or `012`.
+pub(crate) fn parse_int_json(v: &str) -> Option<i64> {
+    if json_int_len(v)? != v.len() {
+        return None;
+    }
+    v.parse().ok()
+}
+
+/// Return whether `v` is a JSON number, e.g. `-1.5e3`.
+pub(crate) fn is_float_json(v: &str) -> bool {
+    let Some(int) = json_int_len(v) else {
+        return false;
+    };
+    let mut rest = &v[int..];
+    if let Some(fraction) = rest.strip_prefix('.') {
+        rest = fraction.trim_start_matches(|c: char| c.is_ascii_digit());
+    }
+    if let Some(exponent) = rest.strip_prefix(['e', 'E']) {
+        let digits = exponent.strip_prefix(['-', '+']).unwrap_or(exponent);
+        return !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit());
+    }
+    rest.is_empty()
+}
+
+/// Return the length of the JSON integer `v` starts with, i.e. `-?(0|[1-9][0-9]*)`, if any.
+fn json_int_len(v: &str) -> Option<usize> {
+    let sign = usize::from(v.starts_with('-'));
+    let digits = &v[sign..];
+    if digits.starts_with('0') {
+        Some(sign + 1)
+    } else if digits.starts_with(|c: char| c.is_ascii_digit()) {
+        Some(
+            sign + digits.len()
+                - digits
+                    .trim_start_matches(|c: char| c.is_ascii_digit())
+                    .len(),
+        )
+    } else {
+        None
+    }
+}
@@ -0,0 +1,477 @@
+//! Serialization of Rust values to YAML with [`serde`].
+
+use alloc::string::{String, ToString};
+use core::fmt::{self, Display};
+
+use serde::ser::{self, Serialize};
+
+use crate::{Array, EmitError, Hash, Yaml, YamlEmitter};
+
+/// Serialize `value` to YAML text, with the default settings of [`YamlEmitter`].
+///
+/// ```
+/// # use serde::Serialize;
+/// #[derive(Serialize)]
+/// struct Config {
+///     name: String,
+///     ports: Vec<u16>,
+///     debug: Option<bool>,
+/// }
+///
+/// let config = Config {
+///     name: "web".into(),
+///     ports: vec![80, 443],
+///     debug: None,
+/// };
+/// assert_eq!(
+///     saphyr::to_string(&config).unwrap(),
+///     "---\nname: web\nports:\n  - 80\n  - 443\ndebug: ~"
+/// );
+/// ```
+///
+/// # Errors
+/// Returns an error if `value` fails to serialize.
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String, SerializeError> {
+    to_string_with(value, |_| {})
+}
+
+/// Serialize `value` to YAML text, with an emitter set up by `configure`.
+///
+/// ```
+/// # use std::collections::BTreeMap;
+/// let mut doc = BTreeMap::new();
+/// doc.insert("ports", vec![80, 443]);
+/// doc.insert("volumes", vec![]);
+/// let output = saphyr::to_string_with(&doc, |emitter| emitter.flow_depth(Some(1))).unwrap();
+/// assert_eq!(output, "---\nports: [80, 443]\nvolumes: []");
+/// ```
+///
+/// # Errors
+/// Returns an error if `value` fails to serialize, or if emission was cancelled through
+/// [`YamlEmitter::cancel_token`].
+pub fn to_string_with<T: Serialize + ?Sized>(
+    value: &T,
+    configure: impl FnOnce(&mut YamlEmitter),
+) -> Result<String, SerializeError> {
+    let doc = to_yaml_value(value)?;
+    let mut output = String::new();
+    let mut emitter = YamlEmitter::new(&mut output);
+    configure(&mut emitter);
+    emitter.dump(&doc).map_err(SerializeError::Emit)?;
+    Ok(output)
+}
+
+/// Serialize `value` to a [`Yaml`] node.
+///
+/// Structs and maps become [`Yaml::Hash`]es, in the order of their fields and entries, and
+/// sequences and tuples become [`Yaml::Array`]s. `None` and `()` become [`Yaml::Null`].
+/// Integers which do not fit in an `i64` become [`Yaml::Real`]s, as when they are loaded. Unit
+/// variants become their name, and the other variants a mapping from their name to their
+/// content. Bytes become a [`Yaml::Binary`] with the `binary` feature, and an array of integers
+/// otherwise.
+///
+/// ```
+/// # use saphyr::yaml;
+/// let value = saphyr::to_yaml_value(&(1, "two", [Some(3.5), None])).unwrap();
+/// assert_eq!(value, yaml!([1, "two", [3.5, null]]));
+/// ```
+///
+/// # Errors
+/// Returns an error if `value` fails to serialize.
+pub fn to_yaml_value<T: Serialize + ?Sized>(value: &T) -> Result<Yaml, SerializeError> {
+    value.serialize(Serializer)
+}
+
+/// An error when serializing a value to YAML.
+#[derive(Debug)]
+pub enum SerializeError {
+    /// The value failed to serialize, e.g. its [`Serialize`] implementation reported an error.
+    Message(String),
+    /// The emitter failed to write the YAML text.
+    Emit(EmitError),
+}
+
+impl std::error::Error for SerializeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SerializeError::Message(_) => None,
+            SerializeError::Emit(error) => Some(error),
+        }
+    }
+}
+
+impl Display for SerializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SerializeError::Message(message) => f.write_str(message),
+            SerializeError::Emit(error) => error.fmt(f),
+        }
+    }
+}
+
+impl ser::Error for SerializeError {
+    fn custom<T: Display>(message: T) -> Self {
+        SerializeError::Message(message.to_string())
+    }
+}
+
+/// A serializer building [`Yaml`] nodes. See [`to_yaml_value`].
+struct Serializer;
+
+/// Return a mapping from the name of `variant` to its content.
+fn variant(variant: &'static str, content: Yaml) -> Yaml {
+    let mut hash = Hash::new();
+    hash.insert(Yaml::String(variant.into()), content);
+    Yaml::Hash(hash)
+}
+
+/// Return `value` as a [`Yaml::Integer`] if it fits, and as a [`Yaml::Real`] otherwise.
+fn integer<T: Copy + Display + TryInto<i64>>(value: T) -> Yaml {
+    value
+        .try_into()
+        .map_or_else(|_| Yaml::Real(value.to_string()), Yaml::Integer)
+}
+
+impl ser::Serializer for Serializer {
+    type Ok = Yaml;
+    type Error = SerializeError;
+    type SerializeSeq = SerializeArray;
+    type SerializeTuple = SerializeArray;
+    type SerializeTupleStruct = SerializeArray;
+    type SerializeTupleVariant = SerializeArray;
+    type SerializeMap = SerializeHash;
+    type SerializeStruct = SerializeHash;
+    type SerializeStructVariant = SerializeHash;
+
+    fn serialize_bool(self, v: bool) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::Boolean(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::Integer(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Yaml, SerializeError> {
+        Ok(integer(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Yaml, SerializeError> {
+        Ok(integer(v))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Yaml, SerializeError> {
+        Ok(integer(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::from(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::from(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::String(v.into()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::String(v.into()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Yaml, SerializeError> {
+        #[cfg(feature = "binary")]
+        let bytes = Yaml::Binary(v.to_vec());
+        #[cfg(not(feature = "binary"))]
+        let bytes = Yaml::Array(v.iter().map(|&byte| Yaml::from(byte)).collect());
+        Ok(bytes)
+    }
+
+    fn serialize_none(self) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::Null)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Yaml, SerializeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::String(variant.into()))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Yaml, SerializeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Yaml, SerializeError> {
+        Ok(variant(name, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeArray, SerializeError> {
+        Ok(SerializeArray {
+            items: Array::with_capacity(len.unwrap_or(0)),
+            variant: None,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeArray, SerializeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeArray, SerializeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeArray, SerializeError> {
+        Ok(SerializeArray {
+            items: Array::with_capacity(len),
+            variant: Some(variant),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<SerializeHash, SerializeError> {
+        Ok(SerializeHash {
+            entries: Hash::new(),
+            key: None,
+            variant: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeHash, SerializeError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SerializeHash, SerializeError> {
+        Ok(SerializeHash {
+            entries: Hash::new(),
+            key: None,
+            variant: Some(variant),
+        })
+    }
+}
+
+/// The items of a sequence, tuple or tuple variant being serialized.
+struct SerializeArray {
+    items: Array,
+    /// The name of the variant, for tuple variants.
+    variant: Option<&'static str>,
+}
+
+impl SerializeArray {
+    fn push<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), SerializeError> {
+        self.items.push(value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn into_yaml(self) -> Yaml {
+        let array = Yaml::Array(self.items);
+        match self.variant {
+            Some(name) => variant(name, array),
+            None => array,
+        }
+    }
+}
+
+impl ser::SerializeSeq for SerializeArray {
+    type Ok = Yaml;
+    type Error = SerializeError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Yaml, SerializeError> {
+        Ok(self.into_yaml())
+    }
+}
+
+impl ser::SerializeTuple for SerializeArray {
+    type Ok = Yaml;
+    type Error = SerializeError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Yaml, SerializeError> {
+        Ok(self.into_yaml())
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeArray {
+    type Ok = Yaml;
+    type Error = SerializeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Yaml, SerializeError> {
+        Ok(self.into_yaml())
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeArray {
+    type Ok = Yaml;
+    type Error = SerializeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Yaml, SerializeError> {
+        Ok(self.into_yaml())
+    }
+}
+
+/// The entries of a map, struct or struct variant being serialized.
+struct SerializeHash {
+    entries: Hash,
+    /// The key of the entry whose value is next, for maps.
+    key: Option<Yaml>,
+    /// The name of the variant, for struct variants.
+    variant: Option<&'static str>,
+}
+
+impl SerializeHash {
+    fn insert<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerializeError> {
+        self.entries
+            .insert(Yaml::String(key.into()), value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn into_yaml(self) -> Yaml {
+        let hash = Yaml::Hash(self.entries);
+        match self.variant {
+            Some(name) => variant(name, hash),
+            None => hash,
+        }
+    }
+}
+
+impl ser::SerializeMap for SerializeHash {
+    type Ok = Yaml;
+    type Error = SerializeError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Self::Error> {
+        self.key = Some(key.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        let key = self.key.take().ok_or_else(|| {
+            SerializeError::Message("a map value was serialized before its key".into())
+        })?;
+        self.entries.insert(key, value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Yaml, SerializeError> {
+        Ok(self.into_yaml())
+    }
+}
+
+impl ser::SerializeStruct for SerializeHash {
+    type Ok = Yaml;
+    type Error = SerializeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Yaml, SerializeError> {
+        Ok(self.into_yaml())
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeHash {
+    type Ok = Yaml;
+    type Error = SerializeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Yaml, SerializeError> {
+        Ok(self.into_yaml())
+    }
+}
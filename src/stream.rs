@@ -0,0 +1,210 @@
+//! Lazy parsing and loading of YAML streams.
+
+use alloc::{borrow::ToOwned, boxed::Box, vec::Vec};
+
+use saphyr_parser::{BufferedInput, Event, Marker, Parser, ScanError, Span, SpannedEventReceiver};
+
+use crate::{Yaml, YamlLoader};
+
+/// An iterator over the documents of a YAML stream, as returned by [`Yaml::load_all_from_str`].
+///
+/// Each document is loaded when it is reached, so that only one is held at a time.
+pub struct DocumentIter<'a> {
+    /// The events of the stream.
+    events: Box<dyn Iterator<Item = Result<(Event, Span), ScanError>> + 'a>,
+    /// The loader the events are fed to, which holds the document being loaded.
+    loader: YamlLoader<Yaml>,
+    /// Whether the stream has ended or failed, after which no document is yielded.
+    done: bool,
+}
+
+/// Parse `source` into the events of the YAML stream it holds, along with their spans.
+///
+/// This is the pull-style counterpart of feeding a [`YamlLoader`] or another
+/// [`SpannedEventReceiver`]: events are parsed as they are requested. The iteration ends after
+/// [`Event::StreamEnd`], or after the first error.
+///
+/// ```
+/// use saphyr::{Event, TScalarStyle};
+///
+/// let mut keys = vec![];
+/// for event in saphyr::parse_events("a: 1\nb: [2]") {
+///     let (event, span) = event.unwrap();
+///     if let Event::Scalar(value, TScalarStyle::Plain, ..) = event {
+///         keys.push((value, span.start.line()));
+///     }
+/// }
+/// assert_eq!(keys[2], ("b".to_owned(), 2));
+///
+/// let events: Vec<_> = saphyr::parse_events("[1").collect();
+/// assert!(events.last().unwrap().is_err());
+/// ```
+pub fn parse_events(source: &str) -> impl Iterator<Item = Result<(Event, Span), ScanError>> + '_ {
+    let mut parser = Parser::new(BufferedInput::new(source.chars()));
+    let mut done = false;
+    core::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+        let next = parser.next()?;
+        done = matches!(next, Ok((Event::StreamEnd, _)) | Err(_));
+        Some(next)
+    })
+}
+
+impl Yaml {
+    /// Load the given string as YAML documents, one at a time.
+    ///
+    /// Unlike [`Self::load_from_str`], each document is yielded as soon as it has been parsed,
+    /// without waiting for the rest of the stream. An error is yielded in place of the document
+    /// in which it occurred, and ends the iteration: the documents before it are still yielded.
+    ///
+    /// ```
+    /// use saphyr::Yaml;
+    ///
+    /// let mut docs = Yaml::load_all_from_str("a: 1\n---\nb: 2\n---\nc: [3]]");
+    /// assert_eq!(docs.next().unwrap().unwrap()["a"].as_i64(), Some(1));
+    /// assert_eq!(docs.next().unwrap().unwrap()["b"].as_i64(), Some(2));
+    /// assert_eq!(docs.next().unwrap().unwrap_err().marker().line(), 5);
+    /// assert!(docs.next().is_none());
+    /// ```
+    #[must_use]
+    pub fn load_all_from_str(source: &str) -> DocumentIter<'_> {
+        DocumentIter {
+            events: Box::new(parse_events(source)),
+            loader: YamlLoader::default(),
+            done: false,
+        }
+    }
+
+    /// Load the given string as YAML documents, skipping the documents in which errors occur.
+    ///
+    /// Unlike [`Self::load_from_str_partial`], loading does not stop at the first error: it
+    /// resumes at the next document marker (`---` or `...` at the start of a line) after it, so
+    /// that every error of the stream is reported rather than only the first one. The documents
+    /// in which errors occurred are not returned. The positions of the errors are relative to the
+    /// whole source.
+    ///
+    /// An error which hides the document markers after it (e.g. an unterminated quoted string)
+    /// hides the errors after them as well.
+    ///
+    /// ```
+    /// use saphyr::Yaml;
+    ///
+    /// let (docs, errors) = Yaml::load_from_str_resilient("a: b: c\n---\nb: 2\n---\nc: d: e\n");
+    /// assert_eq!(docs.len(), 1);
+    /// assert_eq!(docs[0]["b"].as_i64(), Some(2));
+    /// assert_eq!(errors.len(), 2);
+    /// assert_eq!(errors[1].marker().line(), 5);
+    /// ```
+    #[must_use]
+    pub fn load_from_str_resilient(source: &str) -> (Vec<Self>, Vec<ScanError>) {
+        let boundaries = document_boundaries(source);
+        let mut docs = vec![];
+        let mut errors = vec![];
+        let mut start = 0;
+        loop {
+            let chunk = &source[start..];
+            let (chunk_docs, error) = Self::load_from_str_partial(chunk);
+            docs.extend(chunk_docs);
+            let Some(error) = error else {
+                break;
+            };
+            let marker = error.marker();
+            let end = start
+                + chunk
+                    .char_indices()
+                    .nth(marker.index())
+                    .map_or(chunk.len(), |(i, _)| i);
+            let skipped = &source[..start];
+            errors.push(ScanError::new(
+                Marker::new(
+                    marker.index() + skipped.chars().count(),
+                    marker.line() + skipped.matches('\n').count(),
+                    marker.col(),
+                ),
+                error.info().to_owned(),
+            ));
+            match boundaries
+                .iter()
+                .find(|&&boundary| boundary > start && boundary >= end)
+            {
+                Some(&boundary) => start = boundary,
+                None => break,
+            }
+        }
+        (docs, errors)
+    }
+}
+
+impl Iterator for DocumentIter<'_> {
+    type Item = Result<Yaml, ScanError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let (event, span) = match self.events.next() {
+                Some(Ok(event)) => event,
+                Some(Err(error)) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+                None => break,
+            };
+            let end = matches!(event, Event::DocumentEnd);
+            self.done = matches!(event, Event::StreamEnd);
+            self.loader.on_event(event, span);
+            if let Err(error) = self.loader.take_scan_error() {
+                self.done = true;
+                return Some(Err(error));
+            }
+            if end {
+                return self.loader.take_documents().pop().map(Ok);
+            }
+        }
+        self.done = true;
+        None
+    }
+}
+
+/// Return the byte offsets, in order, of the starts of lines between which `source` can be split
+/// into streams of whole documents.
+///
+/// These are the lines which start a document with a `---` marker, unless directives precede
+/// it, and the lines following a `...` marker.
+pub(crate) fn document_boundaries(source: &str) -> Vec<usize> {
+    let mut boundaries = vec![];
+    // Whether the current line is between documents, where directives may be.
+    let mut in_prefix = true;
+    // Whether directives were read since the end of the last document.
+    let mut directives = false;
+    let mut start = 0;
+    for line in source.split_inclusive('\n') {
+        if is_marker(line, "...") {
+            in_prefix = true;
+            directives = false;
+            boundaries.push(start + line.len());
+        } else if is_marker(line, "---") {
+            if !directives && start > 0 {
+                boundaries.push(start);
+            }
+            in_prefix = false;
+            directives = false;
+        } else if in_prefix {
+            let content = line.trim_start();
+            if line.starts_with('%') {
+                directives = true;
+            } else if !content.is_empty() && !content.starts_with('#') {
+                in_prefix = false;
+            }
+        }
+        start += line.len();
+    }
+    boundaries.dedup();
+    boundaries
+}
+
+/// Return whether `line` starts with the document marker `marker`, i.e. `---` or `...`.
+fn is_marker(line: &str, marker: &str) -> bool {
+    line.strip_prefix(marker)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t', '\r', '\n']))
+}
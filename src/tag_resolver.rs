@@ -0,0 +1,127 @@
+//! Building custom nodes from tagged scalars and collections.
+
+use alloc::sync::Arc;
+use core::fmt;
+
+use saphyr_parser::{TScalarStyle, Tag};
+
+use crate::{LoadError, Yaml};
+
+/// How the loader builds the nodes of some tags (see [`LoaderOptions::tag_resolver`]).
+///
+/// The loader calls the resolver for every tagged node, before resolving it with its schema.
+/// Resolvers return `Ok(None)` for the tags they do not handle, which the loader then resolves
+/// as usual. The nodes they return replace the tagged nodes as they are: the loader does not
+/// resolve their tags, nor keep them (see [`LoaderOptions::keep_tags`]).
+///
+/// Tags are given with their handle expanded, e.g. `!!str` as the handle `tag:yaml.org,2002:`
+/// and the suffix `str`, while `!secret` keeps the handle `!`.
+///
+/// ```
+/// # use saphyr::{LoadError, LoaderOptions, SharedTagResolver, TScalarStyle, Tag, TagResolver, Yaml};
+/// struct Secrets;
+///
+/// impl TagResolver for Secrets {
+///     fn resolve_scalar(
+///         &self,
+///         tag: &Tag,
+///         _: &str,
+///         _: TScalarStyle,
+///     ) -> Result<Option<Yaml>, LoadError> {
+///         let is_secret = tag.handle == "!" && tag.suffix == "secret";
+///         Ok(is_secret.then(|| Yaml::String("<redacted>".into())))
+///     }
+/// }
+///
+/// let options = LoaderOptions {
+///     tag_resolver: Some(SharedTagResolver::new(Secrets)),
+///     ..LoaderOptions::default()
+/// };
+/// let docs = Yaml::load_from_str_with_options("password: !secret hunter2", options).unwrap();
+/// assert_eq!(docs[0]["password"].as_str(), Some("<redacted>"));
+/// ```
+///
+/// [`LoaderOptions::tag_resolver`]: crate::LoaderOptions::tag_resolver
+/// [`LoaderOptions::keep_tags`]: crate::LoaderOptions::keep_tags
+pub trait TagResolver: Send + Sync {
+    /// Return the node for a scalar with `tag`, written as `value` in `style`, or `None` to
+    /// load it as usual. The default implementation returns `None`.
+    ///
+    /// # Errors
+    /// Returns an error if the scalar is invalid for its tag. Loading fails with that error,
+    /// within a [`LoadError::TagResolution`].
+    fn resolve_scalar(
+        &self,
+        _tag: &Tag,
+        _value: &str,
+        _style: TScalarStyle,
+    ) -> Result<Option<Yaml>, LoadError> {
+        Ok(None)
+    }
+
+    /// Return the node for a sequence or mapping with `tag`, or `None` to load it as usual.
+    /// The default implementation returns `None`.
+    ///
+    /// The loader calls this once the collection is complete, with its items already resolved.
+    /// Node types other than [`Yaml`] give the collection as [`LoadableYamlNode::to_bare_yaml`]
+    /// returns it.
+    ///
+    /// # Errors
+    /// Returns an error if the collection is invalid for its tag. Loading fails with that error,
+    /// within a [`LoadError::TagResolution`].
+    ///
+    /// [`LoadableYamlNode::to_bare_yaml`]: crate::LoadableYamlNode::to_bare_yaml
+    fn resolve_collection(&self, _tag: &Tag, _collection: Yaml) -> Result<Option<Yaml>, LoadError> {
+        Ok(None)
+    }
+}
+
+/// A [`TagResolver`] shared by the copies of [`LoaderOptions`].
+///
+/// Copies are equal if they share the same resolver.
+///
+/// [`LoaderOptions`]: crate::LoaderOptions
+#[derive(Clone)]
+pub struct SharedTagResolver(Arc<dyn TagResolver>);
+
+impl SharedTagResolver {
+    /// Share `resolver`.
+    #[must_use]
+    pub fn new(resolver: impl TagResolver + 'static) -> Self {
+        Self(Arc::new(resolver))
+    }
+
+    /// Return the node `self` builds for a scalar (see [`TagResolver::resolve_scalar`]).
+    pub(crate) fn resolve_scalar(
+        &self,
+        tag: &Tag,
+        value: &str,
+        style: TScalarStyle,
+    ) -> Result<Option<Yaml>, LoadError> {
+        self.0.resolve_scalar(tag, value, style)
+    }
+
+    /// Return the node `self` builds for a collection (see
+    /// [`TagResolver::resolve_collection`]).
+    pub(crate) fn resolve_collection(
+        &self,
+        tag: &Tag,
+        collection: Yaml,
+    ) -> Result<Option<Yaml>, LoadError> {
+        self.0.resolve_collection(tag, collection)
+    }
+}
+
+impl fmt::Debug for SharedTagResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SharedTagResolver(..)")
+    }
+}
+
+impl PartialEq for SharedTagResolver {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SharedTagResolver {}
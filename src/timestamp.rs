@@ -0,0 +1,240 @@
+//! YAML 1.1 timestamps.
+
+use core::fmt::{self, Display};
+
+/// A YAML 1.1 [timestamp](https://yaml.org/type/timestamp.html).
+///
+/// A timestamp is either a date alone (`2002-12-14`) or a date and a time of day, with an
+/// optional fraction of a second and offset from UTC (`2001-12-14t21:59:43.10-05:00`). Timestamps
+/// without an offset are in UTC.
+///
+/// Comparisons are made field by field: two timestamps denoting the same instant with different
+/// offsets are different.
+///
+/// ```
+/// # use saphyr::Timestamp;
+/// let timestamp = Timestamp::parse("2001-12-14 21:59:43.10 -5").unwrap();
+/// assert_eq!(timestamp.day, 14);
+/// assert_eq!(timestamp.time.unwrap().offset_minutes, -300);
+/// assert_eq!(timestamp.to_string(), "2001-12-14T21:59:43.1-05:00");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp {
+    /// The year, from 0 to 9999.
+    pub year: u16,
+    /// The month, from 1 to 12.
+    pub month: u8,
+    /// The day of the month, from 1 to 31.
+    pub day: u8,
+    /// The time of day, unless the timestamp is a date alone.
+    pub time: Option<TimeOfDay>,
+}
+
+/// The time of day of a [`Timestamp`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TimeOfDay {
+    /// The hour, from 0 to 23.
+    pub hour: u8,
+    /// The minute, from 0 to 59.
+    pub minute: u8,
+    /// The second, from 0 to 60 (to allow for leap seconds).
+    pub second: u8,
+    /// The fraction of the second, in nanoseconds. Digits beyond the nanosecond are dropped.
+    pub nanosecond: u32,
+    /// The offset from UTC, in minutes. This is 0 for `Z` or if there is no offset.
+    pub offset_minutes: i16,
+}
+
+impl Timestamp {
+    /// Parse a timestamp in any of the formats allowed by YAML 1.1.
+    ///
+    /// Returns `None` if `v` is not a valid timestamp, e.g. if it denotes February 30th.
+    #[must_use]
+    pub fn parse(v: &str) -> Option<Self> {
+        let mut cursor = Cursor(v.as_bytes());
+        let year = cursor.number(4, 4)?;
+        cursor.expect(b'-')?;
+        let (month, month_digits) = cursor.number_with_digits(1, 2)?;
+        cursor.expect(b'-')?;
+        let (day, day_digits) = cursor.number_with_digits(1, 2)?;
+
+        let time = if cursor.is_empty() {
+            // A date alone must have two-digit months and days.
+            if month_digits != 2 || day_digits != 2 {
+                return None;
+            }
+            None
+        } else {
+            Some(cursor.time_of_day()?)
+        };
+        let timestamp = Timestamp {
+            year: u16::try_from(year).ok()?,
+            month: u8::try_from(month).ok()?,
+            day: u8::try_from(day).ok()?,
+            time,
+        };
+        timestamp.is_valid().then_some(timestamp)
+    }
+
+    /// Return whether all the fields of `self` are within their ranges.
+    fn is_valid(&self) -> bool {
+        let leap = matches!(
+            (self.year % 4, self.year % 100, self.year % 400),
+            (0, 1.., _) | (_, _, 0)
+        );
+        let days_in_month = match self.month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if leap => 29,
+            2 => 28,
+            _ => return false,
+        };
+        let time_is_valid = match self.time {
+            None => true,
+            Some(time) => {
+                time.hour <= 23
+                    && time.minute <= 59
+                    && time.second <= 60
+                    && time.offset_minutes.abs() < 24 * 60
+            }
+        };
+        (1..=days_in_month).contains(&self.day) && time_is_valid
+    }
+}
+
+impl Display for Timestamp {
+    /// Write the timestamp in canonical form, e.g. `2001-12-14T21:59:43.1-05:00`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)?;
+        let Some(time) = self.time else {
+            return Ok(());
+        };
+        write!(f, "T{:02}:{:02}:{:02}", time.hour, time.minute, time.second)?;
+        if time.nanosecond > 0 {
+            let fraction = format!("{:09}", time.nanosecond);
+            write!(f, ".{}", fraction.trim_end_matches('0'))?;
+        }
+        if time.offset_minutes == 0 {
+            f.write_str("Z")
+        } else {
+            let sign = if time.offset_minutes < 0 { '-' } else { '+' };
+            let offset = time.offset_minutes.unsigned_abs();
+            write!(f, "{sign}{:02}:{:02}", offset / 60, offset % 60)
+        }
+    }
+}
+
+/// The remaining bytes of a timestamp being parsed.
+struct Cursor<'a>(&'a [u8]);
+
+impl Cursor<'_> {
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.0.first().copied()
+    }
+
+    fn bump(&mut self) {
+        self.0 = &self.0[1..];
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        (self.peek()? == byte).then(|| self.bump())
+    }
+
+    fn skip_blanks(&mut self) -> usize {
+        let mut count = 0;
+        while matches!(self.peek(), Some(b' ' | b'\t')) {
+            self.bump();
+            count += 1;
+        }
+        count
+    }
+
+    /// Read a number of `min` to `max` digits, and return it along with its number of digits.
+    fn number_with_digits(&mut self, min: usize, max: usize) -> Option<(u32, usize)> {
+        let mut value = 0;
+        let mut digits = 0;
+        while digits < max {
+            match self.peek() {
+                Some(byte @ b'0'..=b'9') => {
+                    value = value * 10 + u32::from(byte - b'0');
+                    digits += 1;
+                    self.bump();
+                }
+                _ => break,
+            }
+        }
+        (digits >= min).then_some((value, digits))
+    }
+
+    fn number(&mut self, min: usize, max: usize) -> Option<u32> {
+        self.number_with_digits(min, max).map(|(value, _)| value)
+    }
+
+    /// Read the part of a timestamp after the date, including its separator.
+    fn time_of_day(&mut self) -> Option<TimeOfDay> {
+        if matches!(self.peek(), Some(b'T' | b't')) {
+            self.bump();
+        } else if self.skip_blanks() == 0 {
+            return None;
+        }
+        let hour = self.number(1, 2)?;
+        self.expect(b':')?;
+        let minute = self.number(2, 2)?;
+        self.expect(b':')?;
+        let second = self.number(2, 2)?;
+
+        let mut nanosecond = 0;
+        if self.peek() == Some(b'.') {
+            self.bump();
+            let mut scale = 100_000_000;
+            while let Some(byte @ b'0'..=b'9') = self.peek() {
+                nanosecond += u32::from(byte - b'0') * scale;
+                scale /= 10;
+                self.bump();
+            }
+        }
+
+        self.skip_blanks();
+        let offset_minutes = match self.peek() {
+            None => 0,
+            Some(b'Z') => {
+                self.bump();
+                0
+            }
+            Some(sign @ (b'+' | b'-')) => {
+                self.bump();
+                let hours = self.number(1, 2)?;
+                let minutes = if self.peek() == Some(b':') {
+                    self.bump();
+                    self.number(2, 2)?
+                } else {
+                    0
+                };
+                if minutes > 59 {
+                    return None;
+                }
+                let offset = i16::try_from(hours * 60 + minutes).ok()?;
+                if sign == b'-' {
+                    -offset
+                } else {
+                    offset
+                }
+            }
+            Some(_) => return None,
+        };
+        if !self.is_empty() {
+            return None;
+        }
+        Some(TimeOfDay {
+            hour: u8::try_from(hour).ok()?,
+            minute: u8::try_from(minute).ok()?,
+            second: u8::try_from(second).ok()?,
+            nanosecond,
+            offset_minutes,
+        })
+    }
+}
@@ -0,0 +1,133 @@
+//! Iterators over the children and descendants of a [`Yaml`] node.
+
+use alloc::vec::Vec;
+
+use crate::{PathSegment, Yaml, YamlPath};
+
+impl Yaml {
+    /// Return an iterator over the items of `self` if it is a sequence.
+    ///
+    /// The iterator is empty for mappings and scalars. See [`Self::iter_entries`] for mappings.
+    pub fn iter(&self) -> core::slice::Iter<'_, Yaml> {
+        match self {
+            Yaml::Array(items) => items.iter(),
+            _ => [].iter(),
+        }
+    }
+
+    /// Return an iterator over the items of `self` if it is a sequence, for modification.
+    ///
+    /// The iterator is empty for mappings and scalars.
+    pub fn iter_mut(&mut self) -> core::slice::IterMut<'_, Yaml> {
+        match self {
+            Yaml::Array(items) => items.iter_mut(),
+            _ => [].iter_mut(),
+        }
+    }
+
+    /// Return an iterator over the keys and values of `self` if it is a mapping, in order.
+    ///
+    /// The iterator is empty for sequences and scalars.
+    #[must_use]
+    pub fn iter_entries(&self) -> YamlEntries<'_> {
+        YamlEntries {
+            entries: self.as_hash().map(|hash| hash.iter()),
+        }
+    }
+
+    /// Return a depth-first iterator over `self` and all the nodes within it, with their paths.
+    ///
+    /// Nodes are visited before their children. Mapping keys are visited too, before their
+    /// value: a key is designated by a [`PathSegment::EntryKey`] and a value by a
+    /// [`PathSegment::Key`].
+    ///
+    /// ```
+    /// use saphyr::Yaml;
+    ///
+    /// let doc = Yaml::load_one_from_str("a: [1, !!int x]\nb: {c: !!float y}").unwrap();
+    /// let bad: Vec<String> = doc
+    ///     .walk()
+    ///     .filter(|(_, node)| node.is_badvalue())
+    ///     .map(|(path, _)| path.to_string())
+    ///     .collect();
+    /// assert_eq!(bad, ["/a/1", "/b/c"]);
+    /// ```
+    #[must_use]
+    pub fn walk(&self) -> YamlWalk<'_> {
+        YamlWalk {
+            pending: vec![(YamlPath::new(), self)],
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Yaml {
+    type Item = &'a Yaml;
+    type IntoIter = core::slice::Iter<'a, Yaml>;
+
+    /// See [`Yaml::iter`].
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Yaml {
+    type Item = &'a mut Yaml;
+    type IntoIter = core::slice::IterMut<'a, Yaml>;
+
+    /// See [`Yaml::iter_mut`].
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// An iterator over the entries of a mapping, returned by [`Yaml::iter_entries`].
+pub struct YamlEntries<'a> {
+    entries: Option<hashlink::linked_hash_map::Iter<'a, Yaml, Yaml>>,
+}
+
+impl<'a> Iterator for YamlEntries<'a> {
+    type Item = (&'a Yaml, &'a Yaml);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.as_mut()?.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries
+            .as_ref()
+            .map_or((0, Some(0)), Iterator::size_hint)
+    }
+}
+
+/// A depth-first iterator over a node and its descendants, returned by [`Yaml::walk`].
+pub struct YamlWalk<'a> {
+    /// The nodes left to visit, the next one last.
+    pending: Vec<(YamlPath, &'a Yaml)>,
+}
+
+impl<'a> Iterator for YamlWalk<'a> {
+    type Item = (YamlPath, &'a Yaml);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (path, node) = self.pending.pop()?;
+        // The children of a tagged node are those of the node it tags.
+        match node.untagged() {
+            Yaml::Array(items) => {
+                for (index, item) in items.iter().enumerate().rev() {
+                    self.pending
+                        .push((path.join(PathSegment::Index(index)), item));
+                }
+            }
+            Yaml::Hash(entries) => {
+                for (index, (key, value)) in entries.iter().enumerate().rev() {
+                    self.pending
+                        .push((path.join(PathSegment::Key(key.clone())), value));
+                    self.pending
+                        .push((path.join(PathSegment::EntryKey(index)), key));
+                }
+            }
+            _ => {}
+        }
+        Some((path, node))
+    }
+}
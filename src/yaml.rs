@@ -2,12 +2,26 @@
 
 #![allow(clippy::module_name_repetitions)]
 
-use std::{convert::TryFrom, ops::Index, ops::IndexMut};
+use alloc::{
+    borrow::{Cow, ToOwned},
+    boxed::Box,
+    string::String,
+    sync::Arc,
+    vec::Vec,
+};
+use core::cmp::Ordering;
+use core::hash::Hasher;
+use core::{convert::TryFrom, ops::Index, ops::IndexMut, str::FromStr};
 
 use hashlink::LinkedHashMap;
-use saphyr_parser::{BufferedInput, Input, Parser, ScanError};
+use saphyr_parser::{BufferedInput, Input, Parser, ScanError, Tag};
 
-use crate::{loader::parse_f64, YamlLoader};
+use crate::Float;
+use crate::Timestamp;
+use crate::{
+    loader::{load_non_empty, load_single, load_with_options, parse_f64},
+    DocumentMetadata, LoadError, LoaderOptions, YamlLoader,
+};
 
 /// A YAML node is stored as this `Yaml` enumeration, which provides an easy way to
 /// access your YAML document.
@@ -16,7 +30,7 @@ use crate::{loader::parse_f64, YamlLoader};
 ///
 /// ```
 /// use saphyr::Yaml;
-/// let foo = Yaml::from_str("-123"); // convert the string to the appropriate YAML type
+/// let foo = Yaml::scalar_from_str("-123"); // convert the string to the appropriate YAML type
 /// assert_eq!(foo.as_i64().unwrap(), -123);
 ///
 /// // iterate over an Array
@@ -25,23 +39,47 @@ use crate::{loader::parse_f64, YamlLoader};
 ///     assert!(v.as_i64().is_some());
 /// }
 /// ```
-#[derive(Clone, PartialEq, PartialOrd, Debug, Eq, Ord, Hash)]
+#[derive(Clone, Debug)]
 pub enum Yaml {
     /// Float types are stored as String and parsed on demand.
     /// Note that `f64` does NOT implement Eq trait and can NOT be stored in `BTreeMap`.
     Real(String),
+    /// A float stored as an `f64`, compared bit by bit.
+    ///
+    /// The loader only loads floats as such, rather than as [`Yaml::Real`], with the `floats`
+    /// feature and `LoaderOptions::parse_floats`.
+    Float(Float),
     /// YAML int is stored as i64.
     Integer(i64),
     /// YAML scalar.
     String(String),
+    /// A string whose storage is shared with other nodes, e.g. a mapping key loaded with
+    /// [`LoaderOptions::intern_keys`].
+    ///
+    /// It is otherwise the same as a [`Yaml::String`] with the same text: they are equal, hash and
+    /// order the same, and [`Yaml::as_str`] returns the text of both.
+    SharedString(Arc<str>),
     /// YAML bool, e.g. `true` or `false`.
     Boolean(bool),
+    /// YAML 1.1 timestamp, e.g. `2001-12-14t21:59:43.10-05:00`.
+    ///
+    /// The loader only loads timestamps with the `timestamps` feature, which also adds
+    /// `LoaderOptions::resolve_timestamps`.
+    Timestamp(Timestamp),
+    /// YAML 1.1 binary data, loaded from scalars tagged `!!binary` with the `binary` feature and
+    /// written in base64.
+    Binary(Vec<u8>),
     /// YAML array, can be accessed as a `Vec`.
     Array(Array),
     /// YAML hash, can be accessed as a `LinkedHashMap`.
     ///
     /// Insertion order will match the order of insertion into the map.
     Hash(Hash),
+    /// A node with a tag which the loader does not resolve, e.g. `!Ref` or `!!python/object`.
+    ///
+    /// See [`LoaderOptions::keep_tags`] for when nodes are loaded as such. The tagged node is
+    /// loaded as if it had no tag, and is not itself `Tagged`: a node has at most one tag.
+    Tagged(Tag, Box<Yaml>),
     /// Alias, not fully supported yet.
     Alias(usize),
     /// YAML null, e.g. `null` or `~`.
@@ -52,10 +90,152 @@ pub enum Yaml {
     BadValue,
 }
 
+impl Yaml {
+    /// The position of the variant in declaration order, where a [`Yaml::SharedString`] is a
+    /// [`Yaml::String`] so that the two compare the same.
+    fn rank(&self) -> u8 {
+        match self {
+            Yaml::Real(_) => 0,
+            Yaml::Float(_) => 1,
+            Yaml::Integer(_) => 2,
+            Yaml::String(_) | Yaml::SharedString(_) => 3,
+            Yaml::Boolean(_) => 4,
+            Yaml::Timestamp(_) => 5,
+            Yaml::Binary(_) => 6,
+            Yaml::Array(_) => 7,
+            Yaml::Hash(_) => 8,
+            Yaml::Tagged(..) => 9,
+            Yaml::Alias(_) => 10,
+            Yaml::Null => 11,
+            Yaml::BadValue => 12,
+        }
+    }
+}
+
+impl PartialEq for Yaml {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Yaml {}
+
+impl PartialOrd for Yaml {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Yaml {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Yaml::Real(a), Yaml::Real(b)) => a.cmp(b),
+            (Yaml::Float(a), Yaml::Float(b)) => a.cmp(b),
+            (Yaml::Integer(a), Yaml::Integer(b)) => a.cmp(b),
+            (Yaml::Boolean(a), Yaml::Boolean(b)) => a.cmp(b),
+            (Yaml::Timestamp(a), Yaml::Timestamp(b)) => a.cmp(b),
+            (Yaml::Binary(a), Yaml::Binary(b)) => a.cmp(b),
+            (Yaml::Array(a), Yaml::Array(b)) => a.cmp(b),
+            (Yaml::Hash(a), Yaml::Hash(b)) => a.cmp(b),
+            (Yaml::Tagged(a, x), Yaml::Tagged(b, y)) => (a, x).cmp(&(b, y)),
+            (Yaml::Alias(a), Yaml::Alias(b)) => a.cmp(b),
+            _ => match (self.as_str(), other.as_str()) {
+                (Some(a), Some(b)) => a.cmp(b),
+                _ => self.rank().cmp(&other.rank()),
+            },
+        }
+    }
+}
+
+impl core::hash::Hash for Yaml {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rank().hash(state);
+        match self {
+            Yaml::Real(v) => v.hash(state),
+            Yaml::Float(v) => v.hash(state),
+            Yaml::Integer(v) => v.hash(state),
+            Yaml::String(v) => v.as_str().hash(state),
+            Yaml::SharedString(v) => (**v).hash(state),
+            Yaml::Boolean(v) => v.hash(state),
+            Yaml::Timestamp(v) => v.hash(state),
+            Yaml::Binary(v) => v.hash(state),
+            Yaml::Array(v) => v.hash(state),
+            Yaml::Hash(v) => v.hash(state),
+            Yaml::Tagged(tag, v) => (tag, v).hash(state),
+            Yaml::Alias(v) => v.hash(state),
+            Yaml::Null | Yaml::BadValue => {}
+        }
+    }
+}
+
 /// The type contained in the `Yaml::Array` variant. This corresponds to YAML sequences.
 pub type Array = Vec<Yaml>;
 /// The type contained in the `Yaml::Hash` variant. This corresponds to YAML mappings.
 pub type Hash = LinkedHashMap<Yaml, Yaml>;
+/// An entry of a [`Hash`], vacant or occupied (see [`Yaml::entry`]).
+pub type HashEntry<'a> =
+    hashlink::linked_hash_map::Entry<'a, Yaml, Yaml, hashlink::linked_hash_map::DefaultHashBuilder>;
+
+/// The variant of a [`Yaml`] node, without its value (see [`Yaml::kind`]).
+///
+/// It displays as the name used by error messages, e.g. `mapping` for [`YamlKind::Hash`]:
+///
+/// ```
+/// use saphyr::{yaml, YamlKind};
+///
+/// let port = yaml!("8080");
+/// assert_eq!(port.kind(), YamlKind::String);
+/// let message = format!("expected {}, found {}", YamlKind::Int, port.kind());
+/// assert_eq!(message, "expected int, found string");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum YamlKind {
+    /// [`Yaml::Null`].
+    Null,
+    /// [`Yaml::Boolean`].
+    Bool,
+    /// [`Yaml::Integer`].
+    Int,
+    /// [`Yaml::Real`].
+    Real,
+    /// [`Yaml::Float`].
+    Float,
+    /// [`Yaml::Timestamp`].
+    Timestamp,
+    /// [`Yaml::Binary`].
+    Binary,
+    /// [`Yaml::String`].
+    String,
+    /// [`Yaml::Array`].
+    Array,
+    /// [`Yaml::Hash`].
+    Hash,
+    /// [`Yaml::Tagged`].
+    Tagged,
+    /// [`Yaml::Alias`].
+    Alias,
+    /// [`Yaml::BadValue`].
+    BadValue,
+}
+
+impl core::fmt::Display for YamlKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            YamlKind::Null => "null",
+            YamlKind::Bool => "bool",
+            YamlKind::Int => "int",
+            YamlKind::Real | YamlKind::Float => "float",
+            YamlKind::Timestamp => "timestamp",
+            YamlKind::Binary => "binary",
+            YamlKind::String => "string",
+            YamlKind::Array => "sequence",
+            YamlKind::Hash => "mapping",
+            YamlKind::Tagged => "tagged value",
+            YamlKind::Alias => "alias",
+            YamlKind::BadValue => "invalid value",
+        })
+    }
+}
 
 impl Yaml {
     /// Load the given string as an array of YAML documents.
@@ -90,6 +270,35 @@ impl Yaml {
         Self::load_from_iter(BufferedInput::new(source.chars()))
     }
 
+    /// Load the stream read from `reader` as an array of YAML documents.
+    ///
+    /// The encoding of the stream is detected from its first bytes, as the YAML specification
+    /// requires: UTF-8, UTF-16 or UTF-32, of either endianness. A byte order mark is skipped.
+    /// Without one, the encoding is deduced from the null bytes of the first character, which
+    /// YAML requires to be ASCII. The stream is decoded as it is read, rather than read into a
+    /// string first.
+    ///
+    /// ```
+    /// use saphyr::Yaml;
+    ///
+    /// // `[1, 2]` in UTF-16LE, with a byte order mark.
+    /// let bytes: Vec<u8> = [0xFF, 0xFE]
+    ///     .into_iter()
+    ///     .chain("[1, 2]".bytes().flat_map(|byte| [byte, 0]))
+    ///     .collect();
+    /// let docs = Yaml::load_from_reader(bytes.as_slice()).unwrap();
+    /// assert_eq!(docs[0][1].as_i64(), Some(2));
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `LoadError::IO` if reading fails, `LoadError::Decode` if the stream is not valid
+    /// in its encoding, and `LoadError::Scan` or an error reported by the loader (see
+    /// [`YamlLoader::error`]) when loading fails.
+    #[cfg(feature = "std")]
+    pub fn load_from_reader<R: std::io::Read>(reader: R) -> Result<Vec<Yaml>, LoadError> {
+        crate::loader::load_from_reader(reader)
+    }
+
     /// Load the contents of the given iterator as an array of YAML documents.
     ///
     /// See [`Self::load_from_str`] for details.
@@ -110,23 +319,226 @@ impl Yaml {
     pub fn load_from_parser<I: Input>(parser: &mut Parser<I>) -> Result<Vec<Yaml>, ScanError> {
         let mut loader = YamlLoader::default();
         parser.load(&mut loader, true)?;
+        loader.take_scan_error()?;
         Ok(loader.into_documents())
     }
 
+    /// Load the given string as YAML documents, appending them to `docs`.
+    ///
+    /// This is the same as [`Self::load_from_str`], except that the documents are added to the
+    /// end of a vector owned by the caller, which avoids allocating a new one for each call.
+    /// If loading fails, `docs` is left as it was.
+    ///
+    /// ```
+    /// use saphyr::Yaml;
+    ///
+    /// let mut docs = vec![];
+    /// Yaml::load_from_str_into("a: 1", &mut docs).unwrap();
+    /// Yaml::load_from_str_into("b: 2\n---\nc: 3", &mut docs).unwrap();
+    /// assert!(Yaml::load_from_str_into("d: [4", &mut docs).is_err());
+    /// assert_eq!(docs.len(), 3);
+    /// assert_eq!(docs[2]["c"].as_i64(), Some(3));
+    /// ```
+    ///
+    /// To also reuse the state of the loader across calls, see
+    /// [`YamlLoader::append_documents`].
+    ///
+    /// # Errors
+    /// Returns `ScanError` when loading fails.
+    pub fn load_from_str_into(source: &str, docs: &mut Vec<Self>) -> Result<(), ScanError> {
+        let mut loader = YamlLoader::default();
+        let mut parser = Parser::new(BufferedInput::new(source.chars()));
+        parser.load(&mut loader, true)?;
+        loader.take_scan_error()?;
+        loader.append_documents(docs);
+        Ok(())
+    }
+
+    /// Load the given string as YAML documents, keeping those before the first error.
+    ///
+    /// Unlike [`Self::load_from_str`], an error does not discard the whole stream: this returns
+    /// every document which was complete when the error occurred, along with the error. The
+    /// document in which the error occurred is not returned.
+    ///
+    /// ```
+    /// use saphyr::Yaml;
+    ///
+    /// let (docs, error) = Yaml::load_from_str_partial("a: 1\n---\nb: 2\n---\nc: [3]]");
+    /// assert_eq!(docs.len(), 2);
+    /// assert_eq!(docs[1]["b"].as_i64(), Some(2));
+    /// assert_eq!(error.unwrap().marker().line(), 5);
+    ///
+    /// let (docs, error) = Yaml::load_from_str_partial("a: 1");
+    /// assert_eq!((docs.len(), error), (1, None));
+    /// ```
+    #[must_use]
+    pub fn load_from_str_partial(source: &str) -> (Vec<Self>, Option<ScanError>) {
+        let mut loader = YamlLoader::default();
+        let mut parser = Parser::new(BufferedInput::new(source.chars()));
+        let scan_error = parser.load(&mut loader, true).err();
+        // The loader ignores the events after its own errors, which thus come first.
+        let error = loader.take_scan_error().err().or(scan_error);
+        (loader.into_documents(), error)
+    }
+
+    /// Load the given string as a single YAML document.
+    ///
+    /// Unlike [`Self::load_from_str`], this fails if the stream is empty or contains more than one
+    /// document. An empty document (e.g.: `---` alone) counts as a document.
+    ///
+    /// ```
+    /// use saphyr::{LoadError, Yaml};
+    ///
+    /// let doc = Yaml::load_one_from_str("a: 1").unwrap();
+    /// assert_eq!(doc["a"].as_i64(), Some(1));
+    ///
+    /// let error = Yaml::load_one_from_str("a: 1\n---\nb: 2").unwrap_err();
+    /// let LoadError::ExpectedSingleDocument { found, second_document } = error else {
+    ///     panic!("unexpected error");
+    /// };
+    /// assert_eq!(found, 2);
+    /// assert_eq!(second_document.unwrap().line(), 2);
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `LoadError::Scan` when loading fails and `LoadError::ExpectedSingleDocument` if
+    /// the stream does not contain exactly one document.
+    pub fn load_one_from_str(source: &str) -> Result<Self, LoadError> {
+        load_single(&mut Parser::new(BufferedInput::new(source.chars())))
+    }
+
+    /// Load the given string as an array of YAML documents, failing if there are none.
+    ///
+    /// This is the same as [`Self::load_from_str`], except that an empty or comment-only input is
+    /// an error rather than an empty `Vec`.
+    ///
+    /// # Errors
+    /// Returns `LoadError::Scan` when loading fails and `LoadError::EmptyStream` if the stream
+    /// contains no document.
+    pub fn load_from_str_non_empty(source: &str) -> Result<Vec<Self>, LoadError> {
+        load_non_empty(&mut Parser::new(BufferedInput::new(source.chars())))
+    }
+
+    /// Load the given string as an array of YAML documents.
+    ///
+    /// This is the same as [`Self::load_from_str`], except that errors are returned as a
+    /// [`LoadError`], which can be inspected with [`LoadError::kind`], [`LoadError::span`] and
+    /// [`LoadError::path`].
+    ///
+    /// ```
+    /// use saphyr::{LoadErrorKind, Yaml};
+    ///
+    /// let error = Yaml::try_load_from_str("a: [1, 2").unwrap_err();
+    /// assert_eq!(error.kind(), LoadErrorKind::Syntax);
+    /// assert_eq!(error.span().unwrap().start.line(), 1);
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `LoadError::Scan` when loading fails, or an error reported by the loader (see
+    /// [`YamlLoader::error`]).
+    pub fn try_load_from_str(source: &str) -> Result<Vec<Self>, LoadError> {
+        Self::load_from_str_with_options(source, LoaderOptions::default())
+    }
+
+    /// Load the given string as an array of YAML documents, with the given options.
+    ///
+    /// ```
+    /// use saphyr::{DuplicateKeys, LoadError, LoaderOptions, Yaml};
+    ///
+    /// let options = LoaderOptions {
+    ///     duplicate_keys: DuplicateKeys::Error,
+    ///     ..LoaderOptions::default()
+    /// };
+    /// let error = Yaml::load_from_str_with_options("a: 1\n'a': 2", options).unwrap_err();
+    /// let LoadError::DuplicateKey { first, second, .. } = error else {
+    ///     panic!("unexpected error");
+    /// };
+    /// assert_eq!(first.source, "a");
+    /// assert_eq!(second.source, "'a'");
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `LoadError::Scan` when loading fails, or an error reported by the loader (see
+    /// [`YamlLoader::error`]).
+    pub fn load_from_str_with_options(
+        source: &str,
+        options: LoaderOptions,
+    ) -> Result<Vec<Self>, LoadError> {
+        load_with_options(source, options).map(YamlLoader::into_documents)
+    }
+
+    /// Load the given string as an array of YAML documents, with the directives of each.
+    ///
+    /// The metadata of each document holds the version it declared with `%YAML`, the tag
+    /// handles it declared with `%TAG` and whether it started with `---` (see
+    /// [`DocumentMetadata`]). See also
+    /// [`LoaderOptions::schema_from_version`] and [`LoaderOptions::strict_tag_handles`].
+    ///
+    /// ```
+    /// use saphyr::{LoaderOptions, Yaml};
+    ///
+    /// let source = "%YAML 1.1\n---\nenabled: yes\n...\n---\nenabled: yes";
+    /// let options = LoaderOptions {
+    ///     schema_from_version: true,
+    ///     ..LoaderOptions::default()
+    /// };
+    /// let (docs, metadata) = Yaml::load_from_str_with_metadata(source, options).unwrap();
+    /// assert_eq!(metadata[0].version, Some((1, 1)));
+    /// assert_eq!(docs[0]["enabled"].as_bool(), Some(true));
+    /// assert_eq!(metadata[1].version, None);
+    /// assert_eq!(docs[1]["enabled"].as_str(), Some("yes"));
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `LoadError::Scan` when loading fails, or an error reported by the loader (see
+    /// [`YamlLoader::error`]).
+    pub fn load_from_str_with_metadata(
+        source: &str,
+        options: LoaderOptions,
+    ) -> Result<(Vec<Self>, Vec<DocumentMetadata>), LoadError> {
+        let loader = load_with_options(source, options)?;
+        let metadata = loader.document_metadata().to_vec();
+        Ok((loader.into_documents(), metadata))
+    }
+
     define_as!(as_bool, bool, Boolean);
     define_as!(as_i64, i64, Integer);
+    define_as!(as_timestamp, Timestamp, Timestamp);
+    define_as_ref!(as_float, &Float, Float);
+    define_as_ref!(as_binary, &[u8], Binary);
 
     define_as_ref!(as_hash, &Hash, Hash);
-    define_as_ref!(as_str, &str, String);
     define_as_ref!(as_vec, &Array, Array);
 
+    /// Get the text of `self` if it is a [`Yaml::String`] or a [`Yaml::SharedString`].
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Yaml::String(v) => Some(v),
+            Yaml::SharedString(v) => Some(v),
+            _ => None,
+        }
+    }
+
     define_as_mut_ref!(as_mut_hash, &mut Hash, Hash);
     define_as_mut_ref!(as_mut_vec, &mut Array, Array);
 
+    define_into!(into_binary, Vec<u8>, Binary);
     define_into!(into_bool, bool, Boolean);
     define_into!(into_hash, Hash, Hash);
     define_into!(into_i64, i64, Integer);
-    define_into!(into_string, String, String);
+
+    /// Get the text of `self` if it is a [`Yaml::String`] or a [`Yaml::SharedString`], copying the
+    /// text of the latter.
+    #[must_use]
+    pub fn into_string(self) -> Option<String> {
+        match self {
+            Yaml::String(v) => Some(v),
+            Yaml::SharedString(v) => Some(String::from(&*v)),
+            _ => None,
+        }
+    }
+
     define_into!(into_vec, Array, Array);
 
     define_is!(is_alias, Self::Alias(_));
@@ -137,30 +549,131 @@ impl Yaml {
     define_is!(is_integer, Self::Integer(_));
     define_is!(is_null, Self::Null);
     define_is!(is_real, Self::Real(_));
-    define_is!(is_string, Self::String(_));
+    define_is!(is_float, Self::Float(_));
+    define_is!(is_string, Self::String(_) | Self::SharedString(_));
+    define_is!(is_tagged, Self::Tagged(..));
+
+    /// Return the variant of `self`, e.g. to report what was found instead of an expected type.
+    #[must_use]
+    pub fn kind(&self) -> YamlKind {
+        match self {
+            Yaml::Real(_) => YamlKind::Real,
+            Yaml::Float(_) => YamlKind::Float,
+            Yaml::Integer(_) => YamlKind::Int,
+            Yaml::String(_) | Yaml::SharedString(_) => YamlKind::String,
+            Yaml::Boolean(_) => YamlKind::Bool,
+            Yaml::Timestamp(_) => YamlKind::Timestamp,
+            Yaml::Binary(_) => YamlKind::Binary,
+            Yaml::Array(_) => YamlKind::Array,
+            Yaml::Hash(_) => YamlKind::Hash,
+            Yaml::Tagged(..) => YamlKind::Tagged,
+            Yaml::Alias(_) => YamlKind::Alias,
+            Yaml::Null => YamlKind::Null,
+            Yaml::BadValue => YamlKind::BadValue,
+        }
+    }
+
+    /// Return the tag of `self`, if it is a [`Yaml::Tagged`].
+    ///
+    /// ```
+    /// use saphyr::{LoaderOptions, Yaml};
+    ///
+    /// let options = LoaderOptions {
+    ///     keep_tags: true,
+    ///     ..LoaderOptions::default()
+    /// };
+    /// let docs = Yaml::load_from_str_with_options("id: !Ref Bucket", options).unwrap();
+    /// let tag = docs[0]["id"].tag().unwrap();
+    /// assert_eq!((tag.handle.as_str(), tag.suffix.as_str()), ("!", "Ref"));
+    /// assert_eq!(docs[0]["id"].untagged().as_str(), Some("Bucket"));
+    /// ```
+    #[must_use]
+    pub fn tag(&self) -> Option<&Tag> {
+        match self {
+            Yaml::Tagged(tag, _) => Some(tag),
+            _ => None,
+        }
+    }
+
+    /// Return the node tagged by `self` if it is a [`Yaml::Tagged`], or `self` otherwise.
+    #[must_use]
+    pub fn untagged(&self) -> &Self {
+        match self {
+            Yaml::Tagged(_, node) => node,
+            node => node,
+        }
+    }
+
+    /// Return the node tagged by `self` if it is a [`Yaml::Tagged`], or `self` otherwise.
+    #[must_use]
+    pub fn into_untagged(self) -> Self {
+        match self {
+            Yaml::Tagged(_, node) => *node,
+            node => node,
+        }
+    }
 
     /// Return the `f64` value contained in this YAML node.
     ///
     /// If the node is not a [`Yaml::Real`] YAML node or its contents is not a valid `f64` string,
-    /// `None` is returned.
+    /// `None` is returned. The value of a `Yaml::Float` is returned as well.
     #[must_use]
     pub fn as_f64(&self) -> Option<f64> {
-        if let Yaml::Real(ref v) = self {
-            parse_f64(v)
-        } else {
-            None
+        match self {
+            Yaml::Real(v) => parse_f64(v),
+            Yaml::Float(v) => Some(v.value),
+            _ => None,
         }
     }
 
     /// Return the `f64` value contained in this YAML node.
     ///
     /// If the node is not a [`Yaml::Real`] YAML node or its contents is not a valid `f64` string,
-    /// `None` is returned.
+    /// `None` is returned. The value of a `Yaml::Float` is returned as well.
     #[must_use]
     pub fn into_f64(self) -> Option<f64> {
         self.as_f64()
     }
 
+    /// Return the value of this YAML node as a `u64`, if it is an integer in its range.
+    ///
+    /// Integers too large for a [`Yaml::Integer`] (e.g. unsigned 64-bit identifiers) are loaded
+    /// as [`Yaml::Real`]s, which keep their text: their value is returned as well.
+    ///
+    /// ```
+    /// # use saphyr::Yaml;
+    /// let id = Yaml::load_one_from_str("18446744073709551615").unwrap();
+    /// assert_eq!(id.as_i64(), None);
+    /// assert_eq!(id.as_u64(), Some(u64::MAX));
+    /// ```
+    #[must_use]
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Yaml::Integer(v) => u64::try_from(*v).ok(),
+            _ => self.large_integer_text()?.parse().ok(),
+        }
+    }
+
+    /// Return the value of this YAML node as an `i128`, if it is an integer in its range.
+    ///
+    /// As with [`Self::as_u64`], integers too large for a [`Yaml::Integer`] are returned as well.
+    #[must_use]
+    pub fn as_i128(&self) -> Option<i128> {
+        match self {
+            Yaml::Integer(v) => Some(i128::from(*v)),
+            _ => self.large_integer_text()?.parse().ok(),
+        }
+    }
+
+    /// Return the text of a float, which holds an integer if it was too large for an `i64`.
+    fn large_integer_text(&self) -> Option<Cow<'_, str>> {
+        match self {
+            Yaml::Real(v) => Some(Cow::Borrowed(v)),
+            Yaml::Float(v) => Some(v.text()),
+            _ => None,
+        }
+    }
+
     /// If a value is null or otherwise bad (see variants), consume it and
     /// replace it with a given value `other`. Otherwise, return self unchanged.
     ///
@@ -188,29 +701,94 @@ impl Yaml {
             this => this,
         }
     }
+
+    /// If `self` is a mapping whose only key is `key`, return the associated value. Otherwise,
+    /// return `self`.
+    ///
+    /// This is meant for formats which wrap a document under a single root key (e.g.
+    /// `config:`), when the wrapper is optional.
+    ///
+    /// ```
+    /// use saphyr::Yaml;
+    ///
+    /// let wrapped = Yaml::load_one_from_str("config:\n  port: 80").unwrap();
+    /// let bare = Yaml::load_one_from_str("port: 80").unwrap();
+    /// assert_eq!(wrapped.unwrap_root("config"), &bare);
+    /// assert_eq!(bare.unwrap_root("config"), &bare);
+    /// ```
+    #[must_use]
+    pub fn unwrap_root(&self, key: &str) -> &Self {
+        match self {
+            Yaml::Hash(hash) if hash.len() == 1 => {
+                hash.get(&Yaml::String(key.to_owned())).unwrap_or(self)
+            }
+            _ => self,
+        }
+    }
+
+    /// See [`Self::unwrap_root`] for behavior.
+    ///
+    /// This performs the same operation, but fails if `self` is a mapping which contains `key`
+    /// alongside other keys, rather than silently returning `self`.
+    ///
+    /// # Errors
+    /// Returns `UnwrapRootError` if `self` is a mapping with `key` and other keys. The error
+    /// lists the other keys.
+    pub fn try_unwrap_root(&self, key: &str) -> Result<&Self, UnwrapRootError> {
+        let Yaml::Hash(hash) = self else {
+            return Ok(self);
+        };
+        let wrapper = Yaml::String(key.to_owned());
+        match hash.get(&wrapper) {
+            None => Ok(self),
+            Some(value) if hash.len() == 1 => Ok(value),
+            Some(_) => Err(UnwrapRootError {
+                key: key.to_owned(),
+                siblings: hash.keys().filter(|k| **k != wrapper).cloned().collect(),
+            }),
+        }
+    }
+
+    /// Return a mapping whose only entry associates `key` to `self`.
+    ///
+    /// This is the reverse of [`Self::unwrap_root`].
+    ///
+    /// ```
+    /// use saphyr::Yaml;
+    ///
+    /// let doc = Yaml::Integer(80).wrap_root("port");
+    /// assert_eq!(doc["port"].as_i64(), Some(80));
+    /// assert_eq!(doc.unwrap_root("port"), &Yaml::Integer(80));
+    /// ```
+    #[must_use]
+    pub fn wrap_root(self, key: &str) -> Self {
+        let mut hash = Hash::new();
+        hash.insert(Yaml::String(key.to_owned()), self);
+        Yaml::Hash(hash)
+    }
 }
 
-#[allow(clippy::should_implement_trait)]
 impl Yaml {
     /// Convert a string to a [`Yaml`] node.
     ///
-    /// [`Yaml`] does not implement [`std::str::FromStr`] since conversion may not fail. This
+    /// This is the resolution of untagged plain scalars: the string is not parsed as YAML. This
     /// function falls back to [`Yaml::String`] if nothing else matches.
     ///
     /// # Examples
     /// ```
     /// # use saphyr::Yaml;
-    /// assert!(matches!(Yaml::from_str("42"), Yaml::Integer(42)));
-    /// assert!(matches!(Yaml::from_str("0x2A"), Yaml::Integer(42)));
-    /// assert!(matches!(Yaml::from_str("0o52"), Yaml::Integer(42)));
-    /// assert!(matches!(Yaml::from_str("~"), Yaml::Null));
-    /// assert!(matches!(Yaml::from_str("null"), Yaml::Null));
-    /// assert!(matches!(Yaml::from_str("true"), Yaml::Boolean(true)));
-    /// assert!(matches!(Yaml::from_str("3.14"), Yaml::Real(_)));
-    /// assert!(matches!(Yaml::from_str("foo"), Yaml::String(_)));
+    /// assert!(matches!(Yaml::scalar_from_str("42"), Yaml::Integer(42)));
+    /// assert!(matches!(Yaml::scalar_from_str("0x2A"), Yaml::Integer(42)));
+    /// assert!(matches!(Yaml::scalar_from_str("0o52"), Yaml::Integer(42)));
+    /// assert!(matches!(Yaml::scalar_from_str("~"), Yaml::Null));
+    /// assert!(matches!(Yaml::scalar_from_str("null"), Yaml::Null));
+    /// assert!(matches!(Yaml::scalar_from_str("true"), Yaml::Boolean(true)));
+    /// assert!(matches!(Yaml::scalar_from_str("3.14"), Yaml::Real(_)));
+    /// assert!(matches!(Yaml::scalar_from_str("foo"), Yaml::String(_)));
+    /// assert!(matches!(Yaml::scalar_from_str("[1, 2]"), Yaml::String(_)));
     /// ```
     #[must_use]
-    pub fn from_str(v: &str) -> Yaml {
+    pub fn scalar_from_str(v: &str) -> Yaml {
         if let Some(number) = v.strip_prefix("0x") {
             if let Ok(i) = i64::from_str_radix(number, 16) {
                 return Yaml::Integer(i);
@@ -239,12 +817,169 @@ impl Yaml {
             }
         }
     }
+
+    /// Convert a string to a [`Yaml`] node, as a plain scalar.
+    ///
+    /// This method shadows [`FromStr::from_str`], which parses a whole document. Calls to
+    /// `Yaml::from_str` resolve to this method; use [`str::parse`] to parse a document.
+    #[allow(clippy::should_implement_trait)]
+    #[deprecated(note = "use `Yaml::scalar_from_str`, or `str::parse` to parse a document")]
+    #[must_use]
+    pub fn from_str(v: &str) -> Yaml {
+        Yaml::scalar_from_str(v)
+    }
+}
+
+impl FromStr for Yaml {
+    type Err = LoadError;
+
+    /// Parse `s` as a single YAML document. See [`Yaml::load_one_from_str`].
+    ///
+    /// ```
+    /// # use saphyr::Yaml;
+    /// let doc: Yaml = "a: [1, 2]".parse().unwrap();
+    /// assert_eq!(doc["a"][1].as_i64(), Some(2));
+    ///
+    /// assert!("a: [1, 2".parse::<Yaml>().is_err());
+    /// assert!("a\n---\nb".parse::<Yaml>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Yaml::load_one_from_str(s)
+    }
+}
+
+macro_rules! impl_from_integer {
+    ($($t:ty),*) => {$(
+        impl From<$t> for Yaml {
+            fn from(value: $t) -> Self {
+                Yaml::Integer(i64::from(value))
+            }
+        }
+    )*};
+}
+
+impl_from_integer!(i8, i16, i32, i64, u8, u16, u32);
+
+impl From<f64> for Yaml {
+    /// Convert `value` to a [`Yaml::Real`], in the shortest form which reads back as `value`.
+    ///
+    /// Non-finite values are written `.nan`, `.inf` and `-.inf`.
+    fn from(value: f64) -> Self {
+        Yaml::Real(real_text(value))
+    }
+}
+
+impl From<f32> for Yaml {
+    /// Convert `value` to a [`Yaml::Real`], as for `f64`.
+    fn from(value: f32) -> Self {
+        if value.is_finite() {
+            Yaml::Real(format!("{value:?}"))
+        } else {
+            Yaml::from(f64::from(value))
+        }
+    }
+}
+
+/// Return the shortest text which reads back as `value`, e.g. `0.1`, `1e100` or `-.inf`.
+pub(crate) fn real_text(value: f64) -> String {
+    if value.is_nan() {
+        ".nan".into()
+    } else if value.is_infinite() {
+        (if value > 0.0 { ".inf" } else { "-.inf" }).into()
+    } else {
+        format!("{value:?}")
+    }
+}
+
+impl From<bool> for Yaml {
+    fn from(value: bool) -> Self {
+        Yaml::Boolean(value)
+    }
+}
+
+impl From<&str> for Yaml {
+    /// Convert `value` to a [`Yaml::String`], whatever it contains. Use
+    /// [`Yaml::scalar_from_str`] to resolve its type.
+    fn from(value: &str) -> Self {
+        Yaml::String(value.to_owned())
+    }
+}
+
+impl From<String> for Yaml {
+    /// Convert `value` to a [`Yaml::String`], whatever it contains. Use
+    /// [`Yaml::scalar_from_str`] to resolve its type.
+    fn from(value: String) -> Self {
+        Yaml::String(value)
+    }
+}
+
+impl<T: Into<Yaml>> From<Vec<T>> for Yaml {
+    fn from(value: Vec<T>) -> Self {
+        Yaml::Array(value.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: Into<Yaml>, const N: usize> From<[T; N]> for Yaml {
+    fn from(value: [T; N]) -> Self {
+        Yaml::Array(value.into_iter().map(Into::into).collect())
+    }
+}
+
+impl From<Hash> for Yaml {
+    fn from(value: Hash) -> Self {
+        Yaml::Hash(value)
+    }
+}
+
+impl<K: Into<Yaml>, V: Into<Yaml>> FromIterator<(K, V)> for Yaml {
+    /// Collect entries into a [`Yaml::Hash`], converting keys and values with [`From`].
+    ///
+    /// ```
+    /// # use saphyr::{yaml, Yaml};
+    /// let ports: Yaml = [("http", 80), ("https", 443)].into_iter().collect();
+    /// assert_eq!(ports, yaml!({"http": 80, "https": 443}));
+    /// ```
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(entries: I) -> Self {
+        Yaml::Hash(
+            entries
+                .into_iter()
+                .map(|(key, value)| (Yaml::key(key), value.into()))
+                .collect(),
+        )
+    }
+}
+
+impl<T: Into<Yaml>> From<Option<T>> for Yaml {
+    /// Convert `None` to [`Yaml::Null`], and `Some(value)` to `value`.
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Yaml::Null, Into::into)
+    }
+}
+
+impl From<Float> for Yaml {
+    fn from(value: Float) -> Self {
+        Yaml::Float(value)
+    }
+}
+
+impl From<Timestamp> for Yaml {
+    fn from(value: Timestamp) -> Self {
+        Yaml::Timestamp(value)
+    }
 }
 
 static BAD_VALUE: Yaml = Yaml::BadValue;
 impl<'a> Index<&'a str> for Yaml {
     type Output = Yaml;
 
+    /// Return the value of the key which resolved to the string `idx`.
+    ///
+    /// Keys are compared once resolved, whatever their quoting: `a`, `'a'` and `"a"` are all
+    /// found with `["a"]`. Keys which resolved to another type are not, e.g. `1` (an integer) or
+    /// `true` (a boolean) cannot be found with `["1"]` or `["true"]`. Use [`Hash::get`] with the
+    /// resolved key for those.
+    ///
+    /// Returns [`Yaml::BadValue`] if `self` is not a mapping or does not contain the key.
     fn index(&self, idx: &'a str) -> &Yaml {
         let key = Yaml::String(idx.to_owned());
         match self.as_hash() {
@@ -320,7 +1055,7 @@ impl IntoIterator for Yaml {
 
 /// An iterator over a [`Yaml`] node.
 pub struct YamlIter {
-    yaml: std::vec::IntoIter<Yaml>,
+    yaml: alloc::vec::IntoIter<Yaml>,
 }
 
 impl Iterator for YamlIter {
@@ -330,3 +1065,37 @@ impl Iterator for YamlIter {
         self.yaml.next()
     }
 }
+
+/// The error returned by [`Yaml::try_unwrap_root`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnwrapRootError {
+    /// The wrapper key that was requested.
+    pub key: String,
+    /// The keys found alongside the wrapper key, in document order.
+    pub siblings: Vec<Yaml>,
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UnwrapRootError {}
+
+impl core::fmt::Display for UnwrapRootError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "root key `{}` has unexpected sibling keys: ", self.key)?;
+        for (i, sibling) in self.siblings.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            match sibling {
+                Yaml::String(v) | Yaml::Real(v) => write!(f, "`{v}`")?,
+                Yaml::SharedString(v) => write!(f, "`{v}`")?,
+                Yaml::Integer(v) => write!(f, "`{v}`")?,
+                Yaml::Boolean(v) => write!(f, "`{v}`")?,
+                Yaml::Float(v) => write!(f, "`{}`", v.text())?,
+                Yaml::Timestamp(v) => write!(f, "`{v}`")?,
+                Yaml::Null => f.write_str("`null`")?,
+                _ => write!(f, "{sibling:?}")?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use saphyr::{Yaml, YamlArena, YamlKind};
+
+#[test]
+fn test_arena_matches_owned() {
+    for source in [
+        "",
+        "plain scalar",
+        "a: 1\nb: [true, null, ~, 1.5, .inf, 0x1f, '42', \"é\\u00e9\"]\nc: {d: e}",
+        "literal: |\n  line\n  other\nfolded: >-\n  a\n  b\nmultiline: plain\n  continued\n",
+        "- 'it''s'\n- \"tab\\there\"\n- \"quoted\n  folded\"\n- !!str 12\n- !custom tagged",
+        "base: &a {x: [1, 2]}\ncopy: *a\nkey: &k name\n*k : value\ndup: 1\ndup: 2\n",
+        "? [complex, key]\n: value\n? {a: b}\n",
+        "---\nfirst\n---\nsecond: 'ünïcödé'\n...\n---\n- ☃\n",
+        "[[], {}, [[[deep]]], {a: {b: {c: []}}}]",
+    ] {
+        let arena = YamlArena::load_from_str(source).unwrap();
+        assert_eq!(
+            arena.to_documents(),
+            Yaml::load_from_str(source).unwrap(),
+            "{source:?}"
+        );
+    }
+
+    assert!(YamlArena::load_from_str("a: [1").is_err());
+}
+
+#[test]
+fn test_arena_nodes() {
+    let source = "name: ünï\nport: 8080\nratio: 0.5\non: true\nnone: ~\n\
+                  list: &list [a, b]\nagain: *list\ndup: 1\ndup: 2\n";
+    let arena = YamlArena::load_from_str(source).unwrap();
+    assert_eq!(arena.documents().len(), 1);
+    let doc = arena.document(0).unwrap();
+    assert!(arena.document(1).is_none());
+    assert_eq!(doc.kind(), YamlKind::Hash);
+
+    assert_eq!(doc.get("name").unwrap().as_str(), Some("ünï"));
+    assert_eq!(doc.get("port").unwrap().as_i64(), Some(8080));
+    assert_eq!(doc.get("ratio").unwrap().as_f64(), Some(0.5));
+    assert_eq!(doc.get("on").unwrap().as_bool(), Some(true));
+    assert!(doc.get("none").unwrap().is_null());
+    assert!(doc.get("absent").is_none());
+    assert!(doc.index(0).is_none());
+
+    let list = doc.get("list").unwrap();
+    assert_eq!(list.kind(), YamlKind::Array);
+    let items: Vec<_> = list.children().filter_map(|item| item.as_str()).collect();
+    assert_eq!(items, ["a", "b"]);
+    assert_eq!(list.index(1).unwrap().as_str(), Some("b"));
+    assert!(list.index(2).is_none());
+    assert_eq!(doc.get("again").unwrap().id(), list.id());
+    assert_eq!(
+        arena.node(list.id()).to_yaml(),
+        Yaml::load_from_str("[a, b]").unwrap()[0]
+    );
+
+    // Every entry is kept, and the last of duplicate keys wins.
+    assert_eq!(
+        doc.entries()
+            .filter(|(k, _)| k.as_str() == Some("dup"))
+            .count(),
+        2
+    );
+    assert_eq!(doc.get("dup").unwrap().as_i64(), Some(2));
+
+    // Aliases do not add nodes.
+    assert_eq!(arena.len(), arena.nodes().len());
+    assert_eq!(
+        arena
+            .nodes()
+            .filter(|node| node.kind() == YamlKind::Array)
+            .count(),
+        1
+    );
+}
@@ -1,7 +1,17 @@
 #![allow(clippy::bool_assert_comparison)]
 #![allow(clippy::float_cmp)]
 
-use saphyr::{Yaml, YamlEmitter};
+use std::sync::Arc;
+
+use saphyr::{
+    yaml, AnchorUsage, CancelToken, DisplayWithSource, DocumentMetadata, DuplicateKeys,
+    ExpectedType, FilterAction, Hash, HashExt, IndexError, LineIndex, LoadError, LoadErrorKind,
+    LoaderLimit, LoaderOptions, MarkedYaml, ScanError, Schema, UnknownAliases, Yaml, YamlEmitter,
+    YamlKind, YamlLoader, YamlPath,
+};
+use saphyr_parser::{
+    BufferedInput, Event, Marker, Parser, Span, SpannedEventReceiver, TScalarStyle, Tag,
+};
 
 #[test]
 fn test_api() {
@@ -233,3 +243,1863 @@ fn test_integer_key() {
     let first = out.into_iter().next().unwrap();
     assert_eq!(first[0]["important"].as_bool().unwrap(), true);
 }
+
+#[test]
+fn test_anchor_report() {
+    let s = "
+a: &top
+  b: &inner [1, 2]
+  c: *inner
+&key k: v
+d: *top
+e: *key
+---
+f: &unused 3
+";
+    let mut loader = YamlLoader::<Yaml>::default();
+    loader.record_anchors(true);
+    let mut parser = Parser::new(BufferedInput::new(s.chars()));
+    parser.load(&mut loader, true).unwrap();
+
+    let reports = loader.anchor_report();
+    assert_eq!(reports.len(), 2);
+
+    let usages = reports[0].values().collect::<Vec<_>>();
+    assert_eq!(usages.len(), 3);
+    let paths = |usage: &AnchorUsage| {
+        usage
+            .aliases
+            .iter()
+            .map(|site| site.path.to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(usages[0].definition.path.to_string(), "/a");
+    assert_eq!(paths(usages[0]), ["/d"]);
+    assert_eq!(usages[1].definition.path.to_string(), "/a/b");
+    assert_eq!(paths(usages[1]), ["/a/c"]);
+    assert_eq!(usages[2].definition.path.to_string(), "/?1");
+    assert_eq!(paths(usages[2]), ["/e"]);
+
+    let usages = reports[1].values().collect::<Vec<_>>();
+    assert_eq!(usages.len(), 1);
+    assert_eq!(usages[0].definition.path.to_string(), "/f");
+    assert!(usages[0].aliases.is_empty());
+
+    // Names are read from the source, at aliases or at the definition.
+    let names: Vec<_> = reports
+        .iter()
+        .flat_map(|report| report.values().map(|usage| usage.name(s)))
+        .collect();
+    assert_eq!(
+        names,
+        [Some("top"), Some("inner"), Some("key"), Some("unused")]
+    );
+    let anchors = loader.anchors(s);
+    assert_eq!(anchors[0]["inner"].definition.path.to_string(), "/a/b");
+    assert_eq!(anchors[1].keys().collect::<Vec<_>>(), [&"unused"]);
+
+    // The loaded documents are unaffected.
+    let docs = loader.into_documents();
+    assert_eq!(docs[0]["d"]["c"][1].as_i64().unwrap(), 2);
+    assert_eq!(docs[0]["e"].as_str().unwrap(), "k");
+}
+
+#[test]
+fn test_reuse_loader() {
+    let mut loader = YamlLoader::<Yaml>::new();
+    loader.record_anchors(true);
+
+    let mut parser = Parser::new(BufferedInput::new("a: &x 1\nb: *x\n---\nc".chars()));
+    parser.load(&mut loader, true).unwrap();
+    assert_eq!(loader.documents().len(), 2);
+    assert_eq!(loader.documents()[0]["b"].as_i64(), Some(1));
+    assert_eq!(loader.anchor_report().len(), 2);
+
+    loader.reset();
+    assert!(loader.documents().is_empty());
+    assert!(loader.anchor_report().is_empty());
+
+    let mut parser = Parser::new(BufferedInput::new("- &y d\n- *y".chars()));
+    parser.load(&mut loader, true).unwrap();
+    let docs = loader.take_documents();
+    assert_eq!(docs.len(), 1);
+    assert_eq!(docs[0][1].as_str(), Some("d"));
+    assert!(loader.documents().is_empty());
+    assert_eq!(loader.anchor_report()[0].len(), 1);
+}
+
+#[test]
+fn test_load_one() {
+    let doc = Yaml::load_one_from_str("# comment\n---\na: 1\n...\n").unwrap();
+    assert_eq!(doc["a"].as_i64(), Some(1));
+    assert!(Yaml::load_one_from_str("---").unwrap().is_null());
+
+    match Yaml::load_one_from_str("a: 1\n---\nb: 2\n---\nc: 3") {
+        Err(LoadError::ExpectedSingleDocument {
+            found: 3,
+            second_document: Some(marker),
+        }) => assert_eq!(marker.line(), 2),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    for source in ["", "# only a comment\n"] {
+        assert!(matches!(
+            Yaml::load_one_from_str(source),
+            Err(LoadError::ExpectedSingleDocument {
+                found: 0,
+                second_document: None
+            })
+        ));
+    }
+    assert!(matches!(
+        Yaml::load_one_from_str("a: [1"),
+        Err(LoadError::Scan(_))
+    ));
+}
+
+#[test]
+fn test_document_end_markers() {
+    let expected = [
+        Yaml::from("a"),
+        Yaml::from("b"),
+        Yaml::Integer(1),
+        Yaml::Null,
+    ];
+    for source in [
+        "a\n...\nb\n...\n1\n...\n---\n",
+        "--- a\n... # end of a\n# before b\nb\n... # end of b\n--- 1\n...\n...\n---",
+        "a\n...\n%YAML 1.2\n--- b\n---\n1\n---\n...\n",
+    ] {
+        assert_eq!(Yaml::load_from_str(source).unwrap(), expected, "{source:?}");
+    }
+}
+
+#[test]
+fn test_load_non_empty() {
+    assert_eq!(Yaml::load_from_str_non_empty("a\n---\nb").unwrap().len(), 2);
+    assert!(matches!(
+        Yaml::load_from_str_non_empty("# only a comment\n"),
+        Err(LoadError::EmptyStream)
+    ));
+}
+
+#[test]
+fn test_unwrap_root() {
+    let load = |s: &str| Yaml::load_one_from_str(s).unwrap();
+
+    let wrapped = load("config:\n  a: 1\n  b: 2");
+    assert_eq!(wrapped.unwrap_root("config"), &load("a: 1\nb: 2"));
+    assert_eq!(wrapped.try_unwrap_root("config"), Ok(&load("a: 1\nb: 2")));
+
+    // The wrapped value is not necessarily a mapping.
+    let scalar = load("config: 3");
+    assert_eq!(scalar.unwrap_root("config"), &Yaml::Integer(3));
+
+    // Absent wrapper.
+    for source in ["a: 1\nb: 2", "other: 1", "- config", "config", "{}"] {
+        let doc = load(source);
+        assert_eq!(doc.unwrap_root("config"), &doc);
+        assert_eq!(doc.try_unwrap_root("config"), Ok(&doc));
+    }
+
+    // Wrapper with siblings.
+    let doc = load("a: 1\nconfig: {}\n3: x");
+    assert_eq!(doc.unwrap_root("config"), &doc);
+    let error = doc.try_unwrap_root("config").unwrap_err();
+    assert_eq!(error.siblings, [Yaml::String("a".into()), Yaml::Integer(3)]);
+    assert_eq!(
+        error.to_string(),
+        "root key `config` has unexpected sibling keys: `a`, `3`"
+    );
+
+    let rewrapped = wrapped.unwrap_root("config").clone().wrap_root("config");
+    assert_eq!(rewrapped, wrapped);
+    let mut out = String::new();
+    YamlEmitter::new(&mut out).dump(&rewrapped).unwrap();
+    assert_eq!(out, "---\nconfig:\n  a: 1\n  b: 2");
+}
+
+#[test]
+fn test_capabilities() {
+    let capabilities = saphyr::capabilities();
+    assert_eq!(capabilities.std, cfg!(feature = "std"));
+    assert_eq!(capabilities.encoding, cfg!(feature = "encoding"));
+    assert_eq!(capabilities.json, cfg!(feature = "json"));
+    assert_eq!(capabilities.serde, cfg!(feature = "serde"));
+    assert_eq!(capabilities.timestamps, cfg!(feature = "timestamps"));
+    assert_eq!(capabilities.binary, cfg!(feature = "binary"));
+    assert_eq!(capabilities.floats, cfg!(feature = "floats"));
+    assert_eq!(capabilities.number, cfg!(feature = "number"));
+    assert_eq!(capabilities.parallel, cfg!(feature = "parallel"));
+    assert!(!capabilities.merge_keys);
+    assert_eq!(capabilities.loader_defaults, LoaderOptions::default());
+
+    let description = capabilities.describe();
+    assert_eq!(
+        description["version"].as_str(),
+        Some(env!("CARGO_PKG_VERSION"))
+    );
+    assert_eq!(
+        description["features"]["encoding"].as_bool(),
+        Some(cfg!(feature = "encoding"))
+    );
+    assert_eq!(
+        description["loader_defaults"],
+        LoaderOptions::default().describe()
+    );
+    assert_eq!(
+        description["emitter_defaults"]["emit_aliases"].as_bool(),
+        Some(false)
+    );
+
+    let options = LoaderOptions {
+        record_anchors: true,
+        ..LoaderOptions::default()
+    };
+    assert_eq!(options.describe()["record_anchors"].as_bool(), Some(true));
+    assert_eq!(
+        options.describe()["duplicate_keys"].as_str(),
+        Some("overwrite")
+    );
+    assert_eq!(
+        YamlLoader::<Yaml>::with_options(options.clone()).options(),
+        &options
+    );
+}
+
+#[test]
+fn test_duplicate_keys_across_styles() {
+    let reject = LoaderOptions {
+        duplicate_keys: DuplicateKeys::Error,
+        ..LoaderOptions::default()
+    };
+    // (first key, second key, whether they resolve to the same key under the Core schema)
+    let cases = [
+        ("a", "'a'", true),
+        ("on", "\"on\"", true),
+        ("'1'", "\"1\"", true),
+        ("\"1\"", "1", false),
+        ("0x10", "16", true),
+        ("'0x10'", "16", false),
+        ("1.0", "1.00", false),
+        ("~", "null", true),
+        ("~", "''", false),
+        ("true", "'true'", false),
+        ("True", "true", false),
+        ("!!str 1", "'1'", true),
+        ("!!int 1", "1", true),
+        ("[a]", "['a']", true),
+    ];
+    for (first, second, collide) in cases {
+        let source = format!("{first}: x\n{second}: y");
+        let docs = Yaml::load_from_str(&source).unwrap();
+        let hash = docs[0].as_hash().unwrap();
+        assert_eq!(hash.len(), if collide { 1 } else { 2 }, "{source}");
+        if collide {
+            // The last value wins by default.
+            assert_eq!(hash.values().next().unwrap().as_str(), Some("y"));
+        }
+
+        let result = Yaml::load_from_str_with_options(&source, reject.clone());
+        match result {
+            Err(LoadError::DuplicateKey {
+                first: f,
+                second: s,
+                ..
+            }) => {
+                assert!(collide, "{source}");
+                assert_eq!(f.resolved, s.resolved);
+                assert_eq!((f.start.line(), s.start.line()), (1, 2));
+            }
+            Ok(reloaded) => {
+                assert!(!collide, "{source}");
+                assert_eq!(reloaded, docs);
+            }
+            Err(error) => panic!("{source}: {error}"),
+        }
+        let marked = MarkedYaml::load_from_str_with_options(&source, reject.clone());
+        assert_eq!(marked.is_err(), collide, "{source}");
+    }
+
+    // Under YAML 1.1, `on` is the same boolean as `true`.
+    let yaml11 = LoaderOptions {
+        schema: Schema::Yaml11,
+        ..reject
+    };
+    let error = Yaml::load_from_str_with_options("on: 1\ntrue: 2", yaml11).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "key `true` (bool) at line 2 column 1 collides with key `on` (bool) at line 1 \
+         column 1, resolved equal under the `yaml_1_1` schema"
+    );
+}
+
+#[test]
+fn test_duplicate_keys_keep_first() {
+    let options = LoaderOptions {
+        duplicate_keys: DuplicateKeys::KeepFirst,
+        ..LoaderOptions::default()
+    };
+    assert_eq!(
+        options.describe()["duplicate_keys"].as_str(),
+        Some("keep_first")
+    );
+    let source = "a: 1\nb: [2]\n'a': {x: &x 3}\nb: &b [4]\nc: [*x, *b]\na: 5";
+    let docs = Yaml::load_from_str_with_options(source, options.clone()).unwrap();
+    assert_eq!(
+        docs,
+        Yaml::load_from_str("a: 1\nb: [2]\nc: [3, [4]]").unwrap()
+    );
+    let marked = MarkedYaml::load_from_str_with_options(source, options).unwrap();
+    assert_eq!(marked[0].data["a"].data.as_i64(), Some(1));
+    assert_eq!(marked[0].data["a"].span.start.line(), 1);
+}
+
+#[test]
+fn test_duplicate_key_error() {
+    let options = LoaderOptions {
+        duplicate_keys: DuplicateKeys::Error,
+        ..LoaderOptions::default()
+    };
+    let error =
+        Yaml::load_from_str_with_options("top:\n  on: 1\n  \"on\": 2", options).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "key `\"on\"` (string) at line 3 column 3 collides with key `on` (string) at line 2 \
+         column 3, resolved equal under the `core` schema"
+    );
+
+    // Lookups use the resolved key as well.
+    let doc = &Yaml::load_from_str("'on': 1\n1: 2").unwrap()[0];
+    assert_eq!(doc["on"].as_i64(), Some(1));
+    assert!(doc["1"].is_badvalue());
+    assert_eq!(
+        doc.as_hash().unwrap().get(&Yaml::Integer(1)),
+        Some(&Yaml::Integer(2))
+    );
+}
+
+#[test]
+fn test_key_and_tag_limits() {
+    let options = LoaderOptions {
+        max_key_length: 8,
+        max_tag_length: 4,
+        max_anchor_length: 6,
+        ..LoaderOptions::default()
+    };
+    let load = |s: &str| Yaml::load_from_str_with_options(s, options.clone());
+
+    // Values just below the limits load and round-trip.
+    let source =
+        "abcdefgh: !foo &abcdef long value, not a key\nlist: &ünï !bar [a, b]\ncopy: *abcdef";
+    let docs = load(source).unwrap();
+    assert_eq!(docs, Yaml::load_from_str(source).unwrap());
+    let mut out = String::new();
+    YamlEmitter::new(&mut out).dump(&docs[0]).unwrap();
+    assert_eq!(load(&out).unwrap(), docs);
+
+    for (source, expected_limit, length, line) in [
+        ("a: 1\nabcdefghi: 2", LoaderLimit::KeyLength, 9, 2),
+        ("a:\n  - {abcdefghi: 2}", LoaderLimit::KeyLength, 9, 2),
+        ("a: !fooo x", LoaderLimit::TagLength, 5, 1),
+        ("a: 1\nb: !fooo [x]", LoaderLimit::TagLength, 5, 2),
+        ("!fooo {a: 1}", LoaderLimit::TagLength, 5, 1),
+        ("a: &abcdefg x", LoaderLimit::AnchorLength, 7, 1),
+        ("a: 1\nb: !foo &abcdefg x", LoaderLimit::AnchorLength, 7, 2),
+        (
+            "a: 1\nb: &abcdefg !foo [x]",
+            LoaderLimit::AnchorLength,
+            7,
+            2,
+        ),
+        ("- &ünïé {a: 1}", LoaderLimit::AnchorLength, 7, 1),
+    ] {
+        let Err(LoadError::LimitExceeded {
+            limit,
+            length: found,
+            max,
+            marker,
+            ..
+        }) = load(source)
+        else {
+            panic!("{source} did not exceed a limit");
+        };
+        assert_eq!(limit, expected_limit, "{source}");
+        assert_eq!(found, length, "{source}");
+        assert_eq!(
+            max,
+            match limit {
+                LoaderLimit::KeyLength => 8,
+                LoaderLimit::TagLength => 4,
+                LoaderLimit::AnchorLength => 6,
+                _ => unreachable!(),
+            }
+        );
+        assert_eq!(marker.line(), line, "{source}");
+    }
+}
+
+#[test]
+fn test_stream_limits() {
+    let limited = |limit: LoaderLimit, max| {
+        let mut options = LoaderOptions::default();
+        match limit {
+            LoaderLimit::Nodes => options.max_nodes = Some(max),
+            LoaderLimit::TotalStringBytes => options.max_total_string_bytes = Some(max),
+            LoaderLimit::Documents => options.max_documents = Some(max),
+            LoaderLimit::Depth => options.max_depth = Some(max),
+            LoaderLimit::ScalarLength => options.max_scalar_length = Some(max),
+            LoaderLimit::AliasExpansions => options.max_alias_expansions = Some(max),
+            LoaderLimit::AliasDepth => options.max_alias_depth = Some(max),
+            _ => unreachable!(),
+        }
+        options
+    };
+
+    // Nodes: the mapping, 2 keys and the sequence with its 2 items.
+    let source = "a: &x [1, 2]\nb: *x";
+    let options = limited(LoaderLimit::Nodes, 9);
+    assert!(Yaml::load_from_str_with_options(source, options).is_ok());
+    let options = limited(LoaderLimit::Nodes, 8);
+    let error = Yaml::load_from_str_with_options(source, options).unwrap_err();
+    assert!(matches!(
+        error,
+        LoadError::LimitExceeded {
+            limit: LoaderLimit::Nodes,
+            length: 9,
+            max: 8,
+            ..
+        }
+    ));
+    assert_eq!(error.path().unwrap().to_string(), "/b");
+    assert_eq!(
+        error.to_string(),
+        "node count of 9 nodes at line 2 column 4 exceeds the maximum of 8"
+    );
+
+    // Aliases to nested anchors count the strings they repeat.
+    let source = "- &a [&b abc, def]\n- *a\n- *b";
+    let options = limited(LoaderLimit::TotalStringBytes, 15);
+    assert!(Yaml::load_from_str_with_options(source, options).is_ok());
+    let options = limited(LoaderLimit::TotalStringBytes, 14);
+    let error = Yaml::load_from_str_with_options(source, options).unwrap_err();
+    assert_eq!(error.path().unwrap().to_string(), "/2");
+
+    let source = "--- 1\n--- 2\n--- 3";
+    let options = limited(LoaderLimit::Documents, 3);
+    assert_eq!(
+        Yaml::load_from_str_with_options(source, options)
+            .unwrap()
+            .len(),
+        3
+    );
+    let options = limited(LoaderLimit::Documents, 2);
+    let error = Yaml::load_from_str_with_options(source, options).unwrap_err();
+    assert_eq!(error.span().unwrap().start.line(), 3);
+    assert_eq!(error.kind(), LoadErrorKind::LimitExceeded);
+
+    let source = "a: [1, {b: [2]}]
+---
+[[[3]]]";
+    let options = limited(LoaderLimit::Depth, 4);
+    assert!(Yaml::load_from_str_with_options(source, options).is_ok());
+    let options = limited(LoaderLimit::Depth, 3);
+    let error = Yaml::load_from_str_with_options(source, options).unwrap_err();
+    assert!(matches!(
+        error,
+        LoadError::LimitExceeded {
+            limit: LoaderLimit::Depth,
+            length: 4,
+            max: 3,
+            ..
+        }
+    ));
+    assert_eq!(error.path().unwrap().to_string(), "/a/1/b");
+    assert_eq!(
+        error.to_string(),
+        "nesting depth of 4 levels at line 1 column 12 exceeds the maximum of 3"
+    );
+
+    // Each scalar is checked on its own, and aliases do not count again.
+    let source = "a: &x abcd
+b: *x
+abcde: 1";
+    let options = limited(LoaderLimit::ScalarLength, 5);
+    assert!(Yaml::load_from_str_with_options(source, options).is_ok());
+    let options = limited(LoaderLimit::ScalarLength, 4);
+    let error = Yaml::load_from_str_with_options(source, options).unwrap_err();
+    assert!(matches!(
+        error,
+        LoadError::LimitExceeded {
+            limit: LoaderLimit::ScalarLength,
+            length: 5,
+            max: 4,
+            ..
+        }
+    ));
+    assert_eq!(error.span().unwrap().start.line(), 3);
+
+    // Aliases count again for the aliases they copy: 2 for `b`, and 3 for each `*b`.
+    let source = "a: &a [1, 2]\nb: &b [*a, *a]\nc: [*b, *b]";
+    let options = limited(LoaderLimit::AliasExpansions, 8);
+    assert!(Yaml::load_from_str_with_options(source, options).is_ok());
+    let options = limited(LoaderLimit::AliasExpansions, 7);
+    let error = Yaml::load_from_str_with_options(source, options).unwrap_err();
+    assert!(matches!(
+        error,
+        LoadError::LimitExceeded {
+            limit: LoaderLimit::AliasExpansions,
+            length: 8,
+            max: 7,
+            ..
+        }
+    ));
+    assert_eq!(error.path().unwrap().to_string(), "/c/1");
+    assert_eq!(
+        error.to_string(),
+        "alias expansion count of 8 aliases at line 3 column 9 exceeds the maximum of 7"
+    );
+
+    // The depth of an anchored collection is that of the deepest alias it contains.
+    let source = "a: &a [1, 2]\nb: &b {x: [*a], y: 3}\nc: [*a, *b]";
+    let options = limited(LoaderLimit::AliasDepth, 2);
+    assert!(Yaml::load_from_str_with_options(source, options).is_ok());
+    let options = limited(LoaderLimit::AliasDepth, 1);
+    let error = Yaml::load_from_str_with_options(source, options).unwrap_err();
+    assert!(matches!(
+        error,
+        LoadError::LimitExceeded {
+            limit: LoaderLimit::AliasDepth,
+            length: 2,
+            max: 1,
+            ..
+        }
+    ));
+    assert_eq!(error.path().unwrap().to_string(), "/c/1");
+    assert_eq!(
+        error.to_string(),
+        "alias expansion depth of 2 levels at line 3 column 9 exceeds the maximum of 1"
+    );
+}
+
+#[test]
+fn test_billion_laughs() {
+    let mut source = String::from("l0: &l0 [lol, lol, lol, lol, lol, lol, lol, lol, lol]\n");
+    for level in 1..10 {
+        let aliases = vec![format!("*l{}", level - 1); 9].join(", ");
+        source.push_str(&format!("l{level}: &l{level} [{aliases}]\n"));
+    }
+    for (limit, max) in [
+        (LoaderLimit::AliasExpansions, 10_000),
+        (LoaderLimit::AliasDepth, 5),
+    ] {
+        let mut options = LoaderOptions::default();
+        match limit {
+            LoaderLimit::AliasExpansions => options.max_alias_expansions = Some(max),
+            _ => options.max_alias_depth = Some(max),
+        }
+        let error = Yaml::load_from_str_with_options(&source, options).unwrap_err();
+        assert!(
+            matches!(error, LoadError::LimitExceeded { limit: l, max: m, .. } if l == limit && m == max),
+            "{error}"
+        );
+    }
+}
+
+#[test]
+fn test_default_key_limit() {
+    let max = LoaderOptions::default().max_key_length;
+    let key = "k".repeat(max);
+    let docs = Yaml::load_from_str(&format!("{key}: 1")).unwrap();
+    assert_eq!(docs[0][key.as_str()].as_i64(), Some(1));
+
+    let error = Yaml::load_from_str(&format!("a: 1\nk{key}: 1")).unwrap_err();
+    assert_eq!(error.marker().line(), 2);
+    assert_eq!(
+        Yaml::load_one_from_str(&format!("k{key}: 1"))
+            .unwrap_err()
+            .to_string(),
+        format!(
+            "mapping key length of {} bytes at line 1 column 1 exceeds the maximum of {max}",
+            max + 1
+        )
+    );
+}
+
+#[test]
+fn test_node_filter() {
+    let s = "
+users:
+  - name: a
+    password: &pw hunter2
+    secret_token: {nested: [1, 2, {deep: &x 3}]}
+  - name: b
+    password: *pw
+    copy: *x
+drop_me: [1, 2]
+keep: [1, drop, 2]
+";
+    let depths = std::rc::Rc::new(std::cell::RefCell::new(vec![]));
+    let seen = depths.clone();
+    let mut loader = YamlLoader::<Yaml>::new();
+    loader.node_filter(move |node, context| {
+        seen.borrow_mut().push((node.clone(), context.depth));
+        let key = context.key.and_then(Yaml::as_str);
+        if key == Some("password") {
+            return FilterAction::Replace(Yaml::String("<redacted>".into()));
+        }
+        if context.is_key && node.as_str().is_some_and(|key| key.starts_with("secret_")) {
+            return FilterAction::Drop;
+        }
+        if key == Some("drop_me") || node.as_str() == Some("drop") {
+            return FilterAction::Drop;
+        }
+        FilterAction::Keep
+    });
+    let mut parser = Parser::new(BufferedInput::new(s.chars()));
+    parser.load(&mut loader, true).unwrap();
+
+    let expected = Yaml::load_from_str(
+        "
+users:
+  - name: a
+    password: <redacted>
+  - name: b
+    password: <redacted>
+    copy: ~
+keep: [1, 2]
+",
+    )
+    .unwrap();
+    // The anchor defined in the dropped value cannot be aliased.
+    let mut expected = expected[0].clone();
+    expected["users"][1]["copy"] = Yaml::BadValue;
+    assert_eq!(loader.documents(), [expected]);
+
+    let depths = depths.borrow();
+    // The value of the dropped key is never built, nor given to the filter.
+    assert!(!depths.iter().any(|(node, _)| node.as_i64() == Some(3)));
+    assert!(depths.contains(&(Yaml::String("users".into()), 1)));
+    assert!(depths.contains(&(Yaml::String("name".into()), 3)));
+    assert_eq!(depths.last().unwrap().1, 0);
+}
+
+#[test]
+fn test_cancel_loading() {
+    let source: String = (0..10_000).map(|i| format!("- {{index: {i}}}\n")).collect();
+    let token = CancelToken::new();
+    let options = LoaderOptions {
+        cancel: Some(token.clone()),
+        ..LoaderOptions::default()
+    };
+    assert!(Yaml::load_from_str_with_options(&source, options.clone()).is_ok());
+
+    // Cancel from within the load, after a few nodes.
+    let mut loader = YamlLoader::<Yaml>::with_options(options.clone());
+    let mut nodes = 0;
+    let filter_token = token.clone();
+    loader.node_filter(move |_, _| {
+        nodes += 1;
+        if nodes == 100 {
+            filter_token.cancel();
+        }
+        FilterAction::Keep
+    });
+    let mut parser = Parser::new(BufferedInput::new(source.chars()));
+    parser.load(&mut loader, true).unwrap();
+    assert!(matches!(loader.error(), Some(LoadError::Cancelled)));
+    assert!(loader.documents().is_empty());
+
+    // The token can be cancelled from another thread, and cancels any further load.
+    let token = CancelToken::new();
+    let options = LoaderOptions {
+        cancel: Some(token.clone()),
+        ..LoaderOptions::default()
+    };
+    std::thread::spawn(move || token.cancel()).join().unwrap();
+    let error = MarkedYaml::load_from_str_with_options(&source, options).unwrap_err();
+    assert!(matches!(error, LoadError::Cancelled));
+    assert_eq!(error.to_string(), "loading was cancelled");
+}
+
+#[test]
+fn test_parse_document() {
+    let doc: Yaml = "a: [1, '2']\nb: ~".parse().unwrap();
+    assert_eq!(doc["a"][0], Yaml::Integer(1));
+    assert_eq!(doc["a"][1].as_str(), Some("2"));
+    assert!(doc["b"].is_null());
+    // Unlike scalar resolution, the string is parsed.
+    assert_eq!(
+        "[1, 2]".parse::<Yaml>().unwrap(),
+        Yaml::Array(vec![Yaml::Integer(1), Yaml::Integer(2)])
+    );
+    assert_eq!(Yaml::scalar_from_str("[1, 2]").as_str(), Some("[1, 2]"));
+
+    assert!(matches!("a: [1".parse::<Yaml>(), Err(LoadError::Scan(_))));
+    assert!(matches!(
+        "a\n---\nb".parse::<Yaml>(),
+        Err(LoadError::ExpectedSingleDocument { found: 2, .. })
+    ));
+
+    #[allow(deprecated)]
+    let scalar = Yaml::from_str("0x2A");
+    assert_eq!(scalar, Yaml::Integer(42));
+}
+
+#[test]
+fn test_structured_errors() {
+    let error = Yaml::try_load_from_str("a: 1\nb: [1, 2\n").unwrap_err();
+    assert_eq!(error.kind(), LoadErrorKind::Syntax);
+    assert_eq!(error.kind().code(), "syntax");
+    assert!(error.path().is_none());
+    let span = error.span().unwrap();
+    assert_eq!(span.start, span.end);
+
+    let error = Yaml::load_one_from_str("a\n---\nb").unwrap_err();
+    assert_eq!(error.kind(), LoadErrorKind::ExpectedSingleDocument);
+    assert_eq!(error.span().unwrap().start.line(), 2);
+    assert!(matches!(
+        Yaml::load_from_str_non_empty("# nothing").map_err(|error| error.kind()),
+        Err(LoadErrorKind::EmptyStream)
+    ));
+
+    let options = LoaderOptions {
+        duplicate_keys: DuplicateKeys::Error,
+        max_key_length: 8,
+        ..LoaderOptions::default()
+    };
+    let load = |s: &str| MarkedYaml::load_from_str_with_options(s, options.clone());
+
+    let error = load("a:\n  - x\n  - {b: 1, [c]: 2, [c]: 3}").unwrap_err();
+    assert_eq!(error.kind(), LoadErrorKind::DuplicateKey);
+    assert_eq!(error.path().unwrap().to_string(), "/a/1");
+    let span = error.span().unwrap();
+    assert_eq!((span.start.line(), span.start.col()), (3, 19));
+    assert_eq!((span.end.line(), span.end.col()), (3, 22));
+
+    let error = load("a:\n  - x\n  - {b: 1, abcdefghi: 2}").unwrap_err();
+    assert_eq!(error.kind(), LoadErrorKind::LimitExceeded);
+    assert_eq!(error.kind().code(), "limit-exceeded");
+    assert_eq!(error.path().unwrap().to_string(), "/a/1/?1");
+    let span = error.span().unwrap();
+    assert_eq!((span.start.col(), span.end.col()), (11, 20));
+}
+
+#[test]
+fn test_error_reports() {
+    let source = "a: 1\n\tb: [2, 3\n";
+    let error = ScanError::new(Marker::new(9, 2, 3), "unexpected `[`".into());
+    assert_eq!(
+        error.display_with_source(source).to_string(),
+        "error: unexpected `[`\n --> 2:4\n  |\n2 | \tb: [2, 3\n  | \t  ^"
+    );
+    assert_eq!(
+        LoadError::Scan(error).to_report(source),
+        "error: unexpected `[`\n --> 2:4\n  |\n2 | \tb: [2, 3\n  | \t  ^"
+    );
+
+    // Spans on a single line are underlined entirely.
+    let source = "x\r\n".repeat(9) + "---\r\n";
+    let error = LoadError::EmptyDocument {
+        marker: Marker::new(27, 10, 0),
+        end: Marker::new(30, 10, 3),
+    };
+    let report = error.to_report(&source);
+    assert!(report.ends_with("\n  --> 10:1\n   |\n10 | ---\n   | ^^^"));
+
+    // Errors without a position within the source have no snippet.
+    assert_eq!(
+        LoadError::EmptyStream.to_report(""),
+        "error: expected at least one document, found none"
+    );
+    let error = ScanError::new(Marker::new(0, 3, 0), "oops".into());
+    assert_eq!(error.display_with_source("a\n").to_string(), "error: oops");
+}
+
+#[test]
+fn test_line_index() {
+    let source = "a: é\r\nb: [\"🦀\", x]\rc: 3\n";
+    let index = LineIndex::new(source);
+    let doc = MarkedYaml::load_one_from_str(source).unwrap();
+    let x = &doc.data["b"].data[1];
+    assert_eq!(index.byte_range(x.span), 19..20);
+    assert_eq!(index.byte_range(x.span), x.byte_range(source));
+    assert_eq!((x.span.start.col(), index.utf16_col(x.span.start)), (9, 10));
+    assert_eq!(index.marker_at(19), x.span.start);
+
+    // Lone `\r`s break lines as `\n`s and `\r\n`s do.
+    let c = index.marker_at(source.find('c').unwrap());
+    assert_eq!((c.index(), c.line(), c.col()), (18, 3, 0));
+    assert_eq!(index.byte_offset(Marker::new(0, 2, 0)), 7);
+
+    // Positions beyond their line or the source are clamped.
+    assert_eq!(index.byte_offset(Marker::new(0, 1, 99)), 5);
+    assert_eq!(index.byte_offset(Marker::new(0, 9, 0)), source.len());
+    assert_eq!(index.marker_at(99), Marker::new(23, 4, 0));
+    assert_eq!(index.marker_at(15), index.marker_at(12));
+}
+
+#[test]
+fn test_marked_spans() {
+    let source = "a:\n  - 1\n  - 'two'\nb: [x, {y: z}]\nc: |\n  line1\n  line2\nd: \"q\"\n";
+    let doc = MarkedYaml::load_one_from_str(source).unwrap();
+    let text = |node: &MarkedYaml| &source[node.byte_range(source)];
+
+    assert_eq!(doc.byte_range(source), 0..61);
+    assert_eq!(text(&doc.data["a"]), "- 1\n  - 'two'");
+    assert_eq!(text(&doc.data["a"].data[1]), "'two'");
+    assert_eq!(text(&doc.data["b"]), "[x, {y: z}]");
+    assert_eq!(text(&doc.data["b"].data[1]), "{y: z}");
+    assert_eq!(text(&doc.data["c"]), "|\n  line1\n  line2\n");
+    assert_eq!(doc.data["c"].byte_range(source), 37..55);
+    assert_eq!(text(&doc.data["d"]), "\"q\"");
+
+    let (key, _) = doc.data.as_hash().unwrap().front().unwrap();
+    assert_eq!(key.byte_range(source), 0..1);
+    let value = &doc.data["c"];
+    assert_eq!((value.span.end.line(), value.span.end.col()), (8, 0));
+}
+
+#[test]
+fn test_yaml_macro() {
+    let name = "web";
+    let ports = vec![80, 443];
+    let doc = yaml!({
+        "name": name,
+        "ports": ports,
+        "tls": true,
+        "ratio": 0.25,
+        "nothing": null,
+        "nested": { "list": [1, -2, [], {}, null, "x"], 1: "one" },
+        [1, 2]: Yaml::Alias(0),
+        format!("{name}-id"): Some(42_i64),
+    });
+
+    let mut nested = Hash::new();
+    nested.insert(
+        Yaml::String("list".into()),
+        Yaml::Array(vec![
+            Yaml::Integer(1),
+            Yaml::Integer(-2),
+            Yaml::Array(vec![]),
+            Yaml::Hash(Hash::new()),
+            Yaml::Null,
+            Yaml::String("x".into()),
+        ]),
+    );
+    nested.insert(Yaml::Integer(1), Yaml::String("one".into()));
+    let mut expected = Hash::new();
+    for (key, value) in [
+        (Yaml::String("name".into()), Yaml::String("web".into())),
+        (
+            Yaml::String("ports".into()),
+            Yaml::Array(vec![Yaml::Integer(80), Yaml::Integer(443)]),
+        ),
+        (Yaml::String("tls".into()), Yaml::Boolean(true)),
+        (Yaml::String("ratio".into()), Yaml::Real("0.25".into())),
+        (Yaml::String("nothing".into()), Yaml::Null),
+        (Yaml::String("nested".into()), Yaml::Hash(nested)),
+        (
+            Yaml::Array(vec![Yaml::Integer(1), Yaml::Integer(2)]),
+            Yaml::Alias(0),
+        ),
+        (Yaml::String("web-id".into()), Yaml::Integer(42)),
+    ] {
+        expected.insert(key, value);
+    }
+    assert_eq!(doc, Yaml::Hash(expected));
+
+    assert_eq!(yaml!(null), Yaml::Null);
+    assert_eq!(yaml!([]), Yaml::Array(vec![]));
+    assert_eq!(yaml!(["a",]), Yaml::Array(vec![Yaml::String("a".into())]));
+    assert_eq!(yaml!(1.0), Yaml::Real("1.0".into()));
+    assert_eq!(yaml!(1e100), Yaml::Real("1e100".into()));
+    assert_eq!(yaml!(f64::NEG_INFINITY), Yaml::Real("-.inf".into()));
+    assert_eq!(yaml!(f64::NAN), Yaml::Real(".nan".into()));
+    assert_eq!(yaml!(0.1_f32), Yaml::Real("0.1".into()));
+    assert_eq!(yaml!(None::<bool>), Yaml::Null);
+    assert_eq!(Yaml::from(u32::MAX).as_i64(), Some(i64::from(u32::MAX)));
+
+    let doc: Yaml = (1..=3).map(|i| (format!("k{i}"), vec![i; 2])).collect();
+    assert_eq!(doc, yaml!({"k1": [1, 1], "k2": [2, 2], "k3": [3, 3]}));
+    assert_eq!(
+        std::iter::empty::<(&str, bool)>().collect::<Yaml>(),
+        yaml!({})
+    );
+}
+
+#[test]
+fn test_checked_indexing() {
+    let doc = yaml!({ "name": "web", "ports": [80, 443], "tls": true, "ratio": 0.5 });
+
+    assert_eq!(doc.try_index("name").and_then(Yaml::expect_str), Ok("web"));
+    assert_eq!(doc.try_index("tls").and_then(Yaml::expect_bool), Ok(true));
+    assert_eq!(doc.try_index("ratio").and_then(Yaml::expect_f64), Ok(0.5));
+    let ports = doc.try_index("ports").unwrap();
+    assert_eq!(ports.expect_array().map(Vec::len), Ok(2));
+    assert_eq!(ports.try_index_seq(1).and_then(Yaml::expect_i64), Ok(443));
+    assert_eq!(doc.expect_hash().map(Hash::len), Ok(4));
+
+    let error = doc.try_index("nmae").unwrap_err();
+    assert_eq!(error, IndexError::MissingKey("nmae".into()));
+    assert_eq!(error.to_string(), "missing key `nmae`");
+
+    let error = ports.try_index_seq(2).unwrap_err();
+    assert_eq!(error, IndexError::OutOfBounds { index: 2, len: 2 });
+    assert_eq!(
+        error.to_string(),
+        "index 2 is out of bounds of a sequence of 2 items"
+    );
+
+    let error = doc["name"].try_index("first").unwrap_err();
+    assert_eq!(
+        error,
+        IndexError::WrongType {
+            expected: YamlKind::Hash,
+            actual: YamlKind::String
+        }
+    );
+    assert_eq!(error.to_string(), "expected mapping, found string");
+    assert!(matches!(
+        doc.try_index_seq(0),
+        Err(IndexError::WrongType {
+            expected: YamlKind::Array,
+            actual: YamlKind::Hash
+        })
+    ));
+    assert!(matches!(
+        doc["missing"].expect_str(),
+        Err(IndexError::WrongType {
+            actual: YamlKind::BadValue,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_pointer() {
+    let mut doc = yaml!({
+        "spec": {"containers": [{"image": "web"}, {"image": "db"}]},
+        "a/b": {"~": 1},
+        1: [true],
+        null: "none",
+    });
+
+    assert_eq!(doc.pointer(""), Some(&doc));
+    assert_eq!(doc.pointer("/spec/containers/1/image"), Some(&yaml!("db")));
+    assert_eq!(doc.pointer("/a~1b/~0"), Some(&yaml!(1)));
+    assert_eq!(doc.pointer("/1/0"), Some(&yaml!(true)));
+    assert_eq!(doc.pointer("/null"), Some(&yaml!("none")));
+    for missing in [
+        "spec",
+        "/spec/",
+        "/spec/containers/2",
+        "/spec/containers/01",
+        "/1/+0",
+    ] {
+        assert_eq!(doc.pointer(missing), None, "{missing}");
+    }
+
+    *doc.pointer_mut("/spec/containers/0/image").unwrap() = yaml!("api");
+    doc.pointer_mut("/1").unwrap().push(yaml!(false));
+    assert_eq!(doc["spec"]["containers"][0]["image"].as_str(), Some("api"));
+    assert_eq!(doc.pointer("/1"), Some(&yaml!([true, false])));
+    assert!(doc.pointer_mut("/spec/image").is_none());
+}
+
+#[test]
+fn test_kind_and_preview() {
+    let doc = yaml!({"server": {"port": "8080"}});
+    let port = &doc["server"]["port"];
+    let error = port.expect_i64().unwrap_err();
+    assert_eq!(
+        error,
+        IndexError::WrongType {
+            expected: YamlKind::Int,
+            actual: port.kind()
+        }
+    );
+    assert_eq!(error.to_string(), "expected int, found string");
+    assert_eq!(Yaml::BadValue.kind().to_string(), "invalid value");
+
+    let doc = yaml!([
+        "a long string which does not fit",
+        "true",
+        "",
+        null,
+        {},
+        [],
+        {"a": [[[1]]]}
+    ]);
+    let previews: Vec<_> = [80, 40, 30, 20, 10]
+        .into_iter()
+        .map(|max_len| doc.preview(max_len))
+        .collect();
+    assert_eq!(
+        previews,
+        [
+            r#"[a long string which does not fit, "true", "", null, {}, [], {a: [… 1 item …]}]"#,
+            "[a long string which does no…, … 6 more]",
+            "[a long string whi…, … 6 more]",
+            "[a long …, … 6 more]",
+            "[… 7 items …]",
+        ]
+    );
+    assert_eq!(yaml!("a\nb").preview(4), r#""a…""#);
+}
+
+#[test]
+fn test_build_nested_documents() {
+    let mut doc = Yaml::Null;
+    doc.hash_entry_or_insert("name", yaml!("ci"));
+    let steps = doc
+        .hash_entry_or_insert("jobs", Yaml::Null)
+        .hash_entry_or_insert("build", Yaml::BadValue)
+        .hash_entry_or_insert("steps", Yaml::Null);
+    for _ in 0..3 {
+        steps.push(Yaml::Null);
+    }
+    *steps[2].hash_entry_or_insert("run", Yaml::Null) = yaml!("cargo test");
+    // Existing entries are kept.
+    doc.hash_entry_or_insert("name", yaml!("other"));
+    assert_eq!(
+        doc,
+        yaml!({"name": "ci", "jobs": {"build": {"steps": [null, null, {"run": "cargo test"}]}}})
+    );
+
+    // Scalars and collections of the other kind are not replaced.
+    let mut name = doc["name"].clone();
+    assert_eq!(
+        name.try_ensure_array(),
+        Err(IndexError::WrongType {
+            expected: YamlKind::Array,
+            actual: YamlKind::String
+        })
+    );
+    assert_eq!(
+        name.try_hash_entry_or_insert("first", Yaml::Null),
+        Err(IndexError::WrongType {
+            expected: YamlKind::Hash,
+            actual: YamlKind::String
+        })
+    );
+    assert_eq!(name, yaml!("ci"));
+    assert!(doc.try_ensure_array().is_err());
+    assert_eq!(doc.try_ensure_hash().map(|hash| hash.len()), Ok(2));
+
+    let result = std::panic::catch_unwind(move || name.push(Yaml::Null));
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_mapping_helpers() {
+    let mut doc = yaml!({"name": "web", "ports": [80], 1: "one"});
+    assert_eq!(doc.get("name"), Some(&yaml!("web")));
+    assert_eq!(doc.get(1), Some(&yaml!("one")));
+    assert_eq!(doc.get("1"), None);
+    assert_eq!(doc["ports"].get(0), None);
+
+    doc.get_mut("ports").unwrap().push(yaml!(443));
+    assert_eq!(doc.insert("name", yaml!("api")), Ok(Some(yaml!("web"))));
+    assert_eq!(doc.remove(1), Some(yaml!("one")));
+    assert_eq!(doc.remove(1), None);
+    *doc.entry("replicas").unwrap().or_insert(yaml!(1)) = yaml!(2);
+    doc.entry("name")
+        .unwrap()
+        .and_modify(|name| *name = yaml!("db"));
+    assert_eq!(
+        doc,
+        yaml!({"name": "db", "ports": [80, 443], "replicas": 2})
+    );
+
+    // Null values become mappings, other values are left unchanged.
+    let mut doc = Yaml::Null;
+    assert_eq!(doc.insert("a", yaml!(1)), Ok(None));
+    assert_eq!(doc, yaml!({"a": 1}));
+    let mut doc = yaml!([1]);
+    let error = IndexError::WrongType {
+        expected: YamlKind::Hash,
+        actual: YamlKind::Array,
+    };
+    assert_eq!(doc.insert("a", yaml!(1)), Err(error.clone()));
+    assert_eq!(doc.entry("a").err(), Some(error));
+    assert_eq!(doc.get_mut("a"), None);
+    assert_eq!(doc.remove("a"), None);
+    assert_eq!(doc, yaml!([1]));
+}
+
+#[test]
+fn test_typed_extraction() {
+    let doc = yaml!({"server": {"port": "8080", "hosts": ["a", 1]}, "debug": true});
+    assert_eq!(doc["debug"].try_as_bool(), Ok(true));
+    assert_eq!(doc.try_as_hash().map(|hash| hash.len()), Ok(2));
+    assert_eq!(
+        doc.try_as_str(),
+        Err(ExpectedType {
+            expected: YamlKind::String,
+            found: YamlKind::Hash,
+            path: YamlPath::new(),
+        })
+    );
+    assert_eq!(
+        doc.try_as_vec().unwrap_err().to_string(),
+        "expected sequence, found mapping"
+    );
+
+    let server = doc.cursor().get("server");
+    assert_eq!(server.node(), &doc["server"]);
+    let error = server.get("port").try_as_i64().unwrap_err();
+    assert_eq!(error.path.to_string(), "/server/port");
+    assert_eq!(
+        error.to_string(),
+        "expected int at `/server/port`, found string"
+    );
+    assert_eq!(
+        server
+            .get("tls")
+            .index(0)
+            .try_as_str()
+            .unwrap_err()
+            .to_string(),
+        "expected string at `/server/tls/0`, found nothing"
+    );
+    let hosts: Vec<_> = server
+        .get("hosts")
+        .items()
+        .unwrap()
+        .map(|host| host.try_as_str().map_err(|error| error.to_string()))
+        .collect();
+    assert_eq!(
+        hosts,
+        [
+            Ok("a"),
+            Err("expected string at `/server/hosts/1`, found int".to_owned())
+        ]
+    );
+    assert!(server.get("port").items().is_err());
+}
+
+#[test]
+fn test_unknown_aliases() {
+    let strict = LoaderOptions {
+        unknown_aliases: UnknownAliases::Error,
+        ..LoaderOptions::default()
+    };
+    // The collection is not complete when it is aliased from within itself.
+    let source = "a: 1\nb: &list [1, *list]";
+    let docs = Yaml::load_from_str(source).unwrap();
+    assert_eq!(docs[0]["b"][1], Yaml::BadValue);
+    let error = Yaml::load_from_str_with_options(source, strict.clone()).unwrap_err();
+    assert_eq!(error.kind(), LoadErrorKind::UnknownAlias);
+    assert!(matches!(&error, LoadError::UnknownAlias { name: Some(name), .. } if name == "list"));
+    assert_eq!(error.path().unwrap().to_string(), "/b/1");
+    let span = error.span().unwrap();
+    assert_eq!(
+        (span.start.line(), span.start.col(), span.end.col()),
+        (2, 13, 18)
+    );
+    assert_eq!(
+        error.to_string(),
+        "alias `*list` at line 2 column 14 does not refer to a complete anchored node"
+    );
+
+    let error = MarkedYaml::load_from_str_with_options("{k: &m {x: *m}}", strict.clone());
+    assert!(matches!(error, Err(LoadError::UnknownAlias { name: Some(name), .. }) if name == "m"));
+
+    // An alias refers to the last definition of its anchor.
+    let source = "a: &x 1\nb: *x\nc: &x 2\nd: *x";
+    let doc = &Yaml::load_from_str_with_options(source, strict.clone()).unwrap()[0];
+    assert_eq!((doc["b"].as_i64(), doc["d"].as_i64()), (Some(1), Some(2)));
+
+    // Without the source, the name of the anchor is unknown.
+    let mut loader = YamlLoader::<Yaml>::with_options(strict);
+    for event in [
+        Event::StreamStart,
+        Event::DocumentStart(false),
+        Event::SequenceStart(0, None),
+        Event::Alias(3),
+    ] {
+        loader.on_event(event, Span::new(Marker::new(1, 1, 1), Marker::new(3, 1, 3)));
+    }
+    assert!(matches!(
+        loader.error(),
+        Some(LoadError::UnknownAlias { id: 3, name: None, path, .. }) if path.to_string() == "/0"
+    ));
+    assert_eq!(
+        loader.error().unwrap().to_string(),
+        "alias at line 1 column 2 does not refer to a complete anchored node"
+    );
+    loader.name_unknown_alias("[*a]");
+    assert!(matches!(
+        loader.error(),
+        Some(LoadError::UnknownAlias { name: Some(name), .. }) if name == "a"
+    ));
+    assert_eq!(
+        loader.error().unwrap().to_string(),
+        "alias `*a` at line 1 column 2 does not refer to a complete anchored node"
+    );
+}
+
+#[test]
+fn test_forbid_anchors() {
+    let options = LoaderOptions {
+        forbid_anchors: true,
+        ..LoaderOptions::default()
+    };
+    let load = |source| Yaml::load_from_str_with_options(source, options.clone());
+    assert!(load(
+        "a: [1, {b: c}]
+'&x': '*x'"
+    )
+    .is_ok());
+
+    let error = load(
+        "a: 1
+b: [1, &x 2]",
+    )
+    .unwrap_err();
+    assert_eq!(error.kind(), LoadErrorKind::AnchorForbidden);
+    assert_eq!(error.kind().code(), "anchor-forbidden");
+    assert_eq!(error.path().unwrap().to_string(), "/b/1");
+    let message = error.to_string();
+    assert!(message.starts_with("anchor at line 2 column "), "{message}");
+    assert!(message.ends_with(" is not allowed"), "{message}");
+
+    let error = load("a: &x {b: c}").unwrap_err();
+    assert!(
+        matches!(&error, LoadError::AnchorForbidden { alias: false, path, .. } if path.to_string() == "/a")
+    );
+
+    // Aliases are rejected even if their anchor is unknown.
+    let mut loader = YamlLoader::<Yaml>::with_options(options);
+    for event in [
+        Event::StreamStart,
+        Event::DocumentStart(false),
+        Event::SequenceStart(0, None),
+        Event::Alias(3),
+    ] {
+        loader.on_event(event, Span::new(Marker::new(1, 1, 1), Marker::new(3, 1, 3)));
+    }
+    assert!(matches!(
+        loader.error(),
+        Some(LoadError::AnchorForbidden { alias: true, path, .. }) if path.to_string() == "/0"
+    ));
+    assert_eq!(
+        loader.error().unwrap().to_string(),
+        "alias at line 1 column 2 is not allowed"
+    );
+}
+
+#[test]
+fn test_load_into() {
+    let mut docs = vec![Yaml::Null];
+    Yaml::load_from_str_into("--- &a [1]\n--- 2", &mut docs).unwrap();
+    assert_eq!(docs, [Yaml::Null, yaml!([1]), Yaml::Integer(2)]);
+    assert!(Yaml::load_from_str_into("a: 1\n---\n[", &mut docs).is_err());
+    assert_eq!(docs.len(), 3);
+
+    // A reused loader appends only the documents of the current stream.
+    let mut loader = YamlLoader::<Yaml>::new();
+    docs.clear();
+    for source in ["a: &x [1]\nb: *x", "c: 2"] {
+        loader.reset();
+        let mut parser = Parser::new(BufferedInput::new(source.chars()));
+        parser.load(&mut loader, true).unwrap();
+        loader.append_documents(&mut docs);
+        assert!(loader.documents().is_empty());
+    }
+    assert_eq!(docs, [yaml!({"a": [1], "b": [1]}), yaml!({"c": 2})]);
+}
+
+#[test]
+fn test_anchored_nodes() {
+    let source = "
+defaults: &defaults
+  image: ruby
+  script: &script [make, test]
+list: [&first 1, [&second [2, 3]], *first, *second]
+job: *defaults
+steps: *script
+";
+    let doc = Yaml::load_one_from_str(source).unwrap();
+    assert_eq!(doc["job"], doc["defaults"]);
+    assert_eq!(doc["steps"], yaml!(["make", "test"]));
+    assert_eq!(doc["list"], yaml!([1, [[2, 3]], 1, [2, 3]]));
+
+    // Overwriting a value must not change what aliases refer to.
+    let source = "a: &x [1]\nb: &y {c: &z [2]}\na: 3\nb: {c: &w [4]}\nd: [*x, *y, *z, *w]";
+    let doc = Yaml::load_one_from_str(source).unwrap();
+    assert_eq!(
+        doc,
+        yaml!({"a": 3, "b": {"c": [4]}, "d": [[1], {"c": [2]}, [2], [4]]})
+    );
+
+    // Anchors within keys.
+    let source = "? &k [1, {a: &n 2}]\n: v\nk: *k\nn: *n";
+    let doc = Yaml::load_one_from_str(source).unwrap();
+    assert_eq!(doc["k"], yaml!([1, {"a": 2}]));
+    assert_eq!(doc["n"], Yaml::Integer(2));
+
+    // Anchors outlive the documents taken out of the loader.
+    let mut loader = YamlLoader::<Yaml>::default();
+    let span = Span::new(Marker::new(1, 1, 1), Marker::new(3, 1, 3));
+    let scalar = |value: &str, aid| Event::Scalar(value.into(), TScalarStyle::Plain, aid, None);
+    for event in [
+        Event::StreamStart,
+        Event::DocumentStart(false),
+        Event::SequenceStart(0, None),
+        Event::SequenceStart(1, None),
+        scalar("1", 0),
+        Event::SequenceEnd,
+        scalar("2", 2),
+        Event::SequenceEnd,
+        Event::DocumentEnd,
+    ] {
+        loader.on_event(event, span);
+    }
+    assert_eq!(loader.take_documents(), [yaml!([[1], 2])]);
+    for event in [
+        Event::DocumentStart(false),
+        Event::SequenceStart(0, None),
+        Event::Alias(1),
+        Event::Alias(2),
+        Event::SequenceEnd,
+        Event::DocumentEnd,
+    ] {
+        loader.on_event(event, span);
+    }
+    assert_eq!(loader.take_documents(), [yaml!([[1], 2])]);
+
+    // Resolving merge keys moves the entries of a mapping, but not the anchored nodes.
+    let mut loader = YamlLoader::<Yaml>::with_options(LoaderOptions {
+        merge_keys: true,
+        ..LoaderOptions::default()
+    });
+    for event in [
+        Event::DocumentStart(false),
+        Event::MappingStart(0, None),
+        scalar("<<", 0),
+        Event::MappingStart(0, None),
+        scalar("b", 0),
+        scalar("1", 0),
+        scalar("c", 0),
+        scalar("3", 0),
+        Event::MappingEnd,
+        scalar("a", 0),
+        Event::SequenceStart(1, None),
+        scalar("2", 0),
+        Event::SequenceEnd,
+        Event::MappingEnd,
+        Event::DocumentEnd,
+        Event::DocumentStart(false),
+        Event::Alias(1),
+        Event::DocumentEnd,
+    ] {
+        loader.on_event(event, span);
+    }
+    assert_eq!(
+        loader.take_documents(),
+        [yaml!({"b": 1, "c": 3, "a": [2]}), yaml!([2])]
+    );
+}
+
+#[test]
+fn test_complex_key_lookup() {
+    let mut doc = Yaml::load_one_from_str("? [a, b]\n: pair\n? {c: 1}\n: map\nd: plain").unwrap();
+    let hash = doc.as_mut_hash().unwrap();
+    assert_eq!(hash[&Yaml::key(["a", "b"])], yaml!("pair"));
+    assert_eq!(hash.get(&Yaml::key(yaml!({"c": 1}))), Some(&yaml!("map")));
+    assert_eq!(hash.get_by_str("d"), Some(&yaml!("plain")));
+    assert_eq!(hash.get_by_str("a"), None);
+    *hash.get_by_str_mut("d").unwrap() = Yaml::Integer(1);
+    assert_eq!(
+        hash.values().collect::<Vec<_>>(),
+        [&yaml!("pair"), &yaml!("map"), &Yaml::Integer(1)]
+    );
+    assert_eq!(hash.keys().filter(|key| key.is_array()).count(), 1);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "mapping keys cannot contain bad values or aliases")]
+fn test_invalid_key() {
+    let _ = Yaml::key([Yaml::BadValue]);
+}
+
+#[test]
+fn test_iterators() {
+    let mut doc = yaml!({"a": [1, 2], "b": {"c": null}, [3]: "x"});
+    assert_eq!(doc.iter().count(), 0);
+    assert_eq!(doc["a"].iter().collect::<Vec<_>>(), [&yaml!(1), &yaml!(2)]);
+    assert_eq!(Yaml::Null.iter_entries().count(), 0);
+    let keys: Vec<_> = doc.iter_entries().map(|(key, _)| key.clone()).collect();
+    assert_eq!(keys, [yaml!("a"), yaml!("b"), yaml!([3])]);
+
+    for item in doc
+        .as_mut_hash()
+        .unwrap()
+        .get_by_str_mut("a")
+        .unwrap()
+        .iter_mut()
+    {
+        *item = Yaml::Integer(item.as_i64().unwrap() * 10);
+    }
+    assert_eq!(doc["a"], yaml!([10, 20]));
+
+    let walked: Vec<_> = doc
+        .walk()
+        .map(|(path, node)| (path.to_string(), node.clone()))
+        .collect();
+    assert_eq!(
+        walked,
+        [
+            (String::new(), doc.clone()),
+            ("/?0".into(), yaml!("a")),
+            ("/a".into(), yaml!([10, 20])),
+            ("/a/0".into(), yaml!(10)),
+            ("/a/1".into(), yaml!(20)),
+            ("/?1".into(), yaml!("b")),
+            ("/b".into(), yaml!({"c": null})),
+            ("/b/?0".into(), yaml!("c")),
+            ("/b/c".into(), Yaml::Null),
+            ("/?2".into(), yaml!([3])),
+            ("/?2/0".into(), yaml!(3)),
+            ("/[...]".into(), yaml!("x")),
+        ]
+    );
+}
+
+#[test]
+fn test_yaml11_schema() {
+    for (scalar, core, yaml11) in [
+        ("yes", yaml!("yes"), yaml!(true)),
+        ("Off", yaml!("Off"), yaml!(false)),
+        ("N", yaml!("N"), yaml!(false)),
+        ("TRUE", yaml!("TRUE"), yaml!(true)),
+        ("NULL", yaml!("NULL"), Yaml::Null),
+        ("0777", yaml!(777), yaml!(0o777)),
+        ("-0_17", yaml!("-0_17"), yaml!(-0o17)),
+        ("0b1010", yaml!("0b1010"), yaml!(10)),
+        ("0x1F", yaml!(31), yaml!(31)),
+        ("0o17", yaml!(15), yaml!("0o17")),
+        ("1_000", yaml!("1_000"), yaml!(1000)),
+        ("1:30:00", yaml!("1:30:00"), yaml!(5400)),
+        ("-1:30", yaml!("-1:30"), yaml!(-90)),
+        ("1:60", yaml!("1:60"), yaml!("1:60")),
+        ("1e3", Yaml::Real("1e3".into()), yaml!("1e3")),
+        ("1.5", Yaml::Real("1.5".into()), Yaml::Real("1.5".into())),
+        ("yesterday", yaml!("yesterday"), yaml!("yesterday")),
+    ] {
+        assert_eq!(Schema::Core.resolve(scalar), core, "{scalar}");
+        assert_eq!(Schema::Yaml11.resolve(scalar), yaml11, "{scalar}");
+    }
+
+    let options = LoaderOptions {
+        schema: Schema::Yaml11,
+        ..LoaderOptions::default()
+    };
+    let source = "enabled: yes\nmode: 0644\nquoted: 'yes'\ntagged: !!bool on\nbad: !!int 0o7";
+    let doc = &Yaml::load_from_str_with_options(source, options.clone()).unwrap()[0];
+    assert_eq!(
+        *doc,
+        yaml!({"enabled": true, "mode": 0o644, "quoted": "yes", "tagged": true, "bad": Yaml::BadValue})
+    );
+    assert_eq!(options.describe()["schema"].as_str(), Some("yaml_1_1"));
+    let doc = &Yaml::load_from_str(source).unwrap()[0];
+    assert_eq!(doc["enabled"].as_str(), Some("yes"));
+    assert_eq!(doc["tagged"], Yaml::BadValue);
+}
+
+#[test]
+fn test_large_integers() {
+    let doc = Yaml::load_one_from_str(
+        "id: 18446744073709551615
+tagged: !!int 9223372036854775808
+negative: -170141183460469231731687303715884105728
+small: -3
+fraction: 1.5
+octal: !!int 0o7777777777777777777777
+",
+    )
+    .unwrap();
+    assert_eq!(doc["id"].as_i64(), None);
+    assert_eq!(doc["id"].as_u64(), Some(u64::MAX));
+    assert_eq!(doc["id"].as_i128(), Some(i128::from(u64::MAX)));
+    assert_eq!(doc["tagged"], Yaml::Real("9223372036854775808".into()));
+    assert_eq!(doc["tagged"].as_u64(), Some(1 << 63));
+    assert_eq!(doc["negative"].as_u64(), None);
+    assert_eq!(doc["negative"].as_i128(), Some(i128::MIN));
+    assert_eq!(doc["small"].as_u64(), None);
+    assert_eq!(doc["small"].as_i128(), Some(-3));
+    assert_eq!(doc["fraction"].as_i128(), None);
+    assert_eq!(doc["octal"], Yaml::BadValue);
+
+    assert_eq!(
+        Schema::Yaml11.resolve("18446744073709551615"),
+        Yaml::Real("18446744073709551615".into())
+    );
+    assert_eq!(Schema::Yaml11.resolve("09"), yaml!("09"));
+}
+
+#[test]
+fn test_failsafe_and_json_schemas() {
+    for (scalar, failsafe, json) in [
+        ("null", yaml!("null"), Yaml::Null),
+        ("~", yaml!("~"), Yaml::BadValue),
+        ("true", yaml!("true"), yaml!(true)),
+        ("True", yaml!("True"), Yaml::BadValue),
+        ("42", yaml!("42"), yaml!(42)),
+        ("-0", yaml!("-0"), yaml!(0)),
+        ("+1", yaml!("+1"), Yaml::BadValue),
+        ("007", yaml!("007"), Yaml::BadValue),
+        ("0x2A", yaml!("0x2A"), Yaml::BadValue),
+        ("-1.5e3", yaml!("-1.5e3"), Yaml::Real("-1.5e3".into())),
+        ("1.", yaml!("1."), Yaml::Real("1.".into())),
+        ("1e", yaml!("1e"), Yaml::BadValue),
+        (".5", yaml!(".5"), Yaml::BadValue),
+        (".inf", yaml!(".inf"), Yaml::BadValue),
+        ("text", yaml!("text"), Yaml::BadValue),
+    ] {
+        assert_eq!(Schema::Failsafe.resolve(scalar), failsafe, "{scalar}");
+        assert_eq!(Schema::Json.resolve(scalar), json, "{scalar}");
+    }
+
+    let source = "a: 1\nb: '1'\nc: !!int 2\nd: !!float 1.5\ne: !!null ~";
+    let options = LoaderOptions {
+        schema: Schema::Failsafe,
+        ..LoaderOptions::default()
+    };
+    assert_eq!(options.describe()["schema"].as_str(), Some("failsafe"));
+    let doc = &Yaml::load_from_str_with_options(source, options).unwrap()[0];
+    assert_eq!(
+        *doc,
+        yaml!({"a": "1", "b": "1", "c": 2, "d": (Yaml::Real("1.5".into())), "e": null})
+    );
+    let options = LoaderOptions {
+        schema: Schema::Json,
+        ..LoaderOptions::default()
+    };
+    assert_eq!(options.describe()["schema"].as_str(), Some("json"));
+    let doc = &Yaml::load_from_str_with_options(source, options).unwrap()[0];
+    assert_eq!(
+        *doc,
+        yaml!({"a": 1, "b": "1", "c": 2, "d": (Yaml::Real("1.5".into())), "e": (Yaml::BadValue)})
+    );
+}
+
+#[test]
+fn test_load_partial() {
+    let source = "- 1\n---\n- 2\n---\n- [3\n---\n- 4";
+    let (docs, error) = Yaml::load_from_str_partial(source);
+    assert_eq!(docs, [yaml!([1]), yaml!([2])]);
+    assert_eq!(
+        error.unwrap().marker().line(),
+        Yaml::load_from_str(source).unwrap_err().marker().line()
+    );
+
+    // Errors of the loader stop loading too.
+    let key = "k".repeat(LoaderOptions::default().max_key_length + 1);
+    let (docs, error) = Yaml::load_from_str_partial(&format!("a: 1\n---\n{key}: 2\n---\nb: 3"));
+    assert_eq!(docs, [yaml!({"a": 1})]);
+    assert_eq!(error.unwrap().marker().line(), 3);
+
+    assert_eq!(Yaml::load_from_str_partial(""), (vec![], None));
+}
+
+#[test]
+fn test_load_resilient() {
+    let source = "- 1\n---\n- [2\n---\n- 3\n---\n- '4\n- 5\n";
+    let (docs, errors) = Yaml::load_from_str_resilient(source);
+    assert_eq!(docs, [yaml!([1]), yaml!([3])]);
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0], Yaml::load_from_str(source).unwrap_err());
+    // The error is at the same position as in the document alone.
+    let error = Yaml::load_from_str("- '4\n- 5\n").unwrap_err();
+    assert_eq!(errors[1].info(), error.info());
+    assert_eq!(errors[1].marker().line(), error.marker().line() + 6);
+    assert_eq!(errors[1].marker().col(), error.marker().col());
+    assert_eq!(
+        errors[1].marker().index(),
+        error.marker().index() + "- 1\n---\n- [2\n---\n- 3\n---\n".len()
+    );
+
+    let source = "a: 1\n---\nb: 2";
+    assert_eq!(
+        Yaml::load_from_str_resilient(source),
+        (Yaml::load_from_str(source).unwrap(), vec![])
+    );
+    assert_eq!(Yaml::load_from_str_resilient(""), (vec![], vec![]));
+}
+
+#[test]
+fn test_load_all_lazily() {
+    let source = "- 1\n---\n- 2\n...\n---\n- [3\n---\n- 4";
+    let mut docs = Yaml::load_all_from_str(source);
+    assert_eq!(docs.next(), Some(Ok(yaml!([1]))));
+    assert_eq!(docs.next(), Some(Ok(yaml!([2]))));
+    assert_eq!(
+        docs.next().unwrap().unwrap_err(),
+        Yaml::load_from_str(source).unwrap_err()
+    );
+    assert_eq!(docs.next(), None);
+
+    // Errors of the loader end the iteration too.
+    let key = "k".repeat(LoaderOptions::default().max_key_length + 1);
+    let source = format!("a: 1\n---\n{key}: 2\n---\nb: 3");
+    let docs: Vec<_> = Yaml::load_all_from_str(&source).collect();
+    assert_eq!(docs.len(), 2);
+    assert_eq!(docs[1].as_ref().unwrap_err().marker().line(), 3);
+
+    let source = "a: 1\n---\n---\n[b]\n";
+    let docs: Result<Vec<_>, _> = Yaml::load_all_from_str(source).collect();
+    assert_eq!(docs.unwrap(), Yaml::load_from_str(source).unwrap());
+    assert_eq!(Yaml::load_all_from_str("").next(), None);
+}
+
+#[test]
+fn test_directives() {
+    let source =
+        "%YAML 1.1\n%TAG !e! tag:example.com,2020:\n--- !e!point\nx: yes\n...\n---\nx: yes\n";
+    let options = LoaderOptions {
+        schema_from_version: true,
+        ..LoaderOptions::default()
+    };
+    let (docs, metadata) = Yaml::load_from_str_with_metadata(source, options.clone()).unwrap();
+    assert_eq!(metadata.len(), 2);
+    assert_eq!(metadata[0].version, Some((1, 1)));
+    assert_eq!(
+        metadata[0].tag_handles.get("!e!").map(String::as_str),
+        Some("tag:example.com,2020:")
+    );
+    assert_eq!(
+        metadata[1],
+        DocumentMetadata {
+            explicit_start: true,
+            ..DocumentMetadata::default()
+        }
+    );
+    assert_eq!(docs[0]["x"], Yaml::Boolean(true));
+    assert_eq!(docs[1]["x"].as_str(), Some("yes"));
+
+    let (_, metadata) = Yaml::load_from_str_with_metadata(
+        "a
+--- b
+",
+        options.clone(),
+    )
+    .unwrap();
+    let explicit: Vec<_> = metadata.iter().map(|doc| doc.explicit_start).collect();
+    assert_eq!(explicit, [false, true]);
+
+    // Declared handles are expanded before tags are resolved.
+    let source = "%TAG !y! tag:yaml.org,2002:\n---\n[!y!int 3, !y!str 4]";
+    let mut loader = YamlLoader::<Yaml>::with_options(LoaderOptions {
+        strict_tag_handles: true,
+        ..LoaderOptions::default()
+    });
+    loader.read_directives(source);
+    let at_line = |line| Span::new(Marker::new(0, line, 0), Marker::new(0, line, 0));
+    let tagged = |value: &str, suffix: &str| {
+        let tag = Tag {
+            handle: "!y!".into(),
+            suffix: suffix.into(),
+        };
+        Event::Scalar(value.into(), TScalarStyle::Plain, 0, Some(tag))
+    };
+    for (event, line) in [
+        (Event::StreamStart, 1),
+        (Event::DocumentStart(true), 2),
+        (Event::SequenceStart(0, None), 3),
+        (tagged("3", "int"), 3),
+        (tagged("4", "str"), 3),
+        (Event::SequenceEnd, 3),
+        (Event::DocumentEnd, 3),
+    ] {
+        loader.on_event(event, at_line(line));
+    }
+    assert!(loader.error().is_none());
+    assert_eq!(loader.documents(), [yaml!([3, "4"])]);
+
+    // Undeclared handles are rejected on request.
+    loader.on_event(Event::DocumentStart(true), at_line(4));
+    loader.on_event(tagged("3", "int"), at_line(4));
+    let error = loader.error().unwrap();
+    assert_eq!(error.kind(), LoadErrorKind::UnknownTagHandle);
+    assert_eq!(
+        error.to_string(),
+        "tag handle `!y!` at line 4 column 1 was not declared by a `%TAG` directive"
+    );
+}
+
+#[test]
+fn test_keep_tags() {
+    let options = LoaderOptions {
+        keep_tags: true,
+        ..LoaderOptions::default()
+    };
+    let source = "%TAG !e! tag:example.com,2020:\n---\nbucket: !Ref Bucket\nset: !!set {a: ~}\n\
+                  list: !e!list [1, !!int 2]\nobj: !!python/object:app.Config\n  debug: true\n\
+                  port: !!int 80\nplain: ! 12\n";
+    let doc = Yaml::load_from_str_with_options(source, options.clone())
+        .unwrap()
+        .remove(0);
+    let tag = |node: &Yaml| {
+        node.tag()
+            .map(|tag| format!("{}{}", tag.handle, tag.suffix))
+    };
+    assert_eq!(tag(&doc["bucket"]).as_deref(), Some("!Ref"));
+    assert_eq!(doc["bucket"].untagged().as_str(), Some("Bucket"));
+    assert_eq!(tag(&doc["set"]).as_deref(), Some("tag:yaml.org,2002:set"));
+    assert_eq!(doc["set"].untagged(), &yaml!({"a": null}));
+    assert_eq!(
+        tag(&doc["list"]).as_deref(),
+        Some("tag:example.com,2020:list")
+    );
+    assert_eq!(doc["list"].untagged(), &yaml!([1, 2]));
+    assert_eq!(
+        tag(&doc["obj"]).as_deref(),
+        Some("tag:yaml.org,2002:python/object:app.Config")
+    );
+    // Tags the loader resolves are not kept.
+    assert_eq!(doc["port"], Yaml::Integer(80));
+    assert_eq!(doc["plain"], Yaml::String("12".into()));
+
+    // Tags are dropped by default.
+    let doc = Yaml::load_from_str(source).unwrap().remove(0);
+    assert!(doc.walk().all(|(_, node)| !node.is_tagged()));
+    assert_eq!(doc["bucket"].as_str(), Some("Bucket"));
+
+    // Tagged nodes can be anchored, and contain anchors.
+    let local = |suffix: &str| {
+        Some(Tag {
+            handle: "!".into(),
+            suffix: suffix.into(),
+        })
+    };
+    let scalar = |value: &str, aid| Event::Scalar(value.into(), TScalarStyle::Plain, aid, None);
+    let events = [
+        Event::StreamStart,
+        Event::DocumentStart(false),
+        Event::MappingStart(0, None),
+        scalar("a", 0),
+        Event::SequenceStart(1, local("List")),
+        scalar("x", 2),
+        Event::SequenceEnd,
+        scalar("b", 0),
+        Event::Alias(1),
+        scalar("c", 0),
+        Event::Alias(2),
+        scalar("d", 0),
+        Event::Scalar("1".into(), TScalarStyle::Plain, 0, local("Id")),
+        Event::MappingEnd,
+        Event::DocumentEnd,
+        Event::StreamEnd,
+    ];
+    let mut loader = YamlLoader::<Yaml>::with_options(options);
+    for event in events {
+        loader.on_event(event, Span::default());
+    }
+    let tagged = |suffix: &str, node: Yaml| Yaml::Tagged(local(suffix).unwrap(), Box::new(node));
+    let list = tagged("List", yaml!(["x"]));
+    assert_eq!(
+        loader.into_documents(),
+        [yaml!({"a": (list.clone()), "b": list, "c": "x", "d": (tagged("Id", yaml!("1")))})]
+    );
+}
+
+#[test]
+fn test_intern_keys() {
+    let options = LoaderOptions {
+        intern_keys: true,
+        ..LoaderOptions::default()
+    };
+    let source = "- {name: a, ports: [80]}\n- {'name': b, ports: [443]}\n- {1: x, name: c}\n";
+    let docs = Yaml::load_from_str_with_options(source, options.clone()).unwrap();
+    let items = docs[0].as_vec().unwrap();
+    let key = |item: &Yaml, i| item.as_hash().unwrap().keys().nth(i).unwrap().clone();
+    // String keys with the same text share their storage, whether they are quoted or not.
+    let (Yaml::SharedString(first), Yaml::SharedString(second)) =
+        (key(&items[0], 0), key(&items[1], 0))
+    else {
+        panic!("the keys are not shared");
+    };
+    assert!(Arc::ptr_eq(&first, &second));
+    assert!(matches!(key(&items[1], 1), Yaml::SharedString(_)));
+    // Values and other keys are loaded as usual.
+    assert_eq!(key(&items[2], 0), Yaml::Integer(1));
+    assert!(matches!(items[0]["name"], Yaml::String(_)));
+
+    // Shared strings are otherwise the same as strings.
+    assert_eq!(docs, Yaml::load_from_str(source).unwrap());
+    assert_eq!(items[1]["ports"], yaml!([443]));
+    assert_eq!(key(&items[0], 1).as_str(), Some("ports"));
+    assert_eq!(key(&items[0], 1).kind(), YamlKind::String);
+    assert!(Yaml::SharedString("a".into()) < Yaml::String("b".into()));
+    let mut emitted = [String::new(), String::new()];
+    YamlEmitter::new(&mut emitted[0]).dump(&docs[0]).unwrap();
+    YamlEmitter::new(&mut emitted[1])
+        .dump(&Yaml::load_from_str(source).unwrap()[0])
+        .unwrap();
+    assert_eq!(emitted[0], emitted[1]);
+
+    assert_eq!(options.describe()["intern_keys"].as_bool(), Some(true));
+    assert_eq!(
+        LoaderOptions::default().describe()["intern_keys"].as_bool(),
+        Some(false)
+    );
+}
+
+#[test]
+fn test_strict() {
+    let strict = LoaderOptions {
+        strict: true,
+        ..LoaderOptions::default()
+    };
+    let error = Yaml::load_from_str_with_options("a:\n  b: !!int foo", strict.clone()).unwrap_err();
+    assert_eq!(error.kind(), LoadErrorKind::InvalidScalar);
+    assert_eq!(error.kind().code(), "invalid-scalar");
+    assert!(matches!(
+        &error,
+        LoadError::InvalidScalar { value, tag: Some(tag), .. } if value == "foo" && tag == "!!int"
+    ));
+    assert_eq!(error.path().unwrap().to_string(), "/a/b");
+    assert_eq!(
+        error.to_string(),
+        "`foo` at line 2 column 6 is not a valid `!!int`"
+    );
+
+    let json = LoaderOptions {
+        schema: Schema::Json,
+        ..strict.clone()
+    };
+    let error = Yaml::load_from_str_with_options("[1, 'x', hello]", json).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "`hello` at line 1 column 10 is not valid for the schema"
+    );
+
+    // Unknown aliases fail whatever `unknown_aliases` is.
+    let error = Yaml::load_from_str_with_options("&list [1, *list]", strict.clone()).unwrap_err();
+    assert_eq!(error.kind(), LoadErrorKind::UnknownAlias);
+
+    // Valid documents load as usual.
+    let docs = Yaml::load_from_str_with_options("a: !!int 1\nb: &x [x]\nc: *x", strict.clone());
+    assert_eq!(docs.unwrap(), [yaml!({"a": 1, "b": ["x"], "c": ["x"]})]);
+
+    // Only event streams can have documents without a root node.
+    let mut loader = YamlLoader::<Yaml>::with_options(strict);
+    for event in [
+        Event::StreamStart,
+        Event::DocumentStart(true),
+        Event::DocumentEnd,
+        Event::StreamEnd,
+    ] {
+        loader.on_event(event, Span::new(Marker::new(0, 1, 0), Marker::new(3, 1, 3)));
+    }
+    let error = loader.error().unwrap();
+    assert_eq!(error.kind(), LoadErrorKind::EmptyDocument);
+    assert_eq!(error.kind().code(), "empty-document");
+    assert_eq!(
+        error.to_string(),
+        "document at line 1 column 1 has no root node"
+    );
+}
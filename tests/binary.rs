@@ -0,0 +1,76 @@
+use saphyr::{LoaderOptions, Span, Yaml, YamlEmitter, YamlLoader};
+
+fn load(s: &str) -> Yaml {
+    Yaml::load_from_str(s).unwrap().remove(0)
+}
+
+fn dump(doc: &Yaml) -> String {
+    let mut out = String::new();
+    YamlEmitter::new(&mut out).dump(doc).unwrap();
+    out
+}
+
+#[test]
+fn test_load_binary() {
+    // From https://yaml.org/type/binary.html.
+    let doc = load(
+        "picture: !!binary |
+  R0lGODlhDAAMAIQAAP//9/X
+  17unp5WZmZgAAAOfn515eXv
+",
+    );
+    let picture = doc["picture"].as_binary().unwrap();
+    assert_eq!(picture.len(), 34);
+    assert_eq!(&picture[..6], b"GIF89a");
+
+    for (text, bytes) in [
+        ("''", &b""[..]),
+        ("TQ==", b"M"),
+        ("TWE=", b"Ma"),
+        ("TWFu", b"Man"),
+        ("TWE", b"Ma"),
+        ("'TW Fu'", b"Man"),
+    ] {
+        let doc = load(&format!("!!binary {text}"));
+        assert_eq!(doc.as_binary(), Some(bytes), "{text}");
+    }
+    for invalid in ["T", "TWE=x", "TQ===", "TWE=TWE=", "T!Fu", "TWE=="] {
+        assert_eq!(
+            load(&format!("!!binary {invalid}")),
+            Yaml::BadValue,
+            "{invalid}"
+        );
+    }
+
+    // Binary tags are resolved, so they are not kept.
+    let options = LoaderOptions {
+        keep_tags: true,
+        ..LoaderOptions::default()
+    };
+    let docs = Yaml::load_from_str_with_options("!!binary TWFu", options).unwrap();
+    assert_eq!(docs[0], Yaml::Binary(b"Man".to_vec()));
+}
+
+#[test]
+fn test_emit_binary() {
+    let doc = Yaml::Array(vec![
+        Yaml::Binary(b"Man".to_vec()),
+        Yaml::Binary(vec![0xfb, 0xff]),
+        Yaml::Binary(vec![]),
+    ]);
+    let out = dump(&doc);
+    assert_eq!(
+        out,
+        "---\n- !!binary TWFu\n- !!binary +/8=\n- !!binary \"\""
+    );
+    assert_eq!(load(&out), doc);
+
+    let mut out = String::new();
+    let mut emitter = YamlEmitter::new(&mut out);
+    emitter.canonical(true);
+    emitter.dump(&doc[0]).unwrap();
+    assert_eq!(out, "---\n!!binary \"TWFu\"");
+
+    let events = doc.clone().into_events().map(|ev| (ev, Span::default()));
+    assert_eq!(YamlLoader::<Yaml>::from_events(events).unwrap(), [doc]);
+}
@@ -0,0 +1,60 @@
+use std::borrow::Cow;
+
+use saphyr::{BorrowedYaml, Yaml};
+
+#[test]
+fn test_borrowed_matches_owned() {
+    for source in [
+        "",
+        "plain scalar",
+        "a: 1\nb: [true, null, ~, 1.5, .inf, 0x1f, '42', \"é\\u00e9\"]\nc: {d: e}",
+        "literal: |\n  line\n  other\nfolded: >-\n  a\n  b\nmultiline: plain\n  continued\n",
+        "- 'it''s'\n- \"tab\\there\"\n- \"quoted\n  folded\"\n- !!str 12\n- !custom tagged",
+        "base: &a {x: [1, 2]}\ncopy: *a\nkey: &k name\n*k : value\ndup: 1\ndup: 2\n",
+        "? [complex, key]\n: value\n? {a: b}\n",
+        "---\nfirst\n---\nsecond: 'ünïcödé'\n...\n---\n- ☃\n",
+    ] {
+        let borrowed = BorrowedYaml::load_from_str(source).unwrap();
+        let owned: Vec<Yaml> = borrowed.into_iter().map(BorrowedYaml::into_owned).collect();
+        assert_eq!(owned, Yaml::load_from_str(source).unwrap(), "{source:?}");
+    }
+
+    assert!(BorrowedYaml::load_from_str("a: [1").is_err());
+}
+
+#[test]
+fn test_borrowed_scalars() {
+    let source = "ünï: cödé\nquoted: 'single'\ndouble: \"double\"\nreal: 1.5\n\
+                  escaped: \"a\\nb\"\nlines: 'a\n  b'\nblock: |\n  text\n";
+    let docs = BorrowedYaml::load_from_str(source).unwrap();
+    let doc = &docs[0];
+    for (key, value) in [("ünï", "cödé"), ("quoted", "single"), ("double", "double")] {
+        assert!(
+            matches!(&doc[key], BorrowedYaml::String(Cow::Borrowed(v)) if *v == value),
+            "{key}: {:?}",
+            doc[key]
+        );
+    }
+    assert!(matches!(
+        &doc["real"],
+        BorrowedYaml::Real(Cow::Borrowed("1.5"))
+    ));
+    assert_eq!(doc["real"].as_f64(), Some(1.5));
+    for (key, value) in [("escaped", "a\nb"), ("lines", "a b"), ("block", "text\n")] {
+        assert!(
+            matches!(&doc[key], BorrowedYaml::String(Cow::Owned(v)) if v == value),
+            "{key}: {:?}",
+            doc[key]
+        );
+    }
+    // Keys are borrowed too.
+    let (key, _) = doc.as_hash().unwrap().front().unwrap();
+    assert!(matches!(key, BorrowedYaml::String(Cow::Borrowed("ünï"))));
+
+    assert!(doc["missing"].is_badvalue());
+    assert!(doc[0].is_badvalue());
+    assert_eq!(
+        BorrowedYaml::from(Yaml::load_from_str(source).unwrap().remove(0)),
+        *doc
+    );
+}
@@ -0,0 +1,119 @@
+use saphyr::{CommentedYaml, YamlData, YamlEmitter};
+
+fn load(s: &str) -> CommentedYaml {
+    let mut docs = CommentedYaml::load_from_str(s).unwrap();
+    assert_eq!(docs.len(), 1);
+    docs.remove(0)
+}
+
+fn dump(doc: &CommentedYaml) -> String {
+    let mut out = String::new();
+    YamlEmitter::new(&mut out).dump_commented(doc).unwrap();
+    out
+}
+
+#[test]
+fn test_comments_round_trip() {
+    let s = "
+# Application settings.
+name: saphyr # The name.
+
+# Dependencies.
+dependencies:
+  # Parser.
+  parser: 0.0.2
+  hashlink: 0.8 # Pinned.
+items:
+  # First.
+  - a
+  # Second.
+  - b # Trailing.
+nested: # The nested mapping.
+  key: value
+";
+    let expected = "# Application settings.
+---
+name: saphyr # The name.
+# Dependencies.
+dependencies:
+  # Parser.
+  parser: 0.0.2
+  hashlink: 0.8 # Pinned.
+items:
+  # First.
+  - a
+  # Second.
+  - b # Trailing.
+nested: # The nested mapping.
+  key: value";
+
+    let doc = load(s);
+    assert_eq!(doc.comments.leading, [" Application settings."]);
+    assert_eq!(
+        doc.data["name"].comments.trailing.as_deref(),
+        Some(" The name.")
+    );
+    assert_eq!(
+        doc.data["nested"].comments.trailing.as_deref(),
+        Some(" The nested mapping.")
+    );
+
+    let out = dump(&doc);
+    assert_eq!(out, expected);
+    // Comments are attached to the same nodes when loading the output again.
+    assert_eq!(dump(&load(&out)), expected);
+}
+
+#[test]
+fn test_comments_sequence_of_mappings() {
+    let s = "# Servers.
+- name: a # First.
+  port: 1
+# Second server.
+- name: b";
+    let expected = "# Servers.
+---
+- name: a # First.
+  port: 1
+# Second server.
+- name: b";
+    assert_eq!(dump(&load(s)), expected);
+}
+
+#[test]
+fn test_comments_in_block_scalar() {
+    let s = "script: |
+  # not a comment
+  echo hi
+# real
+after: 1";
+    let doc = load(s);
+    assert!(doc.comments.is_empty());
+    assert!(doc.data["script"].comments.is_empty());
+    assert_eq!(
+        doc.data
+            .as_hash()
+            .unwrap()
+            .keys()
+            .nth(1)
+            .unwrap()
+            .comments
+            .leading,
+        [" real"]
+    );
+}
+
+#[test]
+fn test_comments_after_edit() {
+    let mut doc = load("# Header.\nversion: 1 # Bump me.\nname: x");
+    doc.data["version"].data = YamlData::Integer(2);
+    doc.data
+        .as_mut_hash()
+        .unwrap()
+        .insert(load("new"), load("3 # Added."));
+    doc.data["name"].comments.trailing = Some(" Renamed.".to_owned());
+    assert_eq!(
+        dump(&doc),
+        "# Header.\n---\nversion: 2 # Bump me.\nname: x # Renamed.\nnew: 3 # Added."
+    );
+}
@@ -0,0 +1,131 @@
+//! A node type built by the loader without going through `Yaml`.
+
+use std::{cell::RefCell, collections::HashSet, rc::Rc};
+
+use hashlink::LinkedHashMap;
+use saphyr::{LoadableYamlNode, LoaderOptions, TScalarStyle, Tag, Yaml, YamlLoader};
+use saphyr_parser::{BufferedInput, Parser};
+
+thread_local! {
+    static STRINGS: RefCell<HashSet<Rc<str>>> = RefCell::new(HashSet::new());
+}
+
+/// Return the interned copy of `value`.
+fn intern(value: &str) -> Rc<str> {
+    STRINGS.with(|strings| {
+        let mut strings = strings.borrow_mut();
+        if let Some(interned) = strings.get(value) {
+            return interned.clone();
+        }
+        let interned: Rc<str> = value.into();
+        strings.insert(interned.clone());
+        interned
+    })
+}
+
+/// A node keeping scalars unresolved and interned, and the tags of collections.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+enum Node {
+    Scalar(Rc<str>),
+    Sequence(Vec<Node>, Option<String>),
+    Mapping(LinkedHashMap<Node, Node>, Option<String>),
+    Bad,
+}
+
+fn tag_name(tag: Option<&Tag>) -> Option<String> {
+    tag.map(|tag| format!("{}{}", tag.handle, tag.suffix))
+}
+
+impl LoadableYamlNode for Node {
+    fn from_bare_yaml(yaml: Yaml) -> Self {
+        match yaml {
+            Yaml::Array(_) => Node::Sequence(vec![], None),
+            Yaml::Hash(_) => Node::Mapping(LinkedHashMap::new(), None),
+            Yaml::BadValue => Node::Bad,
+            yaml => panic!("scalars are built by `from_scalar`, not from {yaml:?}"),
+        }
+    }
+
+    fn from_scalar(value: String, _: TScalarStyle, _: Option<&Tag>, _: &LoaderOptions) -> Self {
+        Node::Scalar(intern(&value))
+    }
+
+    fn from_sequence_start(tag: Option<&Tag>) -> Self {
+        Node::Sequence(vec![], tag_name(tag))
+    }
+
+    fn from_mapping_start(tag: Option<&Tag>) -> Self {
+        Node::Mapping(LinkedHashMap::new(), tag_name(tag))
+    }
+
+    fn is_array(&self) -> bool {
+        matches!(self, Node::Sequence(..))
+    }
+
+    fn is_hash(&self) -> bool {
+        matches!(self, Node::Mapping(..))
+    }
+
+    fn is_badvalue(&self) -> bool {
+        matches!(self, Node::Bad)
+    }
+
+    fn array_mut(&mut self) -> &mut Vec<Self> {
+        match self {
+            Node::Sequence(items, _) => items,
+            _ => panic!("not a sequence"),
+        }
+    }
+
+    fn hash_mut(&mut self) -> &mut LinkedHashMap<Self, Self> {
+        match self {
+            Node::Mapping(entries, _) => entries,
+            _ => panic!("not a mapping"),
+        }
+    }
+
+    fn take(&mut self) -> Self {
+        std::mem::replace(self, Node::Bad)
+    }
+}
+
+#[test]
+fn test_custom_scalars_and_tags() {
+    let mut loader = YamlLoader::<Node>::new();
+    let mut parser = Parser::new(BufferedInput::new(
+        "!list [web, !!map {web: 1}, web]".chars(),
+    ));
+    parser.load(&mut loader, true).unwrap();
+    let docs = loader.into_documents();
+
+    let Node::Sequence(items, tag) = &docs[0] else {
+        panic!("not a sequence: {:?}", docs[0]);
+    };
+    assert_eq!(tag.as_deref(), Some("!list"));
+    let Node::Mapping(entries, tag) = &items[1] else {
+        panic!("not a mapping: {:?}", items[1]);
+    };
+    assert_eq!(tag.as_deref(), Some("tag:yaml.org,2002:map"));
+    assert_eq!(
+        entries.get(&Node::Scalar("web".into())),
+        Some(&Node::Scalar("1".into()))
+    );
+    let (Node::Scalar(first), Node::Scalar(last)) = (&items[0], &items[2]) else {
+        panic!("not scalars: {items:?}");
+    };
+    assert!(Rc::ptr_eq(first, last));
+}
+
+#[test]
+fn test_declared_tag_handles() {
+    let source = "%TAG !e! tag:example.com,2020:\n--- !e!list [a]";
+    let mut loader = YamlLoader::<Node>::new();
+    loader.read_directives(source);
+    let mut parser = Parser::new(BufferedInput::new(source.chars()));
+    parser.load(&mut loader, true).unwrap();
+
+    let Node::Sequence(_, tag) = &loader.documents()[0] else {
+        panic!("not a sequence: {:?}", loader.documents()[0]);
+    };
+    assert_eq!(tag.as_deref(), Some("tag:example.com,2020:list"));
+}
@@ -0,0 +1,154 @@
+use saphyr::{CompareOptions, PathSegment, Yaml, YamlDiff};
+
+fn yaml(s: &str) -> Yaml {
+    Yaml::load_one_from_str(s).unwrap()
+}
+
+#[test]
+fn test_semantic_eq() {
+    let a = yaml("a: 1.0\nb: [1e3, -0.0, {x: .inf}]\nc: text");
+    let b = yaml("c: text\nb: [1000.0, 0.0, {x: .Inf}]\na: 1.00");
+    assert_ne!(a, b);
+    assert!(a.semantic_eq(&b));
+    assert!(b.semantic_eq(&a));
+
+    // Types still matter.
+    assert!(!yaml("1").semantic_eq(&yaml("1.0")));
+    assert!(!yaml("'1.0'").semantic_eq(&yaml("1.0")));
+    assert!(!yaml("[1, 2]").semantic_eq(&yaml("[2, 1]")));
+    assert!(!yaml("{a: 1}").semantic_eq(&yaml("{a: 1, b: 2}")));
+
+    // Keys are compared semantically as well.
+    assert!(yaml("{1.0: a}").semantic_eq(&yaml("{1.00: a}")));
+}
+
+#[test]
+fn test_semantic_eq_options() {
+    let nan = yaml(".nan");
+    assert!(nan.semantic_eq(&nan));
+    let strict = CompareOptions {
+        nans_equal: false,
+        ..CompareOptions::default()
+    };
+    assert!(!nan.semantic_eq_with(&nan, strict));
+
+    let with_null = yaml("{a: 1, b: ~}");
+    let without = yaml("{a: 1}");
+    assert!(!with_null.semantic_eq(&without));
+    let lenient = CompareOptions {
+        null_is_missing: true,
+        ..CompareOptions::default()
+    };
+    assert!(with_null.semantic_eq_with(&without, lenient));
+    assert!(without.semantic_eq_with(&with_null, lenient));
+    assert!(with_null.diff_with(&without, lenient).is_empty());
+
+    let unordered = CompareOptions {
+        unordered_sequences: true,
+        ..CompareOptions::default()
+    };
+    assert!(yaml("[1, [a, b], {c: [2, 3]}]")
+        .semantic_eq_with(&yaml("[{c: [3, 2]}, 1, [b, a]]"), unordered));
+    assert!(!yaml("[1, 1, 2]").semantic_eq_with(&yaml("[1, 2, 2]"), unordered));
+    assert!(!yaml("[1, 1]").semantic_eq_with(&yaml("[1]"), unordered));
+}
+
+#[test]
+fn test_diff_unordered() {
+    let unordered = CompareOptions {
+        unordered_sequences: true,
+        ..CompareOptions::default()
+    };
+    let path = |i| vec![PathSegment::Key(yaml("hosts")), PathSegment::Index(i)].into();
+    let a = yaml(
+        "hosts: [a, b, c, b]
+ports: [80, 443]",
+    );
+    let b = yaml(
+        "hosts: [c, d, b, a]
+ports: [443, 80]",
+    );
+    assert_eq!(
+        a.diff_with(&b, unordered),
+        [
+            YamlDiff::Removed {
+                path: path(3),
+                value: yaml("b"),
+            },
+            YamlDiff::Added {
+                path: path(1),
+                value: yaml("d"),
+            },
+        ]
+    );
+    assert_eq!(a.diff(&b).len(), 6);
+}
+
+#[test]
+fn test_diff() {
+    let a = yaml(
+        "
+name: app
+replicas: 1
+ports: [80, 443]
+env: {A: 1, B: 2}
+ratio: 0.5
+",
+    );
+    let b = yaml(
+        "
+name: app
+ports: [80]
+env: {B: 3, A: 1, C: 4}
+ratio: 0.50
+replicas: 2
+extra: ~
+",
+    );
+    let s = |v: &str| Yaml::String(v.into());
+    assert_eq!(
+        a.diff(&b)
+            .iter()
+            .map(|diff| diff.path().to_string())
+            .collect::<Vec<_>>(),
+        ["/replicas", "/ports/1", "/env/B", "/env/C", "/extra"]
+    );
+    let diffs = a.diff(&b);
+    assert!(matches!(
+        &diffs[0],
+        YamlDiff::Changed {
+            from: Yaml::Integer(1),
+            to: Yaml::Integer(2),
+            ..
+        }
+    ));
+    assert!(matches!(
+        &diffs[1],
+        YamlDiff::Removed {
+            value: Yaml::Integer(443),
+            ..
+        }
+    ));
+    assert!(matches!(
+        &diffs[3],
+        YamlDiff::Added {
+            value: Yaml::Integer(4),
+            ..
+        }
+    ));
+    assert_eq!(
+        diffs[4].path().segments(),
+        [saphyr::PathSegment::Key(s("extra"))]
+    );
+
+    assert!(a.diff(&a).is_empty());
+    // Different types are reported as changed, without looking inside.
+    assert_eq!(
+        yaml("a: [1]").diff(&yaml("a: {b: 1}")),
+        [YamlDiff::Changed {
+            path: vec![saphyr::PathSegment::Key(s("a"))].into(),
+            from: yaml("[1]"),
+            to: yaml("{b: 1}"),
+        }]
+    );
+}
@@ -0,0 +1,459 @@
+//! Differential tests against a reference YAML implementation.
+//!
+//! Every YAML file in `tests/data/differential` is loaded and compared against the JSON output of
+//! a reference implementation for the same file. See `tests/data/differential/README.md`.
+//!
+//! Both sides are normalized before being compared:
+//!   * Mapping keys are compared regardless of their order.
+//!   * Non-string keys are converted to their string representation, as JSON requires.
+//!   * Integers and floats are compared as `f64`, with a small relative tolerance.
+//!   * Tags are dropped, and timestamps and binary data are compared as the strings a
+//!     JSON-outputting implementation writes them as.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use saphyr::Yaml;
+
+/// The relative tolerance when comparing numbers.
+const FLOAT_TOLERANCE: f64 = 1e-12;
+
+/// A normalized document tree.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(BTreeMap<String, Value>),
+    /// A node saphyr could not load properly (`BadValue`, `Alias`) or a complex key.
+    Invalid(String),
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => f.write_str("null"),
+            Value::Bool(v) => write!(f, "{v}"),
+            Value::Number(v) => write!(f, "{v}"),
+            Value::String(v) => write!(f, "{v:?}"),
+            Value::Array(v) => write!(f, "array of {} elements", v.len()),
+            Value::Object(v) => write!(f, "object of {} entries", v.len()),
+            Value::Invalid(v) => write!(f, "invalid node ({v})"),
+        }
+    }
+}
+
+/// Normalize a [`Yaml`] node.
+fn normalize(yaml: &Yaml) -> Value {
+    match yaml {
+        Yaml::Null => Value::Null,
+        Yaml::Boolean(v) => Value::Bool(*v),
+        #[allow(clippy::cast_precision_loss)]
+        Yaml::Integer(v) => Value::Number(*v as f64),
+        Yaml::Real(_) => Value::Number(yaml.as_f64().unwrap()),
+        Yaml::Float(v) => Value::Number(v.value),
+        Yaml::String(v) => Value::String(v.clone()),
+        Yaml::SharedString(v) => Value::String(v.to_string()),
+        Yaml::Timestamp(v) => Value::String(v.to_string()),
+        Yaml::Binary(v) => Value::String(base64(v)),
+        Yaml::Array(v) => Value::Array(v.iter().map(normalize).collect()),
+        Yaml::Hash(v) => Value::Object(
+            v.iter()
+                .map(|(k, v)| (normalize_key(k), normalize(v)))
+                .collect(),
+        ),
+        Yaml::Tagged(_, v) => normalize(v),
+        Yaml::Alias(_) | Yaml::BadValue => Value::Invalid(format!("{yaml:?}")),
+    }
+}
+
+/// Convert a mapping key to a string, as a JSON-outputting implementation would.
+fn normalize_key(key: &Yaml) -> String {
+    match key {
+        Yaml::String(v) | Yaml::Real(v) => v.clone(),
+        Yaml::SharedString(v) => v.to_string(),
+        Yaml::Integer(v) => v.to_string(),
+        Yaml::Boolean(v) => v.to_string(),
+        Yaml::Float(v) => v.source.clone().unwrap_or_else(|| v.to_string()),
+        Yaml::Timestamp(v) => v.to_string(),
+        Yaml::Binary(v) => base64(v),
+        Yaml::Null => "null".to_owned(),
+        Yaml::Tagged(_, v) => normalize_key(v),
+        _ => format!("<complex key {key:?}>"),
+    }
+}
+
+/// Encode `bytes` in base64 with padding, as `!!binary` scalars are written.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut text = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, &byte)| {
+            group | u32::from(byte) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(char::from(ALPHABET[(group >> (18 - 6 * i) & 63) as usize]));
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+/// A minimal JSON reader, so that expected files are not read by the implementation under test.
+///
+/// It additionally accepts `Infinity`, `-Infinity` and `NaN` as numbers.
+struct JsonReader<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> JsonReader<'a> {
+    fn parse(input: &'a str) -> Result<Value, String> {
+        let mut reader = JsonReader {
+            input: input.as_bytes(),
+            pos: 0,
+        };
+        let value = reader.value()?;
+        reader.skip_whitespace();
+        if reader.pos == reader.input.len() {
+            Ok(value)
+        } else {
+            Err(reader.error("trailing characters"))
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("{message} at byte {}", self.pos)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .input
+            .get(self.pos)
+            .is_some_and(u8::is_ascii_whitespace)
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, literal: &str) -> bool {
+        if self.input[self.pos..].starts_with(literal.as_bytes()) {
+            self.pos += literal.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, literal: &str) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.eat(literal) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{literal}`")))
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        match self.input.get(self.pos) {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => self.string().map(Value::String),
+            Some(_) if self.eat("null") => Ok(Value::Null),
+            Some(_) if self.eat("true") => Ok(Value::Bool(true)),
+            Some(_) if self.eat("false") => Ok(Value::Bool(false)),
+            Some(_) if self.eat("NaN") => Ok(Value::Number(f64::NAN)),
+            Some(_) if self.eat("Infinity") => Ok(Value::Number(f64::INFINITY)),
+            Some(_) if self.eat("-Infinity") => Ok(Value::Number(f64::NEG_INFINITY)),
+            Some(_) => self.number(),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, String> {
+        self.expect("{")?;
+        let mut object = BTreeMap::new();
+        self.skip_whitespace();
+        if self.eat("}") {
+            return Ok(Value::Object(object));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.expect(":")?;
+            object.insert(key, self.value()?);
+            self.skip_whitespace();
+            if self.eat("}") {
+                return Ok(Value::Object(object));
+            }
+            self.expect(",")?;
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.expect("[")?;
+        let mut array = vec![];
+        self.skip_whitespace();
+        if self.eat("]") {
+            return Ok(Value::Array(array));
+        }
+        loop {
+            array.push(self.value()?);
+            self.skip_whitespace();
+            if self.eat("]") {
+                return Ok(Value::Array(array));
+            }
+            self.expect(",")?;
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect("\"")?;
+        let mut bytes = vec![];
+        loop {
+            let Some(&byte) = self.input.get(self.pos) else {
+                return Err(self.error("unterminated string"));
+            };
+            self.pos += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let escape = *self.input.get(self.pos).ok_or("unterminated string")?;
+                    self.pos += 1;
+                    let unescaped = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\x08',
+                        b'f' => '\x0c',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.unicode_escape()?,
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    bytes.extend_from_slice(unescaped.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                _ => bytes.push(byte),
+            }
+        }
+        String::from_utf8(bytes).map_err(|e| e.to_string())
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits = self
+            .input
+            .get(self.pos..self.pos + 4)
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .ok_or_else(|| self.error("truncated unicode escape"))?;
+        self.pos += 4;
+        u32::from_str_radix(digits, 16).map_err(|e| e.to_string())
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.hex4()?;
+        let code = if (0xD800..0xDC00).contains(&high) {
+            if !self.eat("\\u") {
+                return Err(self.error("lone surrogate"));
+            }
+            let low = self.hex4()?;
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            high
+        };
+        char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"))
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        while self
+            .input
+            .get(self.pos)
+            .is_some_and(|c| c.is_ascii_digit() || b"+-.eE".contains(c))
+        {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.input[start..self.pos])
+            .unwrap()
+            .parse()
+            .map(Value::Number)
+            .map_err(|_| self.error("invalid number"))
+    }
+}
+
+/// A difference between what saphyr loaded and what the reference loaded.
+struct Divergence {
+    file: String,
+    document: usize,
+    path: String,
+    expected: String,
+    found: String,
+}
+
+impl Divergence {
+    fn key(&self) -> (String, usize, String) {
+        (self.file.clone(), self.document, self.path.clone())
+    }
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}, document {}, at `{}`: expected {}, found {}",
+            self.file, self.document, self.path, self.expected, self.found
+        )
+    }
+}
+
+fn numbers_match(expected: f64, found: f64) -> bool {
+    if expected.is_nan() || found.is_nan() {
+        expected.is_nan() && found.is_nan()
+    } else if expected.is_infinite() || found.is_infinite() {
+        expected == found
+    } else {
+        (expected - found).abs() <= FLOAT_TOLERANCE * expected.abs().max(found.abs()).max(1.0)
+    }
+}
+
+/// Compare two normalized trees, recording every difference.
+fn compare(
+    expected: &Value,
+    found: &Value,
+    path: &str,
+    report: &mut dyn FnMut(String, String, String),
+) {
+    match (expected, found) {
+        (Value::Number(expected), Value::Number(found)) if numbers_match(*expected, *found) => {}
+        (Value::Array(expected), Value::Array(found)) if expected.len() == found.len() => {
+            for (i, (expected, found)) in expected.iter().zip(found).enumerate() {
+                compare(expected, found, &format!("{path}/{i}"), report);
+            }
+        }
+        (Value::Object(expected), Value::Object(found)) => {
+            let keys: BTreeSet<&String> = expected.keys().chain(found.keys()).collect();
+            for key in keys {
+                let child = format!("{path}/{}", key.replace('~', "~0").replace('/', "~1"));
+                match (expected.get(key), found.get(key)) {
+                    (Some(expected), Some(found)) => compare(expected, found, &child, report),
+                    (Some(expected), None) => {
+                        report(child, expected.to_string(), "nothing".to_owned());
+                    }
+                    (None, Some(found)) => report(child, "nothing".to_owned(), found.to_string()),
+                    (None, None) => unreachable!(),
+                }
+            }
+        }
+        _ if expected == found => {}
+        _ => report(path.to_owned(), expected.to_string(), found.to_string()),
+    }
+}
+
+/// Run saphyr over a corpus file and return its divergences with the reference.
+fn diverge(yaml_path: &Path) -> Vec<Divergence> {
+    let file = yaml_path
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .into_owned();
+    let mut divergences = vec![];
+    let mut push = |document: usize, path: String, expected: String, found: String| {
+        divergences.push(Divergence {
+            file: file.clone(),
+            document,
+            path,
+            expected,
+            found,
+        });
+    };
+
+    let expected = fs::read_to_string(yaml_path.with_extension("json")).unwrap();
+    let Value::Array(expected) = JsonReader::parse(&expected).unwrap() else {
+        panic!("{file}: expected JSON must be an array of documents");
+    };
+    let found = match Yaml::load_from_str(&fs::read_to_string(yaml_path).unwrap()) {
+        Ok(documents) => documents.iter().map(normalize).collect::<Vec<_>>(),
+        Err(error) => {
+            push(0, String::new(), "a stream".to_owned(), format!("{error}"));
+            return divergences;
+        }
+    };
+
+    if expected.len() != found.len() {
+        push(
+            0,
+            String::new(),
+            format!("{} documents", expected.len()),
+            format!("{} documents", found.len()),
+        );
+    }
+    for (document, (expected, found)) in expected.iter().zip(&found).enumerate() {
+        compare(expected, found, "", &mut |path, expected, found| {
+            push(document, path, expected, found);
+        });
+    }
+    divergences
+}
+
+/// Read the list of known divergences.
+fn known_divergences(corpus: &Path) -> BTreeSet<(String, usize, String)> {
+    fs::read_to_string(corpus.join("known-divergences.txt"))
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let mut fields = line.split_whitespace();
+            let mut field = || fields.next().unwrap_or_default().to_owned();
+            (field(), field().parse().unwrap(), field())
+        })
+        .collect()
+}
+
+#[test]
+fn test_differential_corpus() {
+    let corpus = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/data/differential");
+    let mut files = fs::read_dir(&corpus)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "yaml"))
+        .collect::<Vec<_>>();
+    files.sort();
+    assert!(!files.is_empty(), "empty corpus");
+
+    let mut known = known_divergences(&corpus);
+    let mut unexpected = vec![];
+    for file in &files {
+        for divergence in diverge(file) {
+            if !known.remove(&divergence.key()) {
+                unexpected.push(divergence);
+            }
+        }
+    }
+
+    let mut report = String::new();
+    if !unexpected.is_empty() {
+        report += &format!("{} unexpected divergence(s):\n", unexpected.len());
+        for divergence in &unexpected {
+            report += &format!("  {divergence}\n");
+        }
+    }
+    if !known.is_empty() {
+        report += &format!(
+            "{} known divergence(s) no longer happen, remove them from known-divergences.txt:\n",
+            known.len()
+        );
+        for (file, document, path) in &known {
+            report += &format!("  {file} {document} {path}\n");
+        }
+    }
+    assert!(report.is_empty(), "{report}");
+}
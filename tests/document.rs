@@ -0,0 +1,107 @@
+use saphyr::{yaml, EditError, EditErrorKind, PathSegment, Yaml, YamlDocument, YamlKind, YamlPath};
+
+/// Build a path from keys and indices, e.g. `path(&["ports", "0"])`.
+fn path(segments: &[&str]) -> YamlPath {
+    segments
+        .iter()
+        .map(|segment| match segment.parse() {
+            Ok(index) => PathSegment::Index(index),
+            Err(_) => PathSegment::Key((*segment).into()),
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
+
+#[test]
+fn test_set() {
+    let source = "a: 1  # one\nb: \"two\"\nc: [x, 'y']\nd:\n  e: plain\nf: {g: 1}\n";
+    let mut doc = YamlDocument::new(source).unwrap();
+
+    assert_eq!(doc.set(&path(&["a"]), yaml!(2)), Ok(yaml!(1)));
+    assert_eq!(doc.set(&path(&["b"]), yaml!("dos")), Ok(yaml!("two")));
+    doc.set(&path(&["c", "1"]), yaml!("it's")).unwrap();
+    doc.set(&path(&["c", "0"]), yaml!({"k": 1})).unwrap();
+    doc.set(&path(&["d", "e"]), yaml!([1, 2])).unwrap();
+    doc.set(&path(&["f"]), yaml!(["h"])).unwrap();
+    assert_eq!(
+        doc.source(),
+        "a: 2  # one\nb: \"dos\"\nc: [{k: 1}, 'it''s']\nd:\n  e:\n    - 1\n    - 2\nf: [h]\n"
+    );
+    assert_eq!(
+        doc.get(&path(&["d", "e", "1"])).unwrap().data.as_i64(),
+        Some(2)
+    );
+    assert_eq!(doc.to_string(), doc.source());
+}
+
+#[test]
+fn test_insert_push_remove() {
+    let source = "name: web\nlabels: {app: web}\nports:\n  - 80  # http\n  - 443\nempty: []\n";
+    let mut doc = YamlDocument::new(source).unwrap();
+
+    assert_eq!(
+        doc.insert(&path(&["labels"]), "tier", yaml!("front")),
+        Ok(None)
+    );
+    assert_eq!(doc.insert(&YamlPath::new(), "replicas", yaml!(3)), Ok(None));
+    doc.push(&path(&["empty"]), yaml!("x")).unwrap();
+    assert_eq!(doc.remove(&path(&["ports", "0"])), Ok(yaml!(80)));
+    assert_eq!(doc.remove(&path(&["name"])), Ok(yaml!("web")));
+    assert_eq!(
+        doc.insert(&YamlPath::new(), "replicas", yaml!(5)),
+        Ok(Some(yaml!(3)))
+    );
+    assert_eq!(
+        doc.source(),
+        "labels: {app: web, tier: front}\nports:\n  - 443\nempty: [x]\nreplicas: 5\n"
+    );
+
+    // Removing the only entry of a collection leaves it empty.
+    let mut doc = YamlDocument::new("a:\n  b: 1\nc: [1]\n").unwrap();
+    doc.remove(&path(&["a", "b"])).unwrap();
+    doc.remove(&path(&["c", "0"])).unwrap();
+    assert_eq!(doc.source(), "a: {}\nc: []\n");
+}
+
+#[test]
+fn test_edit_errors() {
+    let source = "a: &x [1]\nb: *x\nc: [1]\n";
+    let mut doc = YamlDocument::new(source).unwrap();
+    let error = |path: YamlPath, kind| EditError { path, kind };
+
+    assert_eq!(
+        doc.set(&path(&["d"]), yaml!(1)).unwrap_err(),
+        error(path(&["d"]), EditErrorKind::NotFound)
+    );
+    assert_eq!(
+        doc.push(&YamlPath::new(), yaml!(1)).unwrap_err(),
+        error(
+            YamlPath::new(),
+            EditErrorKind::WrongType {
+                expected: YamlKind::Array,
+                actual: YamlKind::Hash
+            }
+        )
+    );
+    assert_eq!(
+        doc.set(&path(&["c", "0"]), Yaml::BadValue).unwrap_err(),
+        error(path(&["c", "0"]), EditErrorKind::InvalidValue)
+    );
+    assert_eq!(
+        doc.remove(&YamlPath::new()).unwrap_err(),
+        error(YamlPath::new(), EditErrorKind::Unsupported)
+    );
+
+    // The children of aliased collections are those of the anchored one.
+    let result = doc.set(&path(&["b", "0"]), yaml!(2)).unwrap_err();
+    assert_eq!(result, error(path(&["b", "0"]), EditErrorKind::Unsupported));
+    assert_eq!(
+        result.to_string(),
+        "the edit would change other nodes at `/b/0`"
+    );
+    assert_eq!(doc.source(), source);
+
+    // The alias itself can be replaced.
+    doc.set(&path(&["b"]), yaml!([2])).unwrap();
+    assert_eq!(doc.source(), "a: &x [1]\nb:\n  - 2\nc: [1]\n");
+}
@@ -1,4 +1,10 @@
-use saphyr::{Yaml, YamlEmitter};
+use std::rc::Rc;
+
+use saphyr::{
+    yaml, AlignPolicy, AnchorNaming, BlockStyle, CancelToken, CollectionStyle, EmitError, Hash,
+    HashExt, KeyOrder, LoaderOptions, NullStyle, PathSegment, QuoteStyle, Schema, Tag, Yaml,
+    YamlEmitter,
+};
 
 #[allow(clippy::similar_names)]
 #[test]
@@ -292,3 +298,1197 @@ a:
 
     assert_eq!(s, writer);
 }
+
+#[test]
+fn test_align_values() {
+    let s = r"---
+name: saphyr
+version: 0.0.1
+authors:
+  - Yuheng Chen
+  - Ethiraric
+dependencies:
+  arraydeque: 0.5.1
+  hashlink: 0.8
+  saphyr-parser: 0.0.2
+edition: 2021
+metadata: {}
+description: |-
+  A fully YAML 1.2
+  compliant library";
+    let expected = r"---
+name:     saphyr
+version:  0.0.1
+authors:
+  - Yuheng Chen
+  - Ethiraric
+dependencies:
+  arraydeque:    0.5.1
+  hashlink:      0.8
+  saphyr-parser: 0.0.2
+edition:  2021
+metadata: {}
+description: |-
+  A fully YAML 1.2
+  compliant library";
+
+    let docs = Yaml::load_from_str(s).unwrap();
+    let doc = &docs[0];
+    let mut writer = String::new();
+    {
+        let mut emitter = YamlEmitter::new(&mut writer);
+        emitter.multiline_strings(true);
+        emitter.align_values(AlignPolicy::PerMapping { max_pad: 16 });
+        emitter.dump(doc).unwrap();
+    }
+
+    assert_eq!(expected, writer, "actual:\n\n{writer}\n");
+    assert_eq!(Yaml::load_from_str(&writer).unwrap()[0], *doc);
+}
+
+#[test]
+fn test_align_values_max_pad() {
+    let s = r"---
+a: 1
+bb: 2
+a_very_long_key_name: 3";
+    let expected = r"---
+a:  1
+bb: 2
+a_very_long_key_name: 3";
+
+    let docs = Yaml::load_from_str(s).unwrap();
+    let doc = &docs[0];
+    let mut writer = String::new();
+    {
+        let mut emitter = YamlEmitter::new(&mut writer);
+        emitter.align_values(AlignPolicy::PerMapping { max_pad: 4 });
+        emitter.dump(doc).unwrap();
+    }
+
+    assert_eq!(expected, writer, "actual:\n\n{writer}\n");
+}
+
+#[test]
+fn test_blank_lines_between_top_level() {
+    let s = r"---
+a:
+  b: 1
+  c: 2
+d:
+  - e
+  - f: g
+    h: i
+j: k";
+    let expected = r"---
+a:
+  b: 1
+  c: 2
+
+
+d:
+  - e
+  - f: g
+    h: i
+
+
+j: k";
+
+    let docs = Yaml::load_from_str(s).unwrap();
+    let doc = &docs[0];
+    let mut writer = String::new();
+    {
+        let mut emitter = YamlEmitter::new(&mut writer);
+        emitter.blank_lines_between_top_level(2);
+        emitter.dump(doc).unwrap();
+    }
+
+    assert_eq!(expected, writer, "actual:\n\n{writer}\n");
+    assert_eq!(Yaml::load_from_str(&writer).unwrap()[0], *doc);
+}
+
+#[test]
+fn test_emit_aliases() {
+    let s = r"
+base: &base
+  image: nginx
+  ports: [80, 443]
+web: *base
+list:
+  - [1, 2]
+  - [1, 2]
+other:
+  image: nginx
+  ports: [80, 443]
+";
+    let expected = r"---
+base: &base
+  image: nginx
+  ports:
+    - 80
+    - 443
+web: *base
+list:
+  - &list_0
+    - 1
+    - 2
+  - *list_0
+other: *base";
+
+    let docs = Yaml::load_from_str(s).unwrap();
+    let mut writer = String::new();
+    {
+        let mut emitter = YamlEmitter::new(&mut writer);
+        emitter.emit_aliases(true);
+        emitter.dump(&docs[0]).unwrap();
+    }
+    assert_eq!(writer, expected);
+    assert_eq!(Yaml::load_from_str(&writer).unwrap(), docs);
+}
+
+/// Emit `s` with aliases and return the names of the anchors, in order.
+fn anchor_names(s: &str, naming: AnchorNaming) -> Vec<String> {
+    let docs = Yaml::load_from_str(s).unwrap();
+    let mut writer = String::new();
+    let mut emitter = YamlEmitter::new(&mut writer);
+    emitter.emit_aliases(true);
+    emitter.anchor_naming(naming);
+    emitter.dump(&docs[0]).unwrap();
+    writer
+        .split_whitespace()
+        .filter_map(|word| word.strip_prefix('&'))
+        .map(str::to_owned)
+        .collect()
+}
+
+#[test]
+fn test_anchor_names_are_stable() {
+    let s = "a:\n  x: 1\nb:\n  x: 1\nmy key:\n  - y\nd:\n  - y";
+    let edited = "new: 0\na:\n  x: 1\nb:\n  x: 1\nother: [z]\nmy key:\n  - y\nd:\n  - y\nlast: 1";
+
+    let names = anchor_names(s, AnchorNaming::ByFirstPath);
+    assert_eq!(names, ["a", "my_key"]);
+    assert_eq!(anchor_names(edited, AnchorNaming::ByFirstPath), names);
+
+    let by_hash = || AnchorNaming::ByContentHash {
+        prefix: "h".to_owned(),
+    };
+    let names = anchor_names(s, by_hash());
+    assert_eq!(names.len(), 2);
+    assert!(names.iter().all(|name| name.starts_with('h')));
+    assert_ne!(names[0], names[1]);
+    assert_eq!(anchor_names(edited, by_hash()), names);
+}
+
+#[test]
+fn test_anchor_naming_custom() {
+    let s = "a: [1]\nb: [1]\nc: [2]\nd: [2]\ne: [3]\nf: [3]";
+    let naming = AnchorNaming::Custom(Rc::new(|node, _| {
+        if node[0].as_i64() == Some(3) {
+            "other name".to_owned()
+        } else {
+            "shared".to_owned()
+        }
+    }));
+    assert_eq!(
+        anchor_names(s, naming),
+        ["shared", "shared_2", "other_name"]
+    );
+}
+
+fn hash(entries: Vec<(Yaml, Yaml)>) -> Yaml {
+    Yaml::Hash(entries.into_iter().collect::<Hash>())
+}
+
+fn sorted_dump(doc: &Yaml, emit_aliases: bool) -> String {
+    let mut writer = String::new();
+    let mut emitter = YamlEmitter::new(&mut writer);
+    emitter.key_order(KeyOrder::Sorted);
+    emitter.emit_aliases(emit_aliases);
+    emitter.dump(doc).unwrap();
+    writer
+}
+
+#[test]
+fn test_sorted_keys() {
+    let s = |v: &str| Yaml::String(v.to_owned());
+    let r = |v: &str| Yaml::Real(v.to_owned());
+    let nested = |first| {
+        let (a, b) = ((s("b"), Yaml::Integer(1)), (s("a"), Yaml::Integer(2)));
+        hash(if first { vec![a, b] } else { vec![b, a] })
+    };
+    let entries = vec![
+        (s("b"), nested(true)),
+        (s("B"), Yaml::Null),
+        (Yaml::Integer(10), s("ten")),
+        (Yaml::Integer(-1), s("minus one")),
+        (r("1e1"), s("1e1")),
+        (r("2.5"), s("2.5")),
+        (Yaml::Boolean(true), s("true")),
+        (Yaml::Null, s("null")),
+        (Yaml::Array(vec![Yaml::Integer(2)]), s("[2]")),
+        (Yaml::Array(vec![Yaml::Integer(1)]), s("[1]")),
+        (nested(true), s("{a: 2, b: 1}")),
+    ];
+    let mut reversed = entries.clone();
+    reversed.reverse();
+    for (k, v) in &mut reversed {
+        if let Yaml::Hash(_) = k {
+            *k = nested(false);
+        }
+        if let Yaml::Hash(_) = v {
+            *v = nested(false);
+        }
+    }
+
+    let expected = r#"---
+~: "null"
+true: "true"
+-1: minus one
+10: ten
+2.5: "2.5"
+1e1: "1e1"
+B: ~
+b:
+  a: 2
+  b: 1
+? - 1
+: "[1]"
+? - 2
+: "[2]"
+? a: 2
+  b: 1
+: "{a: 2, b: 1}""#;
+    let first = hash(entries);
+    let second = hash(reversed);
+    assert_ne!(first, second);
+    assert_eq!(sorted_dump(&first, false), expected);
+    assert_eq!(sorted_dump(&second, false), expected);
+    // The document itself is not reordered.
+    assert_eq!(first.as_hash().unwrap().front().unwrap().0, &s("b"));
+}
+
+#[test]
+fn test_sorted_keys_with_aliases() {
+    let list = || Yaml::Array(vec![Yaml::Integer(1), Yaml::Integer(2)]);
+    let key = |v: &str| Yaml::String(v.to_owned());
+    let doc = hash(vec![(key("z"), list()), (key("a"), list())]);
+    // The anchor is put on the first occurrence in emission order.
+    assert_eq!(sorted_dump(&doc, true), "---\na: &a\n  - 1\n  - 2\nz: *a");
+}
+
+#[test]
+fn test_custom_key_order() {
+    let doc = yaml!({"b": 1, "a": {"y": 2, "x": 3}, "c": 4, "id": 5});
+    let mut output = String::new();
+    let mut emitter = YamlEmitter::new(&mut output);
+    // `id` first, then in reverse order.
+    emitter.key_order(KeyOrder::Custom(|a, b| {
+        let is_id = |key: &Yaml| key.as_str() == Some("id");
+        is_id(b)
+            .cmp(&is_id(a))
+            .then_with(|| b.as_str().cmp(&a.as_str()))
+    }));
+    emitter.dump(&doc).unwrap();
+    assert_eq!(emitter.describe()["key_order"].as_str(), Some("custom"));
+    assert_eq!(output, "---\nid: 5\nc: 4\nb: 1\na:\n  y: 2\n  x: 3");
+}
+
+/// A writer which cancels `token` once `limit` bytes have been written to it.
+struct CancellingWriter {
+    output: String,
+    limit: usize,
+    token: CancelToken,
+}
+
+impl std::fmt::Write for CancellingWriter {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.output.push_str(s);
+        if self.output.len() >= self.limit {
+            self.token.cancel();
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_cancel_emission() {
+    let doc = Yaml::Array(
+        (0..10_000)
+            .map(|i| {
+                let entry = vec![(Yaml::String("index".into()), Yaml::Integer(i))];
+                Yaml::Hash(entry.into_iter().collect::<Hash>())
+            })
+            .collect(),
+    );
+    let mut full = String::new();
+    YamlEmitter::new(&mut full).dump(&doc).unwrap();
+
+    let token = CancelToken::new();
+    let mut writer = CancellingWriter {
+        output: String::new(),
+        limit: 1000,
+        token: token.clone(),
+    };
+    let mut emitter = YamlEmitter::new(&mut writer);
+    emitter.cancel_token(token.clone());
+    assert!(matches!(emitter.dump(&doc), Err(EmitError::Cancelled)));
+    // Emission stops at the next entry, and the output is a prefix of the complete one.
+    assert!(writer.output.len() < 1100);
+    assert!(full.starts_with(&writer.output));
+
+    // A cancelled token cancels any further emission.
+    let mut output = String::new();
+    let mut emitter = YamlEmitter::new(&mut output);
+    emitter.cancel_token(token);
+    assert!(matches!(emitter.dump(&doc), Err(EmitError::Cancelled)));
+    assert_eq!(output, "---\n");
+}
+
+#[test]
+fn test_line_width() {
+    let prose = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor";
+    let doc = saphyr::yaml!({
+        "prose": prose,
+        "spaced": "two  spaces  between  each  of  these  words  must  not  be  lost",
+        "edges": " leading and trailing spaces on a string which is long enough  ",
+        "lines": "a first line which is rather long\n  and an indented second line\n",
+        "url": format!("https://example.com/{}", "a".repeat(60)),
+        "quoted": "true, but as a string: this needs quotes # and not a comment",
+        "nested": [[prose], { "key": prose }],
+        "short": "fits",
+    });
+
+    let mut output = String::new();
+    let mut emitter = YamlEmitter::new(&mut output);
+    emitter.line_width(Some(40));
+    emitter.dump(&doc).unwrap();
+    println!("emitted:\n{output}");
+
+    assert!(output.starts_with(
+        "---
+prose: >-
+  Lorem ipsum dolor sit amet,
+  consectetur adipiscing elit, sed do
+  eiusmod tempor
+spaced:
+  \"two  spaces  between  each  of  \\
+  these  words  must  not  be  lost\"
+"
+    ));
+    assert!(output.ends_with("\nshort: fits"));
+    for line in output.lines() {
+        assert!(line.chars().count() <= 40, "line too long: {line:?}");
+    }
+    assert_eq!(Yaml::load_one_from_str(&output).unwrap(), doc);
+
+    // A small width cannot break words, but still round-trips.
+    let mut output = String::new();
+    let mut emitter = YamlEmitter::new(&mut output);
+    emitter.line_width(Some(1));
+    emitter.dump(&doc).unwrap();
+    assert_eq!(Yaml::load_one_from_str(&output).unwrap(), doc);
+
+    // `set_best_width` is the same as `line_width`.
+    let mut best = String::new();
+    let mut emitter = YamlEmitter::new(&mut best);
+    emitter.set_best_width(1);
+    assert_eq!(emitter.get_line_width(), Some(1));
+    emitter.dump(&doc).unwrap();
+    assert_eq!(best, output);
+}
+
+#[test]
+fn test_yaml11_schema() {
+    let original = yaml!(["y", "No", "off", "~", "", "0755", "0b11", "1_000", "1:30", "text"]);
+    let mut output = String::new();
+    let mut emitter = YamlEmitter::new(&mut output);
+    emitter.schema(Schema::Yaml11);
+    assert_eq!(emitter.get_schema(), Schema::Yaml11);
+    emitter.dump(&original).unwrap();
+    assert_eq!(
+        output,
+        r#"---
+- "y"
+- "No"
+- "off"
+- "~"
+- ""
+- "0755"
+- "0b11"
+- "1_000"
+- "1:30"
+- text"#
+    );
+    let options = LoaderOptions {
+        schema: Schema::Yaml11,
+        ..LoaderOptions::default()
+    };
+    assert_eq!(
+        Yaml::load_from_str_with_options(&output, options).unwrap(),
+        [original]
+    );
+}
+
+#[test]
+fn test_json_schema() {
+    let original = yaml!({"name": "text", "count": 3, "ratio": 0.5, "none": null, "ok": true});
+    let mut output = String::new();
+    let mut emitter = YamlEmitter::new(&mut output);
+    emitter.schema(Schema::Json);
+    emitter.dump(&original).unwrap();
+    assert_eq!(
+        output,
+        r#"---
+"name": "text"
+"count": 3
+"ratio": 0.5
+"none": null
+"ok": true"#
+    );
+    let options = LoaderOptions {
+        schema: Schema::Json,
+        ..LoaderOptions::default()
+    };
+    assert_eq!(
+        Yaml::load_from_str_with_options(&output, options).unwrap(),
+        [original]
+    );
+}
+
+#[test]
+fn test_canonical() {
+    let canonical = |doc: &Yaml, aliases: bool| {
+        let mut output = String::new();
+        let mut emitter = YamlEmitter::new(&mut output);
+        emitter.canonical(true);
+        emitter.emit_aliases(aliases);
+        emitter.dump(doc).unwrap();
+        output
+    };
+
+    assert_eq!(canonical(&yaml!("plain"), false), "---\n!!str \"plain\"");
+    assert_eq!(
+        canonical(&yaml!([[], {}, 1.5, true, "a \"b\"\n"]), false),
+        r#"---
+!!seq [
+  !!seq [],
+  !!map {},
+  !!float "1.5",
+  !!bool "true",
+  !!str "a \"b\"\n",
+]"#
+    );
+
+    let mut hash = Hash::new();
+    hash.insert(yaml!(["a", 1]), yaml!({"b": [null]}));
+    assert_eq!(
+        canonical(&Yaml::Hash(hash), false),
+        r#"---
+!!map {
+  ? !!seq [
+    !!str "a",
+    !!int "1",
+  ]
+  : !!map {
+    ? !!str "b"
+    : !!seq [
+      !!null "null",
+    ],
+  },
+}"#
+    );
+
+    // Anchors come before the tag.
+    assert_eq!(
+        canonical(&yaml!({"a": [1], "b": [1]}), true),
+        r#"---
+!!map {
+  ? !!str "a"
+  : &a !!seq [
+    !!int "1",
+  ],
+  ? !!str "b"
+  : *a,
+}"#
+    );
+}
+
+#[test]
+fn test_emit_tags() {
+    let tagged = |handle: &str, suffix: &str, node: Yaml| {
+        let tag = Tag {
+            handle: handle.into(),
+            suffix: suffix.into(),
+        };
+        Yaml::Tagged(tag, Box::new(node))
+    };
+    let doc = yaml!({
+        "ref": (tagged("!", "Ref", yaml!("Bucket"))),
+        "set": (tagged("tag:yaml.org,2002:", "set", yaml!({"a": null}))),
+        "list": (tagged("tag:example.com,2020:", "list", yaml!([1, [2]]))),
+        "empty": (tagged("!", "Empty", yaml!([]))),
+        "items": [(tagged("!", "Item", yaml!({"id": 1}))), (tagged("!", "Id", yaml!("true")))]
+    });
+    let dump = |doc: &Yaml, canonical: bool| {
+        let mut output = String::new();
+        let mut emitter = YamlEmitter::new(&mut output);
+        emitter.compact(true);
+        emitter.canonical(canonical);
+        emitter.dump(doc).unwrap();
+        output
+    };
+    let output = dump(&doc, false);
+    assert_eq!(
+        output,
+        "---
+ref: !Ref Bucket
+set: !!set
+  a: ~
+list: !<tag:example.com,2020:list>
+  - 1
+  - - 2
+empty: !Empty []
+items:
+  - !Item
+    id: 1
+  - !Id \"true\""
+    );
+    let options = LoaderOptions {
+        keep_tags: true,
+        ..LoaderOptions::default()
+    };
+    assert_eq!(
+        Yaml::load_from_str_with_options(&output, options).unwrap(),
+        [doc]
+    );
+
+    let root = tagged("!", "Config", yaml!({"a": [1]}));
+    assert_eq!(dump(&root, false), "---\n!Config\na:\n  - 1");
+    assert_eq!(
+        dump(&root, true),
+        r#"---
+!Config {
+  ? !!str "a"
+  : !!seq [
+    !!int "1",
+  ],
+}"#
+    );
+}
+
+#[test]
+fn test_tag_handles() {
+    let tagged = |suffix: &str, node: Yaml| {
+        let tag = Tag {
+            handle: "tag:example.com,2020:".into(),
+            suffix: suffix.into(),
+        };
+        Yaml::Tagged(tag, Box::new(node))
+    };
+    let docs = [
+        yaml!({"point": (tagged("point", yaml!([1, 2])))}),
+        tagged("app/v1", yaml!("web")),
+        // Shorthands cannot contain `!`.
+        tagged("a!b", yaml!(1)),
+    ];
+    let mut output = String::new();
+    let mut emitter = YamlEmitter::new(&mut output);
+    emitter.tag_handle("!e!", "tag:example.com,2020:");
+    emitter.tag_handle("!c!", "tag:example.com,");
+    assert_eq!(
+        emitter.describe()["tag_handles"]["!e!"].as_str(),
+        Some("tag:example.com,2020:")
+    );
+    emitter.dump_all(&docs).unwrap();
+    let directives = "%TAG !c! tag:example.com,\n%TAG !e! tag:example.com,2020:\n---\n";
+    assert_eq!(
+        output,
+        format!(
+            "{directives}point: !e!point\n  - 1\n  - 2\n...\n\
+             {directives}!e!app/v1 web\n...\n\
+             {directives}!<tag:example.com,2020:a!b> 1"
+        )
+    );
+
+    let options = LoaderOptions {
+        keep_tags: true,
+        ..LoaderOptions::default()
+    };
+    assert_eq!(
+        Yaml::load_from_str_with_options(&output, options).unwrap(),
+        docs
+    );
+}
+
+#[test]
+#[should_panic(expected = "`!!` is not a named tag handle")]
+fn test_tag_handle_secondary() {
+    let mut output = String::new();
+    YamlEmitter::new(&mut output).tag_handle("!!", "tag:example.com,2020:");
+}
+
+/// An `io::Write` recording the size of the largest write, which fails past `limit` bytes.
+#[cfg(feature = "std")]
+struct RecordingWriter {
+    output: Vec<u8>,
+    largest_write: usize,
+    limit: usize,
+}
+
+#[cfg(feature = "std")]
+impl std::io::Write for RecordingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.output.len() + buf.len() > self.limit {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::WriteZero,
+                "disk full",
+            ));
+        }
+        self.output.extend_from_slice(buf);
+        self.largest_write = self.largest_write.max(buf.len());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_io_writer() {
+    let doc = Yaml::Array(
+        (0..10_000)
+            .map(|i| yaml!({"index": i, "name": (format!("item {i}"))}))
+            .collect(),
+    );
+    let mut full = String::new();
+    YamlEmitter::new(&mut full).dump(&doc).unwrap();
+
+    let mut writer = RecordingWriter {
+        output: vec![],
+        largest_write: 0,
+        limit: usize::MAX,
+    };
+    YamlEmitter::new_io(&mut writer).dump(&doc).unwrap();
+    assert_eq!(String::from_utf8(writer.output).unwrap(), full);
+    // The output is streamed in chunks of the size of the buffer.
+    assert!(writer.largest_write <= 8 * 1024);
+
+    let mut writer = RecordingWriter {
+        output: vec![],
+        largest_write: 0,
+        limit: 100_000,
+    };
+    let Err(EmitError::Io(err)) = YamlEmitter::new_io(&mut writer).dump(&doc) else {
+        panic!("the error of the writer was not returned");
+    };
+    assert_eq!(err.to_string(), "disk full");
+    assert!(full.starts_with(std::str::from_utf8(&writer.output).unwrap()));
+}
+
+#[test]
+fn test_document_markers() {
+    let docs = [yaml!({"a": 1}), yaml!("---"), yaml!(["x"]), yaml!(null)];
+    let dump_all = |explicit_start: bool, explicit_end: bool| {
+        let mut output = String::new();
+        let mut emitter = YamlEmitter::new(&mut output);
+        emitter.explicit_start(explicit_start);
+        emitter.explicit_end(explicit_end);
+        emitter.dump_all(&docs).unwrap();
+        emitter.dump_all(&[]).unwrap();
+        output
+    };
+
+    assert_eq!(
+        dump_all(true, false),
+        "---\na: 1\n---\n\"---\"\n---\n- x\n---\n~"
+    );
+    assert_eq!(
+        dump_all(true, true),
+        "---\na: 1\n...\n---\n\"---\"\n...\n---\n- x\n...\n---\n~\n..."
+    );
+    assert_eq!(
+        dump_all(false, false),
+        "a: 1\n---\n\"---\"\n---\n- x\n---\n~"
+    );
+    assert_eq!(
+        dump_all(false, true),
+        "a: 1\n...\n\"---\"\n...\n- x\n...\n~\n..."
+    );
+    for (explicit_start, explicit_end) in
+        [(true, false), (true, true), (false, false), (false, true)]
+    {
+        assert_eq!(
+            Yaml::load_from_str(&dump_all(explicit_start, explicit_end)).unwrap(),
+            docs
+        );
+    }
+
+    // Lines of a top-level literal block are not indented, so they must not look like markers.
+    let mut output = String::new();
+    let mut emitter = YamlEmitter::new(&mut output);
+    emitter.multiline_strings(true);
+    emitter.dump(&yaml!("a\nb")).unwrap();
+    emitter.dump(&yaml!("a\n...\nb")).unwrap();
+    assert_eq!(output, "---\n|-\na\nb\n---\n\"a\\n...\\nb\"");
+}
+
+#[test]
+fn test_reorder_keys() {
+    let dump = |doc: &Yaml| {
+        let mut output = String::new();
+        YamlEmitter::new(&mut output).dump(doc).unwrap();
+        output
+    };
+    let mut doc = yaml!({"spec": {"b": 1, "a": [{"y": 1, "x": 2}]}, "kind": "Pod", "apiVersion": "v1", "status": null});
+    let manifest = doc.as_mut_hash().unwrap();
+    assert!(manifest.move_key_to_front("apiVersion"));
+    assert!(manifest.move_key_to_index("kind", 1));
+    assert!(!manifest.move_key_to_index("missing", 0));
+    assert_eq!(
+        manifest.replace_value("kind", yaml!("Deployment")),
+        Some(yaml!("Pod"))
+    );
+    manifest.retain_keys(|key| key.as_str() != Some("status"));
+    assert_eq!(
+        dump(&doc),
+        "---\napiVersion: v1\nkind: Deployment\nspec:\n  b: 1\n  a:\n    - y: 1\n      x: 2"
+    );
+
+    // Past the end is the end.
+    let manifest = doc.as_mut_hash().unwrap();
+    assert!(manifest.move_key_to_index("apiVersion", 10));
+    assert_eq!(manifest.replace_value("replicas", yaml!(3)), None);
+    manifest.sort_keys_by(|a, b| b.as_str().cmp(&a.as_str()));
+    assert_eq!(
+        dump(&doc),
+        "---\nspec:\n  b: 1\n  a:\n    - y: 1\n      x: 2\nreplicas: 3\nkind: Deployment\napiVersion: v1"
+    );
+
+    doc.sort_all_keys();
+    assert_eq!(
+        dump(&doc),
+        "---\napiVersion: v1\nkind: Deployment\nreplicas: 3\nspec:\n  a:\n    - x: 2\n      y: 1\n  b: 1"
+    );
+}
+
+#[test]
+fn test_block_style() {
+    let doc = yaml!({
+        "script": "set -e\ncargo build\n",
+        "gap": "a\n\nb",
+        "spaced": "a\n  b",
+        "long": "one two three four five six\nseven",
+        "indented": " a\nb",
+        "trailing": "a\n\n"
+    });
+    let dump = |style, width| {
+        let mut output = String::new();
+        let mut emitter = YamlEmitter::new(&mut output);
+        emitter.multiline_strings(true);
+        emitter.block_style(style);
+        emitter.line_width(width);
+        emitter.dump(&doc).unwrap();
+        assert_eq!(
+            emitter.describe()["block_style"].as_str(),
+            Some(match style {
+                BlockStyle::Literal => "literal",
+                BlockStyle::Folded => "folded",
+            })
+        );
+        output
+    };
+
+    let literal = dump(BlockStyle::Literal, Some(16));
+    assert_eq!(
+        literal,
+        "---
+script: |
+  set -e
+  cargo build
+gap: |-
+  a\n  \n  b
+spaced: |-
+  a
+    b
+long: |-
+  one two three four five six
+  seven
+indented: \" a\\nb\"
+trailing: \"a\\n\\n\""
+    );
+    assert_eq!(Yaml::load_from_str(&literal).unwrap()[0], doc);
+
+    // Lines starting with a blank are not folded, so their string stays literal.
+    let folded = dump(BlockStyle::Folded, Some(16));
+    assert_eq!(
+        folded,
+        "---
+script: >
+  set -e
+
+  cargo build
+gap: >-
+  a
+
+  \n  b
+spaced: |-
+  a
+    b
+long: >-
+  one two three
+  four five six
+
+  seven
+indented: \" a\\nb\"
+trailing: \"a\\n\\n\""
+    );
+    assert_eq!(Yaml::load_from_str(&folded).unwrap()[0], doc);
+}
+
+#[test]
+fn test_indent() {
+    let doc = yaml!({"a": [{"b": 1, "c": [2, 3]}, [4, 5]], "d": "e\nf"});
+    let dump = |indent| {
+        let mut output = String::new();
+        let mut emitter = YamlEmitter::new(&mut output);
+        emitter.indent(indent).unwrap();
+        emitter.multiline_strings(true);
+        emitter.dump(&doc).unwrap();
+        assert_eq!(emitter.get_indent(), indent);
+        assert_eq!(
+            emitter.describe()["indent"].as_i64(),
+            Some(indent.try_into().unwrap())
+        );
+        output
+    };
+
+    let output = dump(4);
+    assert_eq!(
+        output,
+        "---
+a:
+    -   b: 1
+        c:
+            - 2
+            - 3
+    -   - 4
+        - 5
+d: |-
+    e
+    f"
+    );
+    assert_eq!(Yaml::load_from_str(&output).unwrap()[0], doc);
+
+    // Nested collections cannot follow `-` on the same line.
+    let output = dump(1);
+    assert_eq!(
+        output,
+        "---
+a:
+ -
+  b: 1
+  c:
+   - 2
+   - 3
+ -
+  - 4
+  - 5
+d: |-
+ e
+ f"
+    );
+    assert_eq!(Yaml::load_from_str(&output).unwrap()[0], doc);
+}
+
+#[test]
+fn test_indent_out_of_range() {
+    let mut output = String::new();
+    let mut emitter = YamlEmitter::new(&mut output);
+    for indent in [0, 10] {
+        let error = emitter.indent(indent).unwrap_err();
+        assert!(matches!(error, EmitError::InvalidIndent(i) if i == indent));
+        assert_eq!(
+            error.to_string(),
+            format!("the indentation must be between 1 and 9, not {indent}")
+        );
+    }
+    assert_eq!(emitter.get_indent(), 2);
+}
+
+#[test]
+fn test_flow_style() {
+    let doc = yaml!({
+        "name": "web",
+        "labels": {"app": "web", "tier": "front, back"},
+        "ports": [{"port": 80, "names": ["http"]}, {"port": 443, "names": []}],
+        "script": "a\nb"
+    });
+    let dump = |depth, styles: &[(&[PathSegment], CollectionStyle)]| {
+        let mut output = String::new();
+        let mut emitter = YamlEmitter::new(&mut output);
+        emitter.flow_depth(depth);
+        emitter.multiline_strings(true);
+        for (path, style) in styles {
+            emitter.collection_style(path.to_vec().into(), *style);
+        }
+        emitter.dump(&doc).unwrap();
+        output
+    };
+
+    let output = dump(Some(0), &[]);
+    assert_eq!(
+        output,
+        "---\n{name: web, labels: {app: web, tier: \"front, back\"}, \
+         ports: [{port: 80, names: [http]}, {port: 443, names: []}], script: \"a\\nb\"}"
+    );
+    assert_eq!(Yaml::load_from_str(&output).unwrap()[0], doc);
+
+    let output = dump(Some(3), &[]);
+    assert_eq!(
+        output,
+        "---
+name: web
+labels:
+  app: web
+  tier: \"front, back\"
+ports:
+  - port: 80
+    names: [http]
+  - port: 443
+    names: []
+script: |-
+  a
+  b"
+    );
+    assert_eq!(Yaml::load_from_str(&output).unwrap()[0], doc);
+
+    // Styles of specific collections take precedence, but flow collections only contain flow
+    // collections.
+    let labels = [PathSegment::Key("labels".into())];
+    let ports = [PathSegment::Key("ports".into())];
+    let port = [PathSegment::Key("ports".into()), PathSegment::Index(0)];
+    let output = dump(
+        Some(1),
+        &[
+            (&labels, CollectionStyle::Block),
+            (&ports, CollectionStyle::Block),
+            (&port, CollectionStyle::Block),
+        ],
+    );
+    assert_eq!(
+        output,
+        "---
+name: web
+labels:
+  app: web
+  tier: \"front, back\"
+ports:
+  - port: 80
+    names: [http]
+  - {port: 443, names: []}
+script: |-
+  a
+  b"
+    );
+    assert_eq!(Yaml::load_from_str(&output).unwrap()[0], doc);
+    let output = dump(Some(0), &[(&labels, CollectionStyle::Block)]);
+    assert!(output.contains("labels: {app: web, "));
+}
+
+#[test]
+fn test_flow_line_width() {
+    let dump = |doc: &Yaml, depth, width| {
+        let mut output = String::new();
+        let mut emitter = YamlEmitter::new(&mut output);
+        emitter.flow_depth(Some(depth));
+        emitter.line_width(Some(width));
+        emitter.dump(doc).unwrap();
+        assert_eq!(Yaml::load_from_str(&output).unwrap()[0], *doc);
+        output
+    };
+
+    // Lines hold as many entries as fit, and continue one level deeper.
+    let doc = yaml!({
+        "ports": [80, 443, 8080, 8443, 9090],
+        "labels": {"app": "web", "tier": "frontend", "team": "platform"}
+    });
+    assert_eq!(
+        dump(&doc, 1, 24),
+        "---
+ports: [80, 443, 8080,
+  8443, 9090]
+labels: {app: web,
+  tier: frontend,
+  team: platform}"
+    );
+    assert_eq!(
+        dump(&doc, 1, 80),
+        "---\nports: [80, 443, 8080, 8443, 9090]\nlabels: {app: web, tier: frontend, team: platform}"
+    );
+
+    // Nested collections are split too, if they still do not fit.
+    assert_eq!(
+        dump(&yaml!([[1, 2, 3], [4, 5, 6]]), 0, 12),
+        "---\n[[1, 2, 3],\n  [4, 5, 6]]"
+    );
+    assert_eq!(
+        dump(&yaml!({"a": [[1, 2, 3, 4, 5, 6, 7, 8]]}), 2, 14),
+        "---
+a:
+  - [1, 2, 3,
+    4, 5, 6,
+    7, 8]"
+    );
+
+    // Strings are folded, or split with escaped line breaks if double-quoted, but implicit keys
+    // cannot span lines.
+    let doc = yaml!({
+        "words": ["one two three four five six seven", "it's a long: quoted string here", "x"],
+        "a long key with spaces": {"a long key with spaces": "v"}
+    });
+    assert_eq!(
+        dump(&doc, 1, 20),
+        "---
+words: [one two
+  three four five
+  six seven,
+  \"it's a long: \\
+  quoted string \\
+  here\", x]
+a long key with spaces: {a long key with spaces: v}"
+    );
+    let mut output = String::new();
+    let mut emitter = YamlEmitter::new(&mut output);
+    emitter.flow_depth(Some(1));
+    emitter.set_best_width(20);
+    emitter.quote_style(QuoteStyle::PreferSingle);
+    emitter.dump(&doc).unwrap();
+    assert_eq!(emitter.get_line_width(), Some(20));
+    assert_eq!(
+        output,
+        "---
+words: [one two
+  three four five
+  six seven,
+  'it''s a long:
+  quoted string
+  here', x]
+a long key with spaces: {a long key with spaces: v}"
+    );
+    assert_eq!(Yaml::load_from_str(&output).unwrap()[0], doc);
+}
+
+#[test]
+fn test_quote_style() {
+    let doc = yaml!({"plain": ["web", "true", "it's", "say \"hi\"", "C:\\dir", "tab\there"]});
+    let dump = |style| {
+        let mut output = String::new();
+        let mut emitter = YamlEmitter::new(&mut output);
+        emitter.quote_style(style);
+        emitter.dump(&doc).unwrap();
+        output
+    };
+
+    let expected = [
+        (
+            QuoteStyle::Minimal,
+            [
+                r"plain",
+                r"web",
+                r#""true""#,
+                r#""it's""#,
+                r#""say \"hi\"""#,
+                r#""C:\\dir""#,
+            ],
+        ),
+        (
+            QuoteStyle::PreferSingle,
+            [
+                r"plain",
+                r"web",
+                r"'true'",
+                r"'it''s'",
+                r#"'say "hi"'"#,
+                r"'C:\dir'",
+            ],
+        ),
+        (
+            QuoteStyle::PreferDouble,
+            [
+                r"plain",
+                r"web",
+                r#""true""#,
+                r#""it's""#,
+                r#"'say "hi"'"#,
+                r"'C:\dir'",
+            ],
+        ),
+        (
+            QuoteStyle::AlwaysDouble,
+            [
+                r#""plain""#,
+                r#""web""#,
+                r#""true""#,
+                r#""it's""#,
+                r#""say \"hi\"""#,
+                r#""C:\\dir""#,
+            ],
+        ),
+    ];
+    for (style, [key, items @ ..]) in expected {
+        let output = dump(style);
+        // Tabs need escapes, so they are always double-quoted.
+        assert_eq!(
+            output,
+            format!(
+                "---\n{key}:\n  - {}\n  - \"tab\\there\"",
+                items.join("\n  - ")
+            ),
+            "{style:?}"
+        );
+        assert_eq!(Yaml::load_from_str(&output).unwrap()[0], doc, "{style:?}");
+    }
+}
+
+#[test]
+fn test_null_style() {
+    let mut hash = Hash::new();
+    hash.insert(yaml!("a"), Yaml::Null);
+    hash.insert(Yaml::Null, yaml!(1));
+    hash.insert(yaml!("b"), yaml!([Yaml::Null, [Yaml::Null]]));
+    let doc = Yaml::Hash(hash);
+    let dump = |style, schema| {
+        let mut output = String::new();
+        let mut emitter = YamlEmitter::new(&mut output);
+        emitter.null_style(style);
+        emitter.schema(schema);
+        emitter.flow_depth(Some(2));
+        emitter.dump(&doc).unwrap();
+        output
+    };
+
+    for (style, null) in [
+        (NullStyle::Tilde, "~"),
+        (NullStyle::Lowercase, "null"),
+        (NullStyle::Capitalized, "Null"),
+    ] {
+        let output = dump(style, Schema::Core);
+        assert_eq!(
+            output,
+            format!("---\na: {null}\n{null}: 1\nb:\n  - {null}\n  - [{null}]")
+        );
+        assert_eq!(Yaml::load_from_str(&output).unwrap()[0], doc);
+    }
+
+    // Keys and entries of flow collections cannot be empty.
+    let output = dump(NullStyle::Empty, Schema::Core);
+    assert_eq!(output, "---\na:\nnull: 1\nb:\n  -\n  - [null]");
+    assert_eq!(Yaml::load_from_str(&output).unwrap()[0], doc);
+    assert_eq!(
+        dump(NullStyle::Empty, Schema::Json),
+        "---\n\"a\": null\nnull: 1\n\"b\":\n  - null\n  - [null]"
+    );
+}
@@ -0,0 +1,193 @@
+use saphyr::{Event, Hash, LoadError, LoadErrorKind, Span, TScalarStyle, Yaml, YamlLoader};
+
+fn s(v: &str) -> Yaml {
+    Yaml::String(v.into())
+}
+
+fn scalar(v: &str, style: TScalarStyle) -> Event {
+    Event::Scalar(v.into(), style, 0, None)
+}
+
+fn load(events: impl IntoIterator<Item = Event>) -> Result<Vec<Yaml>, LoadError> {
+    YamlLoader::from_events(events.into_iter().map(|ev| (ev, Span::default())))
+}
+
+fn sample() -> Yaml {
+    let mut inner = Hash::new();
+    inner.insert(s("b"), Yaml::Array(vec![Yaml::Integer(1), Yaml::Null]));
+    inner.insert(Yaml::Integer(2), Yaml::Hash(Hash::new()));
+    let mut root = Hash::new();
+    root.insert(s("a"), Yaml::Hash(inner));
+    root.insert(s("c"), Yaml::Array(vec![]));
+    root.insert(s("d"), Yaml::Real("1.5".into()));
+    root.insert(s("e"), s("true"));
+    root.insert(s("f"), s("plain text"));
+    Yaml::Hash(root)
+}
+
+#[test]
+fn test_into_events() {
+    use TScalarStyle::{DoubleQuoted, Plain};
+    let events: Vec<_> = sample().into_events().collect();
+    assert_eq!(
+        events,
+        [
+            Event::StreamStart,
+            Event::DocumentStart(true),
+            Event::MappingStart(0, None),
+            scalar("a", Plain),
+            Event::MappingStart(0, None),
+            scalar("b", Plain),
+            Event::SequenceStart(0, None),
+            scalar("1", Plain),
+            scalar("~", Plain),
+            Event::SequenceEnd,
+            scalar("2", Plain),
+            Event::MappingStart(0, None),
+            Event::MappingEnd,
+            Event::MappingEnd,
+            scalar("c", Plain),
+            Event::SequenceStart(0, None),
+            Event::SequenceEnd,
+            scalar("d", Plain),
+            scalar("1.5", Plain),
+            scalar("e", Plain),
+            scalar("true", DoubleQuoted),
+            scalar("f", Plain),
+            scalar("plain text", Plain),
+            Event::MappingEnd,
+            Event::DocumentEnd,
+            Event::StreamEnd,
+        ]
+    );
+
+    let events: Vec<_> = Yaml::Integer(3).into_events().collect();
+    assert_eq!(events.len(), 5);
+    assert_eq!(events[2], scalar("3", Plain));
+}
+
+#[test]
+fn test_events_round_trip() {
+    let doc = sample();
+    let reloaded = load(doc.clone().into_events()).unwrap();
+    assert_eq!(reloaded.len(), 1);
+    assert_eq!(reloaded[0], doc);
+
+    // Events can be transformed as they stream through.
+    let events = doc.into_events().flat_map(|ev| match ev {
+        Event::SequenceStart(..) => vec![ev, scalar("first", TScalarStyle::Plain)],
+        ev => vec![ev],
+    });
+    let loaded = load(events).unwrap();
+    assert_eq!(loaded[0]["c"], Yaml::Array(vec![s("first")]));
+    assert_eq!(loaded[0]["a"]["b"][0], s("first"));
+    assert_eq!(loaded[0]["a"]["b"][1], Yaml::Integer(1));
+}
+
+#[test]
+fn test_parse_events() {
+    use TScalarStyle::{Plain, SingleQuoted};
+    let source = "- a\n- 'b'\n";
+    let (events, spans): (Vec<_>, Vec<_>) =
+        saphyr::parse_events(source).map(Result::unwrap).unzip();
+    assert_eq!(
+        events,
+        [
+            Event::StreamStart,
+            Event::DocumentStart(false),
+            Event::SequenceStart(0, None),
+            scalar("a", Plain),
+            scalar("b", SingleQuoted),
+            Event::SequenceEnd,
+            Event::DocumentEnd,
+            Event::StreamEnd,
+        ]
+    );
+    assert_eq!((spans[4].start.line(), spans[4].start.col()), (2, 2));
+    assert_eq!(load(events).unwrap(), Yaml::load_from_str(source).unwrap());
+
+    // The iteration ends after the first error.
+    let events: Vec<_> = saphyr::parse_events("a: [1\n---\nb").collect();
+    assert!(events.last().unwrap().is_err());
+    assert_eq!(events.iter().filter(|ev| ev.is_err()).count(), 1);
+}
+
+#[test]
+fn test_from_events_multiple_documents() {
+    let mut events = vec![Event::StreamStart];
+    for v in ["1", "x"] {
+        events.extend([
+            Event::DocumentStart(true),
+            scalar(v, TScalarStyle::Plain),
+            Event::DocumentEnd,
+        ]);
+    }
+    events.push(Event::StreamEnd);
+    assert_eq!(load(events).unwrap(), [Yaml::Integer(1), s("x")]);
+}
+
+#[test]
+fn test_from_invalid_events() {
+    use Event::{DocumentEnd, DocumentStart, MappingEnd, MappingStart, SequenceEnd, SequenceStart};
+    let a = || scalar("a", TScalarStyle::Plain);
+    for (events, expected) in [
+        (
+            vec![DocumentStart(true), SequenceStart(0, None), DocumentEnd],
+            "a document ends within a collection",
+        ),
+        (
+            vec![DocumentStart(true), MappingStart(0, None), SequenceEnd],
+            "the end of a sequence does not close a sequence",
+        ),
+        (
+            vec![DocumentStart(true), SequenceStart(0, None), a(), MappingEnd],
+            "the end of a mapping does not close a mapping",
+        ),
+        (
+            vec![DocumentStart(true), MappingStart(0, None), a(), MappingEnd],
+            "a mapping ends after a key without a value",
+        ),
+        (
+            vec![DocumentStart(true), a(), a()],
+            "a document has more than one root node",
+        ),
+        (
+            vec![
+                DocumentStart(true),
+                SequenceStart(0, None),
+                SequenceEnd,
+                a(),
+            ],
+            "a document has more than one root node",
+        ),
+        (
+            vec![DocumentStart(true), a(), DocumentStart(true)],
+            "a document starts before the previous one ends",
+        ),
+        (
+            vec![SequenceEnd],
+            "the end of a sequence does not close a sequence",
+        ),
+    ] {
+        let error = load(events).unwrap_err();
+        assert_eq!(error.kind(), LoadErrorKind::InvalidEventStream);
+        let LoadError::InvalidEventStream { reason, .. } = error else {
+            unreachable!()
+        };
+        assert_eq!(reason, expected);
+    }
+
+    let error = load([
+        DocumentStart(true),
+        MappingStart(0, None),
+        a(),
+        SequenceStart(0, None),
+        MappingEnd,
+    ])
+    .unwrap_err();
+    assert_eq!(error.path().unwrap().to_string(), "/a");
+    assert_eq!(
+        error.to_string(),
+        "invalid event stream at line 0 column 1: the end of a mapping does not close a mapping"
+    );
+}
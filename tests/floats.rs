@@ -0,0 +1,121 @@
+use std::collections::HashSet;
+
+use saphyr::{Float, LoaderOptions, Yaml, YamlEmitter};
+
+fn load(s: &str, options: LoaderOptions) -> Yaml {
+    Yaml::load_from_str_with_options(s, options)
+        .map(|mut docs| docs.remove(0))
+        .unwrap()
+}
+
+fn dump(doc: &Yaml) -> String {
+    let mut out = String::new();
+    YamlEmitter::new(&mut out).dump(doc).unwrap();
+    out
+}
+
+#[test]
+fn test_float_equality() {
+    assert_eq!(Float::new(1.0), Float::parse("1.00").unwrap());
+    assert_eq!(Float::new(f64::NAN), Float::parse(".NAN").unwrap());
+    assert_ne!(Float::new(0.0), Float::new(-0.0));
+    assert!(Float::new(-0.0) < Float::new(0.0));
+    assert_eq!(Float::parse("1e"), None);
+
+    let set: HashSet<Float> = ["1.0", "1.00", "1e0", ".nan", ".NAN"]
+        .into_iter()
+        .map(|v| Float::parse(v).unwrap())
+        .collect();
+    assert_eq!(set.len(), 2);
+}
+
+#[test]
+fn test_canonical_floats() {
+    for (value, canonical) in [
+        (1.0, "1.0"),
+        (0.1, "0.1"),
+        (-2.5, "-2.5"),
+        (1e100, "1.0e100"),
+        (1.5e-7, "1.5e-7"),
+        (f64::NAN, ".nan"),
+        (f64::INFINITY, ".inf"),
+        (f64::NEG_INFINITY, "-.inf"),
+    ] {
+        let float = Float::new(value);
+        assert_eq!(float.to_string(), canonical);
+        assert_eq!(dump(&Yaml::Float(float)), format!("---\n{canonical}"));
+        assert_eq!(Float::parse(canonical), Some(Float::new(value)));
+    }
+}
+
+#[test]
+fn test_parse_floats() {
+    let source =
+        "a: 1.00\nb: !!float 1e2\nc: !!float '3'\nd: .inf\ne: 0.1000000000000000000001\nf: 1";
+    // Floats are only parsed on request.
+    let doc = load(source, LoaderOptions::default());
+    assert_eq!(doc["a"], Yaml::Real("1.00".into()));
+
+    let options = LoaderOptions {
+        parse_floats: true,
+        ..LoaderOptions::default()
+    };
+    let doc = load(source, options.clone());
+    assert_eq!(doc["a"], Yaml::Float(Float::new(1.0)));
+    assert_eq!(doc["b"].as_f64(), Some(100.0));
+    assert_eq!(doc["c"].as_str(), Some("3"));
+    assert_eq!(doc["d"].as_float(), Some(&Float::new(f64::INFINITY)));
+    assert_eq!(doc["f"], Yaml::Integer(1));
+    assert_eq!(
+        dump(&doc),
+        "---\na: 1.0\nb: 100.0\nc: \"3\"\nd: .inf\ne: 0.1\nf: 1"
+    );
+
+    // Integers too large for an `i64` are not rounded to floats.
+    let doc = load(
+        "a: 18446744073709551615\nb: !!float 18446744073709551615",
+        options.clone(),
+    );
+    assert_eq!(doc["a"].as_u64(), Some(u64::MAX));
+    assert_eq!(
+        doc["b"].as_float(),
+        Some(&Float::new(1.8446744073709552e19))
+    );
+
+    // Sources are emitted as they were written, but do not change comparisons.
+    let doc_with_sources = load(
+        source,
+        LoaderOptions {
+            keep_float_sources: true,
+            ..options
+        },
+    );
+    assert_eq!(doc_with_sources, doc);
+    assert_eq!(
+        doc_with_sources["e"].as_float().unwrap().source.as_deref(),
+        Some("0.1000000000000000000001")
+    );
+    assert_eq!(
+        dump(&doc_with_sources),
+        "---\na: 1.00\nb: 1e2\nc: \"3\"\nd: .inf\ne: 0.1000000000000000000001\nf: 1"
+    );
+}
+
+#[test]
+fn test_float_keys() {
+    let options = LoaderOptions {
+        parse_floats: true,
+        ..LoaderOptions::default()
+    };
+    let doc = load("1.0: a\n.nan: b", options);
+    let hash = doc.as_hash().unwrap();
+    assert_eq!(
+        hash.get(&Yaml::Float(Float::new(1.00))),
+        Some(&Yaml::from("a"))
+    );
+    assert_eq!(
+        hash.get(&Yaml::Float(Float::new(f64::NAN))),
+        Some(&Yaml::from("b"))
+    );
+    assert!(doc.semantic_eq(&Yaml::load_from_str("1.00: a\n.NAN: b").unwrap()[0]));
+}
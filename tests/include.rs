@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use saphyr::{
+    FsIncludeResolver, IncludeOptions, IncludeResolver, LoadError, LoadErrorKind, LoaderLimit,
+    LoaderOptions, Yaml,
+};
+
+/// Sources served from memory, by their path relative to the root.
+struct InMemory(HashMap<&'static str, &'static str>);
+
+impl IncludeResolver for InMemory {
+    fn resolve(&self, path: &str, from: Option<&Path>) -> Result<String, LoadError> {
+        let file = self.locate(path, from);
+        self.0
+            .get(file.to_str().unwrap())
+            .map(|source| (*source).to_owned())
+            .ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::NotFound, file.display().to_string()).into()
+            })
+    }
+}
+
+/// Sources which include a new source, forever.
+struct Endless;
+
+impl IncludeResolver for Endless {
+    fn resolve(&self, _: &str, _: Option<&Path>) -> Result<String, LoadError> {
+        Ok("!include next.yml".into())
+    }
+
+    fn locate(&self, _: &str, from: Option<&Path>) -> PathBuf {
+        from.map_or_else(|| "next.yml".into(), |from| from.join("next.yml"))
+    }
+}
+
+fn load(source: &str, includes: IncludeOptions) -> Result<Yaml, LoadError> {
+    let options = LoaderOptions {
+        includes: Some(includes),
+        ..LoaderOptions::default()
+    };
+    Yaml::load_from_str_with_options(source, options).map(|mut docs| docs.remove(0))
+}
+
+fn in_memory(sources: &[(&'static str, &'static str)]) -> IncludeOptions {
+    IncludeOptions::new(InMemory(sources.iter().copied().collect()))
+}
+
+#[test]
+fn test_nested_includes() {
+    let includes = in_memory(&[
+        (
+            "ci/jobs.yml",
+            "build: !include steps/build.yml\ntest: !include 'steps/test.yml'",
+        ),
+        ("ci/steps/build.yml", "[cargo build]"),
+        ("ci/steps/test.yml", "- cargo test\n- !include lint.yml"),
+        ("ci/steps/lint.yml", "cargo clippy"),
+    ]);
+    let doc = load("jobs: !include ci/jobs.yml\nname: !other ci", includes).unwrap();
+    assert_eq!(
+        doc,
+        Yaml::load_from_str(
+            "jobs: {build: [cargo build], test: [cargo test, cargo clippy]}\nname: ci"
+        )
+        .unwrap()[0]
+    );
+
+    // Includes are only resolved on request.
+    let doc = Yaml::load_from_str("jobs: !include ci/jobs.yml").unwrap();
+    assert_eq!(doc[0]["jobs"].as_str(), Some("ci/jobs.yml"));
+}
+
+#[test]
+fn test_custom_include_tag() {
+    let mut includes = in_memory(&[("a.yml", "1")]);
+    includes.tag = "tag:example.com,2020:include".into();
+    let doc = load(
+        "%TAG !e! tag:example.com,2020:\n---\n- !e!include a.yml\n- !include a.yml",
+        includes,
+    )
+    .unwrap();
+    assert_eq!(doc, Yaml::load_from_str("[1, a.yml]").unwrap()[0]);
+}
+
+#[test]
+fn test_include_cycles() {
+    let includes = in_memory(&[
+        ("a.yml", "b: !include b.yml"),
+        ("b.yml", "a: !include a.yml"),
+    ]);
+    let error = load("root: !include a.yml", includes.clone()).unwrap_err();
+    let LoadError::IncludeCycle { chain, .. } = &error else {
+        panic!("not a cycle: {error:?}");
+    };
+    assert_eq!(
+        chain,
+        &[PathBuf::from("a.yml"), "b.yml".into(), "a.yml".into()]
+    );
+    assert!(error
+        .to_string()
+        .ends_with("forms a cycle: `a.yml` -> `b.yml` -> `a.yml`"));
+
+    // The document being loaded is part of cycles if it is known.
+    let includes = IncludeOptions {
+        root: Some("b.yml".into()),
+        ..includes
+    };
+    let error = load("a: !include a.yml", includes).unwrap_err();
+    assert!(
+        matches!(&error, LoadError::IncludeCycle { chain, .. } if chain.len() == 3),
+        "{error:?}"
+    );
+
+    // Including the same source twice is not a cycle.
+    let includes = in_memory(&[("a.yml", "[1]")]);
+    let doc = load("[!include a.yml, !include a.yml]", includes).unwrap();
+    assert_eq!(doc, Yaml::load_from_str("[[1], [1]]").unwrap()[0]);
+}
+
+#[test]
+fn test_include_errors() {
+    let includes = in_memory(&[
+        ("deep.yml", "!include deep.yml"),
+        ("broken.yml", "a: [1"),
+        ("two.yml", "1\n---\n2"),
+    ]);
+
+    let error = load("x:\n  - !include missing.yml", includes.clone()).unwrap_err();
+    assert_eq!(error.kind(), LoadErrorKind::Include);
+    assert_eq!(error.path().unwrap().to_string(), "/x/0");
+    let LoadError::Include { file, error, .. } = error else {
+        unreachable!()
+    };
+    assert_eq!(file, PathBuf::from("missing.yml"));
+    assert!(matches!(*error, LoadError::IO(_)));
+
+    let error = load("!include broken.yml", includes.clone()).unwrap_err();
+    assert!(
+        matches!(&error, LoadError::Include { error, .. } if error.kind() == LoadErrorKind::Syntax)
+    );
+    assert!(error
+        .to_string()
+        .starts_with("in `broken.yml` included at line 1 column "));
+
+    let error = load("!include two.yml", includes.clone()).unwrap_err();
+    assert!(
+        matches!(&error, LoadError::Include { error, .. } if matches!(**error, LoadError::ExpectedSingleDocument { found: 2, .. }))
+    );
+
+    assert_eq!(
+        load("!include deep.yml", includes).unwrap_err().kind(),
+        LoadErrorKind::IncludeCycle
+    );
+
+    // Includes which never repeat a source are stopped by the depth limit.
+    let mut includes = IncludeOptions::new(Endless);
+    includes.max_depth = 4;
+    let mut error = load("!include next.yml", includes).unwrap_err();
+    let mut depth = 0;
+    while let LoadError::Include { error: inner, .. } = error {
+        error = *inner;
+        depth += 1;
+    }
+    assert_eq!(depth, 4);
+    assert!(matches!(
+        error,
+        LoadError::LimitExceeded {
+            limit: LoaderLimit::IncludeDepth,
+            length: 5,
+            max: 4,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_include_limits() {
+    let includes = in_memory(&[("list.yml", "[1, 2, 3]")]);
+    let options = LoaderOptions {
+        includes: Some(includes),
+        max_nodes: Some(8),
+        ..LoaderOptions::default()
+    };
+    assert!(Yaml::load_from_str_with_options("[!include list.yml]", options.clone()).is_ok());
+    let error = Yaml::load_from_str_with_options("[!include list.yml, !include list.yml]", options)
+        .unwrap_err();
+    assert!(matches!(
+        &error,
+        LoadError::Include { error, .. } if matches!(**error, LoadError::LimitExceeded { limit: LoaderLimit::Nodes, .. })
+    ));
+}
+
+#[test]
+fn test_fs_includes() {
+    let directory = std::env::temp_dir().join(format!("saphyr-include-{}", std::process::id()));
+    std::fs::create_dir_all(directory.join("nested")).unwrap();
+    // Resolved paths are canonical, and so must be the root.
+    let directory = std::fs::canonicalize(directory).unwrap();
+    std::fs::write(directory.join("main.yml"), "a: !include nested/a.yml").unwrap();
+    std::fs::write(directory.join("nested/a.yml"), "[!include b.yml]").unwrap();
+    std::fs::write(directory.join("nested/b.yml"), "from b").unwrap();
+    std::fs::write(directory.join("nested/c.yml"), "!include ../main.yml").unwrap();
+
+    let includes = IncludeOptions::new(FsIncludeResolver::new(&directory));
+    let doc = load("!include main.yml", includes.clone());
+    let cycle = load(
+        "x: !include nested/c.yml",
+        IncludeOptions {
+            root: Some(directory.join("main.yml")),
+            ..includes
+        },
+    );
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(doc.unwrap(), Yaml::load_from_str("a: [from b]").unwrap()[0]);
+    let Err(LoadError::IncludeCycle { chain, .. }) = cycle else {
+        panic!("not a cycle: {cycle:?}");
+    };
+    let names: Vec<_> = chain
+        .iter()
+        .map(|file| file.file_name().unwrap().to_str().unwrap())
+        .collect();
+    assert_eq!(names, ["main.yml", "c.yml", "main.yml"]);
+}
@@ -0,0 +1,142 @@
+#[macro_use]
+extern crate quickcheck;
+
+use quickcheck::TestResult;
+
+use saphyr::{IncrementalLoader, MarkedYaml, YamlData};
+
+const FIXTURES: &[&str] = &[
+    "a: 1\nb: [2, 3]\n---\n- x\n- y: z\n...\n---\nlast: |\n  literal\n  text\n",
+    "%YAML 1.2\n---\nkey: \"quoted\n  over lines\"\n...\n%YAML 1.2\n---\n? [complex]\n: key\n",
+    "# comment\nfirst\n--- second\n--- 'third'\n---\n{flow: mapping, with: [a, list]}\n",
+    "anchors: &a\n  b: 1\naliases: *a\n---\n- &c [1, 2]\n- *c\n",
+];
+
+/// Fragments inserted by random edits. They are biased towards changing document boundaries.
+const FRAGMENTS: &[&str] = &[
+    "",
+    "-",
+    "---\n",
+    "\n",
+    ":",
+    " ",
+    "a",
+    "#",
+    "'",
+    "\"",
+    "[",
+    "]",
+    "|\n  ",
+    "...\n",
+    "%YAML 1.2\n",
+    "é",
+    "- ",
+    "\n--- x\n",
+];
+
+/// Assert that the spans of `actual` and `expected` are equal, recursively.
+fn assert_same_spans(actual: &MarkedYaml, expected: &MarkedYaml) {
+    assert_eq!(actual.span, expected.span, "{actual:?} vs {expected:?}");
+    match (&actual.data, &expected.data) {
+        (YamlData::Array(actual), YamlData::Array(expected)) => {
+            for (actual, expected) in actual.iter().zip(expected) {
+                assert_same_spans(actual, expected);
+            }
+        }
+        (YamlData::Hash(actual), YamlData::Hash(expected)) => {
+            for ((actual_k, actual_v), (expected_k, expected_v)) in actual.iter().zip(expected) {
+                assert_same_spans(actual_k, expected_k);
+                assert_same_spans(actual_v, expected_v);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Return the closest character boundary of `text` at or before `index`.
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// Apply the given edits to each fixture, comparing against a full re-parse after each edit.
+fn check_edits(edits: &[(u16, u8, u8)]) {
+    for fixture in FIXTURES {
+        let mut loader = IncrementalLoader::new(*fixture).unwrap();
+        for &(position, length, fragment) in edits {
+            let source = loader.source().to_owned();
+            let start = floor_char_boundary(&source, usize::from(position) % (source.len() + 1));
+            let end = floor_char_boundary(&source, start + usize::from(length % 4));
+            let replacement = FRAGMENTS[usize::from(fragment) % FRAGMENTS.len()];
+
+            let mut edited = source.clone();
+            edited.replace_range(start..end, replacement);
+            match MarkedYaml::load_from_str(&edited) {
+                Ok(expected) => {
+                    loader.apply_edit(start..end, replacement).unwrap();
+                    assert_eq!(loader.source(), edited);
+                    assert_eq!(
+                        loader.documents(),
+                        expected.as_slice(),
+                        "source: {edited:?}"
+                    );
+                    for (actual, expected) in loader.documents().iter().zip(&expected) {
+                        assert_same_spans(actual, expected);
+                    }
+                }
+                Err(_) => {
+                    assert!(loader.apply_edit(start..end, replacement).is_err());
+                    assert_eq!(loader.source(), source);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_edit_value() {
+    let mut loader = IncrementalLoader::new("a: 1\n---\nb: 2\n---\nc: 3\n").unwrap();
+    loader.apply_edit(21..22, "[4, 5]").unwrap();
+    assert_eq!(loader.source(), "a: 1\n---\nb: 2\n---\nc: [4, 5]\n");
+    assert_eq!(
+        loader.documents()[2].data["c"].data[1].data.as_i64(),
+        Some(5)
+    );
+}
+
+#[test]
+fn test_fixed_edits() {
+    check_edits(&[(1, 0, 3), (20, 3, 7), (9, 1, 17), (0, 2, 14)]);
+}
+
+#[test]
+fn test_edit_splits_and_merges_documents() {
+    let mut loader = IncrementalLoader::new("- a\n- b\n- c\n").unwrap();
+    loader.apply_edit(4..4, "---\n").unwrap();
+    assert_eq!(loader.documents().len(), 2);
+    assert_eq!(loader.documents()[1].span.start.line(), 3);
+    loader.apply_edit(4..8, "").unwrap();
+    assert_eq!(loader.documents().len(), 1);
+    assert_eq!(loader.documents()[0].data[2].data.as_str(), Some("c"));
+}
+
+#[test]
+fn test_failed_edit_is_reverted() {
+    let mut loader = IncrementalLoader::new("a: [1, 2]\n---\nb: 3\n").unwrap();
+    assert!(loader.apply_edit(8..9, "").is_err());
+    assert_eq!(loader.source(), "a: [1, 2]\n---\nb: 3\n");
+    assert_eq!(
+        loader.documents()[0].data["a"].data[1].data.as_i64(),
+        Some(2)
+    );
+}
+
+quickcheck! {
+    fn test_random_edits_match_full_reparse(edits: Vec<(u16, u8, u8)>) -> TestResult {
+        check_edits(&edits);
+        TestResult::passed()
+    }
+}
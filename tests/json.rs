@@ -0,0 +1,95 @@
+use saphyr::{JsonErrorKind, JsonOptions, NonFiniteFloats, NonStringKeys, Yaml, YamlPath};
+use serde_json::Value;
+
+fn json(s: &str) -> Value {
+    serde_json::from_str(s).unwrap()
+}
+
+fn yaml(s: &str) -> Yaml {
+    Yaml::load_one_from_str(s).unwrap()
+}
+
+#[test]
+fn test_json_round_trip() {
+    let value = json(
+        r#"{"z": 1, "a": [true, null, -3, 1.5, 18446744073709551615, 1e100],
+            "nested": {"s": "text", "empty": {}, "list": []}}"#,
+    );
+    let doc = Yaml::from(&value);
+    assert_eq!(doc["z"], Yaml::Integer(1));
+    assert_eq!(doc["a"][3], Yaml::Real("1.5".to_owned()));
+    assert_eq!(Value::try_from(&doc).unwrap(), value);
+    // Key order is preserved.
+    let keys: Vec<_> = Value::try_from(&doc)
+        .unwrap()
+        .as_object()
+        .unwrap()
+        .keys()
+        .cloned()
+        .collect();
+    assert_eq!(keys, ["z", "a", "nested"]);
+}
+
+#[test]
+fn test_yaml_to_json() {
+    let doc = yaml("a: 1\nb: [true, ~, 1.5, .5]\nc: {d: e}");
+    let value = json(r#"{"a": 1, "b": [true, null, 1.5, 0.5], "c": {"d": "e"}}"#);
+    assert_eq!(Value::try_from(&doc).unwrap(), value);
+    assert_eq!(Yaml::from(&value)["c"], doc["c"]);
+    assert_eq!(Value::try_from(doc.clone()).unwrap(), value);
+    assert_eq!(Yaml::from(value)["c"], doc["c"]);
+}
+
+#[test]
+fn test_json_non_string_keys() {
+    let doc = yaml("1: a\ntrue: b\n~: c\n[x, 2]: d");
+    assert_eq!(
+        Value::try_from(&doc).unwrap(),
+        json(r#"{"1": "a", "true": "b", "null": "c", "[\"x\",2]": "d"}"#)
+    );
+
+    let options = JsonOptions {
+        non_string_keys: NonStringKeys::Error,
+        ..JsonOptions::default()
+    };
+    let error = doc.to_json(options).unwrap_err();
+    assert_eq!(error.path.to_string(), "/?0");
+    assert_eq!(error.kind, JsonErrorKind::NonStringKey(Yaml::Integer(1)));
+
+    let error = yaml("a:\n  1: x\n  '1': y")
+        .to_json(JsonOptions::default())
+        .unwrap_err();
+    assert_eq!(error.path.to_string(), "/a/?1");
+    assert_eq!(error.kind, JsonErrorKind::DuplicateKey("1".to_owned()));
+    assert_eq!(error.to_string(), "duplicate mapping key `1` at `/a/?1`");
+}
+
+#[test]
+fn test_json_non_finite_floats() {
+    let doc = yaml("- 1\n- [.inf]");
+    let error = Value::try_from(&doc).unwrap_err();
+    assert_eq!(error.path.to_string(), "/1/0");
+    assert_eq!(error.kind, JsonErrorKind::NonFiniteFloat(".inf".to_owned()));
+
+    let options = JsonOptions {
+        non_finite_floats: NonFiniteFloats::Null,
+        ..JsonOptions::default()
+    };
+    assert_eq!(doc.to_json(options).unwrap(), json("[1, [null]]"));
+}
+
+#[test]
+fn test_json_invalid_values() {
+    let error = Value::try_from(&Yaml::BadValue).unwrap_err();
+    assert_eq!(error.path, YamlPath::new());
+    assert_eq!(error.kind, JsonErrorKind::BadValue);
+    assert_eq!(
+        error.to_string(),
+        "invalid value at the root of the document"
+    );
+
+    let doc = Yaml::Array(vec![Yaml::Alias(0)]);
+    let error = Value::try_from(&doc).unwrap_err();
+    assert_eq!(error.path.to_string(), "/0");
+    assert_eq!(error.kind, JsonErrorKind::Alias(0));
+}
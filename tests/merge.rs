@@ -0,0 +1,279 @@
+use saphyr::{
+    ArrayMerge, ConflictPolicy, LoaderOptions, MappingMerge, MarkedYaml, MergeStrategy,
+    NullOverlay, Yaml, YamlKind,
+};
+
+fn yaml(s: &str) -> Yaml {
+    Yaml::load_one_from_str(s).unwrap()
+}
+
+fn merged(base: &str, overlay: &str, strategy: &MergeStrategy) -> Yaml {
+    let mut base = yaml(base);
+    base.merge_from(yaml(overlay), strategy).unwrap();
+    base
+}
+
+#[test]
+fn test_merge_deep_nesting() {
+    let base = "
+a:
+  b:
+    c:
+      d: 1
+      e: 2
+    f: [1, 2]
+  g: base
+only_base: 1
+";
+    let overlay = "
+a:
+  b:
+    c:
+      e: 20
+      h: 30
+    f: [3]
+  g: overlay
+only_overlay: {x: 1}
+";
+    let expected = "
+a:
+  b:
+    c:
+      d: 1
+      e: 20
+      h: 30
+    f: [3]
+  g: overlay
+only_base: 1
+only_overlay: {x: 1}
+";
+    let doc = merged(base, overlay, &MergeStrategy::default());
+    assert_eq!(doc, yaml(expected));
+    // Keys of the base keep their position, new keys are appended.
+    let keys: Vec<_> = doc.as_hash().unwrap().keys().cloned().collect();
+    assert_eq!(
+        keys,
+        ["a", "only_base", "only_overlay"].map(|k| Yaml::String(k.into()))
+    );
+}
+
+#[test]
+fn test_merge_arrays() {
+    let base = "list: [{a: 1}, {b: 2}]";
+    let overlay = "list: [{c: 3}, {b: 20}, {d: 4}]";
+    for (arrays, expected) in [
+        (ArrayMerge::Replace, "list: [{c: 3}, {b: 20}, {d: 4}]"),
+        (
+            ArrayMerge::Concat,
+            "list: [{a: 1}, {b: 2}, {c: 3}, {b: 20}, {d: 4}]",
+        ),
+        (ArrayMerge::ByIndex, "list: [{a: 1, c: 3}, {b: 20}, {d: 4}]"),
+        (
+            ArrayMerge::ByKey("b".into()),
+            "list: [{a: 1}, {b: 20}, {c: 3}, {d: 4}]",
+        ),
+    ] {
+        let strategy = MergeStrategy {
+            arrays,
+            ..MergeStrategy::default()
+        };
+        assert_eq!(
+            merged(base, overlay, &strategy),
+            yaml(expected),
+            "{:?}",
+            strategy.arrays
+        );
+    }
+}
+
+#[test]
+fn test_merge_arrays_by_key() {
+    let base = "
+containers:
+  - {name: app, image: app:1, env: [{name: A, value: 1}]}
+  - {name: sidecar, image: proxy:1}
+  - image: unnamed
+";
+    let overlay = "
+containers:
+  - {name: app, image: app:2, env: [{name: B, value: 2}, {name: A, value: 3}]}
+  - {name: init, image: busybox}
+  - image: other
+";
+    let strategy = MergeStrategy {
+        arrays: ArrayMerge::ByKey("name".into()),
+        ..MergeStrategy::default()
+    };
+    assert_eq!(
+        merged(base, overlay, &strategy),
+        yaml(
+            "
+containers:
+  - {name: app, image: app:2, env: [{name: A, value: 3}, {name: B, value: 2}]}
+  - {name: sidecar, image: proxy:1}
+  - image: unnamed
+  - {name: init, image: busybox}
+  - image: other
+"
+        )
+    );
+}
+
+#[test]
+fn test_merge_mappings_replace() {
+    let base = "db: {host: localhost, port: 5432}
+log: {level: info}";
+    let overlay = "db: {host: db.prod}
+debug: true";
+    let strategy = MergeStrategy {
+        mappings: MappingMerge::Replace,
+        ..MergeStrategy::default()
+    };
+    assert_eq!(
+        merged(base, overlay, &strategy),
+        yaml(
+            "db: {host: db.prod}
+log: {level: info}
+debug: true"
+        )
+    );
+    assert_eq!(
+        merged(base, overlay, &MergeStrategy::default()),
+        yaml(
+            "db: {host: db.prod, port: 5432}
+log: {level: info}
+debug: true"
+        )
+    );
+}
+
+#[test]
+fn test_merge_nulls() {
+    let base = "a: 1\nb: {c: 2, d: 3}\nlist: [1, 2]";
+    let overlay = "a: ~\nb: {c: null}\nlist: [~]\nnew: ~";
+    assert_eq!(
+        merged(base, overlay, &MergeStrategy::default()),
+        yaml("a: ~\nb: {c: ~, d: 3}\nlist: [~]\nnew: ~")
+    );
+    let strategy = MergeStrategy {
+        arrays: ArrayMerge::ByIndex,
+        nulls: NullOverlay::Delete,
+        ..MergeStrategy::default()
+    };
+    assert_eq!(
+        merged(base, overlay, &strategy),
+        yaml("b: {d: 3}\nlist: [~, 2]")
+    );
+}
+
+#[test]
+fn test_merge_conflicts() {
+    let base = "a: {b: [1, 2]}\nc: 1";
+    let overlay = "a: {b: {x: 1}}\nc: [1]";
+    assert_eq!(
+        merged(base, overlay, &MergeStrategy::default()),
+        yaml(overlay)
+    );
+
+    let strategy = MergeStrategy {
+        conflicts: ConflictPolicy::Error,
+        ..MergeStrategy::default()
+    };
+    let mut doc = yaml(base);
+    let error = doc.merge_from(yaml(overlay), &strategy).unwrap_err();
+    assert_eq!(error.path.to_string(), "/a/b");
+    assert_eq!(
+        (error.base, error.overlay),
+        (YamlKind::Array, YamlKind::Hash)
+    );
+    assert_eq!(
+        error.to_string(),
+        "cannot merge mapping over sequence at `/a/b`"
+    );
+    let error = yaml("1").merge_from(yaml("[1]"), &strategy).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "cannot merge sequence over int at the root of the document"
+    );
+
+    // Scalars replace each other, and `null` replaces anything.
+    assert_eq!(merged("a: 1", "a: text", &strategy), yaml("a: text"));
+    assert_eq!(merged("a: [1]", "a: ~", &strategy), yaml("a: ~"));
+}
+
+#[test]
+fn test_merge_onto_bad_value() {
+    let strategy = MergeStrategy {
+        conflicts: ConflictPolicy::Error,
+        ..MergeStrategy::default()
+    };
+    let mut doc = Yaml::BadValue;
+    doc.merge_from(yaml("a: {b: 1}"), &strategy).unwrap();
+    assert_eq!(doc, yaml("a: {b: 1}"));
+
+    let mut doc = yaml("a: ~");
+    doc.merge_from(yaml("a: {b: 1}"), &strategy).unwrap();
+    assert_eq!(doc, yaml("a: {b: 1}"));
+
+    // Indexing a missing key returns a `BadValue`, which can be merged onto as well.
+    let mut doc = yaml("a: 1");
+    let mut missing = doc["missing"].clone();
+    missing.merge_from(yaml("[1]"), &strategy).unwrap();
+    doc.as_mut_hash()
+        .unwrap()
+        .insert(Yaml::String("missing".into()), missing);
+    assert_eq!(doc, yaml("a: 1\nmissing: [1]"));
+}
+
+#[test]
+fn test_resolve_merge_keys() {
+    let mut doc = yaml(
+        "
+defaults: &defaults {image: rust, cache: true, stage: test}
+extra: &extra {stage: deploy, retries: 2}
+build:
+  name: build
+  <<: [*defaults, *extra]
+  cache: false
+nested:
+  inner:
+    <<: {<<: *extra, retries: 3, when: always}
+literal:
+  <<: not a mapping
+",
+    );
+    doc.resolve_merge_keys();
+    assert_eq!(
+        doc["build"],
+        yaml("{name: build, image: rust, stage: test, retries: 2, cache: false}")
+    );
+    assert_eq!(
+        doc["nested"]["inner"],
+        yaml("{stage: deploy, retries: 3, when: always}")
+    );
+    assert_eq!(doc["literal"]["<<"].as_str(), Some("not a mapping"));
+
+    let mut list = yaml("[{<<: [{a: 1}, 2]}, {<<: []}]");
+    list.resolve_merge_keys();
+    assert_eq!(list, yaml("[{<<: [{a: 1}, 2]}, {}]"));
+}
+
+#[test]
+fn test_merge_keys_option() {
+    let source = "base: &b {x: 1, y: 2}\nderived: {<<: *b, y: 3}";
+    let options = LoaderOptions {
+        merge_keys: true,
+        ..LoaderOptions::default()
+    };
+    let expected = yaml("base: {x: 1, y: 2}\nderived: {x: 1, y: 3}");
+    assert_eq!(
+        Yaml::load_from_str_with_options(source, options.clone()).unwrap(),
+        [expected]
+    );
+    let marked = MarkedYaml::load_from_str_with_options(source, options).unwrap();
+    assert_eq!(marked[0].data["derived"].data["y"].data.as_i64(), Some(3));
+    assert_eq!(marked[0].data["derived"].data.as_hash().unwrap().len(), 2);
+
+    // Merge keys are ordinary keys by default.
+    assert!(yaml(source)["derived"]["<<"].is_hash());
+}
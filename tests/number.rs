@@ -0,0 +1,45 @@
+use saphyr::{yaml, Number, Yaml};
+
+#[test]
+fn test_numbers() {
+    let number = |value: Yaml| value.as_number().unwrap();
+    assert_eq!(number(yaml!(-3)), Number::from(-3i8));
+    assert_eq!(number(yaml!(3)), Number::from(3u64));
+    assert_eq!(
+        number(Yaml::Real("18446744073709551615".into())).as_u64(),
+        Some(u64::MAX)
+    );
+    assert_eq!(number(Yaml::Real("1e3".into())), Number::from(1000.0));
+    assert_ne!(number(Yaml::Real("1e3".into())), Number::from(1000));
+    assert_eq!(number(Yaml::Real(".nan".into())), Number::from(f64::NAN));
+    assert_eq!(yaml!(true).as_number(), None);
+
+    assert!(Number::from(-1).is_i64() && !Number::from(-1).is_u64());
+    assert!(Number::from(u64::MAX).is_u64() && !Number::from(u64::MAX).is_i64());
+    assert!(Number::from(1.0).is_f64());
+    assert_eq!(Number::from(1.0).as_i64(), None);
+    assert_eq!(Number::from(-2).as_f64(), Some(-2.0));
+
+    // Numbers convert back to the variants they would be loaded as.
+    for (value, text) in [
+        (Number::from(i64::MIN), "-9223372036854775808"),
+        (Number::from(u64::MAX), "18446744073709551615"),
+        (Number::from(0.1), "0.1"),
+        (Number::from(1e100), "1e100"),
+        (Number::from(f64::NEG_INFINITY), "-.inf"),
+    ] {
+        assert_eq!(value.to_string(), text);
+        assert_eq!(Yaml::from(value.clone()).as_number(), Some(value));
+    }
+    assert_eq!(Yaml::from(Number::from(7)), Yaml::Integer(7));
+    assert_eq!(Yaml::from(Number::from(7.0)), Yaml::Real("7.0".into()));
+
+    // The text of a real is kept, but ignored by comparisons.
+    let real = number(Yaml::Real("2.50".into()));
+    assert_eq!(real.source(), Some("2.50"));
+    assert_eq!(real, Number::from(2.5));
+    assert_eq!(real.to_string(), "2.5");
+    assert_eq!(Yaml::from(real), Yaml::Real("2.50".into()));
+    assert_eq!(number(yaml!(2)).source(), None);
+    assert_eq!(Number::from(2.5).source(), None);
+}
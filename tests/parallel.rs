@@ -0,0 +1,83 @@
+use saphyr::Yaml;
+
+/// Documents with `---` and `...` where they are not document markers, or where they end a
+/// scalar.
+const TRICKY_DOCUMENTS: &[&str] = &[
+    "script: |\n  echo ---\n  ---\n  ...\nnext: 1\n",
+    "folded: >\n  a\n  --- b\n",
+    "--- |\n  top-level literal\n  ---\n",
+    "quoted: \"a\n  --- still quoted\"\nsingle: '\n  ...'\n",
+    "key: 1\n---x: not a marker\n...x: neither\n",
+    "--- \"--- a quoted root\"\n",
+    "# ---\n- [a, b]\n# ...\n- {c: ---}\n",
+    "---\t# a comment after a tab\nafter_tab: 1\n",
+    "--- !!str\nplain scalar\n--- still\n",
+    "---\r\nwindows: \"yes\"\r\n",
+    "%YAML 1.1\n%TAG !e! tag:example.com,2000:\n--- !e!config\nflag: yes\n",
+    "---\nbase: &anchor {shared: 1}\ncopy: *anchor\n",
+];
+
+/// Join copies of `documents` into a stream, with the markers they need to be separate.
+fn stream(documents: &[&str], copies: usize) -> String {
+    let mut source = String::from("first: document without marker\n");
+    for _ in 0..copies {
+        for document in documents {
+            if document.starts_with('%') {
+                source.push_str("...\n");
+            } else if !document.starts_with("---") {
+                source.push_str("---\n");
+            }
+            source.push_str(document);
+        }
+    }
+    source
+}
+
+#[test]
+fn test_parallel_loading_matches_sequential() {
+    for copies in [1, 3, 40] {
+        let source = stream(TRICKY_DOCUMENTS, copies);
+        let sequential = Yaml::load_from_str(&source).unwrap();
+        assert!(sequential.len() > TRICKY_DOCUMENTS.len() * copies);
+        for threads in 0..=9 {
+            assert_eq!(
+                Yaml::load_from_str_parallel(&source, threads).unwrap(),
+                sequential,
+                "{copies} copies on {threads} threads"
+            );
+        }
+    }
+
+    for source in [
+        "",
+        "# only a comment\n",
+        "single: document",
+        "...\n",
+        "---\n---\n",
+    ] {
+        assert_eq!(
+            Yaml::load_from_str_parallel(source, 4).unwrap(),
+            Yaml::load_from_str(source).unwrap(),
+            "{source:?}"
+        );
+    }
+}
+
+#[test]
+fn test_parallel_loading_errors() {
+    let mut source = stream(TRICKY_DOCUMENTS, 20);
+    source.push_str("---\nbroken: [1, 2\n---\nafter: 1\n");
+    let expected = Yaml::load_from_str(&source).unwrap_err();
+    for threads in 1..=8 {
+        let error = Yaml::load_from_str_parallel(&source, threads).unwrap_err();
+        assert_eq!(error, expected);
+        assert!(error.marker().line() > 100);
+    }
+
+    // A quoted scalar cannot span a document marker.
+    let source = "a: 1\n---\nquoted: \"a\n--- b\"\n";
+    assert_eq!(
+        Yaml::load_from_str_parallel(source, 2).unwrap_err(),
+        Yaml::load_from_str(source).unwrap_err()
+    );
+}
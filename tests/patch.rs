@@ -0,0 +1,148 @@
+use saphyr::{PatchError, PatchErrorKind, PatchOp, Yaml};
+
+fn yaml(s: &str) -> Yaml {
+    Yaml::load_one_from_str(s).unwrap()
+}
+
+fn patched(doc: &str, patch: &str) -> Result<Yaml, PatchError> {
+    let mut doc = yaml(doc);
+    doc.apply_json_patch(&PatchOp::from_yaml(&yaml(patch))?)?;
+    Ok(doc)
+}
+
+#[test]
+fn test_json_patch_operations() {
+    let doc = "metadata: {name: web, labels: {app: web}}\nspec: {ports: [80, 443], replicas: 1}";
+    for (patch, expected) in [
+        (
+            "[{op: add, path: /spec/ports/1, value: 8080}]",
+            "metadata: {name: web, labels: {app: web}}\nspec: {ports: [80, 8080, 443], replicas: 1}",
+        ),
+        (
+            "[{op: add, path: /spec/ports/-, value: 8080}, {op: add, path: /spec/ports/3, value: 1}]",
+            "metadata: {name: web, labels: {app: web}}\nspec: {ports: [80, 443, 8080, 1], replicas: 1}",
+        ),
+        (
+            // Existing keys keep their position.
+            "[{op: add, path: /metadata/name, value: api}, {op: add, path: /metadata/ns, value: x}]",
+            "metadata: {name: api, labels: {app: web}, ns: x}\nspec: {ports: [80, 443], replicas: 1}",
+        ),
+        (
+            "[{op: remove, path: /spec/ports/0}, {op: remove, path: /metadata}]",
+            "spec: {ports: [443], replicas: 1}",
+        ),
+        (
+            "[{op: replace, path: /spec/replicas, value: {min: 1}}]",
+            "metadata: {name: web, labels: {app: web}}\nspec: {ports: [80, 443], replicas: {min: 1}}",
+        ),
+        (
+            "[{op: move, from: /metadata/labels, path: /spec/labels}]",
+            "metadata: {name: web}\nspec: {ports: [80, 443], replicas: 1, labels: {app: web}}",
+        ),
+        (
+            "[{op: move, from: /spec/ports/0, path: /spec/ports/1}]",
+            "metadata: {name: web, labels: {app: web}}\nspec: {ports: [443, 80], replicas: 1}",
+        ),
+        (
+            "[{op: copy, from: /metadata/name, path: /metadata/labels/name}]",
+            "metadata: {name: web, labels: {app: web, name: web}}\nspec: {ports: [80, 443], replicas: 1}",
+        ),
+        (
+            "[{op: test, path: /spec, value: {replicas: 1, ports: [80, 443]}}]",
+            doc,
+        ),
+        ("[{op: replace, path: '', value: [1]}]", "[1]"),
+    ] {
+        assert_eq!(patched(doc, patch), Ok(yaml(expected)), "{patch}");
+    }
+
+    // Keys are looked up as with `Yaml::pointer`, and escaped as in JSON Pointers.
+    assert_eq!(
+        patched(
+            "{1: a, a/b~: c}",
+            "[{op: remove, path: /1}, {op: replace, path: /a~1b~0, value: d}]"
+        ),
+        Ok(yaml("{a/b~: d}"))
+    );
+}
+
+#[test]
+fn test_json_patch_errors() {
+    let doc = "a: {b: [1, 2]}\nc: text";
+    let error = |patch| patched(doc, patch).unwrap_err();
+    let failure = |index: usize, path: &str, kind| PatchError {
+        index,
+        path: path.to_owned(),
+        kind,
+    };
+    assert_eq!(
+        error("[{op: remove, path: /c}, {op: remove, path: /a/b/2}]"),
+        failure(1, "/a/b/2", PatchErrorKind::NotFound)
+    );
+    assert_eq!(
+        error("[{op: add, path: /a/b/3, value: 1}]"),
+        failure(0, "/a/b/3", PatchErrorKind::NotFound)
+    );
+    assert_eq!(
+        error("[{op: add, path: /x/y, value: 1}]"),
+        failure(0, "/x/y", PatchErrorKind::NotFound)
+    );
+    assert_eq!(
+        error("[{op: add, path: /c/d, value: 1}]"),
+        failure(0, "/c/d", PatchErrorKind::NotFound)
+    );
+    assert_eq!(
+        error("[{op: replace, path: a, value: 1}]"),
+        failure(0, "a", PatchErrorKind::NotFound)
+    );
+    assert_eq!(
+        error("[{op: copy, from: /z, path: /y}]"),
+        failure(0, "/z", PatchErrorKind::NotFound)
+    );
+    assert_eq!(
+        error("[{op: move, from: /a, path: /a/b/0}]"),
+        failure(0, "/a/b/0", PatchErrorKind::Unsupported)
+    );
+    assert_eq!(
+        error("[{op: remove, path: ''}]"),
+        failure(0, "", PatchErrorKind::Unsupported)
+    );
+    let failed = error("[{op: test, path: /a/b, value: [2, 1]}]");
+    assert_eq!(failed, failure(0, "/a/b", PatchErrorKind::TestFailed));
+    assert_eq!(
+        failed.to_string(),
+        "patch operation 0: the node differs at `/a/b`"
+    );
+
+    // The document is only changed if every operation succeeds.
+    let mut original = yaml(doc);
+    let patch = PatchOp::from_yaml(&yaml(
+        "[{op: remove, path: /c}, {op: test, path: /a/b/0, value: 2}]",
+    ))
+    .unwrap();
+    assert!(original.apply_json_patch(&patch).is_err());
+    assert_eq!(original, yaml(doc));
+
+    for (patch, reason) in [
+        ("{op: add}", "a patch is a sequence of operations"),
+        ("[add]", "an operation is a mapping"),
+        ("[{path: /a}]", "an operation has an `op`"),
+        ("[{op: delete, path: /a}]", "unknown `op`"),
+        (
+            "[{op: add, path: 1, value: 1}]",
+            "an operation has a string `path`",
+        ),
+        (
+            "[{op: replace, path: /a}]",
+            "an `add`, `replace` or `test` has a `value`",
+        ),
+        (
+            "[{op: copy, path: /a}]",
+            "a `move` or `copy` has a string `from`",
+        ),
+    ] {
+        let error = PatchOp::from_yaml(&yaml(patch)).unwrap_err();
+        assert_eq!(error.kind, PatchErrorKind::Malformed(reason), "{patch}");
+        assert_eq!(error.to_string(), format!("patch operation 0: {reason}"));
+    }
+}
@@ -3,7 +3,11 @@ extern crate quickcheck;
 
 use quickcheck::TestResult;
 
-use saphyr::{Yaml, YamlEmitter};
+use saphyr::{
+    DuplicateKeys, Event, LoaderOptions, MarkedYaml, Span, TScalarStyle, Yaml, YamlEmitter,
+    YamlLoader,
+};
+use saphyr_parser::SpannedEventReceiver;
 
 quickcheck! {
     fn test_check_weird_keys(xs: Vec<String>) -> TestResult {
@@ -18,4 +22,38 @@ quickcheck! {
             Err(err) => TestResult::error(err.to_string()),
         }
     }
+
+    fn test_random_event_streams(ops: Vec<(u8, u8)>, record_anchors: bool) -> TestResult {
+        let options = LoaderOptions {
+            record_anchors,
+            duplicate_keys: DuplicateKeys::Error,
+            ..LoaderOptions::default()
+        };
+        let mut loader = YamlLoader::<Yaml>::with_options(options.clone());
+        let mut marked_loader = YamlLoader::<MarkedYaml>::with_options(options);
+        // Malformed streams must fail loading rather than panic.
+        for (kind, value) in ops {
+            loader.on_event(random_event(kind, value), Span::default());
+            marked_loader.on_event(random_event(kind, value), Span::default());
+        }
+        TestResult::passed()
+    }
+}
+
+/// Build an event from random numbers, with few distinct anchors and values so that aliases and
+/// duplicate keys are frequent.
+fn random_event(kind: u8, value: u8) -> Event {
+    let anchor = usize::from(value % 3);
+    match kind % 10 {
+        0 => Event::StreamStart,
+        1 => Event::StreamEnd,
+        2 => Event::DocumentStart(value < 128),
+        3 => Event::DocumentEnd,
+        4 => Event::Alias(anchor),
+        5 => Event::SequenceStart(anchor, None),
+        6 => Event::SequenceEnd,
+        7 => Event::MappingStart(anchor, None),
+        8 => Event::MappingEnd,
+        _ => Event::Scalar((value % 4).to_string(), TScalarStyle::Plain, anchor, None),
+    }
 }
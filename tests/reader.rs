@@ -0,0 +1,87 @@
+use std::io::{self, Read};
+
+use saphyr::{LoadError, Yaml};
+
+const SOURCE: &str = "name: café\nnotes: [𝄞, \"---\"]\n---\n- 2\n";
+
+fn utf16(source: &str, to_bytes: fn(u16) -> [u8; 2]) -> Vec<u8> {
+    source.encode_utf16().flat_map(to_bytes).collect()
+}
+
+fn utf32(source: &str, to_bytes: fn(u32) -> [u8; 4]) -> Vec<u8> {
+    source.chars().flat_map(|c| to_bytes(c as u32)).collect()
+}
+
+#[test]
+fn test_load_from_reader_encodings() {
+    let expected = Yaml::load_from_str(SOURCE).unwrap();
+    for with_bom in [false, true] {
+        let source = if with_bom {
+            format!("\u{feff}{SOURCE}")
+        } else {
+            SOURCE.to_owned()
+        };
+        for bytes in [
+            source.clone().into_bytes(),
+            utf16(&source, u16::to_le_bytes),
+            utf16(&source, u16::to_be_bytes),
+            utf32(&source, u32::to_le_bytes),
+            utf32(&source, u32::to_be_bytes),
+        ] {
+            assert_eq!(
+                Yaml::load_from_reader(bytes.as_slice()).unwrap(),
+                expected,
+                "{bytes:?}"
+            );
+        }
+    }
+
+    assert!(Yaml::load_from_reader(&b""[..]).unwrap().is_empty());
+    assert_eq!(
+        Yaml::load_from_reader(&b"1"[..]).unwrap(),
+        [Yaml::Integer(1)]
+    );
+}
+
+#[test]
+fn test_load_from_reader_errors() {
+    let error = Yaml::load_from_reader(&b"a: \xff\n"[..]).unwrap_err();
+    assert!(matches!(error, LoadError::Decode(_)), "{error:?}");
+    assert_eq!(error.to_string(), "invalid UTF-8 sequence at byte 3");
+
+    // An unpaired surrogate, after `a` in UTF-16LE.
+    let error = Yaml::load_from_reader(&[b'a', 0, 0x00, 0xDC][..]).unwrap_err();
+    assert_eq!(error.to_string(), "invalid UTF-16LE sequence at byte 2");
+
+    // A stream cut in the middle of a character.
+    let bytes = utf32("[1, 2]", u32::to_be_bytes);
+    let error = Yaml::load_from_reader(&bytes[..bytes.len() - 1]).unwrap_err();
+    assert_eq!(error.to_string(), "invalid UTF-32BE sequence at byte 20");
+
+    assert!(matches!(
+        Yaml::load_from_reader(&b"[1, 2"[..]),
+        Err(LoadError::Scan(_))
+    ));
+
+    /// A reader failing after its first bytes.
+    struct Failing(&'static [u8]);
+
+    impl Read for Failing {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::BrokenPipe, "gone"));
+            }
+            let len = self.0.len().min(buf.len());
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    // The read error is reported rather than the syntax error of the truncated stream.
+    let error = Yaml::load_from_reader(Failing(b"key: [1, 2")).unwrap_err();
+    assert!(
+        matches!(&error, LoadError::IO(error) if error.kind() == io::ErrorKind::BrokenPipe),
+        "{error:?}"
+    );
+}
@@ -0,0 +1,195 @@
+use std::collections::BTreeMap;
+
+use saphyr::{yaml, DeserializeError, Deserializer, Span, Yaml};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+enum Color {
+    Red,
+    Rgb(u8, u8, u8),
+    Named { name: String },
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct Config {
+    name: String,
+    version: String,
+    replicas: u32,
+    ports: Vec<u16>,
+    labels: BTreeMap<String, String>,
+    debug: Option<bool>,
+    colors: Vec<Color>,
+}
+
+#[test]
+fn test_from_str() {
+    let source = "name: web
+version: 1.10
+replicas: 3
+ports: [80, 443]
+labels: {app: web, 'tier': front}
+debug: ~
+colors:
+  - Red
+  - Rgb: [255, 0, 0]
+  - {Named: {name: teal}}
+";
+    let config: Config = saphyr::from_str(source).unwrap();
+    assert_eq!(
+        config,
+        Config {
+            name: "web".into(),
+            version: "1.10".into(),
+            replicas: 3,
+            ports: vec![80, 443],
+            labels: [("app", "web"), ("tier", "front")]
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+            debug: None,
+            colors: vec![
+                Color::Red,
+                Color::Rgb(255, 0, 0),
+                Color::Named {
+                    name: "teal".into()
+                }
+            ],
+        }
+    );
+
+    // Scalars are resolved by the schema, unless they are quoted.
+    let values: Vec<Option<f64>> = saphyr::from_str("[1, 2.5, .inf, null, 0x10]").unwrap();
+    assert_eq!(
+        values,
+        [Some(1.0), Some(2.5), Some(f64::INFINITY), None, Some(16.0)]
+    );
+    assert!(saphyr::from_str::<Vec<u32>>("['1']").is_err());
+    assert_eq!(
+        saphyr::from_str::<u64>("18446744073709551615").unwrap(),
+        u64::MAX
+    );
+
+    // Values can be read from events too.
+    let events = yaml!({"Rgb": [1, 2, 3]}).into_events();
+    let mut deserializer = Deserializer::from_events(events.map(|ev| (ev, Span::default())));
+    assert_eq!(
+        Color::deserialize(&mut deserializer),
+        Ok(Color::Rgb(1, 2, 3))
+    );
+    assert_eq!(deserializer.end(), Ok(()));
+}
+
+#[test]
+fn test_aliases() {
+    let source = "base: &base {cpu: 1, mem: &mem 512}\ncopy: *base\nmem: {cpu: 2, mem: *mem}\n";
+    let doc: BTreeMap<String, BTreeMap<String, u32>> = saphyr::from_str(source).unwrap();
+    assert_eq!(doc["copy"], doc["base"]);
+    assert_eq!(doc["mem"]["mem"], 512);
+
+    // Aliases which expand exponentially are rejected.
+    let mut source = "a0: &a0 [x, x]\n".to_owned();
+    for i in 1..30 {
+        let previous = format!("*a{}", i - 1);
+        source += &format!("a{i}: &a{i} [{}]\n", [previous.as_str(); 10].join(", "));
+    }
+    let error = saphyr::from_str::<serde::de::IgnoredAny>(&source).unwrap_err();
+    assert_eq!(error.message, "aliases expand to too many nodes");
+}
+
+#[test]
+fn test_errors() {
+    let error = |source| saphyr::from_str::<Config>(source).unwrap_err().to_string();
+
+    assert_eq!(
+        error("name: web\nports: [80, 70000]\n"),
+        "invalid value: integer `70000`, expected u16 at line 2 column 13"
+    );
+    assert_eq!(
+        error("name: web\n"),
+        "missing field `version` at line 1 column 1"
+    );
+    assert_eq!(
+        error("name: [web]\n"),
+        "invalid type: sequence, expected a string at line 1 column 7"
+    );
+    assert_eq!(
+        saphyr::from_str::<BTreeMap<String, String>>("name: web\n---\nname: db\n")
+            .unwrap_err()
+            .to_string(),
+        "expected a single document, found several at line 2 column 1"
+    );
+    assert_eq!(
+        saphyr::from_str::<Color>("{Red: ~, Rgb: [1, 2, 3]}"),
+        Err(DeserializeError {
+            message: "the mapping has more entries than expected".into(),
+            marker: Some(saphyr::Marker::new(9, 1, 9)),
+        })
+    );
+    assert!(saphyr::from_str::<Config>("name: 'web")
+        .unwrap_err()
+        .marker
+        .is_some());
+}
+
+#[test]
+fn test_to_string() {
+    let config = Config {
+        name: "web".into(),
+        version: "1.10".into(),
+        replicas: 3,
+        ports: vec![80, 443],
+        labels: [("app".to_owned(), "web\nfront".to_owned())].into(),
+        debug: Some(true),
+        colors: vec![Color::Red, Color::Rgb(255, 0, 0)],
+    };
+    let output = saphyr::to_string(&config).unwrap();
+    assert_eq!(
+        output,
+        "---
+name: web
+version: \"1.10\"
+replicas: 3
+ports:
+  - 80
+  - 443
+labels:
+  app: \"web\\nfront\"
+debug: true
+colors:
+  - Red
+  - Rgb:
+      - 255
+      - 0
+      - 0"
+    );
+    assert_eq!(saphyr::from_str::<Config>(&output).unwrap(), config);
+
+    let output = saphyr::to_string_with(&config.labels, |emitter| {
+        emitter.multiline_strings(true);
+    });
+    assert_eq!(output.unwrap(), "---\napp: |-\n  web\n  front");
+    let output = saphyr::to_string_with(&config.colors, |emitter| emitter.flow_depth(Some(1)));
+    assert_eq!(output.unwrap(), "---\n- Red\n- {Rgb: [255, 0, 0]}");
+}
+
+#[test]
+fn test_to_yaml_value() {
+    let value = saphyr::to_yaml_value(&Color::Named {
+        name: "teal".into(),
+    });
+    assert_eq!(value.unwrap(), yaml!({"Named": {"name": "teal"}}));
+    assert_eq!(
+        saphyr::to_yaml_value(&(u64::MAX, -1i128, 0.5f32, f64::NEG_INFINITY, ())).unwrap(),
+        Yaml::Array(vec![
+            Yaml::Real("18446744073709551615".into()),
+            Yaml::Integer(-1),
+            Yaml::Real("0.5".into()),
+            Yaml::Real("-.inf".into()),
+            Yaml::Null,
+        ])
+    );
+
+    // Keys of maps can be any value.
+    let map: BTreeMap<_, _> = [((1, 2), "a")].into();
+    assert_eq!(saphyr::to_yaml_value(&map).unwrap(), yaml!({[1, 2]: "a"}));
+}
@@ -0,0 +1,119 @@
+use saphyr::{
+    LoadError, LoadErrorKind, LoaderOptions, MarkedYaml, SharedTagResolver, TScalarStyle, Tag,
+    TagResolver, Yaml, YamlData,
+};
+
+/// Redacts `!secret` scalars, turns `!pairs` sequences into mappings and rejects `!never`.
+struct Custom;
+
+impl TagResolver for Custom {
+    fn resolve_scalar(
+        &self,
+        tag: &Tag,
+        value: &str,
+        _: TScalarStyle,
+    ) -> Result<Option<Yaml>, LoadError> {
+        match (tag.handle.as_str(), tag.suffix.as_str()) {
+            ("!", "secret") => Ok(Some(Yaml::String("*".repeat(value.len())))),
+            ("!", "never") => {
+                Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "never resolved").into())
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn resolve_collection(&self, tag: &Tag, collection: Yaml) -> Result<Option<Yaml>, LoadError> {
+        if tag.handle != "!" || tag.suffix != "pairs" {
+            return Ok(None);
+        }
+        let Yaml::Array(items) = collection else {
+            return Ok(None);
+        };
+        let pairs = items
+            .chunks(2)
+            .map(|pair| (pair[0].clone(), pair.get(1).cloned().unwrap_or(Yaml::Null)))
+            .collect();
+        Ok(Some(Yaml::Hash(pairs)))
+    }
+}
+
+fn options() -> LoaderOptions {
+    LoaderOptions {
+        tag_resolver: Some(SharedTagResolver::new(Custom)),
+        ..LoaderOptions::default()
+    }
+}
+
+#[test]
+fn test_resolve_tags() {
+    let source = "password: !secret hunter2
+ports: !pairs [http, 80, https, 443]
+name: !other app
+count: !!int 3
+";
+    let doc = Yaml::load_from_str_with_options(source, options()).unwrap();
+    assert_eq!(doc[0]["password"].as_str(), Some("*******"));
+    assert_eq!(doc[0]["ports"]["http"].as_i64(), Some(80));
+    assert_eq!(doc[0]["ports"]["https"].as_i64(), Some(443));
+    assert_eq!(doc[0]["name"].as_str(), Some("app"));
+    assert_eq!(doc[0]["count"].as_i64(), Some(3));
+
+    // The resolved nodes do not keep their tags.
+    let options = LoaderOptions {
+        keep_tags: true,
+        ..options()
+    };
+    let doc = Yaml::load_from_str_with_options(source, options).unwrap();
+    assert_eq!(doc[0]["password"].as_str(), Some("*******"));
+    assert!(doc[0]["ports"].is_hash());
+    assert_eq!(
+        doc[0]["name"].tag().map(|tag| tag.suffix.as_str()),
+        Some("other")
+    );
+}
+
+#[test]
+fn test_resolve_tags_marked() {
+    let docs =
+        MarkedYaml::load_from_str_with_options("- !pairs [a, [1, 2]]\n- !secret abc", options())
+            .unwrap();
+    let YamlData::Hash(pairs) = &docs[0].data[0].data else {
+        panic!("expected a mapping, got {:?}", docs[0].data[0]);
+    };
+    let value = pairs.values().next().unwrap();
+    assert_eq!(value.data[1].data.as_i64(), Some(2));
+    assert_eq!(docs[0].data[1].data.as_str(), Some("***"));
+    assert_eq!(docs[0].data[1].span.start.line(), 2);
+}
+
+#[test]
+fn test_resolve_tags_anchors() {
+    // Anchors within a collection the resolver replaces keep their nodes, even once the
+    // collection is overwritten.
+    let source = "x: !pairs [&a k, 1, &b [2], v]\ny: [*a, *b]\nx: 2\nz: *b";
+    let doc = Yaml::load_from_str_with_options(source, options()).unwrap();
+    assert_eq!(doc[0]["x"].as_i64(), Some(2));
+    assert_eq!(doc[0]["y"][0].as_str(), Some("k"));
+    assert_eq!(doc[0]["y"][1][0].as_i64(), Some(2));
+    assert_eq!(doc[0]["z"][0].as_i64(), Some(2));
+}
+
+#[test]
+fn test_resolve_tags_error() {
+    let error = Yaml::load_from_str_with_options("a:\n  b: !never x", options()).unwrap_err();
+    assert_eq!(error.kind(), LoadErrorKind::TagResolution);
+    assert_eq!(error.kind().code(), "tag-resolution");
+    let LoadError::TagResolution {
+        tag, error: inner, ..
+    } = &error
+    else {
+        panic!("expected a tag resolution error, got {error:?}");
+    };
+    assert_eq!(tag, "!never");
+    assert!(matches!(**inner, LoadError::IO(_)));
+    assert_eq!(error.path().unwrap().to_string(), "/a/b");
+    assert_eq!(
+        error.to_string(),
+        "could not resolve the node tagged `!never` at line 2 column 6: never resolved"
+    );
+}
@@ -1,4 +1,4 @@
-use saphyr::{Yaml, YamlEmitter};
+use saphyr::{yaml, Hash, Yaml, YamlEmitter};
 
 fn roundtrip(original: &Yaml) {
     let mut emitted = String::new();
@@ -112,3 +112,23 @@ fn test_multiline_leading_newline() {
     let y = Yaml::Array(vec![Yaml::String("\na".to_owned())]);
     roundtrip_multiline(&y);
 }
+
+#[test]
+fn test_complex_keys() {
+    let mut inner = Hash::new();
+    inner.insert(Yaml::key([1, 2]), yaml!("nested"));
+    let mut hash = Hash::new();
+    hash.insert(Yaml::key(["a", "b"]), Yaml::Integer(1));
+    hash.insert(yaml!({"x": [1, 2], "y": {"z": null}}), yaml!({"q": 2}));
+    hash.insert(yaml!([]), yaml!([3]));
+    hash.insert(yaml!({}), Yaml::Integer(4));
+    hash.insert(Yaml::String("multi\nline".into()), Yaml::Integer(5));
+    hash.insert(Yaml::String("long ".repeat(300)), Yaml::Integer(6));
+    hash.insert(yaml!("inner"), Yaml::Hash(inner.clone()));
+    hash.insert(yaml!([Yaml::Hash(inner)]), Yaml::Integer(7));
+    let original = Yaml::Hash(hash);
+    roundtrip(&original);
+    roundtrip_multiline(&original);
+
+    double_roundtrip("? [a, b]\n: 1\n? {c: [d]}\n: {e: f}\nk: {? [1] : 2}");
+}
@@ -0,0 +1,139 @@
+use saphyr::{LoadError, LoaderOptions, TimeOfDay, Timestamp, Yaml, YamlEmitter};
+
+fn load(s: &str, options: LoaderOptions) -> Result<Yaml, LoadError> {
+    Yaml::load_from_str_with_options(s, options).map(|mut docs| docs.remove(0))
+}
+
+fn dump(doc: &Yaml) -> String {
+    let mut out = String::new();
+    YamlEmitter::new(&mut out).dump(doc).unwrap();
+    out
+}
+
+#[test]
+fn test_parse_timestamp() {
+    // The examples of https://yaml.org/type/timestamp.html.
+    let date = Timestamp::parse("2002-12-14").unwrap();
+    assert_eq!(
+        (date.year, date.month, date.day, date.time),
+        (2002, 12, 14, None)
+    );
+
+    let time = TimeOfDay {
+        hour: 2,
+        minute: 59,
+        second: 43,
+        nanosecond: 100_000_000,
+        offset_minutes: 0,
+    };
+    for canonical in ["2001-12-15T02:59:43.1Z", "2001-12-15 2:59:43.10"] {
+        assert_eq!(Timestamp::parse(canonical).unwrap().time, Some(time));
+    }
+    let time = TimeOfDay {
+        hour: 21,
+        offset_minutes: -5 * 60,
+        ..time
+    };
+    for spaced in ["2001-12-14t21:59:43.10-05:00", "2001-12-14 21:59:43.10 -5"] {
+        assert_eq!(Timestamp::parse(spaced).unwrap().time, Some(time));
+    }
+    assert_eq!(
+        Timestamp::parse("2001-1-2 3:04:05+05:30")
+            .unwrap()
+            .to_string(),
+        "2001-01-02T03:04:05+05:30"
+    );
+
+    for invalid in [
+        "2002-2-14",
+        "2002-02-30",
+        "2000-02-30",
+        "2002-13-01",
+        "02-12-14",
+        "2001-12-14 25:00:00",
+        "2001-12-14 21:60:00",
+        "2001-12-14  21:59:43 +05:60",
+        "2001-12-14T21:59:43Zx",
+        "2001-12-14T",
+        "today",
+    ] {
+        assert_eq!(Timestamp::parse(invalid), None, "{invalid}");
+    }
+    assert!(Timestamp::parse("2000-02-29").is_some());
+    assert!(Timestamp::parse("1900-02-29").is_none());
+}
+
+#[test]
+fn test_tagged_timestamps() {
+    let doc = load(
+        "a: !!timestamp 2001-12-14 21:59:43.10 -5\nb: 2002-12-14\nc: !!timestamp not a date",
+        LoaderOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        doc["a"].as_timestamp().map(|t| t.to_string()),
+        Some("2001-12-14T21:59:43.1-05:00".to_owned())
+    );
+    // Untagged dates are only resolved on request.
+    assert_eq!(doc["b"].as_str(), Some("2002-12-14"));
+    assert_eq!(doc["c"].as_str(), Some("not a date"));
+
+    let options = LoaderOptions {
+        resolve_timestamps: true,
+        ..LoaderOptions::default()
+    };
+    let doc = load("a: 2002-12-14\nb: '2002-12-14'\nc: 2002-12-32", options).unwrap();
+    assert_eq!(
+        doc["a"],
+        Yaml::Timestamp(Timestamp::parse("2002-12-14").unwrap())
+    );
+    assert_eq!(doc["b"].as_str(), Some("2002-12-14"));
+    assert_eq!(doc["c"].as_str(), Some("2002-12-32"));
+}
+
+#[test]
+fn test_strict_timestamps() {
+    let options = LoaderOptions {
+        strict_timestamps: true,
+        ..LoaderOptions::default()
+    };
+    let error = load("a: !!timestamp 2002-02-30", options.clone()).unwrap_err();
+    assert!(matches!(
+        &error,
+        LoadError::InvalidTimestamp { value, .. } if value == "2002-02-30"
+    ));
+    assert_eq!(
+        error.to_string(),
+        "`2002-02-30` at line 1 column 4 is tagged `!!timestamp` but is not a valid timestamp"
+    );
+    // Only explicitly tagged scalars are checked.
+    assert_eq!(
+        load("a: 2002-02-30", options).unwrap()["a"].as_str(),
+        Some("2002-02-30")
+    );
+}
+
+#[test]
+fn test_emit_timestamps() {
+    let options = LoaderOptions {
+        resolve_timestamps: true,
+        ..LoaderOptions::default()
+    };
+    let doc = load(
+        "a: 2001-12-14t21:59:43.10-05:00\nb: 2002-12-14",
+        options.clone(),
+    )
+    .unwrap();
+    let out = dump(&doc);
+    assert_eq!(
+        out,
+        "---\na: !!timestamp 2001-12-14T21:59:43.1-05:00\nb: !!timestamp 2002-12-14"
+    );
+    assert_eq!(load(&out, options).unwrap(), doc);
+    // The tag keeps them timestamps without `resolve_timestamps`.
+    assert_eq!(load(&out, LoaderOptions::default()).unwrap(), doc);
+
+    // Strings that look like timestamps are quoted, so that they are not read back as such.
+    let doc = Yaml::String("2002-12-14".to_owned());
+    assert_eq!(dump(&doc), "---\n\"2002-12-14\"");
+}